@@ -0,0 +1,251 @@
+//! `#[derive(FromParagraph, ToParagraph)]` for
+//! [eight-deep-parser](https://docs.rs/eight-deep-parser)
+//!
+//! Use it through the `derive` feature of `eight-deep-parser` instead of
+//! depending on this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, Data, DeriveInput, Error, Fields, GenericArgument, LitStr, Path,
+    PathArguments, Result, Type,
+};
+
+/// Implement `FromParagraph`, see the crate documentation of
+/// `eight-deep-parser`
+#[proc_macro_derive(FromParagraph, attributes(edp))]
+pub fn derive_from_paragraph(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    from_paragraph(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Implement `ToParagraph`, see the crate documentation of
+/// `eight-deep-parser`
+#[proc_macro_derive(ToParagraph, attributes(edp))]
+pub fn derive_to_paragraph(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    to_paragraph(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+enum Kind {
+    /// `FromStr` and `Display` through a one line value
+    Default,
+    /// `Vec<String>` through a multi line value
+    Multi,
+    /// `from_item` and `to_item` in the given module
+    With(Path),
+}
+
+struct Field {
+    ident: syn::Ident,
+    key: String,
+    kind: Kind,
+    optional: bool,
+}
+
+fn fields(input: &DeriveInput) -> Result<Vec<Field>> {
+    let data = match &input.data {
+        Data::Struct(data) => data,
+        _ => return Err(Error::new_spanned(input, "only structs are supported")),
+    };
+
+    let named = match &data.fields {
+        Fields::Named(named) => named,
+        _ => {
+            return Err(Error::new_spanned(
+                input,
+                "only structs with named fields are supported",
+            ))
+        }
+    };
+
+    let mut result = vec![];
+
+    for f in &named.named {
+        let ident = f.ident.clone().unwrap();
+        let mut key = None;
+        let mut kind = Kind::Default;
+
+        for attr in f.attrs.iter().filter(|x| x.path().is_ident("edp")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    let s: LitStr = meta.value()?.parse()?;
+                    key = Some(s.value());
+                } else if meta.path.is_ident("multi") {
+                    kind = Kind::Multi;
+                } else if meta.path.is_ident("with") {
+                    kind = Kind::With(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error("expected `rename`, `multi` or `with`"));
+                }
+
+                Ok(())
+            })?;
+        }
+
+        let key = key.unwrap_or_else(|| field_key(&ident.to_string()));
+
+        result.push(Field {
+            key,
+            kind,
+            optional: option_inner(&f.ty).is_some(),
+            ident,
+        });
+    }
+
+    Ok(result)
+}
+
+/// `installed_size` → `Installed-Size`
+fn field_key(ident: &str) -> String {
+    ident
+        .trim_start_matches("r#")
+        .split('_')
+        .filter(|x| !x.is_empty())
+        .map(|x| {
+            let mut c = x.chars();
+            match c.next() {
+                Some(first) => first.to_uppercase().chain(c).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("-")
+}
+
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let path = match ty {
+        Type::Path(p) if p.qself.is_none() => &p.path,
+        _ => return None,
+    };
+
+    let last = path.segments.last()?;
+    if last.ident != "Option" {
+        return None;
+    }
+
+    match &last.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn from_paragraph(input: &DeriveInput) -> Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = fields(input)?.into_iter().map(|f| {
+        let ident = &f.ident;
+        let key = &f.key;
+
+        let convert = match &f.kind {
+            Kind::Default => quote!(::eight_deep_parser::__private::from_one_line(item)),
+            Kind::Multi => quote!(::eight_deep_parser::__private::from_multi_line(item)),
+            Kind::With(path) => quote!(#path::from_item(item)),
+        };
+
+        let convert = quote! {
+            #convert.map_err(|reason| ::eight_deep_parser::MapError::Invalid {
+                field: #key.to_string(),
+                reason: reason.to_string(),
+            })?
+        };
+
+        let get = quote!(::eight_deep_parser::ParagraphExt::get_field(p, #key));
+
+        if f.optional {
+            quote! {
+                #ident: match #get {
+                    Some(item) => Some(#convert),
+                    None => None,
+                }
+            }
+        } else {
+            quote! {
+                #ident: {
+                    let item = #get.ok_or_else(|| ::eight_deep_parser::MapError::Missing {
+                        field: #key.to_string(),
+                    })?;
+                    #convert
+                }
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::eight_deep_parser::FromParagraph for #name #ty_generics #where_clause {
+            fn from_paragraph(
+                p: &::eight_deep_parser::IndexMap<::std::string::String, ::eight_deep_parser::Item>,
+            ) -> ::std::result::Result<Self, ::eight_deep_parser::MapError> {
+                Ok(Self {
+                    #(#fields,)*
+                })
+            }
+        }
+    })
+}
+
+fn to_paragraph(input: &DeriveInput) -> Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = fields(input)?.into_iter().map(|f| {
+        let ident = &f.ident;
+        let key = &f.key;
+
+        let convert = match &f.kind {
+            Kind::Default => quote!(::eight_deep_parser::__private::to_one_line(value)),
+            Kind::Multi => quote!(::eight_deep_parser::__private::to_multi_line(value)),
+            Kind::With(path) => quote!(#path::to_item(value)),
+        };
+
+        let insert = quote!(p.insert(#key.to_string(), #convert););
+
+        if f.optional {
+            quote! {
+                if let Some(value) = &self.#ident {
+                    #insert
+                }
+            }
+        } else {
+            quote! {
+                let value = &self.#ident;
+                #insert
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::eight_deep_parser::ToParagraph for #name #ty_generics #where_clause {
+            fn to_paragraph(
+                &self,
+            ) -> ::eight_deep_parser::IndexMap<::std::string::String, ::eight_deep_parser::Item> {
+                let mut p = ::eight_deep_parser::IndexMap::new();
+                #(#fields)*
+                p
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::field_key;
+
+    #[test]
+    fn test_field_key() {
+        assert_eq!(field_key("package"), "Package");
+        assert_eq!(field_key("installed_size"), "Installed-Size");
+        assert_eq!(field_key("r#type"), "Type");
+    }
+}
@@ -0,0 +1,168 @@
+use std::fmt::Display;
+
+use eight_deep_parser::{parse_one, FromParagraph, Item, MapError, ToParagraph};
+
+/// A version kept as its parts, to exercise `#[edp(with = ...)]`
+#[derive(Debug, PartialEq, Eq)]
+struct Version {
+    upstream: String,
+    revision: Option<String>,
+}
+
+mod version {
+    use eight_deep_parser::Item;
+
+    use super::Version;
+
+    pub fn from_item(item: &Item) -> Result<Version, String> {
+        let s = match item {
+            Item::OneLine(s) => s,
+            _ => return Err("expected a one line value".to_string()),
+        };
+
+        if s.is_empty() {
+            return Err("empty version".to_string());
+        }
+
+        Ok(match s.rsplit_once('-') {
+            Some((upstream, revision)) => Version {
+                upstream: upstream.to_string(),
+                revision: Some(revision.to_string()),
+            },
+            None => Version {
+                upstream: s.to_string(),
+                revision: None,
+            },
+        })
+    }
+
+    pub fn to_item(v: &Version) -> Item {
+        match &v.revision {
+            Some(r) => Item::OneLine(format!("{}-{}", v.upstream, r)),
+            None => Item::OneLine(v.upstream.clone()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, FromParagraph, ToParagraph)]
+struct Package {
+    package: String,
+    #[edp(with = version)]
+    version: Version,
+    installed_size: Option<u64>,
+    #[edp(rename = "Maintainer")]
+    maintainer_name: String,
+    #[edp(multi)]
+    conffiles: Vec<String>,
+    #[edp(multi)]
+    md5sums: Option<Vec<String>>,
+    homepage: Option<String>,
+}
+
+const STATUS: &str = "Package: bash
+Version: 5.2.15-2
+Installed-Size: 7164
+Maintainer: Matthias Klose <doko@debian.org>
+Conffiles:
+ /etc/bash.bashrc 89269e1298235f1b12b4c16e4065ad0d
+ /etc/skel/.bashrc 7ed7c6e6c4e9b4b5a2e6f8b9c0d1e2f3
+";
+
+#[test]
+fn test_round_trip() {
+    let p = parse_one(STATUS).unwrap();
+    let r = Package::from_paragraph(&p).unwrap();
+
+    assert_eq!(
+        r,
+        Package {
+            package: "bash".to_string(),
+            version: Version {
+                upstream: "5.2.15".to_string(),
+                revision: Some("2".to_string()),
+            },
+            installed_size: Some(7164),
+            maintainer_name: "Matthias Klose <doko@debian.org>".to_string(),
+            conffiles: vec![
+                "/etc/bash.bashrc 89269e1298235f1b12b4c16e4065ad0d".to_string(),
+                "/etc/skel/.bashrc 7ed7c6e6c4e9b4b5a2e6f8b9c0d1e2f3".to_string(),
+            ],
+            md5sums: None,
+            homepage: None,
+        }
+    );
+
+    assert_eq!(r.to_paragraph(), p);
+    assert_eq!(Package::from_paragraph(&r.to_paragraph()).unwrap(), r);
+}
+
+#[test]
+fn test_case_insensitive_keys() {
+    let p = parse_one(&STATUS.replace("Installed-Size", "installed-size")).unwrap();
+
+    assert_eq!(
+        Package::from_paragraph(&p).unwrap().installed_size,
+        Some(7164)
+    );
+}
+
+fn check_err(input: &str, expected: MapError) {
+    let p = parse_one(input).unwrap();
+    let e = Package::from_paragraph(&p).unwrap_err();
+
+    assert_eq!(e, expected);
+}
+
+fn invalid(field: &str, reason: impl Display) -> MapError {
+    MapError::Invalid {
+        field: field.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+#[test]
+fn test_missing_field() {
+    check_err(
+        &STATUS.replace("Maintainer", "X-Maintainer"),
+        MapError::Missing {
+            field: "Maintainer".to_string(),
+        },
+    );
+}
+
+#[test]
+fn test_invalid_field() {
+    check_err(
+        &STATUS.replace("7164", "big"),
+        invalid("Installed-Size", "invalid digit found in string"),
+    );
+    check_err(
+        &STATUS.replace("Version: 5.2.15-2", "Version:\n 5"),
+        invalid("Version", "expected a one line value"),
+    );
+    check_err(
+        &format!("{}Md5sums: a\n", STATUS),
+        invalid("Md5sums", "expected a multi line value"),
+    );
+}
+
+#[test]
+fn test_optional_fields_serialized_when_set() {
+    let mut r = Package::from_paragraph(&parse_one(STATUS).unwrap()).unwrap();
+    r.homepage = Some("https://www.gnu.org/software/bash/".to_string());
+    r.md5sums = Some(vec!["a b".to_string()]);
+
+    let p = r.to_paragraph();
+
+    assert_eq!(
+        p.get("Homepage"),
+        Some(&Item::OneLine(
+            "https://www.gnu.org/software/bash/".to_string()
+        ))
+    );
+    assert_eq!(
+        p.get("Md5sums"),
+        Some(&Item::MultiLine(vec!["a b".to_string()]))
+    );
+    assert_eq!(Package::from_paragraph(&p).unwrap(), r);
+}
@@ -0,0 +1,71 @@
+//! Normalize the capitalization of known field names, leaving everything
+//! else, comments included, exactly as it was
+//!
+//! ```sh
+//! cargo run --example deb822_fmt -- debian/control
+//! ```
+
+use std::{
+    env, fs,
+    io::{self, Read},
+};
+
+use eight_deep_parser::Cst;
+
+const KNOWN_FIELDS: &[&str] = &[
+    "Architecture",
+    "Breaks",
+    "Build-Conflicts",
+    "Build-Depends",
+    "Build-Depends-Indep",
+    "Conflicts",
+    "Depends",
+    "Description",
+    "Enhances",
+    "Essential",
+    "Homepage",
+    "Maintainer",
+    "Multi-Arch",
+    "Package",
+    "Pre-Depends",
+    "Priority",
+    "Provides",
+    "Recommends",
+    "Replaces",
+    "Section",
+    "Source",
+    "Standards-Version",
+    "Suggests",
+    "Uploaders",
+    "Version",
+    "Vcs-Browser",
+    "Vcs-Git",
+];
+
+fn main() -> io::Result<()> {
+    let input = match env::args().nth(1) {
+        Some(path) => fs::read_to_string(path)?,
+        None => {
+            let mut s = String::new();
+            io::stdin().read_to_string(&mut s)?;
+            s
+        }
+    };
+
+    let mut cst = Cst::parse(&input);
+
+    for field in cst.fields_mut() {
+        if let Some(known) = KNOWN_FIELDS
+            .iter()
+            .find(|x| x.eq_ignore_ascii_case(field.key()))
+        {
+            if field.key() != *known {
+                field.set_key(known);
+            }
+        }
+    }
+
+    print!("{}", cst);
+
+    Ok(())
+}
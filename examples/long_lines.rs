@@ -0,0 +1,56 @@
+//! Time parsing and writing back a paragraph with one very long `Depends`
+//! line, at 1 MB and at 10 MB, to check the time grows linearly
+//!
+//! ```sh
+//! cargo run --release --example long_lines
+//! ```
+
+use std::time::{Duration, Instant};
+
+use eight_deep_parser::{parse_back, parse_multi, Cst, ParagraphDecoder, ParseOptions};
+
+fn time(f: impl FnOnce()) -> Duration {
+    let start = Instant::now();
+    f();
+    start.elapsed()
+}
+
+fn main() {
+    println!(
+        "MB/s   {:>12}{:>12}{:>12}{:>12}",
+        "parse_multi", "parse_back", "Cst", "decoder"
+    );
+
+    for mb in [1, 10] {
+        let value = "libfoo (>= 1.0), ".repeat(mb * 1024 * 1024 / 17);
+        let s = format!("Package: a\nDepends: {}\nVersion: 1\n", value.trim_end());
+        let r = parse_multi(&s).unwrap();
+
+        let times = [
+            time(|| {
+                parse_multi(&s).unwrap();
+            }),
+            time(|| assert_eq!(parse_back(&r), s)),
+            time(|| {
+                Cst::parse(&s).paragraphs(&ParseOptions::default()).unwrap();
+            }),
+            time(|| {
+                let mut decoder = ParagraphDecoder::new();
+
+                for chunk in s.as_bytes().chunks(8192) {
+                    decoder.push(chunk);
+                    while let Some(p) = decoder.next_paragraph() {
+                        p.unwrap();
+                    }
+                }
+                decoder.finish().unwrap();
+            }),
+        ];
+
+        print!("{:>2} MB ", mb);
+        for t in times {
+            print!("{:>12.0}", s.len() as f64 / 1e6 / t.as_secs_f64());
+        }
+        println!();
+    }
+}
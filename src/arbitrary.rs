@@ -0,0 +1,227 @@
+//! Random paragraphs for property tests and fuzzing
+//!
+//! Only available with the `arbitrary` feature. Field names and values are
+//! varied, including the ones that have no exact form:
+//! [`try_parse_back`](crate::try_parse_back) refuses them, e.g. an empty
+//! [`Item::OneLine`] or a field name ending with whitespace, and whatever it
+//! writes parses back unchanged.
+//!
+//! ```rust
+//! use eight_deep_parser::{
+//!     arbitrary::{Arbitrary, Gen},
+//!     parse_multi, try_parse_back, Paragraph,
+//! };
+//!
+//! let mut g = Gen::new(42);
+//!
+//! for _ in 0..100 {
+//!     let doc = Vec::<Paragraph>::arbitrary(&mut g);
+//!
+//!     if let Ok(s) = try_parse_back(&doc) {
+//!         assert_eq!(parse_multi(&s).unwrap(), doc);
+//!     }
+//! }
+//! ```
+
+use crate::{IndexMap, Item, Paragraph};
+
+/// A small deterministic pseudo-random generator (xorshift64)
+#[derive(Debug, Clone)]
+pub struct Gen {
+    state: u64,
+}
+
+impl Gen {
+    /// A generator giving the same sequence for the same seed
+    pub fn new(seed: u64) -> Self {
+        // xorshift is stuck at 0
+        Self { state: seed.max(1) }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+
+        self.state
+    }
+
+    /// A number in `0..n`. `n` must not be 0.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Pick one of `items`, which must not be empty
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    /// Up to `max_len` characters of varied kinds, without line breaks
+    pub fn text(&mut self, max_len: usize) -> String {
+        const CHARS: &[char] = &[
+            'a', 'z', 'A', 'Z', '0', '9', ' ', ' ', '\t', '.', ':', ',', '|', '-', '(', ')', '#',
+            '$', '{', '}', '\\', '"', 'é', 'ß', '中', '\u{a0}',
+        ];
+
+        (0..self.below(max_len + 1))
+            .map(|_| *self.choose(CHARS))
+            .collect()
+    }
+}
+
+/// Types that can be generated at random by a [`Gen`]
+pub trait Arbitrary: Sized {
+    fn arbitrary(g: &mut Gen) -> Self;
+}
+
+impl Arbitrary for Item {
+    fn arbitrary(g: &mut Gen) -> Self {
+        match g.below(3) {
+            0 => Item::OneLine(field_line(g)),
+            1 => Item::MultiLine(continuation_lines(g)),
+            _ => Item::Folded(field_line(g), continuation_lines(g)),
+        }
+    }
+}
+
+impl Arbitrary for Paragraph {
+    fn arbitrary(g: &mut Gen) -> Self {
+        const KEYS: &[&str] = &[
+            "Package",
+            "Version",
+            "Depends",
+            "Description",
+            "Conffiles",
+            "X-Custom",
+            "a",
+            "-",
+            "0",
+        ];
+
+        let mut result = IndexMap::new();
+
+        for _ in 0..g.below(6) + 1 {
+            let key = format!("{}{}", g.choose(KEYS), g.below(4));
+            // names that can't be written now and then
+            let key = match g.below(16) {
+                0 => format!("#{}", key),
+                1 => format!("{} ", key),
+                _ => key,
+            };
+
+            result.insert(key, Item::arbitrary(g));
+        }
+
+        result
+    }
+}
+
+impl<T: Arbitrary> Arbitrary for Vec<T> {
+    fn arbitrary(g: &mut Gen) -> Self {
+        (0..g.below(5)).map(|_| T::arbitrary(g)).collect()
+    }
+}
+
+/// The value on the field line: now and then empty or starting with
+/// whitespace, which the separator would eat
+fn field_line(g: &mut Gen) -> String {
+    let first = *g.choose(&['a', 'Z', '0', '.', ':', '#', 'é']);
+
+    match g.below(16) {
+        0 => String::new(),
+        1 => format!(" {}", g.text(12)),
+        _ => format!("{}{}", first, g.text(12)),
+    }
+}
+
+/// Lines, each possibly empty, and now and then none at all
+fn continuation_lines(g: &mut Gen) -> Vec<String> {
+    let len = if g.below(16) == 0 { 0 } else { g.below(4) + 1 };
+
+    (0..len).map(|_| g.text(12)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Arbitrary, Gen};
+    use crate::{
+        parse_back, parse_multi, try_parse_back, try_parse_back_with, Cst, Paragraph,
+        SerializeError, SerializeOptions,
+    };
+
+    const CASES: usize = 1000;
+
+    #[test]
+    fn test_parse_back_then_parse_is_identity() {
+        let mut g = Gen::new(0x8d);
+        let mut written = 0;
+
+        for _ in 0..CASES {
+            let doc = Vec::<Paragraph>::arbitrary(&mut g);
+
+            match try_parse_back(&doc) {
+                Ok(s) => {
+                    written += 1;
+                    assert_eq!(parse_multi(&s).unwrap(), doc, "{}", s);
+                }
+                Err(e) => assert!(
+                    matches!(
+                        e,
+                        SerializeError::InvalidKey { .. } | SerializeError::InvalidValue { .. }
+                    ),
+                    "{}",
+                    e
+                ),
+            }
+        }
+
+        // both sides are covered
+        assert!(written > CASES / 10 && written < CASES, "{}", written);
+    }
+
+    #[test]
+    fn test_parse_then_parse_back_is_idempotent() {
+        let mut g = Gen::new(0x8d8d);
+
+        for _ in 0..CASES {
+            let doc = Vec::<Paragraph>::arbitrary(&mut g);
+
+            // vary the paragraph separators the parser has to accept
+            let sep = *g.choose(&["\n\n", "\n\n\n", "\n \n\t\n"]);
+            let s = match try_parse_back_with(
+                &doc,
+                &SerializeOptions {
+                    trailing_blank_line: g.below(2) == 0,
+                    ..Default::default()
+                },
+            ) {
+                Ok(s) => s.replace("\n\n", sep),
+                Err(_) => continue,
+            };
+            let s = format!("{}{}", g.choose(&["", "\n", "\n\n"]), s);
+
+            let once = parse_multi(&s).unwrap();
+            let twice = parse_multi(&parse_back(&once)).unwrap();
+
+            assert_eq!(once, twice, "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn test_lenient_parse_is_not_bigger() {
+        let mut g = Gen::new(0x8d8d8d);
+
+        for _ in 0..CASES {
+            let doc = Vec::<Paragraph>::arbitrary(&mut g);
+            let s = match try_parse_back(&doc) {
+                Ok(s) => s,
+                Err(_) => continue,
+            };
+
+            let strict = parse_multi(&s).unwrap();
+            let lenient = Cst::parse(&s).paragraphs(&Default::default()).unwrap();
+
+            assert!(lenient.len() <= strict.len(), "{}", s);
+        }
+    }
+}
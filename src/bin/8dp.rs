@@ -0,0 +1,376 @@
+//! `8dp`, the parser on the command line
+//!
+//! ```sh
+//! 8dp get /var/lib/dpkg/status bash Version
+//! 8dp filter Packages --where Architecture=amd64 --where Section=shells
+//! 8dp fmt debian/control
+//! 8dp lint Packages
+//! 8dp json Sources
+//! ```
+//!
+//! A file named `-` is read from the standard input. With the `deb` feature
+//! gzip input is decompressed. The exit code is 0 on success, 1 when `get`
+//! finds nothing or `lint` finds problems, and 2 on a usage, read or parse
+//! error.
+
+use std::{
+    env, fs,
+    io::{self, Read, Write},
+    process::ExitCode,
+};
+
+use eight_deep_parser::{
+    fields::{FieldInfo, FieldKind},
+    parse_back, parse_multi, parse_multi_bytes, parse_multi_with, to_json, transform, ParagraphExt,
+    ParseOptions, Version,
+};
+
+const USAGE: &str = "\
+usage: 8dp get <file> <package> [field]
+       8dp filter <file> --where <field>=<value>...
+       8dp fmt <file>
+       8dp lint <file>
+       8dp json <file>";
+
+fn main() -> ExitCode {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let code = run(
+        &args,
+        io::stdin().lock(),
+        &mut io::stdout().lock(),
+        &mut io::stderr().lock(),
+    );
+
+    ExitCode::from(code)
+}
+
+/// Run the command of `args`, giving the exit code
+fn run(args: &[String], stdin: impl Read, out: &mut impl Write, err: &mut impl Write) -> u8 {
+    match command(args, stdin, out, err) {
+        Ok(code) => code,
+        Err(e) => {
+            let _ = writeln!(err, "8dp: {}", e);
+            2
+        }
+    }
+}
+
+fn command(
+    args: &[String],
+    stdin: impl Read,
+    out: &mut impl Write,
+    err: &mut impl Write,
+) -> Result<u8, String> {
+    let args = args.iter().map(|x| x.as_str()).collect::<Vec<_>>();
+    let usage = || Err(USAGE.to_string());
+
+    let (command, path, rest) = match &args[..] {
+        [command, path, rest @ ..] => (*command, *path, rest),
+        _ => return usage(),
+    };
+
+    let input = read_input(path, stdin)?;
+    let name = if path == "-" { "<stdin>" } else { path };
+
+    match (command, rest) {
+        ("get", [package, field @ ..]) if field.len() < 2 => {
+            let ps = parse_multi(&input).map_err(|e| format!("{}: {}", name, e))?;
+            let found = ps
+                .into_iter()
+                .filter(|x| x.get_one("Package") == Some(package))
+                .collect::<Vec<_>>();
+
+            if found.is_empty() {
+                writeln!(err, "8dp: {} not found in {}", package, name).map_err(io_error)?;
+                return Ok(1);
+            }
+
+            let field = match field.first() {
+                Some(field) => field,
+                None => {
+                    write!(out, "{}", parse_back(&found)).map_err(io_error)?;
+                    return Ok(0);
+                }
+            };
+
+            let kind = FieldInfo::lookup(field).map_or(FieldKind::Folded, |x| x.kind);
+            let mut code = 0;
+
+            for p in &found {
+                match p.get_field(field) {
+                    Some(item) => writeln!(out, "{}", item.joined_value(kind)).map_err(io_error)?,
+                    None => {
+                        writeln!(err, "8dp: {} has no {}", package, field).map_err(io_error)?;
+                        code = 1;
+                    }
+                }
+            }
+
+            Ok(code)
+        }
+        ("filter", rest) if !rest.is_empty() => {
+            let mut conditions = vec![];
+            let mut rest = rest.iter();
+
+            while let Some(arg) = rest.next() {
+                let condition = match *arg {
+                    "--where" => rest.next().copied(),
+                    _ => arg.strip_prefix("--where="),
+                };
+
+                match condition.and_then(|x| x.split_once('=')) {
+                    Some(condition) => conditions.push(condition),
+                    None => return usage(),
+                }
+            }
+
+            transform(input.as_bytes(), out, |p| {
+                conditions
+                    .iter()
+                    .all(|(k, v)| p.get_one(k) == Some(v))
+                    .then_some(p)
+            })
+            .map_err(|e| format!("{}: {}", name, e))?;
+
+            Ok(0)
+        }
+        ("fmt", []) => {
+            transform(input.as_bytes(), out, Some).map_err(|e| format!("{}: {}", name, e))?;
+
+            Ok(0)
+        }
+        ("lint", []) => lint(&input, name, out),
+        ("json", []) => {
+            let ps = parse_multi(&input).map_err(|e| format!("{}: {}", name, e))?;
+            write!(out, "{}", to_json(&ps)).map_err(io_error)?;
+
+            Ok(0)
+        }
+        _ => usage(),
+    }
+}
+
+/// Check `input` with [`ParseOptions::strict_rfc`], then its `Version` and
+/// relation fields, printing a line for every problem
+fn lint(input: &str, name: &str, out: &mut impl Write) -> Result<u8, String> {
+    let ps = match parse_multi_with(input, &ParseOptions::strict_rfc()) {
+        Ok(ps) => ps,
+        Err(e) => {
+            writeln!(out, "{}: {}", name, e).map_err(io_error)?;
+
+            if let Some(hint) = e.hint(input) {
+                writeln!(out, "  hint: {}", hint).map_err(io_error)?;
+            }
+
+            return Ok(1);
+        }
+    };
+
+    let mut problems = 0;
+
+    for (i, p) in ps.iter().enumerate() {
+        let package = p.get_one("Package").unwrap_or("?");
+
+        for key in p.keys() {
+            let r = match FieldInfo::lookup(key) {
+                Some(info) if info.kind == FieldKind::Relations => p.relations(key),
+                _ if key.eq_ignore_ascii_case("Version") => {
+                    p.get_one(key).map(|x| x.parse::<Version>().map(|_| vec![]))
+                }
+                _ => None,
+            };
+
+            if let Some(Err(e)) = r {
+                writeln!(
+                    out,
+                    "{}: paragraph {} ({}): {}: {}",
+                    name,
+                    i + 1,
+                    package,
+                    key,
+                    e
+                )
+                .map_err(io_error)?;
+                problems += 1;
+            }
+        }
+    }
+
+    Ok(if problems == 0 { 0 } else { 1 })
+}
+
+fn read_input(path: &str, mut stdin: impl Read) -> Result<String, String> {
+    let mut data = vec![];
+
+    if path == "-" {
+        stdin.read_to_end(&mut data).map_err(io_error)?;
+    } else {
+        data = fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+    }
+
+    #[cfg(feature = "deb")]
+    let data = eight_deep_parser::decompress(data, path).map_err(|e| e.to_string())?;
+
+    // parse_multi_bytes tells where the invalid byte is
+    String::from_utf8(data)
+        .map_err(|e| format!("{}: {}", path, parse_multi_bytes(e.as_bytes()).unwrap_err()))
+}
+
+fn io_error(e: io::Error) -> String {
+    e.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::run;
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+    /// Run `8dp` with `args`, `{}` standing for the fixture directory
+    fn run_8dp(args: &[&str], stdin: impl AsRef<[u8]>) -> (u8, String, String) {
+        let args = args
+            .iter()
+            .map(|x| x.replace("{}", FIXTURES))
+            .collect::<Vec<_>>();
+        let (mut out, mut err) = (vec![], vec![]);
+        let code = run(&args, stdin.as_ref(), &mut out, &mut err);
+
+        (
+            code,
+            String::from_utf8(out).unwrap(),
+            String::from_utf8(err).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_get() {
+        let (code, out, _) = run_8dp(&["get", "{}/Packages", "bash", "Version"], "");
+        assert_eq!((code, out.as_str()), (0, "5.2.15-2+b13\n"));
+
+        let (code, out, _) = run_8dp(&["get", "-", "a"], "Package: a\nDepends: b,\n c\n\n");
+        assert_eq!((code, out.as_str()), (0, "Package: a\nDepends: b,\n c\n"));
+
+        let (code, out, _) = run_8dp(
+            &["get", "-", "a", "depends"],
+            "Package: a\nDepends: b,\n c\n",
+        );
+        assert_eq!((code, out.as_str()), (0, "b, c\n"));
+
+        let (code, _, err) = run_8dp(&["get", "{}/Packages", "missing"], "");
+        assert_eq!(code, 1);
+        assert!(err.contains("missing not found"));
+
+        assert_eq!(run_8dp(&["get", "-", "a", "Version"], "Package: a\n").0, 1);
+    }
+
+    #[test]
+    fn test_filter() {
+        let s = "Package: a\nArchitecture: amd64\n\nPackage: b\nArchitecture: i386\n\nPackage: c\nArchitecture: amd64\nSection: x\n";
+
+        let (code, out, _) = run_8dp(&["filter", "-", "--where", "Architecture=amd64"], s);
+        assert_eq!(code, 0);
+        assert_eq!(
+            out,
+            "Package: a\nArchitecture: amd64\n\nPackage: c\nArchitecture: amd64\nSection: x\n"
+        );
+
+        let (_, out, _) = run_8dp(
+            &[
+                "filter",
+                "-",
+                "--where=architecture=amd64",
+                "--where",
+                "Section=x",
+            ],
+            s,
+        );
+        assert_eq!(out, "Package: c\nArchitecture: amd64\nSection: x\n");
+
+        assert_eq!(run_8dp(&["filter", "-", "--where"], s).0, 2);
+        assert_eq!(run_8dp(&["filter", "-", "Section"], s).0, 2);
+    }
+
+    #[test]
+    fn test_fmt_json() {
+        let (code, out, _) = run_8dp(
+            &["fmt", "-"],
+            "\n\nPackage : a\nDescription: b\n c\n\n\n\nPackage: d",
+        );
+        assert_eq!(
+            (code, out.as_str()),
+            (0, "Package: a\nDescription: b\n c\n\nPackage: d\n")
+        );
+
+        let (code, out, _) = run_8dp(&["json", "-"], "Package: a\n");
+        assert_eq!(
+            (code, out.as_str()),
+            (0, "[\n  {\n    \"Package\": \"a\"\n  }\n]\n")
+        );
+    }
+
+    #[test]
+    fn test_lint() {
+        for fixture in ["status", "Packages", "Sources"] {
+            let path = format!("{{}}/{}", fixture);
+            let (code, out, _) = run_8dp(&["lint", &path], "");
+            assert_eq!((code, out.as_str()), (0, ""), "{}", fixture);
+        }
+
+        let (code, out, _) = run_8dp(&["lint", "-"], "Package: a\nVersion=1\n");
+        assert_eq!(code, 1);
+        assert_eq!(
+            out.lines().nth(1),
+            Some("  hint: did you mean `Version: 1`?")
+        );
+
+        let (code, out, _) = run_8dp(&["lint", "-"], "Package: a\n orphan\n\n orphan\n");
+        assert_eq!(
+            (code, out.as_str()),
+            (1, "<stdin>: Continuation line 4 belongs to no field\n")
+        );
+
+        let (code, out, _) = run_8dp(
+            &["lint", "-"],
+            "Package: a\nVersion: x:1\nDepends: b (>> 1\n",
+        );
+        assert_eq!(code, 1);
+        assert_eq!(out.lines().count(), 2, "{}", out);
+        assert!(
+            out.starts_with("<stdin>: paragraph 1 (a): Version: "),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let (code, _, err) = run_8dp(&[], "");
+        assert_eq!(code, 2);
+        assert!(err.contains("usage: 8dp get"));
+
+        assert_eq!(run_8dp(&["frobnicate", "-"], "").0, 2);
+        assert_eq!(run_8dp(&["fmt", "-", "extra"], "").0, 2);
+        let (code, _, err) = run_8dp(&["json", "-"], b"Package: \xff\n");
+        assert_eq!(code, 2);
+        assert!(
+            err.contains("Invalid UTF-8 at byte 9 in paragraph 0, field `Package`"),
+            "{}",
+            err
+        );
+
+        let (code, _, err) = run_8dp(&["json", "{}/missing"], "");
+        assert_eq!(code, 2);
+        assert!(err.contains("/missing: "), "{}", err);
+    }
+
+    #[cfg(feature = "deb")]
+    #[test]
+    fn test_gzip() {
+        let (code, out, _) = run_8dp(&["get", "{}/Packages-sorted.gz", "bash", "Version"], "");
+        assert_eq!(code, 0);
+        assert_eq!(
+            out,
+            run_8dp(&["get", "{}/Packages-sorted", "bash", "Version"], "").1
+        );
+    }
+}
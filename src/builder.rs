@@ -0,0 +1,162 @@
+use crate::{error::BuildError, Item, Paragraph};
+
+/// Build a [`Paragraph`] field by field
+///
+/// ```rust
+/// use eight_deep_parser::{parse_back, ParagraphBuilder};
+///
+/// let p = ParagraphBuilder::new()
+///     .one("Package", "zsync")
+///     .multi("Conffiles", ["/etc/zsync.conf 0123"])
+///     .build();
+///
+/// assert_eq!(parse_back(&[p]), "Package: zsync\nConffiles:\n /etc/zsync.conf 0123\n");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ParagraphBuilder {
+    paragraph: Paragraph,
+}
+
+impl ParagraphBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a one line field, replacing any earlier value of `key`
+    pub fn one(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.paragraph
+            .insert(key.into(), Item::OneLine(value.into()));
+        self
+    }
+
+    /// Set a multi line field, replacing any earlier value of `key`
+    pub fn multi<I, S>(mut self, key: impl Into<String>, lines: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.paragraph.insert(
+            key.into(),
+            Item::MultiLine(lines.into_iter().map(Into::into).collect()),
+        );
+        self
+    }
+
+    pub fn build(self) -> Paragraph {
+        self.paragraph
+    }
+}
+
+/// Like [`ParagraphBuilder`], but checking every field name as it is
+/// inserted
+///
+/// A field name must be non-empty and hold no whitespace, `:` or control
+/// characters. Values aren't checked here, [`try_parse_back`](crate::try_parse_back)
+/// does that when writing the paragraph.
+///
+/// ```rust
+/// use eight_deep_parser::{try_parse_back, TryParagraphBuilder};
+///
+/// let p = TryParagraphBuilder::new()
+///     .one("Package", "zsync")?
+///     .one("Version", "0.6.2-4")?
+///     .build();
+///
+/// assert_eq!(try_parse_back(&[p]).unwrap(), "Package: zsync\nVersion: 0.6.2-4\n");
+/// assert!(TryParagraphBuilder::new().one("Bad Key", "x").is_err());
+/// # Ok::<(), eight_deep_parser::BuildError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TryParagraphBuilder {
+    inner: ParagraphBuilder,
+}
+
+impl TryParagraphBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a one line field, see [`ParagraphBuilder::one`]
+    pub fn one(self, key: impl Into<String>, value: impl Into<String>) -> Result<Self, BuildError> {
+        let key = check_key(key.into())?;
+
+        Ok(Self {
+            inner: self.inner.one(key, value),
+        })
+    }
+
+    /// Set a multi line field, see [`ParagraphBuilder::multi`]
+    pub fn multi<I, S>(self, key: impl Into<String>, lines: I) -> Result<Self, BuildError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let key = check_key(key.into())?;
+
+        Ok(Self {
+            inner: self.inner.multi(key, lines),
+        })
+    }
+
+    pub fn build(self) -> Paragraph {
+        self.inner.build()
+    }
+}
+
+fn check_key(key: String) -> Result<String, BuildError> {
+    if key.is_empty() || key.contains(|c: char| c == ':' || c.is_whitespace() || c.is_control()) {
+        return Err(BuildError::InvalidKey { key });
+    }
+
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{try_parse_back, BuildError, Item, ParagraphBuilder, TryParagraphBuilder};
+
+    #[test]
+    fn test_builder() {
+        let p = ParagraphBuilder::new()
+            .one("Package", "a")
+            .one("Version", "1")
+            .one("Package", "b")
+            .build();
+
+        assert_eq!(p.len(), 2);
+        assert_eq!(p["Package"], Item::OneLine("b".to_string()));
+    }
+
+    #[test]
+    fn test_try_builder() {
+        let p = TryParagraphBuilder::new()
+            .one("Package", "a")
+            .and_then(|b| b.multi("Conffiles", [" /etc/a 00"]))
+            .unwrap()
+            .build();
+
+        assert_eq!(
+            try_parse_back(&[p]).unwrap(),
+            "Package: a\nConffiles:\n  /etc/a 00\n"
+        );
+
+        for key in [
+            "Pack:age",
+            "",
+            "Pack age",
+            " Package",
+            "Package\n",
+            "Pack\u{7f}",
+        ] {
+            assert_eq!(
+                TryParagraphBuilder::new().one(key, "a").unwrap_err(),
+                BuildError::InvalidKey {
+                    key: key.to_string()
+                }
+            );
+            assert!(TryParagraphBuilder::new()
+                .multi(key, Vec::<String>::new())
+                .is_err());
+        }
+    }
+}
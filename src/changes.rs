@@ -0,0 +1,223 @@
+use crate::{ChecksumEntry, Item, Paragraph, ParagraphExt, RowError};
+
+/// An upload `.changes` file
+///
+/// This is a view over the parsed paragraph, like
+/// [`SourcesEntry`](crate::SourcesEntry).
+///
+/// ```rust
+/// use eight_deep_parser::{parse_one, Changes};
+///
+/// let r = parse_one("Source: hello\nBinary: hello\nVersion: 2.10-3\nDistribution: unstable\nDescription:\n hello      - example package based on GNU hello\n").unwrap();
+/// let changes = Changes::new(r);
+///
+/// assert_eq!(changes.source_and_version(), Some(("hello", "2.10-3")));
+/// assert_eq!(
+///     changes.per_binary_descriptions().unwrap(),
+///     vec![("hello".to_string(), "example package based on GNU hello".to_string())]
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Changes {
+    paragraph: Paragraph,
+}
+
+impl Changes {
+    pub fn new(paragraph: Paragraph) -> Self {
+        Self { paragraph }
+    }
+
+    pub fn paragraph(&self) -> &Paragraph {
+        &self.paragraph
+    }
+
+    pub fn into_paragraph(self) -> Paragraph {
+        self.paragraph
+    }
+
+    /// The binary packages of the upload, from the `Binary` field
+    pub fn binaries(&self) -> Vec<&str> {
+        self.paragraph
+            .get_field("Binary")
+            .map(Item::split_whitespace_list)
+            .unwrap_or_default()
+    }
+
+    /// The target suites, from the `Distribution` field
+    pub fn distributions(&self) -> Vec<&str> {
+        self.paragraph
+            .get_field("Distribution")
+            .map(Item::split_whitespace_list)
+            .unwrap_or_default()
+    }
+
+    /// The source package name, without a version in parentheses, and the
+    /// `Version` field
+    pub fn source_and_version(&self) -> Option<(&str, &str)> {
+        let source = self.paragraph.get_one("Source")?;
+        let source = source.split_whitespace().next()?;
+
+        Some((source, self.paragraph.get_one("Version")?))
+    }
+
+    /// The `name - synopsis` lines of the `Description` field
+    ///
+    /// A line without ` - ` is an error holding the line.
+    pub fn per_binary_descriptions(&self) -> Result<Vec<(String, String)>, RowError> {
+        let item = match self.paragraph.get_field("Description") {
+            Some(item) => item,
+            None => return Ok(vec![]),
+        };
+
+        item.value_lines()
+            .filter(|x| !x.trim().is_empty())
+            .map(|row| match row.split_once(" - ") {
+                Some((name, synopsis)) if !name.trim().is_empty() => {
+                    Ok((name.trim().to_string(), synopsis.trim().to_string()))
+                }
+                _ => Err(RowError {
+                    field: "Description".to_string(),
+                    row: row.to_string(),
+                }),
+            })
+            .collect()
+    }
+
+    /// The rows of the `Files` field, with section and priority
+    pub fn files(&self) -> Result<Vec<ChecksumEntry>, RowError> {
+        self.checksums("Files")
+    }
+
+    /// The rows of a checksum field like `Checksums-Sha256`, empty if it's
+    /// missing
+    pub fn checksums(&self, field: &str) -> Result<Vec<ChecksumEntry>, RowError> {
+        match self.paragraph.get_field(field) {
+            Some(item) => ChecksumEntry::parse_field(field, item),
+            None => Ok(vec![]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_one, Changes, RowError};
+
+    // Shaped like the output of sbuild, digests made up
+    const CHANGES: &str = "Format: 1.8
+Date: Sat, 14 Oct 2023 12:31:04 +0200
+Source: zlib
+Binary: lib32z1 lib32z1-dev libx32z1 libx32z1-dev minizip libminizip1
+ libminizip-dev zlib1g zlib1g-dev zlib1g-udeb
+Architecture: source amd64
+Version: 1:1.2.13.dfsg-1
+Distribution: unstable experimental
+Urgency: medium
+Maintainer: Mark Brown <broonie@debian.org>
+Changed-By: Mark Brown <broonie@debian.org>
+Description:
+ lib32z1    - compression library - 32 bit runtime
+ minizip    - compression library - minizip tools
+ zlib1g     - compression library - runtime
+ zlib1g-dev - compression library - development
+Changes:
+ zlib (1:1.2.13.dfsg-1) unstable; urgency=medium
+ .
+   * New upstream release.
+Checksums-Sha256:
+ 76761e275be7beeb6075f86751223fae6268023af670e10ccb695b3ec531f759 2346 zlib_1.2.13.dfsg-1.dsc
+ 08b153f36d0ddddac62d9053a7b3bf7fcf89a88aa2b01c3c5e5d3cd5bbb8ae7b 19432 zlib_1.2.13.dfsg-1.debian.tar.xz
+ 85ff50ca539bcfb9f3723b6e923c69e71115e852e38babde45f3fefb1370b880 93472 zlib1g_1.2.13.dfsg-1_amd64.deb
+Files:
+ e096238df987abb83b42d7476fb8565c 2346 libs optional zlib_1.2.13.dfsg-1.dsc
+ cff5fe78a5e69984c9e5dc64146f3b1a 19432 libs optional zlib_1.2.13.dfsg-1.debian.tar.xz
+ ee3be6a9a7a4cdce16a83051486a0b6d 93472 libs optional zlib1g_1.2.13.dfsg-1_amd64.deb
+ b8ba412a412cd07af7c384ff8754b55f 192204 libdevel optional zlib1g-dev_1.2.13.dfsg-1_amd64.deb
+ b91a7ce3afd56e6d7a5fbd40d1528913 23708 utils optional minizip_1.2.13.dfsg-1_amd64.deb
+";
+
+    #[test]
+    fn test_changes() {
+        let changes = Changes::new(parse_one(CHANGES).unwrap());
+
+        assert_eq!(changes.binaries().len(), 10);
+        assert_eq!(changes.binaries()[9], "zlib1g-udeb");
+        assert_eq!(changes.distributions(), vec!["unstable", "experimental"]);
+        assert_eq!(
+            changes.source_and_version(),
+            Some(("zlib", "1:1.2.13.dfsg-1"))
+        );
+
+        let descriptions = changes.per_binary_descriptions().unwrap();
+
+        assert_eq!(descriptions.len(), 4);
+        assert_eq!(
+            descriptions[3],
+            (
+                "zlib1g-dev".to_string(),
+                "compression library - development".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_changes_files() {
+        let changes = Changes::new(parse_one(CHANGES).unwrap());
+
+        let files = changes.files().unwrap();
+
+        assert_eq!(files.len(), 5);
+        assert_eq!(files[3].size, 192204);
+        assert_eq!(files[3].section.as_deref(), Some("libdevel"));
+        assert_eq!(files[3].priority.as_deref(), Some("optional"));
+        assert_eq!(files[3].path, "zlib1g-dev_1.2.13.dfsg-1_amd64.deb");
+
+        let sha256 = changes.checksums("Checksums-Sha256").unwrap();
+
+        assert_eq!(sha256.len(), 3);
+        assert_eq!(sha256[0].section, None);
+        assert!(changes.checksums("Checksums-Sha1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_changes_files_whitespace() {
+        let s = CHANGES.replace(
+            " cff5fe78a5e69984c9e5dc64146f3b1a 19432 libs optional",
+            "   cff5fe78a5e69984c9e5dc64146f3b1a  19432\tlibs   optional ",
+        );
+        let spaced = Changes::new(parse_one(&s).unwrap());
+
+        assert_eq!(
+            spaced.files().unwrap(),
+            Changes::new(parse_one(CHANGES).unwrap()).files().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_changes_malformed() {
+        let s = CHANGES.replace(" 23708 utils optional", " 23708 utils");
+        let changes = Changes::new(parse_one(&s).unwrap());
+
+        assert_eq!(
+            changes.files(),
+            Err(RowError {
+                field: "Files".to_string(),
+                row: "b91a7ce3afd56e6d7a5fbd40d1528913 23708 utils minizip_1.2.13.dfsg-1_amd64.deb"
+                    .to_string()
+            })
+        );
+
+        let s = CHANGES.replace(
+            "minizip    - compression library - minizip tools",
+            "minizip",
+        );
+        let changes = Changes::new(parse_one(&s).unwrap());
+
+        assert_eq!(
+            changes.per_binary_descriptions(),
+            Err(RowError {
+                field: "Description".to_string(),
+                row: "minizip".to_string()
+            })
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use crate::{Item, RowError};
+
+/// A row of a checksum list field, like `Files` or `Checksums-Sha256`
+///
+/// Rows have three columns, `digest size path`, or five in the `Files`
+/// field of a `.changes` file: `digest size section priority path`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChecksumEntry {
+    pub digest: String,
+    pub size: u64,
+    pub section: Option<String>,
+    pub priority: Option<String>,
+    pub path: String,
+}
+
+impl ChecksumEntry {
+    /// Parse one row of the field `field`
+    pub fn parse_row(field: &str, row: &str) -> Result<Self, RowError> {
+        let invalid = || RowError {
+            field: field.to_string(),
+            row: row.to_string(),
+        };
+
+        let columns = row.split_whitespace().collect::<Vec<_>>();
+
+        let (digest, size, section, priority, path) = match columns[..] {
+            [digest, size, path] => (digest, size, None, None, path),
+            [digest, size, section, priority, path] => {
+                (digest, size, Some(section), Some(priority), path)
+            }
+            _ => return Err(invalid()),
+        };
+
+        if !digest.bytes().all(|x| x.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            digest: digest.to_string(),
+            size: size.parse().map_err(|_| invalid())?,
+            section: section.map(|x| x.to_string()),
+            priority: priority.map(|x| x.to_string()),
+            path: path.to_string(),
+        })
+    }
+
+    /// Parse every row of the field `field`, skipping blank lines
+    pub fn parse_field(field: &str, item: &Item) -> Result<Vec<Self>, RowError> {
+        item.value_lines()
+            .filter(|x| !x.trim().is_empty())
+            .map(|x| Self::parse_row(field, x))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ChecksumEntry, Item, RowError};
+
+    #[test]
+    fn test_checksum_entry() {
+        let r = ChecksumEntry::parse_row(
+            "SHA256",
+            "0c3d0d4b6cd6e8fce07da8e5e3f0fdc5a6b85f6d0c7be7e4f7a1a7c2c5b9d1e2 1739 main/binary-amd64/Release",
+        )
+        .unwrap();
+
+        assert_eq!(r.size, 1739);
+        assert_eq!(r.path, "main/binary-amd64/Release");
+        assert_eq!(r.section, None);
+
+        let item = Item::MultiLine(vec![
+            "e096238df987abb83b42d7476fb8565c 2346 libs optional zlib_1.2.13.dfsg-1.dsc"
+                .to_string(),
+        ]);
+        let r = ChecksumEntry::parse_field("Files", &item).unwrap();
+
+        assert_eq!(r[0].section.as_deref(), Some("libs"));
+        assert_eq!(r[0].priority.as_deref(), Some("optional"));
+    }
+
+    #[test]
+    fn test_checksum_entry_invalid() {
+        for row in [
+            "e096238df987abb83b42d7476fb8565c 2346",
+            "e096238df987abb83b42d7476fb8565c big zlib.dsc",
+            "not-a-digest 2346 zlib.dsc",
+            "e096238df987abb83b42d7476fb8565c 2346 libs zlib.dsc",
+        ] {
+            assert_eq!(
+                ChecksumEntry::parse_row("Files", row),
+                Err(RowError {
+                    field: "Files".to_string(),
+                    row: row.to_string()
+                })
+            );
+        }
+    }
+}
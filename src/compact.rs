@@ -0,0 +1,302 @@
+use std::{
+    cmp::Ordering,
+    collections::HashSet,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+use crate::{parse_interned, IndexMap, Item, Result};
+
+/// An [`Item`] taking less memory, for keeping millions of fields
+///
+/// Lines are `Box<str>` instead of `String`, without spare capacity, and
+/// the value is 24 bytes instead of the 48 of an `Item`. Convert it with
+/// [`Item::from`] to use the rest of the crate.
+///
+/// A [`CompactItem::Shared`] value is equal to a [`CompactItem::OneLine`]
+/// holding the same text.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum CompactItem {
+    OneLine(Box<str>),
+    MultiLine(Box<[Box<str>]>),
+    /// The first line, on the line of the key, followed by the others
+    Folded(Box<[Box<str>]>),
+    /// A one line value kept once for all the fields holding it, see
+    /// [`parse_multi_compact_with`]
+    Shared(Arc<str>),
+    /// Like [`Item::Streamed`]
+    Streamed,
+}
+
+/// A paragraph of [`parse_multi_compact`]
+pub type CompactParagraph = IndexMap<Arc<str>, CompactItem>;
+
+impl CompactItem {
+    /// The lines of the value, like [`Item::lines`]
+    pub fn lines(&self) -> Vec<&str> {
+        match self {
+            CompactItem::OneLine(s) if s.is_empty() => vec![],
+            CompactItem::Shared(s) if s.is_empty() => vec![],
+            CompactItem::OneLine(s) => vec![s],
+            CompactItem::Shared(s) => vec![s],
+            CompactItem::MultiLine(v) | CompactItem::Folded(v) => v.iter().map(|x| &**x).collect(),
+            CompactItem::Streamed => vec![],
+        }
+    }
+
+    /// The value of a [`CompactItem::OneLine`] or a [`CompactItem::Shared`]
+    pub fn one_line(&self) -> Option<&str> {
+        match self {
+            CompactItem::OneLine(s) => Some(s),
+            CompactItem::Shared(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// The variant, with `Shared` as `OneLine`, and the value, to compare
+    /// items
+    fn key(&self) -> (u8, &str, &[Box<str>]) {
+        match self {
+            CompactItem::OneLine(s) => (0, s, &[]),
+            CompactItem::Shared(s) => (0, s, &[]),
+            CompactItem::MultiLine(v) => (1, "", v),
+            CompactItem::Folded(v) => (2, "", v),
+            CompactItem::Streamed => (3, "", &[]),
+        }
+    }
+}
+
+impl PartialEq for CompactItem {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for CompactItem {}
+
+impl Hash for CompactItem {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state)
+    }
+}
+
+impl PartialOrd for CompactItem {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CompactItem {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+fn boxed(v: Vec<String>) -> Box<[Box<str>]> {
+    v.into_iter().map(String::into_boxed_str).collect()
+}
+
+fn unboxed(v: Box<[Box<str>]>) -> Vec<String> {
+    v.into_vec().into_iter().map(String::from).collect()
+}
+
+impl From<Item> for CompactItem {
+    fn from(item: Item) -> Self {
+        match item {
+            Item::OneLine(s) => CompactItem::OneLine(s.into_boxed_str()),
+            Item::MultiLine(v) => CompactItem::MultiLine(boxed(v)),
+            Item::Folded(s, mut v) => {
+                v.insert(0, s);
+                CompactItem::Folded(boxed(v))
+            }
+            Item::Streamed => CompactItem::Streamed,
+        }
+    }
+}
+
+impl From<CompactItem> for Item {
+    fn from(item: CompactItem) -> Self {
+        match item {
+            CompactItem::OneLine(s) => Item::OneLine(s.into()),
+            CompactItem::Shared(s) => Item::OneLine(s.to_string()),
+            CompactItem::MultiLine(v) => Item::MultiLine(unboxed(v)),
+            CompactItem::Folded(v) => {
+                let mut v = unboxed(v);
+                let s = v.remove(0);
+                Item::Folded(s, v)
+            }
+            CompactItem::Streamed => Item::Streamed,
+        }
+    }
+}
+
+/// Parse multi package into [`CompactParagraph`]s, for keeping large
+/// indices in memory
+///
+/// Field names are shared like in
+/// [`parse_multi_interned`](crate::parse_multi_interned), and values are
+/// [`CompactItem`]s.
+///
+/// ```rust
+/// use eight_deep_parser::parse_multi_compact;
+///
+/// let r = parse_multi_compact("Package: a\nVersion: 1\n").unwrap();
+///
+/// assert_eq!(r[0]["Version"].one_line(), Some("1"));
+/// ```
+pub fn parse_multi_compact(s: &str) -> Result<Vec<CompactParagraph>> {
+    parse_multi_compact_with(s, &[])
+}
+
+/// Like [`parse_multi_compact`], also sharing the values of the fields in
+/// `intern`
+///
+/// Fields like `Architecture`, `Priority` or `Section` take a few values
+/// across a whole index; each of their one line values is kept once, in an
+/// [`CompactItem::Shared`]. Field names in `intern` are matched ignoring
+/// case.
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use eight_deep_parser::{parse_multi_compact_with, CompactItem};
+///
+/// let r = parse_multi_compact_with(
+///     "Package: a\nArchitecture: amd64\n\nPackage: b\nArchitecture: amd64\n",
+///     &["Architecture"],
+/// )
+/// .unwrap();
+///
+/// match (&r[0]["Architecture"], &r[1]["Architecture"]) {
+///     (CompactItem::Shared(a), CompactItem::Shared(b)) => assert!(Arc::ptr_eq(a, b)),
+///     _ => unreachable!(),
+/// }
+/// ```
+pub fn parse_multi_compact_with(s: &str, intern: &[&str]) -> Result<Vec<CompactParagraph>> {
+    let mut pool: HashSet<Arc<str>> = HashSet::new();
+
+    parse_interned(s, |k, item| match item {
+        Item::OneLine(v) if intern.iter().any(|x| x.eq_ignore_ascii_case(k)) => {
+            let v = match pool.get(v.as_str()) {
+                Some(v) => v.clone(),
+                None => {
+                    let v: Arc<str> = v.into();
+                    pool.insert(v.clone());
+                    v
+                }
+            };
+
+            CompactItem::Shared(v)
+        }
+        item => CompactItem::from(item),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        mem::{size_of, size_of_val},
+        sync::Arc,
+    };
+
+    use crate::{
+        parse_multi, parse_multi_compact, parse_multi_compact_with, test_util::fixtures,
+        CompactItem, Item, Paragraph,
+    };
+
+    /// Bytes taken by a value, inline and on the heap
+    fn item_bytes(item: &Item) -> usize {
+        let lines = |v: &Vec<String>| {
+            v.capacity() * size_of::<String>() + v.iter().map(|x| x.capacity()).sum::<usize>()
+        };
+
+        size_of::<Item>()
+            + match item {
+                Item::OneLine(s) => s.capacity(),
+                Item::MultiLine(v) => lines(v),
+                Item::Folded(s, v) => s.capacity() + lines(v),
+                Item::Streamed => 0,
+            }
+    }
+
+    /// Like `item_bytes`, a shared value counting for its share
+    fn compact_bytes(item: &CompactItem) -> usize {
+        let lines = |v: &[Box<str>]| size_of_val(v) + v.iter().map(|x| x.len()).sum::<usize>();
+
+        size_of::<CompactItem>()
+            + match item {
+                CompactItem::OneLine(s) => s.len(),
+                // the reference counts and the text, shared
+                CompactItem::Shared(s) => {
+                    (size_of::<[usize; 2]>() + s.len()) / Arc::strong_count(s)
+                }
+                CompactItem::MultiLine(v) | CompactItem::Folded(v) => lines(v),
+                CompactItem::Streamed => 0,
+            }
+    }
+
+    #[test]
+    fn test_compact_item() {
+        let s = std::fs::read_to_string(fixtures::PACKAGES_SORTED_PATH).unwrap();
+        let full = parse_multi(&s).unwrap();
+        let compact = parse_multi_compact(&s).unwrap();
+
+        let back = compact
+            .iter()
+            .map(|p| {
+                p.iter()
+                    .map(|(k, v)| (k.to_string(), Item::from(v.clone())))
+                    .collect::<Paragraph>()
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(back, full);
+
+        for (p, c) in full.iter().zip(&compact) {
+            for ((_, a), (_, b)) in p.iter().zip(c) {
+                assert_eq!(a.lines(), b.lines());
+                assert_eq!(CompactItem::from(a.clone()), *b);
+            }
+        }
+
+        let full_bytes = full.iter().flat_map(|x| x.values()).map(item_bytes);
+        let compact_bytes = compact.iter().flat_map(|x| x.values()).map(compact_bytes);
+
+        assert_eq!(size_of::<CompactItem>() * 2, size_of::<Item>());
+        assert!(compact_bytes.sum::<usize>() * 4 < full_bytes.sum::<usize>() * 3);
+    }
+
+    #[test]
+    fn test_compact_interned_values() {
+        let s = std::fs::read_to_string(fixtures::PACKAGES_SORTED_PATH).unwrap();
+        let intern = ["architecture", "Priority", "Section", "Multi-Arch"];
+        let plain = parse_multi_compact(&s).unwrap();
+        let interned = parse_multi_compact_with(&s, &intern).unwrap();
+
+        assert_eq!(interned, plain);
+
+        let arch = |p: &crate::CompactParagraph| match &p["Architecture"] {
+            CompactItem::Shared(s) => s.clone(),
+            _ => panic!("Architecture isn't shared"),
+        };
+        let amd64 = interned
+            .iter()
+            .map(arch)
+            .filter(|x| &**x == "amd64")
+            .collect::<Vec<_>>();
+
+        assert!(amd64.len() > 1);
+        assert!(amd64.iter().all(|x| Arc::ptr_eq(x, &amd64[0])));
+        assert!(matches!(plain[0]["Architecture"], CompactItem::OneLine(_)));
+        assert!(matches!(interned[0]["Package"], CompactItem::OneLine(_)));
+
+        let bytes = |ps: &[crate::CompactParagraph]| {
+            ps.iter()
+                .flat_map(|x| x.values())
+                .map(compact_bytes)
+                .sum::<usize>()
+        };
+
+        assert!(bytes(&interned) < bytes(&plain));
+    }
+}
@@ -0,0 +1,365 @@
+use std::fmt::Display;
+
+use crate::{Paragraph, ParagraphExt};
+
+/// The `Multi-Arch` field of a binary package
+///
+/// Values dpkg doesn't know are kept in [`MultiArch::Other`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MultiArch {
+    Same,
+    Foreign,
+    Allowed,
+    No,
+    Other(String),
+}
+
+impl From<&str> for MultiArch {
+    fn from(s: &str) -> Self {
+        match s {
+            "same" => MultiArch::Same,
+            "foreign" => MultiArch::Foreign,
+            "allowed" => MultiArch::Allowed,
+            "no" => MultiArch::No,
+            _ => MultiArch::Other(s.to_string()),
+        }
+    }
+}
+
+impl Display for MultiArch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            MultiArch::Same => "same",
+            MultiArch::Foreign => "foreign",
+            MultiArch::Allowed => "allowed",
+            MultiArch::No => "no",
+            MultiArch::Other(s) => s,
+        })
+    }
+}
+
+/// The `Priority` field
+///
+/// Values outside the Debian policy list are kept in [`Priority::Other`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Priority {
+    Required,
+    Important,
+    Standard,
+    Optional,
+    /// Deprecated, treated like `optional` by dpkg
+    Extra,
+    Other(String),
+}
+
+impl From<&str> for Priority {
+    fn from(s: &str) -> Self {
+        match s {
+            "required" => Priority::Required,
+            "important" => Priority::Important,
+            "standard" => Priority::Standard,
+            "optional" => Priority::Optional,
+            "extra" => Priority::Extra,
+            _ => Priority::Other(s.to_string()),
+        }
+    }
+}
+
+impl Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Priority::Required => "required",
+            Priority::Important => "important",
+            Priority::Standard => "standard",
+            Priority::Optional => "optional",
+            Priority::Extra => "extra",
+            Priority::Other(s) => s,
+        })
+    }
+}
+
+/// The `Status` field of a package in the dpkg database, see
+/// [`ParagraphExt::status`](crate::ParagraphExt::status)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PackageStatus {
+    /// What was requested: `install`, `hold`, `deinstall`, `purge` or
+    /// `unknown`
+    pub want: String,
+    /// `ok`, or `reinstreq` if the package must be reinstalled
+    pub flag: String,
+    pub state: PackageState,
+}
+
+impl PackageStatus {
+    /// Read a `want flag state` value, `None` without exactly three words
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut words = s.split_ascii_whitespace();
+        let (want, flag, state) = (words.next()?, words.next()?, words.next()?);
+
+        if words.next().is_some() {
+            return None;
+        }
+
+        Some(Self {
+            want: want.to_string(),
+            flag: flag.to_string(),
+            state: PackageState::from(state),
+        })
+    }
+}
+
+impl Display for PackageStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.want, self.flag, self.state)
+    }
+}
+
+/// The last word of the `Status` field: how far a package got in being
+/// installed
+///
+/// States dpkg doesn't know are kept in [`PackageState::Other`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PackageState {
+    NotInstalled,
+    /// Removed, but its configuration files are left
+    ConfigFiles,
+    HalfInstalled,
+    Unpacked,
+    HalfConfigured,
+    TriggersAwaited,
+    TriggersPending,
+    Installed,
+    Other(String),
+}
+
+impl From<&str> for PackageState {
+    fn from(s: &str) -> Self {
+        match s {
+            "not-installed" => PackageState::NotInstalled,
+            "config-files" => PackageState::ConfigFiles,
+            "half-installed" => PackageState::HalfInstalled,
+            "unpacked" => PackageState::Unpacked,
+            "half-configured" => PackageState::HalfConfigured,
+            "triggers-awaited" => PackageState::TriggersAwaited,
+            "triggers-pending" => PackageState::TriggersPending,
+            "installed" => PackageState::Installed,
+            _ => PackageState::Other(s.to_string()),
+        }
+    }
+}
+
+impl Display for PackageState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            PackageState::NotInstalled => "not-installed",
+            PackageState::ConfigFiles => "config-files",
+            PackageState::HalfInstalled => "half-installed",
+            PackageState::Unpacked => "unpacked",
+            PackageState::HalfConfigured => "half-configured",
+            PackageState::TriggersAwaited => "triggers-awaited",
+            PackageState::TriggersPending => "triggers-pending",
+            PackageState::Installed => "installed",
+            PackageState::Other(s) => s,
+        })
+    }
+}
+
+/// The `yes`/`no` field `key` of `p`, matched case-insensitively, like
+/// `Essential` or `Build-Essential`
+///
+/// The value is matched ignoring case. `None` if the field is absent, and
+/// also if its value is neither `yes` nor `no`: tell the two apart with
+/// [`ParagraphExt::get_field`].
+///
+/// ```rust
+/// use eight_deep_parser::{get_bool, parse_one};
+///
+/// let r = parse_one("Package: a\nEssential: Yes\nBuild-Essential: no\nProtected: maybe\n").unwrap();
+///
+/// assert_eq!(get_bool(&r, "essential"), Some(true));
+/// assert_eq!(get_bool(&r, "Build-Essential"), Some(false));
+/// assert_eq!(get_bool(&r, "Protected"), None);
+/// assert_eq!(get_bool(&r, "Important"), None);
+/// ```
+pub fn get_bool(p: &Paragraph, key: &str) -> Option<bool> {
+    p.get_one(key).and_then(parse_yes_no)
+}
+
+/// Read a `yes`/`no` value, ignoring case
+pub(crate) fn parse_yes_no(s: &str) -> Option<bool> {
+    if s.eq_ignore_ascii_case("yes") {
+        Some(true)
+    } else if s.eq_ignore_ascii_case("no") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Split a `Section` value into archive area and section name
+pub(crate) fn split_section(s: &str) -> (Option<&str>, &str) {
+    match s.split_once('/') {
+        Some((component, section)) => (Some(component), section),
+        None => (None, s),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        get_bool, parse_multi, test_util::fixtures, MultiArch, PackageState, PackageStatus,
+        ParagraphExt, Priority,
+    };
+
+    const STATUS: &str = "Package: bash
+Essential: yes
+Status: install ok installed
+Priority: required
+Section: shells
+Architecture: amd64
+Multi-Arch: foreign
+Version: 5.2.15-2+b8
+
+Package: libcrypt1
+Protected: yes
+Status: install ok installed
+Priority: optional
+Section: libs
+Architecture: amd64
+Multi-Arch: same
+Version: 1:4.4.33-2
+
+Package: perl
+Status: install ok installed
+Priority: standard
+Section: perl
+Architecture: amd64
+Multi-Arch: allowed
+Version: 5.36.0-7+deb12u2
+
+Package: python3-pip
+Status: install ok installed
+Priority: optional
+Section: python
+Architecture: all
+Version: 23.0.1+dfsg-1
+
+Package: libdvdcss2
+Essential: No
+Priority: extra
+Section: non-free/libs
+Multi-Arch: bogus
+Version: 1.4.3-1
+
+Package: local-tool
+Priority: site-local
+Section: universe/net
+Essential: maybe
+Version: 1.0
+";
+
+    #[test]
+    fn test_multi_arch() {
+        let r = parse_multi(STATUS).unwrap();
+
+        assert_eq!(
+            r.iter().map(|x| x.multi_arch()).collect::<Vec<_>>(),
+            vec![
+                Some(MultiArch::Foreign),
+                Some(MultiArch::Same),
+                Some(MultiArch::Allowed),
+                None,
+                Some(MultiArch::Other("bogus".to_string())),
+                None,
+            ]
+        );
+        assert_eq!(MultiArch::from("bogus").to_string(), "bogus");
+    }
+
+    #[test]
+    fn test_essential_protected() {
+        let r = parse_multi(STATUS).unwrap();
+
+        assert_eq!(
+            r.iter().map(|x| x.essential()).collect::<Vec<_>>(),
+            vec![Some(true), None, None, None, Some(false), None]
+        );
+        assert_eq!(
+            r.iter().map(|x| x.protected()).collect::<Vec<_>>(),
+            vec![None, Some(true), None, None, None, None]
+        );
+    }
+
+    #[test]
+    fn test_priority() {
+        let r = parse_multi(STATUS).unwrap();
+
+        assert_eq!(
+            r.iter().map(|x| x.priority()).collect::<Vec<_>>(),
+            vec![
+                Some(Priority::Required),
+                Some(Priority::Optional),
+                Some(Priority::Standard),
+                Some(Priority::Optional),
+                Some(Priority::Extra),
+                Some(Priority::Other("site-local".to_string())),
+            ]
+        );
+        assert_eq!(Priority::from("site-local").to_string(), "site-local");
+        assert_eq!(parse_multi("Package: a\n").unwrap()[0].priority(), None);
+    }
+
+    #[test]
+    fn test_section() {
+        let r = parse_multi(STATUS).unwrap();
+
+        assert_eq!(r[0].section(), Some((None, "shells")));
+        assert_eq!(r[4].section(), Some((Some("non-free"), "libs")));
+        assert_eq!(r[5].section(), Some((Some("universe"), "net")));
+        assert_eq!(parse_multi("Package: a\n").unwrap()[0].section(), None);
+    }
+
+    #[test]
+    fn test_get_bool() {
+        let r = parse_multi(STATUS).unwrap();
+
+        assert_eq!(get_bool(&r[0], "Essential"), Some(true));
+        assert_eq!(get_bool(&r[1], "protected"), Some(true));
+        assert_eq!(get_bool(&r[4], "Essential"), Some(false));
+        assert_eq!(get_bool(&r[5], "Essential"), None);
+        assert_eq!(get_bool(&r[2], "Essential"), None);
+        assert!(r.iter().all(|x| get_bool(x, "Essential") == x.essential()));
+    }
+
+    #[test]
+    fn test_status() {
+        let r = parse_multi(fixtures::STATUS_STATES).unwrap();
+
+        assert_eq!(
+            r.iter()
+                .map(|x| x.status().unwrap().state)
+                .filter(|x| *x != PackageState::Installed)
+                .collect::<Vec<_>>(),
+            vec![
+                PackageState::ConfigFiles,
+                PackageState::HalfConfigured,
+                PackageState::TriggersPending,
+                PackageState::TriggersAwaited,
+                PackageState::HalfInstalled,
+                PackageState::Unpacked,
+                PackageState::NotInstalled,
+            ]
+        );
+
+        let status = r[9].status().unwrap();
+
+        assert_eq!(status.flag, "reinstreq");
+        assert_eq!(status.to_string(), "install reinstreq half-installed");
+        assert_eq!(
+            PackageStatus::parse("hold ok  frozen").unwrap().state,
+            PackageState::Other("frozen".to_string())
+        );
+        assert_eq!(PackageStatus::parse("install ok"), None);
+        assert_eq!(PackageStatus::parse("install ok installed x"), None);
+    }
+}
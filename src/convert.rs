@@ -0,0 +1,75 @@
+use crate::{MapError, Paragraph};
+
+/// Build a value from a parsed paragraph
+///
+/// With the `derive` feature this can be derived for structs with named
+/// fields:
+///
+/// - A field `installed_size` is read from `Installed-Size`, matched
+///   case-insensitively. `#[edp(rename = "...")]` picks another name.
+/// - By default the value must be a one line field and is converted with
+///   `FromStr`.
+/// - `#[edp(multi)]` reads a multi line field into a `Vec<String>`.
+/// - `#[edp(with = path)]` calls `path::from_item(&Item) -> Result<T, E>`
+///   where `E: Display`, and `path::to_item(&T) -> Item` for
+///   [`ToParagraph`].
+/// - `Option<T>` fields are optional, all others are required.
+///
+/// ```rust
+/// # #[cfg(feature = "derive")]
+/// # {
+/// use eight_deep_parser::{parse_one, FromParagraph, ToParagraph};
+///
+/// #[derive(FromParagraph, ToParagraph)]
+/// struct Package {
+///     package: String,
+///     installed_size: Option<u64>,
+/// }
+///
+/// let p = Package::from_paragraph(&parse_one("Package: a\nInstalled-Size: 12\n").unwrap()).unwrap();
+///
+/// assert_eq!(p.installed_size, Some(12));
+/// # }
+/// ```
+pub trait FromParagraph: Sized {
+    fn from_paragraph(p: &Paragraph) -> Result<Self, MapError>;
+}
+
+/// Turn a value into a paragraph, see [`FromParagraph`] for deriving it
+pub trait ToParagraph {
+    fn to_paragraph(&self) -> Paragraph;
+}
+
+/// Runtime support of the derive macros, not public API
+#[doc(hidden)]
+pub mod __private {
+    use std::{fmt::Display, str::FromStr};
+
+    use crate::Item;
+
+    pub fn from_one_line<T>(item: &Item) -> Result<T, String>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        match item {
+            Item::OneLine(s) => s.parse().map_err(|e: T::Err| e.to_string()),
+            _ => Err("expected a one line value".to_string()),
+        }
+    }
+
+    pub fn from_multi_line(item: &Item) -> Result<Vec<String>, String> {
+        match item {
+            Item::MultiLine(v) => Ok(v.clone()),
+            _ => Err("expected a multi line value".to_string()),
+        }
+    }
+
+    pub fn to_one_line<T: Display>(value: &T) -> Item {
+        Item::OneLine(value.to_string())
+    }
+
+    pub fn to_multi_line(value: &[String]) -> Item {
+        Item::MultiLine(value.to_vec())
+    }
+}
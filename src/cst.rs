@@ -0,0 +1,762 @@
+use std::{fmt::Display, ops::Range};
+
+use crate::{
+    error::{ParseError, Result},
+    invalid_field_name,
+    recover::plausible_start,
+    to_map, Item, NomParseItem, OrphanLines, Paragraph, ParseOptions, TrailingData,
+};
+
+/// A concrete syntax tree: every line of the input, including comments and
+/// lines that aren't deb822, so that the input can be written back byte for
+/// byte
+///
+/// Unlike [`parse_multi`](crate::parse_multi), nothing is dropped or
+/// normalized. Nodes that aren't changed keep their exact text, which makes
+/// this the base for tools that edit files written by people.
+///
+/// ```rust
+/// use eight_deep_parser::{Cst, Node};
+///
+/// let s = "# main\nPackage:  a\ndepends: b,\n  c\n\nPackage: d\n";
+/// let mut cst = Cst::parse(s);
+///
+/// assert_eq!(cst.to_string(), s);
+///
+/// for node in cst.nodes_mut() {
+///     if let Node::Field(f) = node {
+///         if f.key() == "depends" {
+///             f.set_key("Depends");
+///         }
+///     }
+/// }
+///
+/// assert_eq!(cst.to_string(), "# main\nPackage:  a\nDepends: b,\n  c\n\nPackage: d\n");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cst {
+    nodes: Vec<Node>,
+}
+
+/// A node of a [`Cst`]
+///
+/// The text of every node includes its line breaks, except when the input
+/// didn't end with one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    /// A line starting with `#`
+    Comment(String),
+    /// A line holding nothing but spaces and tabs, and a carriage return
+    /// before its line break
+    BlankLine(String),
+    /// A field and its continuation lines
+    Field(Field),
+    /// A line that is none of the above, e.g. a line without a colon, or a
+    /// continuation line not following a field
+    Unknown(String),
+}
+
+impl Node {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Node::Comment(s) | Node::BlankLine(s) | Node::Unknown(s) => s,
+            Node::Field(f) => f.as_str(),
+        }
+    }
+}
+
+/// A field of a [`Cst`]: a `key:` line and its continuation lines
+///
+/// Spans are byte ranges into [`Field::as_str`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    text: String,
+    key: Range<usize>,
+    separator: Range<usize>,
+    value_lines: Vec<Range<usize>>,
+}
+
+impl Field {
+    /// A new field written as [`parse_back`](crate::parse_back) would
+    ///
+    /// Fails if the field can't be written, see
+    /// [`try_parse_back`](crate::try_parse_back).
+    pub fn new(key: &str, value: &Item) -> std::result::Result<Self, crate::SerializeError> {
+        let mut p = Paragraph::new();
+        p.insert(key.to_string(), value.clone());
+
+        let text = crate::try_parse_back(&[p])?;
+        let mut lines = Lines::new(&text);
+
+        Ok(Self::scan(&text, &mut lines).expect("a serialized field is a field"))
+    }
+
+    /// Scan the field at the start of `lines`, if any
+    fn scan(s: &str, lines: &mut Lines) -> Option<Self> {
+        let (start, line) = lines.peek()?;
+        let colon = field_colon(line)?;
+        lines.next();
+
+        let sep_end = colon
+            + 1
+            + line[colon + 1..]
+                .bytes()
+                .take_while(|x| *x == b' ' || *x == b'\t')
+                .count();
+        let mut value_lines = Vec::new();
+        value_lines.push(sep_end..strip_eol(line).len());
+        let mut end = start + line.len();
+
+        while let Some((line_start, line)) = lines.peek() {
+            if !line.starts_with([' ', '\t']) {
+                break;
+            }
+
+            lines.next();
+            let offset = line_start - start;
+            value_lines.push(offset..offset + strip_eol(line).len());
+            end = line_start + line.len();
+        }
+
+        Some(Self {
+            text: s[start..end].to_string(),
+            key: 0..line[..colon].trim_end_matches([' ', '\t']).len(),
+            separator: colon..sep_end,
+            value_lines,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn key(&self) -> &str {
+        &self.text[self.key.clone()]
+    }
+
+    pub fn key_span(&self) -> Range<usize> {
+        self.key.clone()
+    }
+
+    /// The colon and the spaces after it
+    pub fn separator_span(&self) -> Range<usize> {
+        self.separator.clone()
+    }
+
+    /// The value on the field line, then every continuation line with its
+    /// leading whitespace, all without line breaks
+    pub fn value_lines(&self) -> impl Iterator<Item = &str> {
+        self.value_lines.iter().map(|x| &self.text[x.clone()])
+    }
+
+    pub fn value_line_spans(&self) -> &[Range<usize>] {
+        &self.value_lines
+    }
+
+    /// Rename the field, leaving the rest of its text as it is
+    pub fn set_key(&mut self, key: &str) {
+        let old = self.key.len();
+        self.text.replace_range(self.key.clone(), key);
+        self.key = 0..key.len();
+
+        let shift = |x: usize| x + key.len() - old;
+        self.separator = shift(self.separator.start)..shift(self.separator.end);
+        for i in &mut self.value_lines {
+            *i = shift(i.start)..shift(i.end);
+        }
+    }
+
+    /// Replace the value, keeping the name and the separator
+    ///
+    /// The value is written as [`parse_back`](crate::parse_back) would.
+    pub fn set_value(&mut self, value: &Item) -> std::result::Result<(), crate::SerializeError> {
+        let mut new = Field::new(self.key(), value)?;
+
+        // a value on the field line keeps the old spacing after the colon
+        let separator = &self.text[self.separator.clone()];
+        if new.separator.len() > 1 && separator.len() > 1 {
+            new.set_separator(separator);
+        }
+
+        *self = new;
+
+        Ok(())
+    }
+
+    fn set_separator(&mut self, separator: &str) {
+        let old = self.separator.len();
+        self.text.replace_range(self.separator.clone(), separator);
+        self.separator.end = self.separator.start + separator.len();
+
+        for i in &mut self.value_lines {
+            *i = i.start + separator.len() - old..i.end + separator.len() - old;
+        }
+    }
+
+    /// The field as the parser gives it, with the whitespace before the
+    /// colon in the name
+    fn to_nom(&self) -> (&[u8], (&[u8], Vec<u8>)) {
+        let mut lines = self.value_lines();
+        let first = lines.next().unwrap_or_default();
+
+        (
+            &self.text.as_bytes()[..self.separator.start],
+            (
+                first.as_bytes(),
+                lines.collect::<Vec<_>>().join("\n").into_bytes(),
+            ),
+        )
+    }
+}
+
+impl Cst {
+    /// Parse any input. Lines that aren't deb822 become [`Node::Unknown`].
+    pub fn parse(s: &str) -> Self {
+        let mut nodes = vec![];
+        let mut lines = Lines::new(s);
+
+        while let Some((_, line)) = lines.peek() {
+            if let Some(field) = Field::scan(s, &mut lines) {
+                nodes.push(Node::Field(field));
+                continue;
+            }
+
+            lines.next();
+
+            let line = line.to_string();
+            nodes.push(if line.starts_with('#') {
+                Node::Comment(line)
+            } else if is_blank_line(line.as_bytes()) {
+                Node::BlankLine(line)
+            } else {
+                Node::Unknown(line)
+            });
+        }
+
+        Self { nodes }
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// The nodes, to change, insert or remove. Nodes left alone are written
+    /// back unchanged.
+    pub fn nodes_mut(&mut self) -> &mut Vec<Node> {
+        &mut self.nodes
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = &Field> {
+        self.nodes.iter().filter_map(|x| match x {
+            Node::Field(f) => Some(f),
+            _ => None,
+        })
+    }
+
+    pub fn fields_mut(&mut self) -> impl Iterator<Item = &mut Field> {
+        self.nodes.iter_mut().filter_map(|x| match x {
+            Node::Field(f) => Some(f),
+            _ => None,
+        })
+    }
+
+    /// The simplified view, as [`parse_multi_with`](crate::parse_multi_with)
+    /// returns it
+    ///
+    /// Comments are skipped, as with [`ParseOptions::skip_comments`], and
+    /// the rules are those of the parser: a [`Node::BlankLine`] in a
+    /// paragraph continues the field before it unless it is empty, and a
+    /// [`Node::Unknown`] is an orphan continuation line, trailing data, or
+    /// else fails with [`ParseError::Malformed`]. With
+    /// [`TrailingData::Resync`] a paragraph that fails is skipped instead,
+    /// as [`parse_multi_recover`](crate::parse_multi_recover) does. Line
+    /// endings are taken as they are, so `opts.line_endings` has no effect.
+    pub fn paragraphs(&self, opts: &ParseOptions) -> Result<Vec<Paragraph>> {
+        Ok(self.paragraphs_with_comments(opts)?.0)
+    }
+
+    /// Like [`Cst::paragraphs`], also returning the comments with their
+    /// positions, to be written back by
+    /// [`parse_back_with_comments`](crate::parse_back_with_comments)
+    pub fn paragraphs_with_comments(
+        &self,
+        opts: &ParseOptions,
+    ) -> Result<(Vec<Paragraph>, Vec<Comment>)> {
+        let mut result = vec![];
+        let mut comments = vec![];
+        let mut current: NomParseItem = vec![];
+        let mut line = 1;
+        let mut offset = 0;
+        let mut seen_field = false;
+        // with TrailingData::Resync, in a paragraph that failed, or after one
+        let mut garbage = false;
+        let mut skipping = false;
+
+        for node in &self.nodes {
+            let text = node.as_str();
+            let r = match node {
+                Node::Comment(text) => {
+                    comments.push(Comment {
+                        paragraph: result.len(),
+                        field: current.len(),
+                        text: strip_eol(text).to_string(),
+                    });
+                    Ok(())
+                }
+                _ if skipping => {
+                    skipping =
+                        !matches!(node, Node::BlankLine(_)) || !ends_paragraph(text.as_bytes());
+                    Ok(())
+                }
+                Node::Field(_)
+                    if garbage && current.is_empty() && !plausible_start(text.as_bytes()) =>
+                {
+                    skipping = true;
+                    Ok(())
+                }
+                Node::Field(f)
+                    if opts.strict_field_names
+                        && invalid_field_name(&f.text[..f.separator.start]) =>
+                {
+                    Err(ParseError::Malformed { line })
+                }
+                Node::Field(f) => {
+                    seen_field = true;
+                    current.push(f.to_nom());
+                    Ok(())
+                }
+                // after a comment, which the parser doesn't see
+                Node::BlankLine(text) | Node::Unknown(text)
+                    if text.starts_with([' ', '\t']) && !current.is_empty() =>
+                {
+                    let (_, (_, multi)) = current.last_mut().expect("in a paragraph");
+                    if !multi.is_empty() {
+                        multi.push(b'\n');
+                    }
+                    multi.extend_from_slice(strip_eol(text).as_bytes());
+                    Ok(())
+                }
+                Node::BlankLine(_) if !current.is_empty() => {
+                    let p = std::mem::take(&mut current);
+                    to_map(p, opts).map(|p| {
+                        result.push(p);
+                        garbage = false;
+                    })
+                }
+                Node::BlankLine(_) => {
+                    if opts.keep_empty && opts.trailing_data != TrailingData::Resync {
+                        result.push(Paragraph::new());
+                    }
+                    Ok(())
+                }
+                Node::Unknown(text) if text.starts_with([' ', '\t']) => match opts.orphan_lines {
+                    _ if garbage => {
+                        skipping = true;
+                        Ok(())
+                    }
+                    OrphanLines::Skip => Ok(()),
+                    OrphanLines::Error => Err(ParseError::OrphanContinuation { line }),
+                },
+                Node::Unknown(_) if current.is_empty() && seen_field => match opts.trailing_data {
+                    TrailingData::Skip => break,
+                    TrailingData::Error => Err(ParseError::TrailingData { offset }),
+                    TrailingData::Resync => Err(ParseError::Malformed { line }),
+                },
+                Node::Unknown(_) => Err(ParseError::Malformed { line }),
+            };
+
+            match r {
+                // skip to the next paragraph, like parse_multi_recover
+                Err(_) if opts.trailing_data == TrailingData::Resync => {
+                    current.clear();
+                    garbage = true;
+                    skipping =
+                        !matches!(node, Node::BlankLine(_)) || !ends_paragraph(text.as_bytes());
+                }
+                r => r?,
+            }
+
+            line += text.matches('\n').count();
+            offset += text.len();
+        }
+
+        if !current.is_empty() {
+            match to_map(current, opts) {
+                Ok(p) => result.push(p),
+                Err(_) if opts.trailing_data == TrailingData::Resync => (),
+                Err(e) => return Err(e),
+            }
+        }
+
+        while result.last().map(|x| x.is_empty()).unwrap_or(false) {
+            result.pop();
+        }
+
+        Ok((result, comments))
+    }
+}
+
+/// A comment line and where it was, see [`Cst::paragraphs_with_comments`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    /// The paragraph the comment is in or before. Comments after the last
+    /// paragraph have the number of paragraphs here.
+    pub paragraph: usize,
+    /// The field of `paragraph` the comment is before, or the number of
+    /// fields if it comes after them
+    pub field: usize,
+    /// The line, starting with `#`, without line break
+    pub text: String,
+}
+
+impl Display for Cst {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for node in &self.nodes {
+            f.write_str(node.as_str())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Lines of the input with their line breaks and byte offsets
+struct Lines<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl<'a> Lines<'a> {
+    fn new(s: &'a str) -> Self {
+        Self { s, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<(usize, &'a str)> {
+        if self.pos == self.s.len() {
+            return None;
+        }
+
+        let rest = &self.s[self.pos..];
+        let len = rest.find('\n').map(|x| x + 1).unwrap_or(rest.len());
+
+        Some((self.pos, &rest[..len]))
+    }
+
+    fn next(&mut self) {
+        if let Some((_, line)) = self.peek() {
+            self.pos += line.len();
+        }
+    }
+}
+
+/// The position of the colon if `line` is a field line
+pub(crate) fn field_colon(line: &str) -> Option<usize> {
+    if line.starts_with([' ', '\t', '#']) {
+        return None;
+    }
+
+    strip_eol(line).find(':').filter(|x| *x > 0)
+}
+
+/// Whether `line` is a blank line: nothing but spaces and tabs, and a
+/// carriage return before its line break. Only an empty one ends a
+/// paragraph, see [`ends_paragraph`].
+pub(crate) fn is_blank_line(line: &[u8]) -> bool {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+
+    line.strip_suffix(b"\r")
+        .unwrap_or(line)
+        .iter()
+        .all(|x| matches!(x, b' ' | b'\t'))
+}
+
+/// Whether `line` ends the paragraph it is in, being empty: in a paragraph
+/// other blank lines continue the field before them
+pub(crate) fn ends_paragraph(line: &[u8]) -> bool {
+    matches!(line, b"\n" | b"\r\n" | b"\r" | b"")
+}
+
+fn strip_eol(line: &str) -> &str {
+    line.strip_suffix('\n').unwrap_or(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{
+        parse_back_with_comments, parse_multi_with,
+        test_util::{
+            fixtures,
+            vectors::{describe_error, VECTORS_PATH},
+        },
+        Comment, Cst, Field, Item, Node, OrphanLines, ParseError, ParseOptions, TrailingData,
+    };
+
+    const TEST: &str = "# generated, do not edit
+Package: zsync
+Version:\t0.6.2-4
+Depends: libc6 (>= 2.34),
+   zlib1g
+Description: client for rsync-like downloads
+ zsync is a file transfer program.
+ .
+  indented
+
+not a field
+Package: b
+trailing:";
+
+    #[test]
+    fn test_cst_roundtrip() {
+        let cst = Cst::parse(TEST);
+
+        assert_eq!(cst.to_string(), TEST);
+        assert_eq!(cst.nodes().len(), 9);
+        assert_eq!(
+            cst.nodes()[0],
+            Node::Comment("# generated, do not edit\n".to_string())
+        );
+        assert_eq!(cst.nodes()[6], Node::Unknown("not a field\n".to_string()));
+
+        let depends = cst.fields().nth(2).unwrap();
+
+        assert_eq!(depends.key(), "Depends");
+        assert_eq!(depends.separator_span(), 7..9);
+        assert_eq!(
+            depends.value_lines().collect::<Vec<_>>(),
+            vec!["libc6 (>= 2.34),", "   zlib1g"]
+        );
+        assert_eq!(cst.fields().nth(1).unwrap().separator_span(), 7..9);
+        assert_eq!(
+            cst.fields()
+                .last()
+                .unwrap()
+                .value_lines()
+                .collect::<Vec<_>>(),
+            vec![""]
+        );
+
+        for s in ["", "\n", "a", "\r\n", " x\n\n#", "A: b\r\n c\r\n"] {
+            assert_eq!(Cst::parse(s).to_string(), s);
+        }
+    }
+
+    #[test]
+    fn test_cst_edit() {
+        let mut cst = Cst::parse(TEST);
+
+        for f in cst.fields_mut() {
+            match f.key() {
+                "Depends" => f.set_key("Pre-Depends"),
+                "Version" => f.set_value(&Item::OneLine("0.6.2-5".to_string())).unwrap(),
+                "trailing" => f.set_key("Trailing"),
+                _ => (),
+            }
+        }
+
+        cst.nodes_mut().insert(
+            1,
+            Node::Field(Field::new("Section", &Item::OneLine("net".to_string())).unwrap()),
+        );
+
+        assert_eq!(
+            cst.to_string(),
+            TEST.replace("Package: zsync\n", "Section: net\nPackage: zsync\n")
+                .replace("Depends:", "Pre-Depends:")
+                .replace("0.6.2-4", "0.6.2-5")
+                .replace("trailing:", "Trailing:")
+        );
+
+        let f = cst.fields().find(|x| x.key() == "Pre-Depends").unwrap();
+
+        assert_eq!(&f.as_str()[f.separator_span()], ": ");
+        assert_eq!(f.value_lines().nth(1), Some("   zlib1g"));
+        assert_eq!(
+            cst.fields()
+                .find(|x| x.key() == "Version")
+                .unwrap()
+                .as_str(),
+            "Version:\t0.6.2-5\n"
+        );
+    }
+
+    #[test]
+    fn test_cst_space_before_colon() {
+        let mut cst = Cst::parse("Package : x\n");
+        let f = cst.fields_mut().next().unwrap();
+
+        assert_eq!(f.key(), "Package");
+
+        f.set_key("Source");
+
+        assert_eq!(cst.to_string(), "Source : x\n");
+    }
+
+    #[test]
+    fn test_cst_paragraphs() {
+        let opts = ParseOptions::default();
+
+        // after a blank line, like trailing data of the parser
+        assert_eq!(Cst::parse(TEST).paragraphs(&opts).unwrap().len(), 1);
+        assert!(matches!(
+            Cst::parse(TEST).paragraphs(&ParseOptions::strict_rfc()),
+            Err(ParseError::TrailingData { offset: 188 })
+        ));
+        assert!(matches!(
+            Cst::parse(&TEST.replace("\n\nnot", "\nnot")).paragraphs(&opts),
+            Err(ParseError::Malformed { line: 10 })
+        ));
+
+        let s = TEST
+            .replace("not a field\n", "\n")
+            .replace("# generated, do not edit\n", "");
+        let s = format!("\n{}\n\n", s);
+
+        for opts in [
+            opts.clone(),
+            ParseOptions {
+                keep_empty: true,
+                ..Default::default()
+            },
+        ] {
+            assert_eq!(
+                Cst::parse(&s).paragraphs(&opts).unwrap(),
+                parse_multi_with(&s, &opts).unwrap()
+            );
+        }
+
+        let with_comment = Cst::parse(TEST.replace("not a field\n", "").as_str())
+            .paragraphs(&opts)
+            .unwrap();
+
+        assert_eq!(with_comment.len(), 2);
+        assert_eq!(with_comment[0].len(), 4);
+    }
+
+    /// `s` through [`Cst::paragraphs`] and through `parse_multi_with`
+    /// skipping comments give the same paragraphs or the same error
+    #[track_caller]
+    fn assert_same_as_parser(s: &str, opts: &ParseOptions) {
+        let cst = Cst::parse(s).paragraphs(opts);
+        let multi = parse_multi_with(
+            s,
+            &ParseOptions {
+                skip_comments: true,
+                ..opts.clone()
+            },
+        );
+
+        match (cst, multi) {
+            (Ok(a), Ok(b)) => assert_eq!(a, b, "{:?} {:?}", s, opts),
+            // lines are counted without the comments by the parser
+            (Err(a), Err(b)) if s.contains('#') => {
+                assert_eq!(a.kind(), b.kind(), "{:?} {:?}", s, opts)
+            }
+            (Err(a), Err(b)) => {
+                assert_eq!(describe_error(&a), describe_error(&b), "{:?} {:?}", s, opts)
+            }
+            (a, b) => panic!("{:?} {:?}: {:?} != {:?}", s, opts, a, b),
+        }
+    }
+
+    #[test]
+    fn test_cst_paragraphs_parser_rules() {
+        let mut corpus = [
+            fixtures::STATUS,
+            fixtures::STATUS_STATES,
+            fixtures::PACKAGES,
+            fixtures::SOURCES,
+            fixtures::SOURCES_TEXLIVE,
+            fixtures::DEB822_SOURCES,
+            fixtures::RELEASE,
+            TEST,
+            // whitespace-only lines continue a field, only an empty line
+            // ends a paragraph
+            "A: a\n b\n \n c\nB: x\n",
+            "Package: a\n \t\nVersion: 1\n\n  \n\nPackage: b\n",
+            "Package: a\r\nVersion: 1\r\n\r\nPackage: b\r\n",
+            // lines without a colon
+            "A: 1\nno colon line\nC: 2\n",
+            "garbage\n\nPackage: x\n",
+            "Package: a\n\nbroken\n\nPackage: b\n",
+            ": a\n",
+            // orphan continuation lines
+            " orphan\nPackage: a\n",
+            "Package: a\n\n orphan\nVersion: 1\n",
+            // comments between continuation lines
+            "Package: a\nDepends: b,\n# c\n d\n# e\n \nVersion: 1\n",
+            "Package: a\n# c\n\n d\n",
+            "Package : a\nTwo Words: b\n",
+            // garbage to resynchronize after
+            "Package: a\n\nPackage: b\nVersion=1\n\n-x: y\n\nbroken\n\n  \nPackage: c\n",
+            "Package: a\n\n orphan\n\nbroken\n x\n\n# c\nPackage: b\nA: 1\nA: 2\n",
+        ]
+        .map(str::to_string)
+        .to_vec();
+
+        for entry in fs::read_dir(VECTORS_PATH).unwrap() {
+            let path = entry.unwrap().path();
+
+            if path.extension().is_some_and(|x| x == "in") {
+                if let Ok(s) = fs::read_to_string(path) {
+                    corpus.push(s);
+                }
+            }
+        }
+
+        for opts in [
+            ParseOptions::default(),
+            ParseOptions {
+                keep_empty: true,
+                ..Default::default()
+            },
+            ParseOptions {
+                trailing_data: TrailingData::Error,
+                orphan_lines: OrphanLines::Error,
+                ..Default::default()
+            },
+            ParseOptions::strict_rfc(),
+            ParseOptions {
+                trailing_data: TrailingData::Resync,
+                ..Default::default()
+            },
+        ] {
+            for s in &corpus {
+                assert_same_as_parser(s, &opts);
+            }
+        }
+    }
+
+    #[test]
+    fn test_cst_comment_between_fields() {
+        let s = "Package: a\nDepends: b,\n c\n# note\nVersion: 1\n";
+        let opts = ParseOptions::default();
+        let (r, comments) = Cst::parse(s).paragraphs_with_comments(&opts).unwrap();
+
+        assert_eq!(r.len(), 1);
+        assert_eq!(
+            r[0].keys().collect::<Vec<_>>(),
+            ["Package", "Depends", "Version"]
+        );
+        assert_eq!(
+            r,
+            parse_multi_with(
+                s,
+                &ParseOptions {
+                    skip_comments: true,
+                    ..Default::default()
+                }
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            comments,
+            [Comment {
+                paragraph: 0,
+                field: 2,
+                text: "# note".to_string(),
+            }]
+        );
+        assert_eq!(parse_back_with_comments(&r, &comments), s);
+    }
+}
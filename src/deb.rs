@@ -0,0 +1,547 @@
+//! Reading the control files of a binary package (`.deb`)
+//!
+//! A `.deb` is an `ar` archive of `debian-binary`, `control.tar` and
+//! `data.tar`, the tarballs usually compressed. Only `control.tar` is
+//! unpacked, and the archive isn't read past it.
+
+use std::{
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use crate::{error::DebError, inflate, parse_one, IndexMap, Paragraph, ParagraphExt};
+
+/// The control files of a `.deb`, see [`read_deb_control_files`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebControl {
+    /// The `control` file: the paragraph `dpkg` adds to its status
+    pub control: Paragraph,
+    /// The lines of `conffiles`: absolute paths of configuration files,
+    /// possibly after a flag like `remove-on-upgrade`
+    pub conffiles: Vec<String>,
+    /// The MD5 digests of `md5sums`, by path relative to `/`
+    pub md5sums: IndexMap<String, String>,
+    /// Every other file of `control.tar` by name, e.g. the maintainer
+    /// scripts
+    pub other: IndexMap<String, Vec<u8>>,
+}
+
+/// The `control` file of the `.deb` read from `r`
+///
+/// ```rust
+/// use eight_deep_parser::{read_deb_control, ParagraphExt};
+/// use std::fs::File;
+///
+/// # let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/deb/eightdp-test-gzip.deb");
+/// let r = read_deb_control(File::open(path).unwrap()).unwrap();
+///
+/// assert_eq!(r.get_one("Package"), Some("eightdp-test"));
+/// ```
+pub fn read_deb_control<R: Read>(r: R) -> Result<Paragraph, DebError> {
+    Ok(read_deb_control_files(r)?.control)
+}
+
+/// Every file in the `control.tar` of the `.deb` read from `r`, the known
+/// ones parsed
+///
+/// `control.tar` may be anywhere after `debian-binary`, and may be
+/// uncompressed or compressed with gzip. `control.tar.xz` and
+/// `control.tar.zst` are [`DebError::Unsupported`].
+pub fn read_deb_control_files<R: Read>(mut r: R) -> Result<DebControl, DebError> {
+    let mut magic = [0; 8];
+    if read_full(&mut r, &mut magic)? != 8 || &magic != b"!<arch>\n" {
+        return Err(DebError::NotDeb);
+    }
+
+    let mut first = true;
+
+    loop {
+        let (name, size) = match next_member(&mut r)? {
+            Some(x) => x,
+            None => {
+                return Err(DebError::Missing {
+                    name: "control.tar".to_string(),
+                })
+            }
+        };
+
+        if first {
+            if name != "debian-binary" {
+                return Err(DebError::NotDeb);
+            }
+
+            first = false;
+        }
+
+        if name.starts_with("control.tar") {
+            let data = read_member(&mut r, &name, size)?;
+            let tar = match name.as_str() {
+                "control.tar" => data,
+                "control.tar.gz" => inflate::gunzip(&data).map_err(|reason| DebError::Corrupt {
+                    member: name.clone(),
+                    reason: reason.to_string(),
+                })?,
+                _ => return Err(DebError::Unsupported { member: name }),
+            };
+
+            return control_files(untar(&tar, &name)?);
+        }
+
+        skip_member(&mut r, &name, size)?;
+    }
+}
+
+/// A `Packages` index of every `.deb` below `root`, like `apt-ftparchive
+/// packages` writes
+///
+/// Each paragraph is the `control` file of a package, with `Filename`,
+/// `Size` and the digests of
+/// [`PackagesIndexOptions::digests`](crate::PackagesIndexOptions::digests)
+/// added before `Description`, or at the end without one. Paragraphs are
+/// sorted by `Package`, then `Version`, then `Filename`, whatever order the
+/// files are found in.
+///
+/// ```rust,no_run
+/// use eight_deep_parser::{generate_packages_index, parse_back, PackagesIndexOptions};
+/// use std::path::Path;
+///
+/// let opts = PackagesIndexOptions {
+///     prefix: "pool/".to_string(),
+///     ..Default::default()
+/// };
+/// let r = generate_packages_index(Path::new("repo/pool"), &opts).unwrap();
+///
+/// std::fs::write("repo/Packages", parse_back(&r)).unwrap();
+/// ```
+pub fn generate_packages_index(
+    root: &Path,
+    opts: &crate::PackagesIndexOptions,
+) -> Result<Vec<Paragraph>, DebError> {
+    let mut files = vec![];
+    find_debs(root, &mut files)?;
+
+    let mut result = vec![];
+
+    for path in files {
+        let in_file = |source| DebError::File {
+            path: path.display().to_string(),
+            source: Box::new(source),
+        };
+
+        let data = std::fs::read(&path).map_err(|e| in_file(e.into()))?;
+        let control = read_deb_control(&data[..]).map_err(in_file)?;
+
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        let filename = relative
+            .components()
+            .map(|x| x.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let mut fields = vec![
+            ("Filename", format!("{}{}", opts.prefix, filename)),
+            ("Size", data.len().to_string()),
+        ];
+        for algorithm in &opts.digests {
+            let field = match algorithm {
+                crate::Algorithm::Md5 => "MD5sum",
+                x => x.field(),
+            };
+
+            fields.push((field, algorithm.digest(&data)));
+        }
+
+        let mut paragraph = Paragraph::new();
+        let mut added = false;
+
+        for (key, value) in control {
+            if key.eq_ignore_ascii_case("Description") && !added {
+                add_fields(&mut paragraph, &mut fields);
+                added = true;
+            }
+
+            paragraph.insert(key, value);
+        }
+        add_fields(&mut paragraph, &mut fields);
+
+        result.push(paragraph);
+    }
+
+    let sort_key = |p: &Paragraph| {
+        let get = |key| p.get_one(key).unwrap_or_default().to_string();
+        let version = get("Version");
+
+        (
+            get("Package"),
+            version.parse::<crate::Version>().ok(),
+            version,
+            get("Filename"),
+        )
+    };
+    result.sort_by_cached_key(sort_key);
+
+    Ok(result)
+}
+
+fn add_fields(p: &mut Paragraph, fields: &mut Vec<(&str, String)>) {
+    for (key, value) in fields.drain(..) {
+        p.insert(key.to_string(), crate::Item::OneLine(value));
+    }
+}
+
+/// Every `*.deb` file below `dir`, in path order
+fn find_debs(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries = std::fs::read_dir(dir)?
+        .map(|x| x.map(|x| x.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            find_debs(&path, files)?;
+        } else if path.extension().is_some_and(|x| x == "deb") {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn control_files(files: Vec<(String, &[u8])>) -> Result<DebControl, DebError> {
+    let mut control = None;
+    let mut conffiles = vec![];
+    let mut md5sums = IndexMap::new();
+    let mut other = IndexMap::new();
+
+    for (name, data) in files {
+        let text = || {
+            std::str::from_utf8(data).map_err(|_| DebError::Corrupt {
+                member: name.clone(),
+                reason: "invalid UTF-8".to_string(),
+            })
+        };
+
+        match name.as_str() {
+            "control" => control = Some(parse_one(text()?)?),
+            "conffiles" => {
+                conffiles = text()?
+                    .lines()
+                    .map(|x| x.trim())
+                    .filter(|x| !x.is_empty())
+                    .map(|x| x.to_string())
+                    .collect()
+            }
+            "md5sums" => {
+                for line in text()?.lines().filter(|x| !x.trim().is_empty()) {
+                    let (digest, path) =
+                        line.split_once(char::is_whitespace)
+                            .ok_or_else(|| DebError::Corrupt {
+                                member: name.clone(),
+                                reason: format!("malformed line `{}`", line),
+                            })?;
+
+                    md5sums.insert(path.trim_start().to_string(), digest.to_string());
+                }
+            }
+            _ => {
+                other.insert(name, data.to_vec());
+            }
+        }
+    }
+
+    Ok(DebControl {
+        control: control.ok_or_else(|| DebError::Missing {
+            name: "control".to_string(),
+        })?,
+        conffiles,
+        md5sums,
+        other,
+    })
+}
+
+/// Read as much of `buf` as `r` has, returning how much that was
+fn read_full<R: Read>(r: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+
+    while len < buf.len() {
+        match r.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(len)
+}
+
+/// Name and size of the next `ar` member, `None` at the end
+fn next_member<R: Read>(r: &mut R) -> Result<Option<(String, u64)>, DebError> {
+    let mut header = [0u8; 60];
+
+    match read_full(r, &mut header)? {
+        0 => return Ok(None),
+        60 => (),
+        _ => {
+            return Err(DebError::Truncated {
+                member: "ar header".to_string(),
+            })
+        }
+    }
+
+    let field = |range: std::ops::Range<usize>| String::from_utf8_lossy(&header[range]);
+    let name = field(0..16).trim_end().trim_end_matches('/').to_string();
+
+    if &header[58..60] != b"`\n" {
+        return Err(DebError::Corrupt {
+            member: name,
+            reason: "bad ar header".to_string(),
+        });
+    }
+
+    let size = field(48..58)
+        .trim_end()
+        .parse()
+        .map_err(|_| DebError::Corrupt {
+            member: name.clone(),
+            reason: "bad ar member size".to_string(),
+        })?;
+
+    Ok(Some((name, size)))
+}
+
+/// The data of an `ar` member and its padding
+fn read_member<R: Read>(r: &mut R, name: &str, size: u64) -> Result<Vec<u8>, DebError> {
+    let mut data = vec![];
+    r.by_ref().take(size).read_to_end(&mut data)?;
+
+    if (data.len() as u64) < size {
+        return Err(DebError::Truncated {
+            member: name.to_string(),
+        });
+    }
+
+    // members start at even offsets; the archive may end without padding
+    if size % 2 == 1 {
+        read_full(r, &mut [0])?;
+    }
+
+    Ok(data)
+}
+
+fn skip_member<R: Read>(r: &mut R, name: &str, size: u64) -> Result<(), DebError> {
+    if io::copy(&mut r.by_ref().take(size), &mut io::sink())? < size {
+        return Err(DebError::Truncated {
+            member: name.to_string(),
+        });
+    }
+
+    if size % 2 == 1 {
+        read_full(r, &mut [0])?;
+    }
+
+    Ok(())
+}
+
+/// The regular files of a tarball, named without a leading `./`
+fn untar<'a>(data: &'a [u8], member: &str) -> Result<Vec<(String, &'a [u8])>, DebError> {
+    let truncated = || DebError::Truncated {
+        member: member.to_string(),
+    };
+    let corrupt = |reason: &str| DebError::Corrupt {
+        member: member.to_string(),
+        reason: reason.to_string(),
+    };
+    let cstr = |x: &'a [u8]| {
+        let end = x.iter().position(|x| *x == 0).unwrap_or(x.len());
+
+        String::from_utf8_lossy(&x[..end]).into_owned()
+    };
+
+    let mut result = vec![];
+    let mut pos = 0;
+    let mut long_name = None;
+
+    loop {
+        let header = match data.get(pos..pos + 512) {
+            Some(x) => x,
+            // the end-of-archive blocks are sometimes left out
+            None if pos == data.len() => break,
+            None => return Err(truncated()),
+        };
+
+        if header.iter().all(|x| *x == 0) {
+            break;
+        }
+
+        // the checksum is computed with its own field as spaces
+        let sum: u32 = header
+            .iter()
+            .enumerate()
+            .map(|(i, x)| if (148..156).contains(&i) { b' ' } else { *x } as u32)
+            .sum();
+        if octal(&header[148..156]) != Some(sum as u64) {
+            return Err(corrupt("bad tar header checksum"));
+        }
+
+        let size = octal(&header[124..136]).ok_or_else(|| corrupt("bad tar entry size"))? as usize;
+        let content = data
+            .get(pos + 512..pos + 512 + size)
+            .ok_or_else(truncated)?;
+        pos += 512 + size.div_ceil(512) * 512;
+
+        let mut name = cstr(&header[..100]);
+        if &header[257..262] == b"ustar" && header[345] != 0 {
+            name = format!("{}/{}", cstr(&header[345..500]), name);
+        }
+        if let Some(long) = long_name.take() {
+            name = long;
+        }
+
+        match header[156] {
+            b'0' | 0 => {
+                let name = name.trim_start_matches("./").to_string();
+                result.push((name, content));
+            }
+            // GNU long name of the next entry
+            b'L' => long_name = Some(cstr(content)),
+            _ => (),
+        }
+    }
+
+    Ok(result)
+}
+
+/// A tar number field: octal digits, padded by spaces or NULs
+fn octal(field: &[u8]) -> Option<u64> {
+    let s = std::str::from_utf8(field).ok()?;
+    let s = s.trim_matches(|x| x == ' ' || x == '\0');
+
+    if s.is_empty() {
+        return Some(0);
+    }
+
+    u64::from_str_radix(s, 8).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_packages_index, read_deb_control, read_deb_control_files};
+    use crate::{parse_back, DebError, PackagesIndexOptions, ParagraphExt};
+
+    const GZIP: &[u8] = include_bytes!("../tests/fixtures/deb/eightdp-test-gzip.deb");
+    const NONE: &[u8] = include_bytes!("../tests/fixtures/deb/eightdp-test-none.deb");
+    const DATA_FIRST: &[u8] = include_bytes!("../tests/fixtures/deb/eightdp-test-data-first.deb");
+    const XZ: &[u8] = include_bytes!("../tests/fixtures/deb/eightdp-test-xz.deb");
+    const RC: &[u8] = include_bytes!("../tests/fixtures/deb/eightdp-test-rc.deb");
+    const ZSTD: &[u8] = include_bytes!("../tests/fixtures/deb/eightdp-test-zstd.deb");
+
+    #[test]
+    fn test_read_deb_control_files() {
+        let r = read_deb_control_files(GZIP).unwrap();
+
+        assert_eq!(r.control.get_one("Package"), Some("eightdp-test"));
+        assert_eq!(r.control.get_one("Depends"), Some("libc6 (>= 2.34)"));
+        assert_eq!(
+            r.control.description().unwrap().synopsis,
+            "test package for 8dparser"
+        );
+        assert_eq!(r.conffiles, ["/etc/eightdp-test.conf"]);
+        assert_eq!(
+            r.md5sums["usr/share/doc/eightdp-test/README"],
+            "a9855288da026d5c296719259f33d1c3"
+        );
+        assert_eq!(r.md5sums.len(), 1);
+        assert_eq!(r.other.keys().collect::<Vec<_>>(), ["postinst"]);
+        assert!(r.other["postinst"].starts_with(b"#!/bin/sh\n"));
+
+        assert_eq!(read_deb_control_files(NONE).unwrap(), r);
+        assert_eq!(read_deb_control_files(DATA_FIRST).unwrap(), r);
+        assert_eq!(read_deb_control(GZIP).unwrap(), r.control);
+    }
+
+    #[test]
+    fn test_read_deb_control_unsupported() {
+        for (data, member) in [(XZ, "control.tar.xz"), (ZSTD, "control.tar.zst")] {
+            let r = read_deb_control(data);
+
+            assert!(
+                matches!(&r, Err(DebError::Unsupported { member: x }) if x == member),
+                "{:?}",
+                r
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_deb_control_truncated() {
+        for len in [10, 70, 140, 300, GZIP.len() / 2] {
+            let r = read_deb_control(&GZIP[..len]);
+
+            assert!(
+                matches!(r, Err(DebError::Truncated { .. })),
+                "{} {:?}",
+                len,
+                r
+            );
+        }
+
+        // cut inside `data.tar.gz`, before `control.tar.gz`
+        let r = read_deb_control(&DATA_FIRST[..DATA_FIRST.len() - 600]);
+
+        assert!(matches!(&r, Err(DebError::Truncated { member }) if member == "data.tar.gz"));
+
+        // the control files were read before the cut
+        assert!(read_deb_control(&GZIP[..GZIP.len() - 100]).is_ok());
+    }
+
+    #[test]
+    fn test_read_deb_control_not_deb() {
+        for data in [&b""[..], b"!<arch>", b"Package: a\n", &GZIP[..8]] {
+            let r = read_deb_control(data);
+
+            assert!(
+                matches!(r, Err(DebError::NotDeb | DebError::Missing { .. })),
+                "{:?}",
+                r
+            );
+        }
+
+        let mut swapped = GZIP.to_vec();
+        swapped[8..21].copy_from_slice(b"something-els");
+
+        assert!(matches!(
+            read_deb_control(&swapped[..]),
+            Err(DebError::NotDeb)
+        ));
+    }
+
+    #[test]
+    fn test_generate_packages_index() {
+        let root = std::env::temp_dir().join(format!("8dp-pool-{}", std::process::id()));
+        let dir = root.join("main/e");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("eightdp-test-gzip.deb"), GZIP).unwrap();
+        std::fs::write(dir.join("eightdp-test-rc.deb"), RC).unwrap();
+        std::fs::write(dir.join("README"), "not a package").unwrap();
+
+        let opts = PackagesIndexOptions {
+            prefix: "pool/".to_string(),
+            ..Default::default()
+        };
+        let r = generate_packages_index(&root, &opts);
+        std::fs::write(dir.join("broken.deb"), &GZIP[..100]).unwrap();
+        let broken = generate_packages_index(&root, &opts);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            parse_back(&r.unwrap()),
+            include_str!("../tests/fixtures/deb/Packages")
+        );
+        assert!(matches!(
+            broken,
+            Err(DebError::File { path, source }) if path.ends_with("broken.deb")
+                && matches!(*source, DebError::Truncated { .. })
+        ));
+    }
+}
@@ -0,0 +1,737 @@
+use std::{
+    fmt,
+    io::{ErrorKind, Read},
+};
+
+use crate::{
+    parse_one,
+    view::{fields, LineStream},
+    Item, Paragraph, ParseError, ReadLimits, Result, StreamedFields,
+};
+
+/// A parser fed with chunks of input as they arrive, e.g. while
+/// downloading, giving every paragraph once the blank line after it is in
+///
+/// The input can be split anywhere, even inside a line or a UTF-8
+/// character. Paragraphs are parsed by [`parse_one`]; blank lines and
+/// comment lines between them are skipped. An async reader drives it the
+/// same way as this sync one: push what was read, then take the paragraphs.
+///
+/// ```rust
+/// use eight_deep_parser::{ParagraphDecoder, ParagraphExt};
+///
+/// let mut decoder = ParagraphDecoder::new();
+/// let mut r = vec![];
+///
+/// for chunk in ["Package: a\nVers", "ion: 1\n\nPack", "age: b\n"] {
+///     decoder.push(chunk.as_bytes());
+///
+///     while let Some(p) = decoder.next_paragraph() {
+///         r.push(p.unwrap());
+///     }
+/// }
+/// r.extend(decoder.finish().unwrap());
+///
+/// assert_eq!(r[0].get_one("Version"), Some("1"));
+/// assert_eq!(r[1].get_one("Package"), Some("b"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ParagraphDecoder {
+    buf: Vec<u8>,
+    /// Where the input not given back yet starts in `buf`, the bytes
+    /// before it being removed once they are half of it
+    start: usize,
+    /// Where the first line not looked at yet starts
+    scanned: usize,
+    /// Where to go on looking for the end of that line, so that a long
+    /// line pushed in many chunks is searched once
+    searched: usize,
+    /// Whether a line of the current paragraph was seen
+    in_paragraph: bool,
+    /// Bytes given back before `start`, for error offsets
+    consumed: usize,
+    /// Paragraphs given so far
+    paragraphs: usize,
+}
+
+impl ParagraphDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the next chunk of input
+    pub fn push(&mut self, chunk: &[u8]) {
+        if self.start > self.buf.len() / 2 {
+            self.buf.drain(..self.start);
+            self.scanned -= self.start;
+            self.searched -= self.start;
+            self.start = 0;
+        }
+
+        self.buf.extend_from_slice(chunk);
+    }
+
+    /// Bytes pushed but not given back as paragraphs yet: once
+    /// [`next_paragraph`](ParagraphDecoder::next_paragraph) gave `None`,
+    /// those of the paragraph being read
+    pub fn buffered(&self) -> usize {
+        self.rest().len()
+    }
+
+    /// The input not given back yet
+    fn rest(&self) -> &[u8] {
+        &self.buf[self.start..]
+    }
+
+    /// The next complete paragraph, `None` until more input is pushed
+    ///
+    /// A paragraph that doesn't parse gives its error, and the decoder goes
+    /// on with the paragraph after it.
+    pub fn next_paragraph(&mut self) -> Option<Result<Paragraph>> {
+        let (text, offset, paragraph) = self.next_text()?;
+
+        Some(parse(&text, offset, paragraph))
+    }
+
+    /// The text of the next complete paragraph, with where it starts in the
+    /// input and its index
+    fn next_text(&mut self) -> Option<(Vec<u8>, usize, usize)> {
+        while let Some(len) = self.buf[self.searched..].iter().position(|&x| x == b'\n') {
+            let end = self.searched + len + 1;
+            let line = &self.buf[self.scanned..end - 1];
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+
+            // like the parser, a line of whitespace continues a field, only
+            // an empty line ends a paragraph
+            let blank = match self.in_paragraph {
+                true => line.is_empty(),
+                false => line.iter().all(|x| matches!(x, b' ' | b'\t')),
+            };
+
+            self.scanned = end;
+            self.searched = end;
+
+            if !blank && (self.in_paragraph || !line.starts_with(b"#")) {
+                self.in_paragraph = true;
+                continue;
+            }
+
+            let start = std::mem::replace(&mut self.start, end);
+            let offset = self.consumed;
+            self.consumed += end - start;
+
+            if !self.in_paragraph {
+                continue;
+            }
+
+            let text = self.buf[start..end].to_vec();
+            self.in_paragraph = false;
+            self.paragraphs += 1;
+
+            return Some((text, offset, self.paragraphs - 1));
+        }
+
+        self.searched = self.buf.len();
+
+        None
+    }
+
+    /// The paragraph at the end of the input, which needs no blank line
+    /// after it
+    ///
+    /// Call it once everything was pushed and [`next_paragraph`] gave
+    /// `None`; paragraphs left in the decoder before that are a
+    /// [`ParseError::TrailingData`] error.
+    ///
+    /// [`next_paragraph`]: ParagraphDecoder::next_paragraph
+    pub fn finish(self) -> Result<Option<Paragraph>> {
+        match parse(self.rest(), self.consumed, self.paragraphs) {
+            Err(ParseError::Empty) => Ok(None),
+            r => r.map(Some),
+        }
+    }
+}
+
+/// The paragraphs of a reader, read as they are needed, with
+/// [`ReadLimits`] for input that can't be trusted
+///
+/// Input is pushed to a [`ParagraphDecoder`]. With limits, no more than one
+/// byte over a limit is buffered: a server sending a never ending paragraph
+/// gives [`ParseError::LimitExceeded`], after which the reader ends.
+///
+/// Errors reading other than [`ErrorKind::Interrupted`] are given as
+/// [`ParseError::Io`], e.g. `TimedOut` from a socket with a read timeout or
+/// `WouldBlock` from a non-blocking one. Nothing read is lost: calling
+/// `next` again reads on.
+///
+/// ```rust
+/// use eight_deep_parser::{ParagraphReader, ParseError, ReadLimits};
+///
+/// let limits = ReadLimits {
+///     max_paragraph_bytes: 16,
+///     ..Default::default()
+/// };
+/// let mut r = ParagraphReader::with_limits(&b"Package: a\n\nPackage: long-name\n"[..], limits);
+///
+/// assert!(r.next().unwrap().is_ok());
+/// assert!(matches!(r.next(), Some(Err(ParseError::LimitExceeded { limit: 16, .. }))));
+/// assert!(r.next().is_none());
+/// ```
+pub struct ParagraphReader<R, F = fn(&str, LineStream<'_>)> {
+    reader: R,
+    decoder: ParagraphDecoder,
+    limits: ReadLimits,
+    /// Bytes read so far
+    read: usize,
+    done: bool,
+    on_field: Option<OnField<F>>,
+}
+
+/// The fields handed to a callback by [`ParagraphReader::on_field`]
+struct OnField<F> {
+    keys: Vec<String>,
+    fields: StreamedFields,
+    f: F,
+}
+
+impl<R: Read> ParagraphReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_limits(reader, ReadLimits::default())
+    }
+
+    pub fn with_limits(reader: R, limits: ReadLimits) -> Self {
+        Self {
+            reader,
+            decoder: ParagraphDecoder::new(),
+            limits,
+            read: 0,
+            done: false,
+            on_field: None,
+        }
+    }
+}
+
+impl<R: Read, F: FnMut(&str, LineStream<'_>)> ParagraphReader<R, F> {
+    /// Hand the fields named `keys`, ignoring case, to `f` instead of
+    /// keeping them in the paragraphs
+    ///
+    /// `f` gets the field name as written and its lines, borrowed from the
+    /// text of the paragraph like [`ParagraphView::field_lines`] does, so a
+    /// `Checksums-Sha256` of tens of thousands of lines can be checked or
+    /// counted without a `String` for each. It is called for the paragraphs
+    /// that parse, in the order of their fields, before the paragraph is
+    /// given. `fields` tells whether the paragraph keeps the field as an
+    /// [`Item::Streamed`] or leaves it out.
+    ///
+    /// The text of a paragraph is still read whole, under the
+    /// [`ReadLimits`] of the reader.
+    ///
+    /// ```rust
+    /// use eight_deep_parser::{Item, ParagraphReader, StreamedFields};
+    ///
+    /// let s = "Package: a\nFiles:\n 1 2 a.dsc\n 3 4 a.tar.xz\nFormat: 3.0\n";
+    /// let mut files = 0;
+    /// let r = ParagraphReader::new(s.as_bytes())
+    ///     .on_field(&["Files"], StreamedFields::Mark, |_, lines| files += lines.count())
+    ///     .collect::<Result<Vec<_>, _>>()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(files, 2);
+    /// assert_eq!(r[0]["Files"], Item::Streamed);
+    /// assert_eq!(r[0].get_index_of("Files"), Some(1));
+    /// ```
+    ///
+    /// [`ParagraphView::field_lines`]: crate::ParagraphView::field_lines
+    pub fn on_field<G>(self, keys: &[&str], fields: StreamedFields, f: G) -> ParagraphReader<R, G>
+    where
+        G: FnMut(&str, LineStream<'_>),
+    {
+        ParagraphReader {
+            reader: self.reader,
+            decoder: self.decoder,
+            limits: self.limits,
+            read: self.read,
+            done: self.done,
+            on_field: Some(OnField {
+                keys: keys.iter().map(|x| x.to_string()).collect(),
+                fields,
+                f,
+            }),
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn exceeded(&mut self, limit: usize, paragraph: bool) -> Option<Result<Paragraph>> {
+        self.done = true;
+
+        Some(Err(ParseError::LimitExceeded { limit, paragraph }))
+    }
+
+    fn parse(&mut self, text: &[u8], offset: usize, paragraph: usize) -> Result<Paragraph> {
+        match &mut self.on_field {
+            Some(on_field) => {
+                let s = std::str::from_utf8(text)
+                    .map_err(|e| ParseError::invalid_utf8(text, &e, offset, Some(paragraph)))?;
+
+                on_field.parse(s)
+            }
+            None => parse(text, offset, paragraph),
+        }
+    }
+}
+
+impl<F: FnMut(&str, LineStream<'_>)> OnField<F> {
+    /// Parse `s` without the fields to stream, then hand them to the
+    /// callback
+    fn parse(&mut self, s: &str) -> Result<Paragraph> {
+        let streamed = fields(s)
+            .filter(|(k, _)| self.keys.iter().any(|x| x.eq_ignore_ascii_case(k)))
+            .collect::<Vec<_>>();
+
+        if streamed.is_empty() {
+            return parse_one(s);
+        }
+
+        let mut rest = String::with_capacity(s.len());
+        let mut last = 0;
+
+        for (_, span) in &streamed {
+            rest += &s[last..span.start];
+            last = span.end;
+
+            if self.fields == StreamedFields::Mark {
+                // the field line up to the colon, for the field to keep its
+                // place
+                let colon = s[span.start..].find(':').unwrap_or_default();
+                rest += &s[span.start..=span.start + colon];
+                rest.push('\n');
+            }
+        }
+        rest += &s[last..];
+
+        let mut p = match parse_one(&rest) {
+            Ok(p) => p,
+            // nothing but fields left out
+            Err(ParseError::Empty) => Paragraph::default(),
+            // lines of the text without the fields are off
+            Err(e) => return Err(parse_one(s).err().unwrap_or(e)),
+        };
+
+        for (k, span) in streamed {
+            if self.fields == StreamedFields::Mark {
+                if let Some(v) = p.get_mut(k) {
+                    *v = Item::Streamed;
+                }
+            }
+
+            (self.f)(k, LineStream::new(&s[span]));
+        }
+
+        Ok(p)
+    }
+}
+
+impl<R: fmt::Debug, F> fmt::Debug for ParagraphReader<R, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParagraphReader")
+            .field("reader", &self.reader)
+            .field("decoder", &self.decoder)
+            .field("limits", &self.limits)
+            .field("read", &self.read)
+            .field("done", &self.done)
+            .field(
+                "on_field",
+                &self.on_field.as_ref().map(|x| (&x.keys, x.fields)),
+            )
+            .finish()
+    }
+}
+
+impl<R: Read, F: FnMut(&str, LineStream<'_>)> Iterator for ParagraphReader<R, F> {
+    type Item = Result<Paragraph>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buf = [0; 8192];
+        let ReadLimits {
+            max_paragraph_bytes,
+            max_total_bytes,
+        } = self.limits;
+
+        while !self.done {
+            if let Some((text, offset, paragraph)) = self.decoder.next_text() {
+                return Some(self.parse(&text, offset, paragraph));
+            }
+
+            let buffered = self.decoder.buffered();
+
+            if buffered > max_paragraph_bytes {
+                return self.exceeded(max_paragraph_bytes, true);
+            }
+
+            if self.read > max_total_bytes {
+                return self.exceeded(max_total_bytes, false);
+            }
+
+            // up to one byte over a limit, to tell that it is exceeded
+            let len = buf
+                .len()
+                .min((max_paragraph_bytes - buffered).saturating_add(1))
+                .min((max_total_bytes - self.read).saturating_add(1));
+
+            match self.reader.read(&mut buf[..len]) {
+                Ok(0) => {
+                    self.done = true;
+                    let decoder = std::mem::take(&mut self.decoder);
+
+                    return match self.parse(decoder.rest(), decoder.consumed, decoder.paragraphs) {
+                        Err(ParseError::Empty) => None,
+                        r => Some(r),
+                    };
+                }
+                Ok(n) => {
+                    self.read += n;
+                    self.decoder.push(&buf[..n]);
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Some(Err(e.into())),
+            }
+        }
+
+        None
+    }
+}
+
+/// Parse `text`, which starts at byte `offset` of the input in paragraph
+/// `paragraph`
+fn parse(text: &[u8], offset: usize, paragraph: usize) -> Result<Paragraph> {
+    match std::str::from_utf8(text) {
+        Ok(s) => parse_one(s),
+        Err(e) => Err(ParseError::invalid_utf8(text, &e, offset, Some(paragraph))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, ErrorKind, Read};
+
+    use crate::{
+        parse_multi, test_util::fixtures, Item, ParagraphDecoder, ParagraphExt, ParagraphReader,
+        ParseError, ReadLimits, StreamedFields,
+    };
+
+    /// A server sending `head`, then `tail` over and over, never ending
+    struct Endless {
+        head: &'static [u8],
+        tail: &'static [u8],
+        sent: usize,
+    }
+
+    impl Endless {
+        fn new(head: &'static [u8], tail: &'static [u8]) -> Self {
+            Self {
+                head,
+                tail,
+                sent: 0,
+            }
+        }
+    }
+
+    impl Read for Endless {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            for x in buf.iter_mut() {
+                *x = match self.head.get(self.sent) {
+                    Some(x) => *x,
+                    None => self.tail[(self.sent - self.head.len()) % self.tail.len()],
+                };
+                self.sent += 1;
+            }
+
+            Ok(buf.len())
+        }
+    }
+
+    /// A server giving these chunks or errors, one per read
+    struct Slow(Vec<io::Result<&'static [u8]>>);
+
+    impl Read for Slow {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Ok(0);
+            }
+
+            let chunk = self.0.remove(0)?;
+            buf[..chunk.len()].copy_from_slice(chunk);
+
+            Ok(chunk.len())
+        }
+    }
+
+    #[test]
+    fn test_paragraph_decoder() {
+        let s = format!(
+            "\n# comment\n \n{}\n\n{}",
+            fixtures::STATUS,
+            fixtures::PACKAGES
+        );
+        let expected = parse_multi(&s.replace("# comment\n", "")).unwrap();
+
+        for size in [1, 2, 7, 64, s.len()] {
+            let mut decoder = ParagraphDecoder::new();
+            let mut r = vec![];
+
+            for chunk in s.as_bytes().chunks(size) {
+                decoder.push(chunk);
+
+                while let Some(p) = decoder.next_paragraph() {
+                    r.push(p.unwrap());
+                }
+            }
+            r.extend(decoder.finish().unwrap());
+
+            assert_eq!(r, expected, "{}", size);
+        }
+    }
+
+    #[test]
+    fn test_paragraph_decoder_whitespace_lines() {
+        let s = "\n \t\nPackage: a\nDescription: b\n \n c\n\t\nVersion: 1\n\n \n\n\
+                 Package: b\r\nVersion: 2\r\n\r\nPackage: c\n \n";
+        let expected = parse_multi(s).unwrap();
+
+        assert_eq!(expected.len(), 3);
+
+        for size in [1, 3, s.len()] {
+            let mut decoder = ParagraphDecoder::new();
+            let mut r = vec![];
+
+            for chunk in s.as_bytes().chunks(size) {
+                decoder.push(chunk);
+
+                while let Some(p) = decoder.next_paragraph() {
+                    r.push(p.unwrap());
+                }
+            }
+            r.extend(decoder.finish().unwrap());
+
+            assert_eq!(r, expected, "{}", size);
+        }
+    }
+
+    #[test]
+    fn test_paragraph_decoder_many_paragraphs() {
+        // a chunk with many paragraphs isn't moved once per paragraph
+        let s = "Package: a\n\n".repeat(200_000);
+        let mut decoder = ParagraphDecoder::new();
+        decoder.push(s.as_bytes());
+
+        let mut n = 0;
+        while let Some(p) = decoder.next_paragraph() {
+            p.unwrap();
+            n += 1;
+        }
+
+        assert_eq!(n, 200_000);
+
+        decoder.push(b"Package: b\n");
+
+        assert_eq!(decoder.buf.len(), decoder.buffered());
+        assert!(decoder.finish().unwrap().is_some());
+    }
+
+    #[test]
+    fn test_paragraph_decoder_errors() {
+        let mut decoder = ParagraphDecoder::new();
+        decoder.push(b"Package: a\nbroken\n\nPackage: b\n\n\n");
+
+        assert!(decoder.next_paragraph().unwrap().is_err());
+        assert!(decoder.next_paragraph().unwrap().is_ok());
+        assert!(decoder.next_paragraph().is_none());
+        assert_eq!(decoder.finish().unwrap(), None);
+
+        let mut decoder = ParagraphDecoder::new();
+        decoder.push(b"Package: a\n\nPackage: b");
+
+        assert!(matches!(
+            decoder.finish(),
+            Err(ParseError::TrailingData { .. })
+        ));
+    }
+
+    #[test]
+    fn test_paragraph_decoder_long_line() {
+        let value = "a, ".repeat(10 << 20);
+        let s = format!("Package: a\nDepends: {}\n\nPackage: b\n", value);
+        let mut decoder = ParagraphDecoder::new();
+        let mut r = vec![];
+
+        for chunk in s.as_bytes().chunks(4096) {
+            decoder.push(chunk);
+
+            while let Some(p) = decoder.next_paragraph() {
+                r.push(p.unwrap());
+            }
+        }
+        r.extend(decoder.finish().unwrap());
+
+        assert!(r == parse_multi(&s).unwrap());
+    }
+
+    #[test]
+    fn test_paragraph_reader() {
+        let s = format!("{}\n{}", fixtures::STATUS, fixtures::PACKAGES);
+        let r = ParagraphReader::new(s.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert!(r == parse_multi(&s).unwrap());
+
+        let limits = ReadLimits {
+            max_paragraph_bytes: s.len(),
+            max_total_bytes: s.len(),
+        };
+        assert_eq!(
+            ParagraphReader::with_limits(s.as_bytes(), limits).count(),
+            r.len()
+        );
+    }
+
+    #[test]
+    fn test_paragraph_reader_limits() {
+        let limits = ReadLimits {
+            max_paragraph_bytes: 100_000,
+            ..Default::default()
+        };
+        let mut r =
+            ParagraphReader::with_limits(Endless::new(b"Package: a\nDepends: ", b"b, "), limits);
+
+        assert!(matches!(
+            r.next(),
+            Some(Err(ParseError::LimitExceeded {
+                limit: 100_000,
+                paragraph: true
+            }))
+        ));
+        assert!(r.next().is_none());
+        assert_eq!(r.into_inner().sent, 100_001);
+
+        let limits = ReadLimits {
+            max_total_bytes: 50_000,
+            ..Default::default()
+        };
+        let mut r = ParagraphReader::with_limits(Endless::new(b"", b"Package: a\n\n"), limits);
+
+        assert_eq!(r.by_ref().take(4166).filter(|x| x.is_ok()).count(), 4166);
+        assert!(matches!(
+            r.next(),
+            Some(Err(ParseError::LimitExceeded {
+                limit: 50_000,
+                paragraph: false
+            }))
+        ));
+        assert_eq!(r.into_inner().sent, 50_001);
+    }
+
+    #[test]
+    fn test_paragraph_reader_timeout() {
+        let mut r = ParagraphReader::new(Slow(vec![
+            Ok(b"Package: a\nVers"),
+            Err(ErrorKind::TimedOut.into()),
+            Err(ErrorKind::Interrupted.into()),
+            Ok(b"ion: 1\n\nPackage: b\n"),
+        ]));
+
+        assert!(matches!(
+            r.next(),
+            Some(Err(ParseError::Io(e))) if e.kind() == ErrorKind::TimedOut
+        ));
+        assert_eq!(r.next().unwrap().unwrap().get_one("Version"), Some("1"));
+        assert_eq!(r.next().unwrap().unwrap().get_one("Package"), Some("b"));
+        assert!(r.next().is_none());
+    }
+
+    /// The texlive stanza with `lines` more `Checksums-Sha256` lines, after
+    /// `status`
+    fn texlive(lines: usize) -> String {
+        let extra = (0..lines)
+            .map(|i| format!(" {:064x} {} texlive-base_{}.tar.xz\n", i, i, i))
+            .collect::<String>();
+        let texlive = fixtures::SOURCES_TEXLIVE.replacen(
+            "Checksums-Sha256:\n",
+            &format!("Checksums-Sha256:\n{}", extra),
+            1,
+        );
+
+        format!("{}\n{}", fixtures::STATUS, texlive)
+    }
+
+    #[test]
+    fn test_paragraph_reader_on_field() {
+        let s = texlive(20_000);
+        let full = parse_multi(&s).unwrap();
+        let mut seen = vec![];
+        let marked = ParagraphReader::new(s.as_bytes())
+            .on_field(
+                &["checksums-sha256", "Files"],
+                StreamedFields::Mark,
+                |k, lines| {
+                    let lines = lines.collect::<Vec<_>>();
+                    let item = full.last().unwrap().get_field(k).unwrap();
+
+                    assert!(lines == item.lines());
+                    seen.push((k.to_string(), lines.len()));
+                },
+            )
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(
+            seen,
+            [
+                ("Files".to_string(), 3),
+                ("Checksums-Sha256".to_string(), 20_003)
+            ]
+        );
+        assert_eq!(marked.len(), full.len());
+        assert!(marked[..full.len() - 1] == full[..full.len() - 1]);
+
+        let (texlive, marked) = (full.last().unwrap(), marked.last().unwrap());
+        assert!(texlive.keys().eq(marked.keys()));
+        for (k, v) in marked {
+            match k.as_str() {
+                "Files" | "Checksums-Sha256" => assert_eq!(*v, Item::Streamed),
+                _ => assert_eq!(v, &texlive[k]),
+            }
+        }
+
+        let mut count = 0;
+        let omitted = ParagraphReader::new(s.as_bytes())
+            .on_field(&["Checksums-Sha256"], StreamedFields::Omit, |_, lines| {
+                count += lines.count()
+            })
+            .last()
+            .unwrap()
+            .unwrap();
+        let mut expected = texlive.clone();
+        expected.shift_remove("Checksums-Sha256");
+
+        assert_eq!(count, 20_003);
+        assert!(omitted == expected);
+    }
+
+    #[test]
+    fn test_paragraph_reader_on_field_errors() {
+        let mut calls = 0;
+        let s = "Package: a\nFiles:\n 1\n 2\nbroken\n\nFiles:\n 3\n";
+        let r = ParagraphReader::new(s.as_bytes())
+            .on_field(&["Files"], StreamedFields::Omit, |_, _| calls += 1)
+            .collect::<Vec<_>>();
+
+        // the line in the text with the field
+        assert!(matches!(r[0], Err(ParseError::Malformed { line: 5 })));
+        assert!(r[1].as_ref().unwrap().is_empty());
+        assert_eq!((r.len(), calls), (2, 1));
+    }
+}
@@ -0,0 +1,165 @@
+use crate::Item;
+
+/// A `Description` field split the way dpkg reads it
+///
+/// The first line is the synopsis, every continuation line belongs to the
+/// extended description. Blank lines of the extended description are
+/// written as ` .` in the file and show up here as empty strings.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Description {
+    pub synopsis: String,
+    pub extended: Vec<String>,
+}
+
+impl Description {
+    /// Read a description from a parsed field value
+    ///
+    /// A [`Item::OneLine`] only has a synopsis, a [`Item::MultiLine`] only has
+    /// extended text. A [`Item::Streamed`] gives an empty description.
+    pub fn from_item(item: &Item) -> Self {
+        let (synopsis, lines) = match item {
+            Item::OneLine(s) => (s.clone(), &[][..]),
+            Item::Folded(s, lines) => (s.clone(), &lines[..]),
+            Item::MultiLine(lines) => (String::new(), &lines[..]),
+            Item::Streamed => (String::new(), &[][..]),
+        };
+
+        let extended = lines
+            .iter()
+            .map(|x| if x == "." { String::new() } else { x.clone() })
+            .collect();
+
+        Self { synopsis, extended }
+    }
+
+    /// Encode back into a field value, writing blank lines as `.`
+    ///
+    /// ```rust
+    /// use eight_deep_parser::{Description, Item};
+    ///
+    /// let d = Description {
+    ///     synopsis: "a".to_string(),
+    ///     extended: vec!["b".to_string(), "".to_string(), "c".to_string()],
+    /// };
+    ///
+    /// assert_eq!(
+    ///     d.to_item(),
+    ///     Item::Folded(
+    ///         "a".to_string(),
+    ///         vec!["b".to_string(), ".".to_string(), "c".to_string()]
+    ///     )
+    /// );
+    /// ```
+    pub fn to_item(&self) -> Item {
+        let lines = self
+            .extended
+            .iter()
+            .map(|x| {
+                if x.is_empty() {
+                    ".".to_string()
+                } else {
+                    x.clone()
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if lines.is_empty() {
+            Item::OneLine(self.synopsis.clone())
+        } else if self.synopsis.is_empty() {
+            Item::MultiLine(lines)
+        } else {
+            Item::Folded(self.synopsis.clone(), lines)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_back, parse_multi, parse_one, Description, Item, ParagraphExt};
+
+    const BASH: &str = r#"Package: bash
+Version: 5.2.15-2+b7
+Description: GNU Bourne Again SHell
+ Bash is an sh-compatible command language interpreter that executes
+ commands read from the standard input or from a file.  Bash also
+ incorporates useful features from the Korn and C shells (ksh and csh).
+ .
+ Bash is ultimately intended to be a conformant implementation of the
+ IEEE POSIX Shell and Tools specification (IEEE Working Group 1003.2).
+ .
+ The Programmable Completion Code, by Ian Macdonald, is now found in
+ the bash-completion package.
+Homepage: http://tiswww.case.edu/php/chet/bash/bashtop.html
+"#;
+
+    #[test]
+    fn test_description_bash() {
+        let r = parse_one(BASH).unwrap();
+        let d = r.description().unwrap();
+
+        assert_eq!(d.synopsis, "GNU Bourne Again SHell");
+        assert_eq!(d.extended.len(), 9);
+        assert_eq!(d.extended[3], "");
+        assert_eq!(d.extended[6], "");
+        assert_eq!(d.extended[8], "the bash-completion package.");
+
+        assert_eq!(&d.to_item(), r.get("Description").unwrap());
+        assert_eq!(Description::from_item(&d.to_item()), d);
+        assert_eq!(parse_back(&[r]), BASH);
+    }
+
+    #[test]
+    fn test_description_one_line() {
+        let r = parse_one("Package: a\nDescription: just a synopsis\n").unwrap();
+        let d = r.description().unwrap();
+
+        assert_eq!(d.synopsis, "just a synopsis");
+        assert!(d.extended.is_empty());
+        assert_eq!(d.to_item(), Item::OneLine("just a synopsis".to_string()));
+    }
+
+    #[test]
+    fn test_description_absent() {
+        let r = parse_one("Package: a\n").unwrap();
+
+        assert!(r.description().is_none());
+    }
+
+    #[test]
+    fn test_translated_description() {
+        let r = parse_multi(
+            "Package: bash
+Description-md5: 3522aa7b4374048d6450e348a5bb45d9
+Description-fr: Interpréteur de commandes GNU Bourne-Again SHell
+ Bash est un interpréteur de commandes compatible avec sh qui exécute les
+ commandes lues depuis l'entrée standard ou depuis un fichier.
+ .
+ Bash peut être utilisé de façon interactive.
+
+Package: dash
+Description-md5: 2b7d3cf4a2bc6e1bd4e6e4a8a7a3fb08
+Description-en: POSIX-compliant shell
+",
+        )
+        .unwrap();
+
+        let d = r[0].translated_description("fr").unwrap();
+
+        assert_eq!(
+            d.synopsis,
+            "Interpréteur de commandes GNU Bourne-Again SHell"
+        );
+        assert_eq!(d.extended.len(), 4);
+        assert_eq!(d.extended[2], "");
+        assert_eq!(
+            r[0].get_one("Description-md5"),
+            Some("3522aa7b4374048d6450e348a5bb45d9")
+        );
+        assert_eq!(r[0].translated_description("de"), None);
+        assert_eq!(r[0].description(), None);
+        assert_eq!(
+            r[1].translated_description("en").unwrap().synopsis,
+            "POSIX-compliant shell"
+        );
+    }
+}
@@ -24,6 +24,515 @@ pub enum ParseError {
     Utf8Error { bytes: Vec<u8>, source: Utf8Error },
     #[error(transparent)]
     TransUtf8Error(#[from] std::str::Utf8Error),
+    /// There is no paragraph to parse, only whitespace and `#` comment
+    /// lines, see [`parse_one`](crate::parse_one)
+    #[error("Empty input")]
+    Empty,
+    /// A field appeared twice in one paragraph under
+    /// [`DuplicateKeys::Error`](crate::DuplicateKeys::Error)
+    #[error("Duplicate field `{key}`")]
+    DuplicateKey { key: String },
+    /// A continuation line starts a paragraph under
+    /// [`OrphanLines::Error`](crate::OrphanLines::Error)
+    #[error("Continuation line {line} belongs to no field")]
+    OrphanContinuation {
+        /// Line number, starting from 1
+        line: usize,
+    },
+    /// Whitespace between a field name and its colon under
+    /// [`SpaceBeforeColon::Error`](crate::SpaceBeforeColon::Error)
+    #[error("Space before the colon of field `{key}`")]
+    SpaceBeforeColon {
+        /// The field name, without the whitespace
+        key: String,
+    },
+    /// A decoder of [`parse_with_decoders`](crate::parse_with_decoders)
+    /// rejected the value of `key`
+    #[error("Error decoding the value of `{key}`: {reason}")]
+    Decode { key: String, reason: String },
+    /// A line in a paragraph or before the first one is neither a field, a
+    /// continuation line nor blank, like `Version=1`
+    #[error("Malformed line {line}")]
+    Malformed {
+        /// Line number, starting from 1
+        line: usize,
+    },
+    /// The input holds more than `limit` paragraphs, see
+    /// [`parse_multi_limited`](crate::parse_multi_limited)
+    #[error("More than {limit} paragraphs")]
+    TooManyParagraphs { limit: usize },
+    /// The value of `key` is longer than `limit` bytes, see
+    /// [`parse_multi_limited`](crate::parse_multi_limited)
+    #[error("Value of `{key}` longer than {limit} bytes")]
+    FieldTooLong { key: String, limit: usize },
+    /// A [`ParagraphReader`](crate::ParagraphReader) read more than `limit`
+    /// bytes of one paragraph if `paragraph`, else more than `limit` bytes
+    /// in all, see [`ReadLimits`](crate::ReadLimits)
+    #[error(
+        "More than {limit} bytes {}",
+        if *.paragraph { "in a paragraph" } else { "of input" }
+    )]
+    LimitExceeded { limit: usize, paragraph: bool },
+    /// A paragraph returned by the closure of
+    /// [`transform`](crate::transform) can't be written
+    #[error(transparent)]
+    Serialize(#[from] SerializeError),
+    /// Reading the input failed, see
+    /// [`find_package_sorted`](crate::find_package_sorted)
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// Something other than whitespace followed the paragraph, or the last
+    /// paragraph under [`TrailingData::Error`](crate::TrailingData::Error)
+    #[error("Unexpected data after the paragraph at byte {offset}")]
+    TrailingData {
+        /// Byte offset of the trailing data in the input
+        offset: usize,
+    },
+    /// The paragraph `index` of [`parse_multi`](crate::parse_multi),
+    /// starting at byte `offset`, failed with `source`
+    ///
+    /// Match on [`ParseError::root`] to tell what went wrong.
+    #[error("Paragraph {index} at byte {offset}: {source}")]
+    Paragraph {
+        /// Index of the paragraph among those returned, starting from 0
+        index: usize,
+        /// Byte offset of its first field in the input
+        offset: usize,
+        source: Box<ParseError>,
+    },
+    /// The input isn't valid UTF-8, see
+    /// [`parse_multi_bytes`](crate::parse_multi_bytes)
+    #[error(
+        "Invalid UTF-8 at byte {offset}{}{}: {context}",
+        .paragraph.map(|x| format!(" in paragraph {}", x)).unwrap_or_default(),
+        .key.as_ref().map(|x| format!(", field `{}`", x)).unwrap_or_default()
+    )]
+    InvalidUtf8 {
+        /// Byte offset of the first invalid byte in the input
+        offset: usize,
+        /// Index of the paragraph it is in, if known
+        paragraph: Option<usize>,
+        /// Name of the field it is in, decoded lossily, if it is in one
+        key: Option<String>,
+        /// Hex dump of the 16 bytes around it, the invalid one in brackets
+        context: String,
+    },
+}
+
+impl ParseError {
+    /// A stable name of the kind of error, for reports read by programs
+    ///
+    /// Unlike the message, this doesn't change between releases.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ParseError::Paragraph { source, .. } => source.kind(),
+            ParseError::Other { .. } => "syntax",
+            ParseError::Incomplete => "incomplete",
+            ParseError::Utf8Error { .. }
+            | ParseError::TransUtf8Error(_)
+            | ParseError::InvalidUtf8 { .. } => "utf8",
+            ParseError::Empty => "empty",
+            ParseError::DuplicateKey { .. } => "duplicate_key",
+            ParseError::OrphanContinuation { .. } => "orphan_continuation",
+            ParseError::SpaceBeforeColon { .. } => "space_before_colon",
+            ParseError::Decode { .. } => "decode",
+            ParseError::Malformed { .. } => "malformed",
+            ParseError::TooManyParagraphs { .. } => "too_many_paragraphs",
+            ParseError::FieldTooLong { .. } => "field_too_long",
+            ParseError::LimitExceeded { .. } => "limit_exceeded",
+            ParseError::Serialize(_) => "serialize",
+            ParseError::Io(_) => "io",
+            ParseError::TrailingData { .. } => "trailing_data",
+        }
+    }
+
+    /// The line the error is on, starting from 1, if it is known
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            ParseError::Paragraph { source, .. } => source.line(),
+            ParseError::OrphanContinuation { line } | ParseError::Malformed { line } => Some(*line),
+            _ => None,
+        }
+    }
+
+    /// A guess at the mistake behind the error, for people editing `input`
+    /// by hand
+    ///
+    /// `input` is what was parsed, by [`parse_one`](crate::parse_one),
+    /// [`parse_multi_with`](crate::parse_multi_with) or
+    /// [`Cst::paragraphs`](crate::Cst::paragraphs). Only the line the error
+    /// points at is looked at, and only when this is called. Recognized are
+    /// `=` instead of `:`, a continuation line without its leading space,
+    /// typographic quotes in a field name, and a second `Package` field
+    /// where a blank line is missing between two paragraphs.
+    ///
+    /// ```rust
+    /// use eight_deep_parser::parse_one;
+    ///
+    /// let s = "Package: a\nVersion=1\n";
+    /// let e = parse_one(s).unwrap_err();
+    ///
+    /// assert_eq!(e.hint(s).as_deref(), Some("did you mean `Version: 1`?"));
+    /// ```
+    pub fn hint(&self, input: &str) -> Option<String> {
+        let start = match self.root() {
+            ParseError::DuplicateKey { key } if key.eq_ignore_ascii_case("Package") => {
+                return Some(
+                    "a blank line is missing before the second `Package` field".to_string(),
+                )
+            }
+            ParseError::Malformed { line } => input
+                .split_inclusive('\n')
+                .take(line.saturating_sub(1))
+                .map(str::len)
+                .sum(),
+            ParseError::TrailingData { offset } => *offset,
+            ParseError::Other {
+                at: ErrorBytes::Valid(at),
+                ..
+            } if input.ends_with(at.as_str()) => input.len() - at.len(),
+            _ => return None,
+        };
+
+        let before = input.get(..start)?;
+        let start = before.rfind('\n').map(|x| x + 1).unwrap_or(0);
+        let line = input[start..].lines().next()?;
+        let prev = input[..start]
+            .strip_suffix('\n')
+            .and_then(|x| x.rsplit('\n').next());
+
+        line_hint(prev, line)
+    }
+
+    /// The error inside the [`ParseError::Paragraph`] wrappers, `self` for
+    /// other errors
+    ///
+    /// ```rust
+    /// use eight_deep_parser::{parse_multi_with, DuplicateKeys, ParseError, ParseOptions};
+    ///
+    /// let opts = ParseOptions {
+    ///     duplicates: DuplicateKeys::Error,
+    ///     ..Default::default()
+    /// };
+    /// let e = parse_multi_with("A: 1\n\nA: 2\nA: 3\n", &opts).unwrap_err();
+    ///
+    /// assert!(matches!(e, ParseError::Paragraph { index: 1, offset: 6, .. }));
+    /// assert!(matches!(e.root(), ParseError::DuplicateKey { key } if key == "A"));
+    /// ```
+    pub fn root(&self) -> &ParseError {
+        match self {
+            ParseError::Paragraph { source, .. } => source.root(),
+            e => e,
+        }
+    }
+
+    /// `self` wrapped in a [`ParseError::Paragraph`]
+    pub(crate) fn in_paragraph(self, index: usize, offset: usize) -> Self {
+        ParseError::Paragraph {
+            index,
+            offset,
+            source: Box::new(self),
+        }
+    }
+
+    /// [`ParseError::InvalidUtf8`] for the error `e` of decoding `input`,
+    /// which starts at byte `offset` of the whole input, in paragraph
+    /// `paragraph`
+    pub(crate) fn invalid_utf8(
+        input: &[u8],
+        e: &Utf8Error,
+        offset: usize,
+        paragraph: Option<usize>,
+    ) -> Self {
+        let bad = e.valid_up_to();
+        let line_start = |end: usize| {
+            input[..end]
+                .iter()
+                .rposition(|&x| x == b'\n')
+                .map_or(0, |x| x + 1)
+        };
+
+        // the field line is the first one of the bad line and the
+        // continuation lines before it
+        let mut start = line_start(bad);
+        while start > 0 && matches!(input[start], b' ' | b'\t') {
+            start = line_start(start - 1);
+        }
+
+        let line = input[start..]
+            .split(|&x| x == b'\n')
+            .next()
+            .unwrap_or_default();
+        let key = match line.first() {
+            Some(b' ' | b'\t' | b'#') | None => None,
+            Some(_) => line
+                .iter()
+                .position(|&x| x == b':')
+                .map(|i| String::from_utf8_lossy(&line[..i]).trim_end().to_string()),
+        };
+
+        let mut index = 0;
+        let mut in_paragraph = false;
+
+        for line in input[..start].split_inclusive(|&x| x == b'\n') {
+            if matches!(line, b"\n" | b"\r\n") {
+                index += usize::from(in_paragraph);
+                in_paragraph = false;
+            } else if !line.starts_with(b"#") {
+                in_paragraph = true;
+            }
+        }
+
+        let around = bad.saturating_sub(8)..(bad.saturating_sub(8) + 16).min(input.len());
+        let context = input[around.clone()]
+            .iter()
+            .zip(around)
+            .map(|(x, i)| match i == bad {
+                true => format!("[{:02x}]", x),
+                false => format!("{:02x}", x),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        ParseError::InvalidUtf8 {
+            offset: offset + bad,
+            paragraph: paragraph.map(|x| x + index),
+            key,
+            context,
+        }
+    }
+}
+
+fn line_hint(prev: Option<&str>, line: &str) -> Option<String> {
+    const QUOTES: [char; 6] = [
+        '\u{2018}', '\u{2019}', '\u{201a}', '\u{201c}', '\u{201d}', '\u{201e}',
+    ];
+
+    if line.trim().is_empty() || line.starts_with([' ', '\t', '#']) {
+        return None;
+    }
+
+    let key = line.split([':', '=']).next().unwrap_or(line);
+
+    if key.contains(QUOTES) {
+        return Some(format!(
+            "field names can't have typographic quotes, did you mean `{}`?",
+            key.replace(QUOTES, "").trim()
+        ));
+    }
+
+    if let Some((k, v)) = line.split_once('=') {
+        let k = k.trim();
+
+        if !line.contains(':') && !k.is_empty() && !k.contains(char::is_whitespace) {
+            return Some(format!("did you mean `{}: {}`?", k, v.trim()));
+        }
+    }
+
+    let in_paragraph = prev.is_some_and(|x| !x.trim().is_empty() && !x.starts_with('#'));
+
+    if in_paragraph && !line.contains(':') {
+        return Some(format!(
+            "continuation lines start with a space, did you mean ` {}`?",
+            line
+        ));
+    }
+
+    None
+}
+
+/// An error loading a [`PackageIndex`](crate::PackageIndex) with
+/// [`PackageIndex::load`](crate::PackageIndex::load)
+///
+/// On [`IndexError::Stale`] and [`IndexError::Corrupt`] the index should be
+/// built again.
+#[derive(Debug, Error)]
+pub enum IndexError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// The indexed file changed since the index was built
+    #[error("Index of `{path}` is out of date")]
+    Stale { path: String },
+    /// The file isn't an index written by
+    /// [`PackageIndex::save`](crate::PackageIndex::save)
+    #[error("Corrupt index file: {reason}")]
+    Corrupt { reason: String },
+}
+
+/// An error that occurred in [`try_parse_back`](crate::try_parse_back): the
+/// data can't be written in a form that parses back to the same data.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SerializeError {
+    /// The field name is empty, starts with whitespace or `#`, ends with
+    /// whitespace or contains `:` or a line break
+    #[error("Invalid field name `{key}` in paragraph {paragraph}")]
+    InvalidKey { paragraph: usize, key: String },
+    /// The value parses back as something else: an empty [`Item::OneLine`]
+    /// or [`Item::MultiLine`], an [`Item::Folded`] without continuation
+    /// lines, or a value on the field line that is empty or starts with
+    /// whitespace
+    ///
+    /// [`Item::OneLine`]: crate::Item::OneLine
+    /// [`Item::MultiLine`]: crate::Item::MultiLine
+    /// [`Item::Folded`]: crate::Item::Folded
+    #[error("Value of `{key}` in paragraph {paragraph} can't be written as is")]
+    InvalidValue { paragraph: usize, key: String },
+    /// A line of the value contains a line break, `\n` or `\r`
+    #[error("Line break in the value of `{key}` in paragraph {paragraph}")]
+    NewlineInValue { paragraph: usize, key: String },
+    /// The value is an [`Item::Streamed`](crate::Item::Streamed), which has
+    /// nothing to write
+    #[error("Streamed value of `{key}` in paragraph {paragraph}")]
+    Streamed { paragraph: usize, key: String },
+}
+
+/// An error that occurred in [`TryParagraphBuilder`](crate::TryParagraphBuilder)
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BuildError {
+    /// The field name is empty or contains whitespace, `:` or a control
+    /// character
+    #[error("Invalid field name `{key}`")]
+    InvalidKey { key: String },
+}
+
+/// An error that occurred in [`substitute`](crate::substitute)
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SubstError {
+    /// `${name}` isn't in the variables, under
+    /// [`UnknownVar::Error`](crate::UnknownVar::Error)
+    #[error("Unknown variable `{name}`")]
+    Unknown { name: String },
+    /// Variables refer to each other deeper than
+    /// [`SubstOptions::max_depth`](crate::SubstOptions::max_depth), likely in
+    /// a loop
+    #[error("Variable `{name}` nested too deep")]
+    TooDeep { name: String },
+}
+
+/// An error that occurred in
+/// [`FromParagraph::from_paragraph`](crate::FromParagraph::from_paragraph)
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MapError {
+    /// A required field is missing
+    #[error("Missing field `{field}`")]
+    Missing { field: String },
+    /// A field has a value that can't be converted
+    #[error("Invalid value of field `{field}`: {reason}")]
+    Invalid { field: String, reason: String },
+}
+
+/// A string that isn't a valid `name` or `name:arch`, see
+/// [`PackageId`](crate::PackageId)
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("Invalid package id `{0}`")]
+pub struct PackageIdError(pub String);
+
+/// An error that occurred while parsing a relation field or a version, see
+/// [`parse_relations`](crate::parse_relations)
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum RelationError {
+    /// A relation isn't `name[:arch] [(op version)] [[arch ...]] [<profile ...>]`
+    #[error("Invalid relation `{relation}`")]
+    Syntax { relation: String },
+    /// A version has an invalid epoch or an empty part
+    #[error("Invalid version `{version}`")]
+    Version { version: String },
+}
+
+/// A version that doesn't follow Debian's version syntax, see
+/// [`validate_version`](crate::validate_version)
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum VersionError {
+    #[error("Empty version")]
+    Empty,
+    /// The epoch, before the first `:`, isn't a number
+    #[error("Invalid epoch `{epoch}`")]
+    Epoch { epoch: String },
+    /// The upstream version is empty or doesn't start with a digit
+    #[error("Upstream version `{upstream}` doesn't start with a digit")]
+    Upstream { upstream: String },
+    /// Nothing after the last `-`
+    #[error("Empty revision")]
+    EmptyRevision,
+    /// A character not allowed where it is: the upstream version allows
+    /// letters, digits and `.+~-`, the revision letters, digits and `.+~`
+    #[error("Invalid character `{ch}` at byte {offset}")]
+    InvalidChar { ch: char, offset: usize },
+}
+
+/// A malformed row of a field holding one record per line, like `Files`
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+#[error("Malformed row in `{field}`: `{row}`")]
+pub struct RowError {
+    pub field: String,
+    pub row: String,
+}
+
+/// An error reading the entries of a
+/// [`ReleaseFile`](crate::ReleaseFile)
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum ReleaseError {
+    #[error(transparent)]
+    Row(#[from] RowError),
+    /// The entries of a path disagree on its digest or size
+    #[error("Conflicting entries for `{path}`")]
+    Conflict { path: String },
+}
+
+/// An error reading a `.deb`, see
+/// [`read_deb_control_files`](crate::read_deb_control_files)
+#[cfg(feature = "deb")]
+#[derive(Debug, Error)]
+pub enum DebError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// Not an `ar` archive starting with `debian-binary`
+    #[error("Not a Debian binary package")]
+    NotDeb,
+    /// The archive ends inside `member`
+    #[error("Archive truncated in `{member}`")]
+    Truncated { member: String },
+    /// There is no `control.tar`, or it has no `control` file
+    #[error("Missing `{name}`")]
+    Missing { name: String },
+    /// `control.tar`, or the data given to [`decompress`](crate::decompress),
+    /// is compressed in a format that can't be read, xz or zstd
+    #[error("Unsupported compression of `{member}`")]
+    Unsupported { member: String },
+    /// A member can't be decompressed or unpacked
+    #[error("Corrupt `{member}`: {reason}")]
+    Corrupt { member: String, reason: String },
+    /// The `control` file doesn't parse
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    /// An error reading one of the files of
+    /// [`generate_packages_index`](crate::generate_packages_index)
+    #[error("Error reading `{path}`: {source}")]
+    File { path: String, source: Box<DebError> },
+}
+
+/// A file that doesn't match its Release entry, see
+/// [`ReleaseFile::verify_file`](crate::ReleaseFile::verify_file)
+#[cfg(feature = "digest")]
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum VerifyError {
+    #[error("`{path}` is not listed in the Release file")]
+    NotListed { path: String },
+    #[error("Size mismatch for `{path}`: expected {expected}, got {actual}")]
+    Size {
+        path: String,
+        expected: u64,
+        actual: u64,
+    },
+    #[error("{algorithm} mismatch for `{path}`: expected {expected}, got {actual}")]
+    Digest {
+        path: String,
+        algorithm: crate::Algorithm,
+        expected: String,
+        actual: String,
+    },
+    #[error(transparent)]
+    Row(#[from] RowError),
 }
 
 /// The remaining input from the parser.  Useful for debugging to see where the
@@ -58,3 +567,155 @@ impl From<nom::Err<NomError<&[u8]>>> for ParseError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use nom::error::ErrorKind;
+
+    use crate::{
+        parse_multi_with, parse_one, Cst, ErrorBytes, ParseError, ParseOptions, SerializeError,
+    };
+
+    /// The hint for the error of parsing `s`, the same whichever parser
+    /// reports it
+    fn hint(s: &str) -> Option<String> {
+        let strict = ParseOptions::strict_rfc();
+        let hint = parse_one(s).unwrap_err().hint(s);
+
+        assert_eq!(
+            parse_multi_with(s, &strict).unwrap_err().hint(s),
+            hint,
+            "{:?}",
+            s
+        );
+        assert_eq!(
+            Cst::parse(s).paragraphs(&strict).unwrap_err().hint(s),
+            hint,
+            "{:?}",
+            s
+        );
+
+        hint
+    }
+
+    #[test]
+    fn test_hint() {
+        assert_eq!(
+            hint("Package = a\nVersion: 1\n").as_deref(),
+            Some("did you mean `Package: a`?")
+        );
+        assert_eq!(
+            hint("Package: a\nDepends: b,\nc\n").as_deref(),
+            Some("continuation lines start with a space, did you mean ` c`?")
+        );
+        assert_eq!(
+            hint("Package: a\n\u{201c}Version\u{201d}=1\n").as_deref(),
+            Some("field names can't have typographic quotes, did you mean `Version`?")
+        );
+
+        let s = "Package: a\nVersion: 1\nPackage: b\nVersion: 2\n";
+        let e = crate::parse_one_with(s, &ParseOptions::strict_rfc()).unwrap_err();
+
+        assert_eq!(
+            e.hint(s).as_deref(),
+            Some("a blank line is missing before the second `Package` field")
+        );
+
+        // after a paragraph, reported as trailing data
+        assert_eq!(
+            hint("Package: a\n\nVersion=1\n").as_deref(),
+            Some("did you mean `Version: 1`?")
+        );
+    }
+
+    #[test]
+    fn test_hint_unknown() {
+        assert_eq!(hint("Package a\n"), None);
+        assert_eq!(hint("Package: a\n\nb\n"), None);
+        assert_eq!(ParseError::Incomplete.hint(""), None);
+        assert_eq!(
+            ParseError::DuplicateKey {
+                key: "Version".to_string()
+            }
+            .hint(""),
+            None
+        );
+        assert_eq!(ParseError::Malformed { line: 9 }.hint("Package: a\n"), None);
+    }
+
+    #[test]
+    fn test_kind() {
+        let bytes = vec![0xff];
+        let utf8 = std::str::from_utf8(&bytes).unwrap_err();
+        let errors = [
+            ParseError::Other {
+                at: ErrorBytes::Valid(String::new()),
+                kind: ErrorKind::Tag,
+            },
+            ParseError::Incomplete,
+            ParseError::Utf8Error {
+                bytes: bytes.clone(),
+                source: utf8,
+            },
+            ParseError::TransUtf8Error(utf8),
+            ParseError::Empty,
+            ParseError::DuplicateKey { key: String::new() },
+            ParseError::OrphanContinuation { line: 3 },
+            ParseError::SpaceBeforeColon { key: String::new() },
+            ParseError::Decode {
+                key: String::new(),
+                reason: String::new(),
+            },
+            ParseError::Malformed { line: 5 },
+            ParseError::TooManyParagraphs { limit: 1 },
+            ParseError::FieldTooLong {
+                key: String::new(),
+                limit: 1,
+            },
+            ParseError::LimitExceeded {
+                limit: 1,
+                paragraph: true,
+            },
+            ParseError::Serialize(SerializeError::InvalidKey {
+                paragraph: 0,
+                key: String::new(),
+            }),
+            ParseError::Io(std::io::ErrorKind::NotFound.into()),
+            ParseError::TrailingData { offset: 0 },
+            ParseError::InvalidUtf8 {
+                offset: 0,
+                paragraph: None,
+                key: None,
+                context: String::new(),
+            },
+        ];
+
+        // renaming any of these breaks the programs reading them
+        assert_eq!(
+            errors.iter().map(|x| x.kind()).collect::<Vec<_>>(),
+            [
+                "syntax",
+                "incomplete",
+                "utf8",
+                "utf8",
+                "empty",
+                "duplicate_key",
+                "orphan_continuation",
+                "space_before_colon",
+                "decode",
+                "malformed",
+                "too_many_paragraphs",
+                "field_too_long",
+                "limit_exceeded",
+                "serialize",
+                "io",
+                "trailing_data",
+                "utf8",
+            ]
+        );
+        assert_eq!(
+            errors.iter().filter_map(|x| x.line()).collect::<Vec<_>>(),
+            [3, 5]
+        );
+    }
+}
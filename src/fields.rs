@@ -0,0 +1,443 @@
+//! The standard deb822 fields, with the kind of their values and the files
+//! they appear in
+//!
+//! ```rust
+//! use eight_deep_parser::fields::{FieldInfo, FieldKind, FileKind};
+//!
+//! let info = FieldInfo::lookup("pre-depends").unwrap();
+//!
+//! assert_eq!(info.name, "Pre-Depends");
+//! assert_eq!(info.kind, FieldKind::Relations);
+//! assert!(info.belongs_to(FileKind::Binary));
+//! ```
+
+use crate::{list::split_commas, Item};
+
+/// How the value of a field is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FieldKind {
+    /// One line
+    Simple,
+    /// A first line followed by continuation lines, like `Description`
+    Folded,
+    /// Continuation lines only, the first line is empty, like `Files`
+    MultiLine,
+    /// A relation field like `Depends`, see
+    /// [`parse_relations`](crate::parse_relations); may be wrapped
+    Relations,
+    /// A list separated by commas or whitespace; may be wrapped
+    List,
+}
+
+/// A kind of deb822 file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileKind {
+    /// Binary package control files: `DEBIAN/control`, `Packages` and the
+    /// dpkg database
+    Binary,
+    /// Source package control files: `.dsc` and `Sources`
+    Source,
+    /// `Release` and `InRelease`
+    Release,
+    /// `.changes` uploads
+    Changes,
+    /// deb822 APT sources, `*.sources`
+    SourcesList,
+}
+
+/// A standard field, see [`FieldInfo::lookup`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FieldInfo {
+    /// The canonical spelling. Checksum fields are spelled as in `Packages`,
+    /// e.g. `MD5sum`.
+    pub name: &'static str,
+    pub kind: FieldKind,
+    /// The files this field appears in
+    pub files: &'static [FileKind],
+}
+
+impl FieldInfo {
+    /// The standard field `name`, ignoring case
+    pub fn lookup(name: &str) -> Option<&'static FieldInfo> {
+        FIELDS.iter().find(|x| x.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Every standard field
+    pub fn all() -> &'static [FieldInfo] {
+        FIELDS
+    }
+
+    pub fn belongs_to(&self, file: FileKind) -> bool {
+        self.files.contains(&file)
+    }
+
+    /// The kind of the value in `file`, which differs from
+    /// [`FieldInfo::kind`] for a few fields: the checksums of a `Release`
+    /// file and the `Description` of a `.changes` are lists of lines.
+    pub fn kind_in(&self, file: FileKind) -> FieldKind {
+        match (file, self.name) {
+            (FileKind::Release, "MD5sum" | "SHA1" | "SHA256" | "SHA512") => FieldKind::MultiLine,
+            (FileKind::Changes, "Description") => FieldKind::MultiLine,
+            _ => self.kind,
+        }
+    }
+}
+
+/// Longer relation fields are wrapped by [`Item::from_logical`]
+const WRAP_WIDTH: usize = 80;
+
+impl Item {
+    /// The value as APT sees it, whatever its line breaks
+    ///
+    /// The lines of [`FieldKind::Simple`], [`FieldKind::Relations`] and
+    /// [`FieldKind::List`] values are joined with spaces. The lines of
+    /// [`FieldKind::Folded`] and [`FieldKind::MultiLine`] values are joined
+    /// with line breaks, and a `.` line stands for an empty line. Look the
+    /// kind up with [`FieldInfo::lookup`].
+    ///
+    /// ```rust
+    /// use eight_deep_parser::{fields::FieldKind, parse_one};
+    ///
+    /// let r = parse_one("Depends: a,\n b\nDescription: c\n d\n .\n e\n").unwrap();
+    ///
+    /// assert_eq!(r["Depends"].joined_value(FieldKind::Relations), "a, b");
+    /// assert_eq!(r["Description"].joined_value(FieldKind::Folded), "c\nd\n\ne");
+    /// ```
+    pub fn joined_value(&self, kind: FieldKind) -> String {
+        let lines = self.value_lines();
+
+        match kind {
+            FieldKind::Simple | FieldKind::Relations | FieldKind::List => lines
+                .map(|x| x.trim())
+                .filter(|x| !x.is_empty())
+                .collect::<Vec<_>>()
+                .join(" "),
+            FieldKind::Folded | FieldKind::MultiLine => lines
+                .map(|x| if x.trim() == "." { "" } else { x })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+
+    /// Split a value given as by [`Item::joined_value`] back into lines
+    ///
+    /// [`FieldKind::Relations`] longer than 80 bytes are wrapped one
+    /// relation per line, and empty lines of [`FieldKind::Folded`] and
+    /// [`FieldKind::MultiLine`] values are written as `.`. Other values are
+    /// kept on one line.
+    ///
+    /// ```rust
+    /// use eight_deep_parser::{fields::FieldKind, Item};
+    ///
+    /// assert_eq!(
+    ///     Item::from_logical(FieldKind::Folded, "a\nb\n\nc"),
+    ///     Item::Folded(
+    ///         "a".to_string(),
+    ///         vec!["b".to_string(), ".".to_string(), "c".to_string()]
+    ///     )
+    /// );
+    /// ```
+    pub fn from_logical(kind: FieldKind, s: &str) -> Item {
+        let dot = |x: &str| if x.is_empty() { "." } else { x }.to_string();
+
+        match kind {
+            FieldKind::Relations if s.len() > WRAP_WIDTH && split_commas(s).nth(1).is_some() => {
+                let mut lines = split_commas(s)
+                    .map(|x| format!("{},", x))
+                    .collect::<Vec<_>>();
+                if let Some(last) = lines.last_mut() {
+                    last.pop();
+                }
+
+                Item::MultiLine(lines)
+            }
+            FieldKind::Simple | FieldKind::Relations | FieldKind::List => {
+                Item::OneLine(s.to_string())
+            }
+            FieldKind::Folded => match s.split_once('\n') {
+                Some((first, rest)) => {
+                    Item::Folded(first.to_string(), rest.split('\n').map(dot).collect())
+                }
+                None => Item::OneLine(s.to_string()),
+            },
+            FieldKind::MultiLine => Item::MultiLine(s.split('\n').map(dot).collect()),
+        }
+    }
+}
+
+const fn field(name: &'static str, kind: FieldKind, files: &'static [FileKind]) -> FieldInfo {
+    FieldInfo { name, kind, files }
+}
+
+use FieldKind::*;
+use FileKind::*;
+
+const BIN: &[FileKind] = &[Binary];
+const SRC: &[FileKind] = &[Source];
+const BIN_SRC: &[FileKind] = &[Binary, Source];
+const REL: &[FileKind] = &[Release];
+const CHG: &[FileKind] = &[Changes];
+const APT: &[FileKind] = &[SourcesList];
+
+const FIELDS: &[FieldInfo] = &[
+    field("Package", Simple, BIN_SRC),
+    field("Source", Simple, &[Binary, Source, Changes]),
+    field("Version", Simple, &[Binary, Source, Changes, Release]),
+    field("Architecture", List, &[Binary, Source, Changes]),
+    field("Architectures", List, &[Release, SourcesList]),
+    field("Maintainer", Simple, &[Binary, Source, Changes]),
+    field("Original-Maintainer", Simple, BIN_SRC),
+    field("Uploaders", List, SRC),
+    field("Changed-By", Simple, CHG),
+    field("Bugs", Simple, BIN),
+    field("Installed-Size", Simple, BIN),
+    field("Size", Simple, BIN),
+    field("Filename", Simple, BIN),
+    field("MSDOS-Filename", Simple, BIN),
+    field("Section", Simple, BIN_SRC),
+    field("Priority", Simple, BIN_SRC),
+    field("Essential", Simple, BIN),
+    field("Protected", Simple, BIN),
+    field("Important", Simple, BIN),
+    field("Multi-Arch", Simple, BIN),
+    field("Status", Simple, BIN),
+    field("Conffiles", MultiLine, BIN),
+    field("Config-Version", Simple, BIN),
+    field("Revision", Simple, BIN),
+    field("Triggers-Pending", List, BIN),
+    field("Triggers-Awaited", List, BIN),
+    field("Pre-Depends", Relations, BIN),
+    field("Depends", Relations, BIN),
+    field("Recommends", Relations, BIN),
+    field("Suggests", Relations, BIN),
+    field("Enhances", Relations, BIN),
+    field("Breaks", Relations, BIN),
+    field("Conflicts", Relations, BIN),
+    field("Replaces", Relations, BIN),
+    field("Provides", Relations, BIN),
+    field("Built-Using", Relations, BIN),
+    field("Static-Built-Using", Relations, BIN),
+    field("Build-Depends", Relations, SRC),
+    field("Build-Depends-Indep", Relations, SRC),
+    field("Build-Depends-Arch", Relations, SRC),
+    field("Build-Conflicts", Relations, SRC),
+    field("Build-Conflicts-Indep", Relations, SRC),
+    field("Build-Conflicts-Arch", Relations, SRC),
+    field("Build-Essential", Simple, BIN),
+    field("Rules-Requires-Root", Simple, SRC),
+    field("Standards-Version", Simple, SRC),
+    field("Homepage", Simple, BIN_SRC),
+    field("Description", Folded, &[Binary, Changes, Release]),
+    field("Description-md5", Simple, BIN),
+    field("Tag", List, BIN),
+    field("Task", List, BIN),
+    field("Vcs-Arch", Simple, SRC),
+    field("Vcs-Bzr", Simple, SRC),
+    field("Vcs-Browser", Simple, SRC),
+    field("Vcs-Cvs", Simple, SRC),
+    field("Vcs-Darcs", Simple, SRC),
+    field("Vcs-Git", Simple, SRC),
+    field("Vcs-Hg", Simple, SRC),
+    field("Vcs-Mtn", Simple, SRC),
+    field("Vcs-Svn", Simple, SRC),
+    field("Testsuite", List, SRC),
+    field("Testsuite-Triggers", List, SRC),
+    field("Package-List", MultiLine, SRC),
+    field("Package-Type", Simple, BIN),
+    field("Binary", List, &[Source, Changes]),
+    field("Format", Simple, &[Source, Changes]),
+    field("Files", MultiLine, &[Source, Changes]),
+    field("Directory", Simple, SRC),
+    field("Checksums-Sha1", MultiLine, &[Source, Changes]),
+    field("Checksums-Sha256", MultiLine, &[Source, Changes]),
+    field("Checksums-Sha512", MultiLine, &[Source, Changes]),
+    field("MD5sum", Simple, &[Binary, Release]),
+    field("SHA1", Simple, &[Binary, Release]),
+    field("SHA256", Simple, &[Binary, Release]),
+    field("SHA512", Simple, &[Binary, Release]),
+    field("Date", Simple, &[Changes, Release]),
+    field("Distribution", Simple, CHG),
+    field("Urgency", Simple, CHG),
+    field("Changes", MultiLine, CHG),
+    field("Closes", List, CHG),
+    field("Origin", Simple, &[Binary, Release]),
+    field("Label", Simple, REL),
+    field("Suite", Simple, REL),
+    field("Codename", Simple, REL),
+    field("Components", List, &[Release, SourcesList]),
+    field("Valid-Until", Simple, REL),
+    field("NotAutomatic", Simple, REL),
+    field("ButAutomaticUpgrades", Simple, REL),
+    field("Acquire-By-Hash", Simple, REL),
+    field("No-Support-for-Architecture-all", Simple, REL),
+    field("Signed-By", Simple, &[Release, SourcesList]),
+    field("Types", List, APT),
+    field("URIs", List, APT),
+    field("Suites", List, APT),
+    field("Enabled", Simple, APT),
+];
+
+/// The names of [`FIELDS`], for [`known_fields`](crate::known_fields)
+pub(crate) const NAMES: [&str; FIELDS.len()] = {
+    let mut names = [""; FIELDS.len()];
+    let mut i = 0;
+
+    while i < FIELDS.len() {
+        names[i] = FIELDS[i].name;
+        i += 1;
+    }
+
+    names
+};
+
+const fn eq_ignore_ascii_case(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut i = 0;
+    while i < a.len() {
+        if !a[i].eq_ignore_ascii_case(&b[i]) {
+            return false;
+        }
+        i += 1;
+    }
+
+    true
+}
+
+/// Every field once, ignoring case, and in at least one file, checked at
+/// compile time
+const _: () = {
+    let mut i = 0;
+
+    while i < FIELDS.len() {
+        assert!(!FIELDS[i].files.is_empty(), "field in no file");
+
+        let mut j = i + 1;
+        while j < FIELDS.len() {
+            assert!(
+                !eq_ignore_ascii_case(FIELDS[i].name, FIELDS[j].name),
+                "duplicate field"
+            );
+            j += 1;
+        }
+
+        i += 1;
+    }
+};
+
+#[cfg(test)]
+mod tests {
+    use super::{FieldInfo, FieldKind, FileKind};
+    use crate::{parse_multi, parse_one, test_util::fixtures, Item, Paragraph};
+
+    #[test]
+    fn test_fixture_fields_known() {
+        let mut ps = vec![];
+        ps.extend(fixtures::status());
+        ps.extend(parse_multi(fixtures::STATUS_STATES).unwrap());
+        ps.extend(fixtures::packages());
+        ps.extend(
+            parse_multi(&std::fs::read_to_string(fixtures::PACKAGES_SORTED_PATH).unwrap()).unwrap(),
+        );
+        ps.extend(parse_multi(include_str!("../tests/fixtures/deb/Packages")).unwrap());
+        ps.extend(fixtures::sources());
+        ps.push(fixtures::release().paragraph().clone());
+
+        let unknown = ps
+            .iter()
+            .flat_map(Paragraph::keys)
+            .filter(|x| FieldInfo::lookup(x).is_none())
+            .collect::<Vec<_>>();
+
+        assert!(unknown.is_empty(), "unknown fields {:?}", unknown);
+    }
+
+    #[test]
+    fn test_lookup() {
+        let md5 = FieldInfo::lookup("MD5Sum").unwrap();
+
+        assert_eq!(md5.name, "MD5sum");
+        assert_eq!(md5.kind, FieldKind::Simple);
+        assert_eq!(md5.kind_in(FileKind::Release), FieldKind::MultiLine);
+        assert_eq!(
+            FieldInfo::lookup("description").unwrap().kind,
+            FieldKind::Folded
+        );
+        assert!(FieldInfo::lookup("Build-Depends")
+            .unwrap()
+            .belongs_to(FileKind::Source));
+        assert!(!FieldInfo::lookup("Build-Depends")
+            .unwrap()
+            .belongs_to(FileKind::Binary));
+        assert!(FieldInfo::lookup("X-Custom").is_none());
+        assert_eq!(FieldInfo::all().len(), super::NAMES.len());
+    }
+
+    #[test]
+    fn test_joined_value() {
+        let r = parse_one(
+            "Package: a
+Depends: libc6 (>= 2.36),
+ libgcc-s1 (>= 3.0), libstdc++6 (>= 12),
+ zlib1g (>= 1:1.2.0) | libz-ng
+Description: synopsis
+ First paragraph,
+ two lines.
+ .
+ Second paragraph:
+   verbatim
+",
+        )
+        .unwrap();
+
+        let depends = r["Depends"].joined_value(FieldKind::Relations);
+
+        assert_eq!(
+            depends,
+            "libc6 (>= 2.36), libgcc-s1 (>= 3.0), libstdc++6 (>= 12), \
+             zlib1g (>= 1:1.2.0) | libz-ng"
+        );
+        assert_eq!(
+            Item::from_logical(FieldKind::Relations, &depends),
+            Item::MultiLine(vec![
+                "libc6 (>= 2.36),".to_string(),
+                "libgcc-s1 (>= 3.0),".to_string(),
+                "libstdc++6 (>= 12),".to_string(),
+                "zlib1g (>= 1:1.2.0) | libz-ng".to_string(),
+            ])
+        );
+        assert_eq!(
+            Item::from_logical(FieldKind::Relations, "a, b"),
+            Item::OneLine("a, b".to_string())
+        );
+
+        let description = r["Description"].joined_value(FieldKind::Folded);
+
+        assert_eq!(
+            description,
+            "synopsis\nFirst paragraph,\ntwo lines.\n\nSecond paragraph:\n  verbatim"
+        );
+        assert_eq!(
+            Item::from_logical(FieldKind::Folded, &description),
+            r["Description"]
+        );
+        assert_eq!(
+            Item::from_logical(FieldKind::Folded, "synopsis"),
+            Item::OneLine("synopsis".to_string())
+        );
+
+        for (k, v) in &r {
+            let kind = FieldInfo::lookup(k).unwrap().kind;
+            let logical = v.joined_value(kind);
+
+            assert_eq!(
+                Item::from_logical(kind, &logical).joined_value(kind),
+                logical
+            );
+        }
+    }
+}
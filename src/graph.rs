@@ -0,0 +1,536 @@
+use std::{collections::VecDeque, fmt::Display};
+
+use crate::{IndexMap, PackageId, Paragraph, ParagraphExt, Relation, RelationError, Version};
+
+/// The field a dependency edge comes from
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DependencyKind {
+    PreDepends,
+    Depends,
+    Recommends,
+    Suggests,
+    Enhances,
+    Breaks,
+    Conflicts,
+    Replaces,
+    Other(String),
+}
+
+impl DependencyKind {
+    /// Whether a package of this relation is expected to exist: a missing
+    /// `Breaks`, `Conflicts`, `Replaces` or `Enhances` target is no problem
+    fn needs_target(&self) -> bool {
+        !matches!(
+            self,
+            DependencyKind::Breaks
+                | DependencyKind::Conflicts
+                | DependencyKind::Replaces
+                | DependencyKind::Enhances
+        )
+    }
+}
+
+impl From<&str> for DependencyKind {
+    fn from(s: &str) -> Self {
+        match s.to_ascii_lowercase().as_str() {
+            "pre-depends" => DependencyKind::PreDepends,
+            "depends" => DependencyKind::Depends,
+            "recommends" => DependencyKind::Recommends,
+            "suggests" => DependencyKind::Suggests,
+            "enhances" => DependencyKind::Enhances,
+            "breaks" => DependencyKind::Breaks,
+            "conflicts" => DependencyKind::Conflicts,
+            "replaces" => DependencyKind::Replaces,
+            _ => DependencyKind::Other(s.to_string()),
+        }
+    }
+}
+
+impl Display for DependencyKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            DependencyKind::PreDepends => "Pre-Depends",
+            DependencyKind::Depends => "Depends",
+            DependencyKind::Recommends => "Recommends",
+            DependencyKind::Suggests => "Suggests",
+            DependencyKind::Enhances => "Enhances",
+            DependencyKind::Breaks => "Breaks",
+            DependencyKind::Conflicts => "Conflicts",
+            DependencyKind::Replaces => "Replaces",
+            DependencyKind::Other(s) => s,
+        })
+    }
+}
+
+/// A relation from one node of a [`DepGraph`] to a package satisfying it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edge {
+    /// Index into [`DepGraph::nodes`] of the package having the relation
+    pub from: usize,
+    /// Index into [`DepGraph::nodes`] of the package satisfying it
+    pub to: usize,
+    pub kind: DependencyKind,
+    pub relation: Relation,
+}
+
+/// A clause no package in the set satisfies
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unresolved {
+    pub package: PackageId,
+    pub kind: DependencyKind,
+    /// The `|`-separated alternatives of the clause
+    pub alternatives: Vec<Relation>,
+}
+
+/// A relation field that couldn't be parsed, and so has no edges
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidRelations {
+    pub package: PackageId,
+    pub kind: DependencyKind,
+    pub error: RelationError,
+}
+
+/// Relations between the packages of a set, see [`build_graph`]
+#[derive(Debug, Clone)]
+pub struct DepGraph {
+    nodes: Vec<PackageId>,
+    edges: Vec<Edge>,
+    /// Edge indices by source node
+    out: Vec<Vec<usize>>,
+    unresolved: Vec<Unresolved>,
+    invalid: Vec<InvalidRelations>,
+}
+
+/// Build the graph of the relations in `fields` between the packages of
+/// `paragraphs`
+///
+/// Every paragraph with a `Package` field is a node, identified by
+/// [`ParagraphExt::identity`]. A relation gets an edge to every package
+/// satisfying it, either by name and `Version` or through `Provides`. An
+/// unversioned provide only satisfies unversioned relations, as in dpkg.
+/// Every alternative of a clause gets its edges. Architecture qualifiers are
+/// ignored, and so are relations of a package to itself, like conflicting
+/// with a virtual package it provides.
+///
+/// Clauses nothing satisfies end up in [`DepGraph::unresolved`], except for
+/// `Breaks`, `Conflicts`, `Replaces` and `Enhances`; fields that don't parse
+/// end up in [`DepGraph::invalid`].
+///
+/// ```rust
+/// use eight_deep_parser::{build_graph, parse_multi};
+///
+/// let r = parse_multi(
+///     "Package: a\nDepends: b\n\nPackage: b\nVersion: 1.0\nDepends: missing\n",
+/// )
+/// .unwrap();
+/// let graph = build_graph(&r, &["Depends"]);
+///
+/// assert_eq!(graph.reverse_dependencies("b")[0].name, "a");
+/// assert_eq!(graph.unresolved()[0].alternatives[0].name, "missing");
+/// ```
+pub fn build_graph(paragraphs: &[Paragraph], fields: &[&str]) -> DepGraph {
+    let mut nodes = vec![];
+    let mut sources = vec![];
+    // Everything answering to a name: the node and the version it provides
+    let mut providers: IndexMap<String, Vec<(usize, Option<Version>)>> = IndexMap::new();
+
+    for p in paragraphs {
+        let id = match p.identity() {
+            Some(id) => id,
+            None => continue,
+        };
+
+        let node = nodes.len();
+        let version = p.get_one("Version").and_then(|x| x.parse().ok());
+
+        providers
+            .entry(id.name.clone())
+            .or_default()
+            .push((node, version));
+
+        for provide in p.provides().unwrap_or_default() {
+            providers
+                .entry(provide.name)
+                .or_default()
+                .push((node, provide.version));
+        }
+
+        nodes.push(id);
+        sources.push(p);
+    }
+
+    let mut graph = DepGraph {
+        out: vec![vec![]; nodes.len()],
+        nodes,
+        edges: vec![],
+        unresolved: vec![],
+        invalid: vec![],
+    };
+
+    for (from, p) in sources.into_iter().enumerate() {
+        for field in fields {
+            let kind = DependencyKind::from(*field);
+
+            let clauses = match p.relations(field) {
+                None => continue,
+                Some(Ok(clauses)) => clauses,
+                Some(Err(error)) => {
+                    graph.invalid.push(InvalidRelations {
+                        package: graph.nodes[from].clone(),
+                        kind,
+                        error,
+                    });
+                    continue;
+                }
+            };
+
+            for clause in clauses {
+                let mut resolved = false;
+
+                for relation in &clause {
+                    let candidates = providers.get(&relation.name);
+                    let mut targets = vec![];
+
+                    for (to, version) in candidates.into_iter().flatten() {
+                        if !relation.satisfied_by(version.as_ref()) {
+                            continue;
+                        }
+
+                        resolved = true;
+
+                        // A package can answer to a name both as itself and
+                        // through its own Provides
+                        if *to != from && !targets.contains(to) {
+                            targets.push(*to);
+                            graph.out[from].push(graph.edges.len());
+                            graph.edges.push(Edge {
+                                from,
+                                to: *to,
+                                kind: kind.clone(),
+                                relation: relation.clone(),
+                            });
+                        }
+                    }
+                }
+
+                if !resolved && kind.needs_target() {
+                    graph.unresolved.push(Unresolved {
+                        package: graph.nodes[from].clone(),
+                        kind: kind.clone(),
+                        alternatives: clause,
+                    });
+                }
+            }
+        }
+    }
+
+    graph
+}
+
+impl DepGraph {
+    /// All packages, in the order of the input
+    pub fn nodes(&self) -> &[PackageId] {
+        &self.nodes
+    }
+
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// Clauses of the graph's fields no package satisfies
+    pub fn unresolved(&self) -> &[Unresolved] {
+        &self.unresolved
+    }
+
+    /// Relation fields that couldn't be parsed
+    pub fn invalid(&self) -> &[InvalidRelations] {
+        &self.invalid
+    }
+
+    fn named(&self, name: &str) -> Vec<usize> {
+        (0..self.nodes.len())
+            .filter(|x| self.nodes[*x].name == name)
+            .collect()
+    }
+
+    /// Packages with a relation satisfied by a package called `name`, i.e.
+    /// the ones affected by removing it
+    ///
+    /// A relation counts even if another alternative of its clause is
+    /// satisfied by some other package.
+    pub fn reverse_dependencies(&self, name: &str) -> Vec<&PackageId> {
+        let targets = self.named(name);
+        let mut result = vec![false; self.nodes.len()];
+
+        for edge in &self.edges {
+            if targets.contains(&edge.to) && !targets.contains(&edge.from) {
+                result[edge.from] = true;
+            }
+        }
+
+        self.select(&result)
+    }
+
+    /// Packages reachable from the ones called `name` through relations of
+    /// `kinds`, not including the packages called `name`
+    pub fn transitive_closure(&self, name: &str, kinds: &[DependencyKind]) -> Vec<&PackageId> {
+        let starts = self.named(name);
+        let mut seen = vec![false; self.nodes.len()];
+        let mut queue = VecDeque::new();
+
+        for start in &starts {
+            seen[*start] = true;
+            queue.push_back(*start);
+        }
+
+        while let Some(node) = queue.pop_front() {
+            for edge in self.out[node].iter().map(|x| &self.edges[*x]) {
+                if !seen[edge.to] && kinds.contains(&edge.kind) {
+                    seen[edge.to] = true;
+                    queue.push_back(edge.to);
+                }
+            }
+        }
+
+        for start in starts {
+            seen[start] = false;
+        }
+
+        self.select(&seen)
+    }
+
+    fn select(&self, mask: &[bool]) -> Vec<&PackageId> {
+        self.nodes
+            .iter()
+            .zip(mask)
+            .filter(|(_, x)| **x)
+            .map(|(x, _)| x)
+            .collect()
+    }
+
+    /// Find a cycle of relations of `kinds`
+    ///
+    /// Returns the packages of the cycle in order: each one has a relation
+    /// satisfied by the next, and the last one by the first.
+    pub fn find_cycle(&self, kinds: &[DependencyKind]) -> Option<Vec<&PackageId>> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum State {
+            New,
+            OnPath,
+            Done,
+        }
+
+        let mut state = vec![State::New; self.nodes.len()];
+
+        for root in 0..self.nodes.len() {
+            if state[root] != State::New {
+                continue;
+            }
+
+            // The current path, with the next outgoing edge to look at
+            let mut path = vec![(root, 0)];
+            state[root] = State::OnPath;
+
+            while let Some((node, next)) = path.last_mut() {
+                let node = *node;
+
+                let edge = match self.out[node].get(*next) {
+                    Some(edge) => &self.edges[*edge],
+                    None => {
+                        state[node] = State::Done;
+                        path.pop();
+                        continue;
+                    }
+                };
+
+                *next += 1;
+
+                if !kinds.contains(&edge.kind) {
+                    continue;
+                }
+
+                match state[edge.to] {
+                    State::New => {
+                        state[edge.to] = State::OnPath;
+                        path.push((edge.to, 0));
+                    }
+                    State::OnPath => {
+                        let start = path.iter().position(|(x, _)| *x == edge.to).unwrap();
+
+                        return Some(path[start..].iter().map(|(x, _)| &self.nodes[*x]).collect());
+                    }
+                    State::Done => {}
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{build_graph, parse_multi, DependencyKind, PackageId};
+
+    const REPO: &str = "Package: app
+Version: 1.0
+Architecture: amd64
+Depends: libxyz (>= 1.2), mail-transport-agent, libxyz-abi (= 1.3)
+Suggests: app-doc
+
+Package: libxyz
+Version: 1.3-1
+Architecture: amd64
+Provides: libxyz-abi (= 1.3)
+Depends: libxyz-data (= 1.3-1), libbase
+
+Package: libxyz-data
+Version: 1.3-1
+Architecture: all
+Depends: libxyz (>= 1.0)
+
+Package: libbase
+Version: 2.0
+Architecture: amd64
+Pre-Depends: missing-loader | libbase
+
+Package: postfix
+Version: 3.7.10-0+deb12u1
+Architecture: amd64
+Provides: mail-transport-agent
+Conflicts: mail-transport-agent
+
+Package: exim4-daemon-light
+Version: 4.96-15+deb12u4
+Architecture: amd64
+Provides: mail-transport-agent
+Depends: exim4-base (>= 4.96)
+
+Package: legacy
+Version: 0.1
+Architecture: amd64
+Depends: libxyz (<< 1.0), mail-transport-agent (>= 1)
+Breaks: app (<< 0.5), gone
+Recommends: libxyz (>= 1.0
+";
+
+    const FIELDS: &[&str] = &[
+        "Pre-Depends",
+        "Depends",
+        "Recommends",
+        "Suggests",
+        "Breaks",
+        "Conflicts",
+    ];
+
+    fn names(ids: Vec<&PackageId>) -> Vec<&str> {
+        ids.into_iter().map(|x| x.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_reverse_dependencies() {
+        let r = parse_multi(REPO).unwrap();
+        let graph = build_graph(&r, FIELDS);
+
+        assert_eq!(graph.nodes().len(), 7);
+        assert_eq!(
+            names(graph.reverse_dependencies("libxyz")),
+            vec!["app", "libxyz-data"]
+        );
+        assert_eq!(names(graph.reverse_dependencies("libbase")), vec!["libxyz"]);
+        assert_eq!(
+            names(graph.reverse_dependencies("postfix")),
+            vec!["app"],
+            "through the virtual mail-transport-agent, ignoring its own Conflicts"
+        );
+        assert!(graph.reverse_dependencies("legacy").is_empty());
+    }
+
+    #[test]
+    fn test_virtual_package() {
+        let r = parse_multi(REPO).unwrap();
+        let graph = build_graph(&r, FIELDS);
+
+        let mta = graph
+            .edges()
+            .iter()
+            .filter(|x| x.relation.name == "mail-transport-agent")
+            .map(|x| {
+                (
+                    graph.nodes()[x.from].name.as_str(),
+                    graph.nodes()[x.to].name.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            mta,
+            vec![
+                ("app", "postfix"),
+                ("app", "exim4-daemon-light"),
+                ("postfix", "exim4-daemon-light"),
+            ]
+        );
+        assert!(graph
+            .edges()
+            .iter()
+            .any(|x| x.relation.name == "libxyz-abi" && graph.nodes()[x.to].name == "libxyz"));
+    }
+
+    #[test]
+    fn test_unresolved() {
+        let r = parse_multi(REPO).unwrap();
+        let graph = build_graph(&r, FIELDS);
+
+        assert_eq!(
+            graph
+                .unresolved()
+                .iter()
+                .map(|x| format!("{} {}: {}", x.package, x.kind, x.alternatives[0]))
+                .collect::<Vec<_>>(),
+            vec![
+                "app:amd64 Suggests: app-doc",
+                "exim4-daemon-light:amd64 Depends: exim4-base (>= 4.96)",
+                "legacy:amd64 Depends: libxyz (<< 1.0)",
+                // An unversioned provide doesn't satisfy a versioned relation
+                "legacy:amd64 Depends: mail-transport-agent (>= 1)",
+            ]
+        );
+
+        assert_eq!(graph.invalid().len(), 1);
+        assert_eq!(graph.invalid()[0].kind, DependencyKind::Recommends);
+    }
+
+    #[test]
+    fn test_transitive_closure() {
+        let r = parse_multi(REPO).unwrap();
+        let graph = build_graph(&r, FIELDS);
+
+        assert_eq!(
+            names(graph.transitive_closure("app", &[DependencyKind::Depends])),
+            vec![
+                "libxyz",
+                "libxyz-data",
+                "libbase",
+                "postfix",
+                "exim4-daemon-light"
+            ]
+        );
+        assert!(graph.transitive_closure("app", &[]).is_empty());
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        let r = parse_multi(REPO).unwrap();
+        let graph = build_graph(&r, FIELDS);
+
+        let depends = [DependencyKind::PreDepends, DependencyKind::Depends];
+
+        assert_eq!(
+            graph.find_cycle(&depends).map(names),
+            Some(vec!["libxyz", "libxyz-data"])
+        );
+        assert_eq!(graph.find_cycle(&[DependencyKind::Suggests]), None);
+        assert_eq!(build_graph(&r[..2], FIELDS).find_cycle(&depends), None);
+    }
+}
@@ -0,0 +1,248 @@
+use std::{borrow::Borrow, fmt::Display, str::FromStr};
+
+use crate::{IndexMap, PackageIdError, Paragraph, ParagraphExt};
+
+/// A package as dpkg tells Multi-Arch packages apart: name and architecture
+///
+/// Displayed and parsed in the `name:arch` form, or just `name` without an
+/// architecture.
+///
+/// ```rust
+/// use eight_deep_parser::{parse_one, PackageId, ParagraphExt};
+///
+/// let r = parse_one("Package: libc6\nArchitecture: i386\n").unwrap();
+/// let id = r.identity().unwrap();
+///
+/// assert_eq!(id.to_string(), "libc6:i386");
+/// assert_eq!("libc6:i386".parse::<PackageId>().unwrap(), id);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PackageId {
+    pub name: String,
+    pub arch: Option<String>,
+}
+
+impl PackageId {
+    pub fn new(name: impl Into<String>, arch: Option<String>) -> Self {
+        Self {
+            name: name.into(),
+            arch,
+        }
+    }
+}
+
+impl Display for PackageId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.arch {
+            Some(arch) => write!(f, "{}:{}", self.name, arch),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+impl FromStr for PackageId {
+    type Err = PackageIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, arch) = match s.split_once(':') {
+            Some((name, arch)) => (name, Some(arch)),
+            None => (s, None),
+        };
+
+        let invalid = |x: &str| x.is_empty() || x.contains(|c: char| c.is_whitespace() || c == ':');
+
+        if invalid(name) || arch.map(invalid).unwrap_or(false) {
+            return Err(PackageIdError(s.to_string()));
+        }
+
+        Ok(Self::new(name, arch.map(|x| x.to_string())))
+    }
+}
+
+pub(crate) fn identity(p: &Paragraph, keep_arch_all: bool) -> Option<PackageId> {
+    let name = p.get_one("Package")?;
+    let arch = p
+        .get_one("Architecture")
+        .filter(|x| keep_arch_all || *x != "all");
+
+    Some(PackageId::new(name, arch.map(|x| x.to_string())))
+}
+
+/// What identifies a paragraph when indexing or deduplicating a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyBy {
+    /// The `Package` field alone
+    Package,
+    /// `Package` and `Architecture`, see [`ParagraphExt::identity`]
+    PackageId,
+}
+
+impl KeyBy {
+    /// The key of `p`, or `None` if it has no `Package` field
+    pub fn key(self, p: &Paragraph) -> Option<PackageId> {
+        match self {
+            KeyBy::Package => p.get_one("Package").map(|x| PackageId::new(x, None)),
+            KeyBy::PackageId => p.identity(),
+        }
+    }
+}
+
+/// Map every key to the positions of the paragraphs having it, in order
+///
+/// Paragraphs without a `Package` field are left out.
+///
+/// ```rust
+/// use eight_deep_parser::{index_by, parse_multi, KeyBy};
+///
+/// let r = parse_multi("Package: a\nArchitecture: amd64\n\nPackage: a\nArchitecture: i386\n").unwrap();
+///
+/// assert_eq!(index_by(&r, KeyBy::Package).len(), 1);
+/// assert_eq!(index_by(&r, KeyBy::PackageId).len(), 2);
+/// ```
+pub fn index_by<P>(ps: &[P], key: KeyBy) -> IndexMap<PackageId, Vec<usize>>
+where
+    P: Borrow<Paragraph>,
+{
+    let mut result: IndexMap<PackageId, Vec<usize>> = IndexMap::new();
+
+    for (i, p) in ps.iter().enumerate() {
+        if let Some(k) = key.key(p.borrow()) {
+            result.entry(k).or_default().push(i);
+        }
+    }
+
+    result
+}
+
+/// Merge paragraphs from several sources, a later paragraph replacing an
+/// earlier one with the same key
+///
+/// The replacement takes the place of the first paragraph with its key.
+/// Paragraphs without a `Package` field are all kept.
+///
+/// ```rust
+/// use eight_deep_parser::{merge_by, parse_multi, KeyBy, ParagraphExt};
+///
+/// let mut r = parse_multi("Package: a\nVersion: 1\n\nPackage: b\n").unwrap();
+/// r.extend(parse_multi("Package: a\nVersion: 2\n").unwrap());
+///
+/// let merged = merge_by(r, KeyBy::Package);
+///
+/// assert_eq!(merged.len(), 2);
+/// assert_eq!(merged[0].get_one("Version"), Some("2"));
+/// ```
+pub fn merge_by<P>(ps: impl IntoIterator<Item = P>, key: KeyBy) -> Vec<P>
+where
+    P: Borrow<Paragraph>,
+{
+    let mut result: Vec<P> = vec![];
+    let mut positions: IndexMap<PackageId, usize> = IndexMap::new();
+
+    for p in ps {
+        match key.key(p.borrow()) {
+            Some(k) => match positions.get(&k) {
+                Some(i) => result[*i] = p,
+                None => {
+                    positions.insert(k, result.len());
+                    result.push(p);
+                }
+            },
+            None => result.push(p),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{index_by, parse_multi, parse_one, KeyBy, PackageId, PackageIdError, ParagraphExt};
+
+    const STATUS: &str = "Package: libc6
+Status: install ok installed
+Architecture: amd64
+Multi-Arch: same
+Version: 2.36-9+deb12u4
+
+Package: bash
+Status: install ok installed
+Architecture: amd64
+Version: 5.2.15-2+b7
+
+Package: libc6
+Status: install ok installed
+Architecture: i386
+Multi-Arch: same
+Version: 2.36-9+deb12u4
+
+Package: tzdata
+Status: install ok installed
+Architecture: all
+Multi-Arch: foreign
+Version: 2024a-0+deb12u1
+";
+
+    #[test]
+    fn test_identity() {
+        let r = parse_multi(STATUS).unwrap();
+
+        assert_eq!(
+            r[0].identity(),
+            Some(PackageId::new("libc6", Some("amd64".to_string())))
+        );
+        assert_eq!(r[3].identity(), Some(PackageId::new("tzdata", None)));
+        assert_eq!(
+            r[3].identity_with(true),
+            Some(PackageId::new("tzdata", Some("all".to_string())))
+        );
+        assert_eq!(
+            parse_one("Package: a\n").unwrap().identity(),
+            Some(PackageId::new("a", None))
+        );
+        assert_eq!(parse_one("Source: a\n").unwrap().identity(), None);
+    }
+
+    #[test]
+    fn test_package_id_str() {
+        for s in ["libc6:i386", "tzdata"] {
+            assert_eq!(s.parse::<PackageId>().unwrap().to_string(), s);
+        }
+
+        for s in ["", ":i386", "libc6:", "a:b:c", "a b"] {
+            assert_eq!(s.parse::<PackageId>(), Err(PackageIdError(s.to_string())));
+        }
+
+        let mut ids = [
+            PackageId::new("libc6", Some("i386".to_string())),
+            PackageId::new("libc6", None),
+            PackageId::new("bash", Some("amd64".to_string())),
+        ];
+        ids.sort();
+
+        assert_eq!(
+            ids.iter().map(|x| x.to_string()).collect::<Vec<_>>(),
+            vec!["bash:amd64", "libc6", "libc6:i386"]
+        );
+    }
+
+    fn id(s: &str) -> PackageId {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_index_by_package_id() {
+        let r = parse_multi(STATUS).unwrap();
+
+        let by_id = index_by(&r, KeyBy::PackageId);
+
+        assert_eq!(by_id.len(), 4);
+        assert_eq!(by_id.get(&id("libc6:amd64")), Some(&vec![0]));
+        assert_eq!(by_id.get(&id("libc6:i386")), Some(&vec![2]));
+        assert_eq!(by_id.get(&id("tzdata")), Some(&vec![3]));
+
+        let by_name = index_by(&r, KeyBy::Package);
+
+        assert_eq!(by_name.len(), 3);
+        assert_eq!(by_name.get(&id("libc6")), Some(&vec![0, 2]));
+    }
+}
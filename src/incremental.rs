@@ -0,0 +1,337 @@
+use std::ops::Range;
+
+use crate::{
+    cst::{ends_paragraph, is_blank_line},
+    parse_one, Paragraph, Result,
+};
+
+/// A file parsed paragraph by paragraph, with the byte span of each, to be
+/// kept up to date by [`reparse`] while the file is edited
+///
+/// Paragraphs are split like [`ParagraphDecoder`](crate::ParagraphDecoder)
+/// does: at empty lines, skipping lines of whitespace and comment lines
+/// between paragraphs. Each is parsed by [`parse_one`] on its own, so a
+/// paragraph that doesn't parse has its error and the others are still
+/// there. Error lines and offsets are those of the paragraph text.
+///
+/// ```rust
+/// use eight_deep_parser::{reparse, ParagraphExt, ParsedFile};
+///
+/// let old = "Package: a\nVersion: 1\n\nPackage: b\n";
+/// let file = ParsedFile::parse(old);
+///
+/// let new = "Package: a\nVersion: 2\n\nPackage: b\n";
+/// let file = reparse(&file, old, new, 20..21);
+///
+/// let a = &file.paragraphs()[0];
+/// assert_eq!(a.paragraph.as_ref().unwrap().get_one("Version"), Some("2"));
+/// assert_eq!(file.paragraphs()[1].span, 23..34);
+/// ```
+#[derive(Debug, Default)]
+pub struct ParsedFile {
+    paragraphs: Vec<ParsedParagraph>,
+}
+
+/// A paragraph of a [`ParsedFile`]
+#[derive(Debug)]
+pub struct ParsedParagraph {
+    /// From the start of the first line to the end of the last, with its
+    /// line break
+    pub span: Range<usize>,
+    pub paragraph: Result<Paragraph>,
+}
+
+impl ParsedFile {
+    pub fn parse(s: &str) -> Self {
+        let mut paragraphs = vec![];
+        scan(s, 0, &mut paragraphs);
+
+        Self { paragraphs }
+    }
+
+    pub fn paragraphs(&self) -> &[ParsedParagraph] {
+        &self.paragraphs
+    }
+
+    /// The paragraph whose span holds byte `offset`
+    pub fn paragraph_at(&self, offset: usize) -> Option<&ParsedParagraph> {
+        let i = self.paragraphs.partition_point(|x| x.span.end <= offset);
+
+        self.paragraphs.get(i).filter(|x| x.span.contains(&offset))
+    }
+}
+
+/// Update `prev`, parsed from `old_text`, for `new_text`, which is
+/// `old_text` with the bytes of `edit` replaced
+///
+/// Only the paragraphs the edit can change are parsed again: from the empty
+/// line before the edit to the first empty line after it, which takes in
+/// paragraphs merged by removing a blank line or split by adding one. The
+/// spans of the paragraphs after that are shifted. The result is what
+/// [`ParsedFile::parse`] gives for `new_text`.
+///
+/// # Panics
+///
+/// Panics if `edit` isn't in `old_text`, or isn't on character boundaries.
+pub fn reparse(
+    prev: &ParsedFile,
+    old_text: &str,
+    new_text: &str,
+    edit: Range<usize>,
+) -> ParsedFile {
+    assert!(
+        old_text.is_char_boundary(edit.start)
+            && old_text.is_char_boundary(edit.end)
+            && edit.start <= edit.end,
+        "edit {:?} out of the old text",
+        edit
+    );
+
+    let new_end = new_text.len() + edit.end - old_text.len();
+
+    // after an empty line, a paragraph starts with the next line whatever
+    // came before, so paragraphs can be parsed again from there
+    let mut start = old_text[..edit.start].rfind('\n').map_or(0, |x| x + 1);
+    while start > 0 {
+        let line_start = old_text[..start - 1].rfind('\n').map_or(0, |x| x + 1);
+
+        if ends_paragraph(&old_text.as_bytes()[line_start..start]) {
+            break;
+        }
+
+        start = line_start;
+    }
+
+    // the first empty line after the edit whose line break before it isn't
+    // part of the edit either
+    let mut end = new_text[new_end..]
+        .find('\n')
+        .map_or(new_text.len(), |x| new_end + x + 1);
+    while end < new_text.len() {
+        let line_end = new_text[end..]
+            .find('\n')
+            .map_or(new_text.len(), |x| end + x + 1);
+        let empty = ends_paragraph(&new_text.as_bytes()[end..line_end]);
+        end = line_end;
+
+        if empty {
+            break;
+        }
+    }
+
+    let old_end = end + old_text.len() - new_text.len();
+    let before = prev.paragraphs.partition_point(|x| x.span.start < start);
+    let after = prev.paragraphs.partition_point(|x| x.span.start < old_end);
+
+    // errors can't be cloned, paragraphs that failed are parsed again
+    let keep = |x: &ParsedParagraph, moved_to: usize, moved_from: usize| ParsedParagraph {
+        span: x.span.start + moved_to - moved_from..x.span.end + moved_to - moved_from,
+        paragraph: match &x.paragraph {
+            Ok(p) => Ok(p.clone()),
+            Err(_) => parse_one(&old_text[x.span.clone()]),
+        },
+    };
+
+    let mut paragraphs = Vec::with_capacity(prev.paragraphs.len());
+    paragraphs.extend(prev.paragraphs[..before].iter().map(|x| keep(x, 0, 0)));
+    scan(&new_text[start..end], start, &mut paragraphs);
+    paragraphs.extend(
+        prev.paragraphs[after..]
+            .iter()
+            .map(|x| keep(x, end, old_end)),
+    );
+
+    ParsedFile { paragraphs }
+}
+
+/// Split `s`, which starts at byte `offset` of the file, after an empty line
+/// or at the start, into paragraphs and parse them
+fn scan(s: &str, offset: usize, paragraphs: &mut Vec<ParsedParagraph>) {
+    paragraphs.extend(paragraph_spans(s.as_bytes()).map(|span| ParsedParagraph {
+        paragraph: parse_one(&s[span.clone()]),
+        span: span.start + offset..span.end + offset,
+    }));
+}
+
+/// The spans of the paragraphs of `s`, split like
+/// [`ParagraphDecoder`](crate::ParagraphDecoder) does, whether or not `s`
+/// is UTF-8
+pub(crate) fn paragraph_spans(s: &[u8]) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut pos = 0;
+
+    std::iter::from_fn(move || {
+        let mut start = None;
+
+        while pos < s.len() {
+            let line_end = s[pos..]
+                .iter()
+                .position(|&x| x == b'\n')
+                .map_or(s.len(), |x| pos + x + 1);
+            let line = &s[pos..line_end];
+            let line_start = pos;
+            pos = line_end;
+
+            match start {
+                // in a paragraph a line of whitespace continues a field
+                Some(start) if ends_paragraph(line) => return Some(start..line_start),
+                Some(_) => (),
+                None if is_blank_line(line) || line.starts_with(b"#") => (),
+                None => start = Some(line_start),
+            }
+        }
+
+        start.map(|start| start..s.len())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        arbitrary::Gen, parse_multi_with, reparse, test_util::fixtures, ParseOptions, ParsedFile,
+    };
+
+    /// Spans and paragraphs, with errors as text
+    fn summary(file: &ParsedFile) -> Vec<(std::ops::Range<usize>, Result<String, String>)> {
+        file.paragraphs()
+            .iter()
+            .map(|x| {
+                let p = match &x.paragraph {
+                    Ok(p) => Ok(format!("{:?}", p)),
+                    Err(e) => Err(e.to_string()),
+                };
+
+                (x.span.clone(), p)
+            })
+            .collect()
+    }
+
+    /// Apply 2000 random edits to `text`, built from `pieces` and random
+    /// text, checking that [`reparse`] gives what [`ParsedFile::parse`]
+    /// does and `check` is happy with the result
+    fn edit_randomly(
+        seed: u64,
+        mut text: String,
+        pieces: &[&str],
+        check: impl Fn(&ParsedFile, &str),
+    ) -> ParsedFile {
+        let mut g = Gen::new(seed);
+        let mut file = ParsedFile::parse(&text);
+
+        for i in 0..2000 {
+            let floor = |s: &str, mut x: usize| {
+                while !s.is_char_boundary(x) {
+                    x -= 1;
+                }
+                x
+            };
+            let start = floor(&text, g.below(text.len() + 1));
+            let end = floor(&text, (start + g.below(8)).min(text.len()));
+            let insert = match g.below(3) {
+                0 => String::new(),
+                1 => g.choose(pieces).to_string(),
+                _ => g.text(6),
+            };
+
+            let mut new = text.clone();
+            new.replace_range(start..end, &insert);
+
+            file = reparse(&file, &text, &new, start..end);
+            text = new;
+
+            assert_eq!(
+                summary(&file),
+                summary(&ParsedFile::parse(&text)),
+                "edit {} at {}..{}: {:?}",
+                i,
+                start,
+                end,
+                insert
+            );
+            check(&file, &text);
+        }
+
+        file
+    }
+
+    #[test]
+    fn test_reparse() {
+        let text = format!("# header\n\n{}\n{}", fixtures::STATUS, fixtures::PACKAGES);
+        let pieces = [
+            "\n",
+            "\n\n",
+            " ",
+            "\t",
+            "#",
+            ":",
+            "X-Field: y\n",
+            " \n",
+            "\r\n",
+            "é",
+        ];
+        let file = edit_randomly(0x8d_4e9a, text, &pieces, |_, _| ());
+
+        assert!(file.paragraphs().len() > 10);
+    }
+
+    #[test]
+    fn test_reparse_parse_multi() {
+        let text = format!("{}\n{}", fixtures::STATUS, fixtures::PACKAGES);
+        let pieces = [
+            "\n",
+            "\n\n",
+            " ",
+            "\t",
+            ":",
+            "X-Field: y\n",
+            " \n",
+            "\n \n",
+            "\n\t\n c\n",
+            "\r\n",
+        ];
+
+        // without comments, paragraphs split and parse like parse_multi_with
+        // does, checked on every run of paragraphs that parse
+        let file = edit_randomly(0x8d_4e9b, text, &pieces, |file, text| {
+            let runs = file
+                .paragraphs()
+                .split(|x| x.paragraph.is_err())
+                .filter(|x| !x.is_empty());
+
+            for run in runs {
+                let span = run[0].span.start..run[run.len() - 1].span.end;
+                let parsed = run
+                    .iter()
+                    .map(|x| x.paragraph.as_ref().unwrap().clone())
+                    .collect::<Vec<_>>();
+
+                assert_eq!(
+                    parse_multi_with(&text[span.clone()], &ParseOptions::default()).unwrap(),
+                    parsed,
+                    "{:?}",
+                    &text[span]
+                );
+            }
+        });
+
+        assert!(file.paragraphs().len() > 10);
+    }
+
+    #[test]
+    fn test_parsed_file() {
+        let s = "# a\n\nPackage: a\n# b\nbroken\n \n\n# c\nPackage: c";
+        let file = ParsedFile::parse(s);
+        let spans = file
+            .paragraphs()
+            .iter()
+            .map(|x| x.span.clone())
+            .collect::<Vec<_>>();
+
+        // the line of whitespace is in the first paragraph
+        assert_eq!(spans, [5..29, 34..44]);
+        assert!(file.paragraphs()[0].paragraph.is_err());
+        assert!(file.paragraphs()[1].paragraph.is_ok());
+        assert_eq!(file.paragraph_at(34).map(|x| x.span.start), Some(34));
+        assert!(file.paragraph_at(30).is_none());
+        assert!(ParsedFile::parse("").paragraphs().is_empty());
+    }
+}
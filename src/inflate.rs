@@ -0,0 +1,431 @@
+//! A small gzip decoder (RFC 1951 and 1952), enough for the `control.tar.gz`
+//! of a `.deb` and `Packages.gz` indices
+
+use crate::DebError;
+
+const MAX_BITS: usize = 15;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// Order of the code length code lengths in a dynamic block header
+const CLEN_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// `data` decompressed if it is gzip, like a `Packages.gz`, or as it is
+///
+/// xz and zstd data fail with [`DebError::Unsupported`], gzip data that
+/// doesn't decompress with [`DebError::Corrupt`]; `name` is the file named
+/// in the error.
+///
+/// ```rust
+/// use eight_deep_parser::decompress;
+///
+/// assert_eq!(decompress(b"Package: a\n".to_vec(), "Packages").unwrap(), b"Package: a\n");
+/// ```
+pub fn decompress(data: Vec<u8>, name: &str) -> Result<Vec<u8>, DebError> {
+    if data.starts_with(&[0x1f, 0x8b]) {
+        return gunzip(&data).map_err(|reason| DebError::Corrupt {
+            member: name.to_string(),
+            reason: reason.to_string(),
+        });
+    }
+
+    if data.starts_with(b"\xfd7zXZ\0") || data.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        return Err(DebError::Unsupported {
+            member: name.to_string(),
+        });
+    }
+
+    Ok(data)
+}
+
+/// Decompress a gzip stream of one member, checking its CRC-32 and size
+pub(crate) fn gunzip(data: &[u8]) -> Result<Vec<u8>, &'static str> {
+    if data.len() < 18 || data[0..2] != [0x1f, 0x8b] {
+        return Err("not gzip data");
+    }
+    if data[2] != 8 {
+        return Err("unknown gzip compression method");
+    }
+
+    let flags = data[3];
+    let mut pos = 10;
+
+    let field = |pos: usize, len: usize| -> Result<usize, &'static str> {
+        pos.checked_add(len)
+            .filter(|x| *x <= data.len())
+            .ok_or("truncated gzip header")
+    };
+    let until_nul = |pos: usize| -> Result<usize, &'static str> {
+        data[pos.min(data.len())..]
+            .iter()
+            .position(|x| *x == 0)
+            .map(|x| pos + x + 1)
+            .ok_or("truncated gzip header")
+    };
+
+    // FEXTRA, FNAME, FCOMMENT, FHCRC
+    if flags & 4 != 0 {
+        let len = u16::from_le_bytes([data[pos], data[pos + 1]]) as usize;
+        pos = field(pos + 2, len)?;
+    }
+    if flags & 8 != 0 {
+        pos = until_nul(pos)?;
+    }
+    if flags & 16 != 0 {
+        pos = until_nul(pos)?;
+    }
+    if flags & 2 != 0 {
+        pos = field(pos, 2)?;
+    }
+
+    let mut bits = Bits::new(&data[pos..]);
+    let out = inflate(&mut bits)?;
+    let trailer = bits.rest().get(..8).ok_or("truncated gzip trailer")?;
+
+    if crc32(&out) != u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]) {
+        return Err("gzip CRC mismatch");
+    }
+    if out.len() as u32 != u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]) {
+        return Err("gzip size mismatch");
+    }
+
+    Ok(out)
+}
+
+/// Decompress a raw deflate stream
+fn inflate(bits: &mut Bits) -> Result<Vec<u8>, &'static str> {
+    let mut out = Vec::new();
+
+    loop {
+        let last = bits.take(1)? == 1;
+
+        match bits.take(2)? {
+            0 => stored(bits, &mut out)?,
+            1 => {
+                let (lit, dist) = fixed();
+                codes(bits, &mut out, &lit, &dist)?;
+            }
+            2 => {
+                let (lit, dist) = dynamic(bits)?;
+                codes(bits, &mut out, &lit, &dist)?;
+            }
+            _ => return Err("invalid deflate block type"),
+        }
+
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+/// Deflate input, read from the least significant bit of each byte
+struct Bits<'a> {
+    data: &'a [u8],
+    pos: usize,
+    buf: u32,
+    count: u32,
+}
+
+impl<'a> Bits<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            buf: 0,
+            count: 0,
+        }
+    }
+
+    fn take(&mut self, n: u32) -> Result<u32, &'static str> {
+        while self.count < n {
+            let byte = *self.data.get(self.pos).ok_or("truncated deflate data")?;
+            self.pos += 1;
+            self.buf |= (byte as u32) << self.count;
+            self.count += 8;
+        }
+
+        let value = self.buf & ((1u64 << n) - 1) as u32;
+        self.buf >>= n;
+        self.count -= n;
+
+        Ok(value)
+    }
+
+    /// Drop the bits left of the current byte
+    fn align(&mut self) {
+        self.buf = 0;
+        self.count = 0;
+    }
+
+    /// The bytes after the last one read from
+    fn rest(&self) -> &'a [u8] {
+        &self.data[self.pos..]
+    }
+}
+
+/// A canonical Huffman code: the number of codes of each length, and the
+/// symbols ordered by code
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self, &'static str> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+
+        // no more codes of a length than fit
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = left * 2 - count as i32;
+            if left < 0 {
+                return Err("oversubscribed Huffman code");
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for len in 1..=MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+
+        let mut symbols = vec![0; offsets[MAX_BITS + 1] as usize];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+
+        Ok(Self { counts, symbols })
+    }
+
+    fn decode(&self, bits: &mut Bits) -> Result<u16, &'static str> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+
+        for len in 1..=MAX_BITS {
+            code |= bits.take(1)? as i32;
+            let count = self.counts[len] as i32;
+
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        Err("invalid Huffman code")
+    }
+}
+
+fn stored(bits: &mut Bits, out: &mut Vec<u8>) -> Result<(), &'static str> {
+    bits.align();
+
+    let header = bits.rest().get(..4).ok_or("truncated stored block")?;
+    let len = u16::from_le_bytes([header[0], header[1]]);
+    if len != !u16::from_le_bytes([header[2], header[3]]) {
+        return Err("corrupt stored block length");
+    }
+
+    let start = bits.pos + 4;
+    let data = bits
+        .data
+        .get(start..start + len as usize)
+        .ok_or("truncated stored block")?;
+    out.extend_from_slice(data);
+    bits.pos = start + len as usize;
+
+    Ok(())
+}
+
+fn fixed() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+
+    (
+        Huffman::new(&lengths).expect("fixed code is valid"),
+        Huffman::new(&[5; 30]).expect("fixed code is valid"),
+    )
+}
+
+fn dynamic(bits: &mut Bits) -> Result<(Huffman, Huffman), &'static str> {
+    let nlen = bits.take(5)? as usize + 257;
+    let ndist = bits.take(5)? as usize + 1;
+    let ncode = bits.take(4)? as usize + 4;
+
+    let mut clens = [0u8; 19];
+    for &i in &CLEN_ORDER[..ncode] {
+        clens[i] = bits.take(3)? as u8;
+    }
+    let clen = Huffman::new(&clens)?;
+
+    let mut lengths = vec![];
+    while lengths.len() < nlen + ndist {
+        let (len, repeat) = match clen.decode(bits)? {
+            x @ 0..=15 => (x as u8, 1),
+            16 => (
+                *lengths.last().ok_or("repeat with no length")?,
+                3 + bits.take(2)?,
+            ),
+            17 => (0, 3 + bits.take(3)?),
+            _ => (0, 11 + bits.take(7)?),
+        };
+
+        lengths.extend(std::iter::repeat_n(len, repeat as usize));
+    }
+
+    if lengths.len() > nlen + ndist {
+        return Err("too many code lengths");
+    }
+    if lengths[256] == 0 {
+        return Err("no end of block code");
+    }
+
+    Ok((
+        Huffman::new(&lengths[..nlen])?,
+        Huffman::new(&lengths[nlen..])?,
+    ))
+}
+
+fn codes(
+    bits: &mut Bits,
+    out: &mut Vec<u8>,
+    lit: &Huffman,
+    dist: &Huffman,
+) -> Result<(), &'static str> {
+    loop {
+        let symbol = lit.decode(bits)? as usize;
+
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let i = symbol - 257;
+                if i >= LENGTH_BASE.len() {
+                    return Err("invalid length code");
+                }
+                let len = LENGTH_BASE[i] as usize + bits.take(LENGTH_EXTRA[i] as u32)? as usize;
+
+                let i = dist.decode(bits)? as usize;
+                if i >= DIST_BASE.len() {
+                    return Err("invalid distance code");
+                }
+                let back = DIST_BASE[i] as usize + bits.take(DIST_EXTRA[i] as u32)? as usize;
+
+                if back > out.len() {
+                    return Err("distance too far back");
+                }
+
+                // the copy may overlap what it writes
+                let start = out.len() - back;
+                for j in 0..len {
+                    out.push(out[start + j]);
+                }
+            }
+        }
+    }
+}
+
+/// CRC-32 as used by gzip
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crc32, decompress, gunzip};
+    use crate::DebError;
+
+    const PACKAGES: &[u8] = include_bytes!("../tests/fixtures/Packages-sorted");
+    const PACKAGES_GZ: &[u8] = include_bytes!("../tests/fixtures/Packages-sorted.gz");
+
+    #[test]
+    fn test_gunzip() {
+        assert_eq!(gunzip(PACKAGES_GZ).unwrap(), PACKAGES);
+
+        // `gzip -c` of "a\n" with a file name, and of nothing
+        let named = b"\x1f\x8b\x08\x08\x00\x00\x00\x00\x00\x03a\x00\x4b\xe4\x02\x00\x07\xa1\xea\xdd\x02\x00\x00\x00";
+        let empty =
+            b"\x1f\x8b\x08\x00\x00\x00\x00\x00\x00\x03\x03\x00\x00\x00\x00\x00\x00\x00\x00\x00";
+
+        assert_eq!(gunzip(named).unwrap(), b"a\n");
+        assert_eq!(gunzip(empty).unwrap(), b"");
+    }
+
+    #[test]
+    fn test_gunzip_stored() {
+        let mut data = b"\x1f\x8b\x08\x00\x00\x00\x00\x00\x00\xff\x01\x03\x00\xfc\xffabc".to_vec();
+        data.extend_from_slice(&crc32(b"abc").to_le_bytes());
+        data.extend_from_slice(&3u32.to_le_bytes());
+
+        assert_eq!(gunzip(&data).unwrap(), b"abc");
+    }
+
+    #[test]
+    fn test_gunzip_corrupt() {
+        assert!(gunzip(&PACKAGES_GZ[..PACKAGES_GZ.len() / 2]).is_err());
+        assert!(gunzip(&PACKAGES_GZ[..PACKAGES_GZ.len() - 1]).is_err());
+        assert!(gunzip(b"not gzip at all, really").is_err());
+
+        let mut flipped = PACKAGES_GZ.to_vec();
+        let last = flipped.len() - 5;
+        flipped[last] ^= 1;
+
+        assert_eq!(gunzip(&flipped), Err("gzip CRC mismatch"));
+    }
+
+    #[test]
+    fn test_decompress() {
+        assert_eq!(decompress(PACKAGES_GZ.to_vec(), "a").unwrap(), PACKAGES);
+        assert_eq!(decompress(PACKAGES.to_vec(), "a").unwrap(), PACKAGES);
+        assert!(matches!(
+            decompress(b"\xfd7zXZ\0\0".to_vec(), "Packages.xz"),
+            Err(DebError::Unsupported { member }) if member == "Packages.xz"
+        ));
+        assert!(matches!(
+            decompress(PACKAGES_GZ[..100].to_vec(), "a"),
+            Err(DebError::Corrupt { .. })
+        ));
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+}
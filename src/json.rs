@@ -0,0 +1,108 @@
+use std::fmt::Write;
+
+use crate::{Item, Paragraph};
+
+/// The canonical JSON of parsed paragraphs, one field per line
+///
+/// Meant for other tools and for comparing what two versions of the parser
+/// make of an input. A [`Item::OneLine`] is a string, a [`Item::MultiLine`]
+/// an array of its lines and a [`Item::Folded`]
+/// `{"folded": first, "lines": [...]}`, and a [`Item::Streamed`] is `null`.
+/// Fields keep their order.
+///
+/// ```rust
+/// use eight_deep_parser::{parse_multi, to_json};
+///
+/// let r = parse_multi("Package: a\nDescription: b\n c\n").unwrap();
+///
+/// assert_eq!(
+///     to_json(&r),
+///     "[\n  {\n    \"Package\": \"a\",\n    \"Description\": {\"folded\": \"b\", \"lines\": [\"c\"]}\n  }\n]\n"
+/// );
+/// ```
+pub fn to_json(ps: &[Paragraph]) -> String {
+    let mut s = String::from("[");
+
+    for (i, p) in ps.iter().enumerate() {
+        s.push_str(if i == 0 { "\n  {" } else { ",\n  {" });
+
+        for (j, (k, v)) in p.iter().enumerate() {
+            s.push_str(if j == 0 { "\n    " } else { ",\n    " });
+            push_string(&mut s, k);
+            s.push_str(": ");
+
+            match v {
+                Item::OneLine(x) => push_string(&mut s, x),
+                Item::MultiLine(v) => push_array(&mut s, v),
+                Item::Folded(x, v) => {
+                    s.push_str("{\"folded\": ");
+                    push_string(&mut s, x);
+                    s.push_str(", \"lines\": ");
+                    push_array(&mut s, v);
+                    s.push('}');
+                }
+                Item::Streamed => s.push_str("null"),
+            }
+        }
+
+        s.push_str(if p.is_empty() { "}" } else { "\n  }" });
+    }
+
+    s.push_str(if ps.is_empty() { "]\n" } else { "\n]\n" });
+
+    s
+}
+
+fn push_array(s: &mut String, v: &[String]) {
+    s.push('[');
+
+    for (i, x) in v.iter().enumerate() {
+        if i != 0 {
+            s.push_str(", ");
+        }
+        push_string(s, x);
+    }
+
+    s.push(']');
+}
+
+fn push_string(s: &mut String, x: &str) {
+    s.push('"');
+
+    for c in x.chars() {
+        match c {
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            '\n' => s.push_str("\\n"),
+            '\r' => s.push_str("\\r"),
+            '\t' => s.push_str("\\t"),
+            c if c < ' ' => write!(s, "\\u{:04x}", c as u32).unwrap(),
+            c => s.push(c),
+        }
+    }
+
+    s.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_multi, to_json, Item, Paragraph};
+
+    #[test]
+    fn test_to_json() {
+        let mut p = Paragraph::new();
+        p.insert("A\"\\".to_string(), Item::OneLine("\t\u{1}é".to_string()));
+        p.insert("B".to_string(), Item::MultiLine(vec![]));
+
+        assert_eq!(to_json(&[]), "[]\n");
+        assert_eq!(to_json(&[Paragraph::new()]), "[\n  {}\n]\n");
+        assert_eq!(
+            to_json(&[p]),
+            "[\n  {\n    \"A\\\"\\\\\": \"\\t\\u0001é\",\n    \"B\": []\n  }\n]\n"
+        );
+        assert_eq!(
+            to_json(&parse_multi("A: 1\n\nB:\n x\n y\n").unwrap()),
+            "[\n  {\n    \"A\": \"1\"\n  },\n  {\n    \"B\": [\"x\", \"y\"]\n  }\n]\n"
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use crate::{
+    fields::{FieldInfo, NAMES},
+    IndexMap, Item, KeyStyle,
+};
+
+/// The canonical spelling of the Debian field names known to
+/// [`normalize_keys`], the names of [`FieldInfo::all`](crate::fields::FieldInfo::all)
+///
+/// Checksum fields are spelled as in `Packages` files, e.g. `MD5sum`.
+pub fn known_fields() -> &'static [&'static str] {
+    &NAMES
+}
+
+/// Respell every field name in the given [`KeyStyle`], e.g. `Md5Sum` as
+/// `MD5sum` and `pre-depends` as `Pre-Depends`
+///
+/// When two fields end up with the same name, the last one wins and keeps
+/// the position of the first, like [`DuplicateKeys::LastWins`](crate::DuplicateKeys::LastWins).
+/// To fail instead, normalize while parsing with
+/// [`ParseOptions::normalize_keys`](crate::ParseOptions::normalize_keys).
+///
+/// ```rust
+/// use eight_deep_parser::{normalize_keys, parse_one, KeyStyle};
+///
+/// let mut r = parse_one("package: a\nSha256: 00\nx-custom-field: b\n").unwrap();
+/// normalize_keys(&mut r, KeyStyle::TrainCase);
+///
+/// assert_eq!(r.keys().collect::<Vec<_>>(), vec!["Package", "SHA256", "X-Custom-Field"]);
+/// ```
+pub fn normalize_keys(p: &mut IndexMap<String, Item>, style: KeyStyle) {
+    let old = std::mem::take(p);
+
+    for (k, v) in old {
+        p.insert(normalize_key(&k, style), v);
+    }
+}
+
+pub(crate) fn normalize_key(key: &str, style: KeyStyle) -> String {
+    if let Some(known) = FieldInfo::lookup(key) {
+        return known.name.to_string();
+    }
+
+    match style {
+        KeyStyle::TrainCase => key
+            .split('-')
+            .map(|x| {
+                let mut chars = x.chars();
+
+                match chars.next() {
+                    Some(first) => {
+                        first.to_uppercase().collect::<String>() + &chars.as_str().to_lowercase()
+                    }
+                    None => String::new(),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("-"),
+        KeyStyle::KnownOnly => key.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        known_fields, normalize_keys, parse_multi_with, DuplicateKeys, KeyStyle, ParagraphExt,
+        ParseError, ParseOptions,
+    };
+
+    const MESSY: &str = "package: zsync
+VERSION: 0.6.2-4
+pre-depends: libc6
+Md5Sum: 0123
+sha256: 4567
+x-python3-VERSION: 3.11
+Installed-size: 100
+
+Package: b
+MD5sum: 89ab
+md5SUM: cdef
+";
+
+    #[test]
+    fn test_normalize_keys() {
+        let opts = ParseOptions {
+            normalize_keys: Some(KeyStyle::TrainCase),
+            ..Default::default()
+        };
+        let r = parse_multi_with(MESSY, &opts).unwrap();
+
+        assert_eq!(
+            r[0].keys().collect::<Vec<_>>(),
+            vec![
+                "Package",
+                "Version",
+                "Pre-Depends",
+                "MD5sum",
+                "SHA256",
+                "X-Python3-Version",
+                "Installed-Size"
+            ]
+        );
+        assert_eq!(r[1].len(), 2);
+        assert_eq!(r[1]["MD5sum"].value_lines().next(), Some("cdef"));
+
+        let mut r = parse_multi_with(MESSY, &ParseOptions::default()).unwrap();
+        normalize_keys(&mut r[0], KeyStyle::KnownOnly);
+        normalize_keys(&mut r[1], KeyStyle::KnownOnly);
+
+        assert_eq!(r[0].get_index_of("x-python3-VERSION"), Some(5));
+        assert_eq!(r[0].get_index_of("MD5sum"), Some(3));
+        assert_eq!(r[1].get_one("MD5sum"), Some("cdef"));
+        assert_eq!(r[1].len(), 2);
+
+        let opts = ParseOptions {
+            normalize_keys: Some(KeyStyle::KnownOnly),
+            duplicates: DuplicateKeys::Error,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            parse_multi_with(MESSY, &opts).unwrap_err().root(),
+            ParseError::DuplicateKey { key } if key == "MD5sum"
+        ));
+    }
+
+    #[test]
+    fn test_known_fields_unique() {
+        let fields = known_fields();
+
+        for (i, a) in fields.iter().enumerate() {
+            assert!(
+                !fields[i + 1..].iter().any(|b| a.eq_ignore_ascii_case(b)),
+                "{}",
+                a
+            );
+        }
+    }
+}
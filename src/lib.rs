@@ -1,16 +1,205 @@
-use std::fmt::Display;
+use std::{collections::HashMap, fmt::Display, ops::Range, sync::Arc};
 
+pub use builder::{ParagraphBuilder, TryParagraphBuilder};
+pub use changes::Changes;
+pub use checksums::ChecksumEntry;
+pub use compact::{parse_multi_compact, parse_multi_compact_with, CompactItem, CompactParagraph};
+pub use control::{get_bool, MultiArch, PackageState, PackageStatus, Priority};
+#[doc(hidden)]
+pub use convert::__private;
+pub use convert::{FromParagraph, ToParagraph};
+pub use cst::{Comment, Cst, Field, Node};
+#[cfg(feature = "deb")]
+pub use deb::{generate_packages_index, read_deb_control, read_deb_control_files, DebControl};
+pub use decoder::{ParagraphDecoder, ParagraphReader};
+pub use description::Description;
+#[cfg(feature = "derive")]
+pub use eight_deep_parser_derive::{FromParagraph, ToParagraph};
+#[cfg(feature = "deb")]
+pub use error::DebError;
 use error::Result;
-pub use indexmap::IndexMap;
+#[cfg(feature = "digest")]
+pub use error::VerifyError;
+pub use error::{
+    BuildError, ErrorBytes, IndexError, MapError, PackageIdError, ParseError, RelationError,
+    ReleaseError, RowError, SerializeError, SubstError, VersionError,
+};
+pub use graph::{build_graph, DepGraph, DependencyKind, Edge, InvalidRelations, Unresolved};
+pub use identity::{index_by, merge_by, KeyBy, PackageId};
+pub use incremental::{reparse, ParsedFile, ParsedParagraph};
+pub use indexmap::{IndexMap, IndexSet};
+#[cfg(feature = "deb")]
+pub use inflate::decompress;
+pub use json::to_json;
+pub use keys::{known_fields, normalize_keys};
+pub use list::{get_list, ListSep};
+pub use lookup::{find_package_sorted, find_packages_sorted, PackageIndex};
+#[cfg(feature = "deb")]
+pub use options::PackagesIndexOptions;
+use options::Prepared;
+pub use options::{
+    ContinuationWhitespace, DiffOptions, DuplicateKeys, FieldCompare, KeyStyle, LineEndings,
+    OrphanLines, ParseOptions, ReadLimits, SerializeOptions, SpaceBeforeColon, StreamedFields,
+    SubstOptions, TrailingData, UnknownVar,
+};
+pub use paragraph::{
+    eq_in_order, field_names, position_by_field, rename_field, sort_by_field, sort_by_field_with,
+    strip_field, FieldSet, Paragraph, ParagraphExt,
+};
+pub use provenance::{parse_multi_tagged, Origin, TaggedParagraph};
+pub use recover::{parse_multi_recover, Diagnostic};
+pub use relation::{
+    canonicalize_relations, canonicalize_relations_with, parse_relations, relation_names_set,
+    resolve_provides, sort_relations, Provide, Relation, VersionOp,
+};
+#[cfg(feature = "digest")]
+pub use release::VerifyStatus;
+pub use release::{Algorithm, ReleaseFile};
+pub use semantic::{
+    document_diff, document_diff_by, semantic_diff, semantic_diff_by, semantic_diff_with,
+    semantic_eq, semantic_eq_by, DocumentDiff, FieldDelta,
+};
+pub use shared::SharedParagraphs;
+pub use sources::{SignedBy, SourceType, SourcesEntry};
+pub use stats::{quick_stats, QuickStats};
+pub use status::{parse_back_dpkg_status, StatusFile};
+pub use subst::substitute;
+pub use tags::parse_tags;
 use thiserror::Error;
+pub use transform::{transform, transform_with, TransformStats};
+pub use translation::{apply_translations, parse_translations};
+pub use version::{validate_version, Version};
+pub use view::{paragraph_views, LineStream, ParagraphView};
 
+#[cfg(any(test, feature = "arbitrary"))]
+pub mod arbitrary;
+mod builder;
+mod changes;
+mod checksums;
+mod compact;
+mod control;
+mod convert;
+mod cst;
+#[cfg(feature = "deb")]
+mod deb;
+mod decoder;
+mod description;
+#[cfg(feature = "digest")]
+mod digest;
 mod error;
+pub mod fields;
+mod graph;
+mod identity;
+mod incremental;
+#[cfg(feature = "deb")]
+mod inflate;
+mod json;
+mod keys;
+mod list;
+mod lookup;
+#[cfg(feature = "md5")]
+mod md5;
+mod options;
+mod paragraph;
 mod parser;
+mod provenance;
+pub mod raw;
+mod recover;
+mod relation;
+mod release;
+mod semantic;
+mod shared;
+mod sources;
+mod stats;
+mod status;
+mod subst;
+mod tags;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
+mod transform;
+mod translation;
+mod version;
+mod view;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+/// The value of a field
+///
+/// Items are ordered by variant, `OneLine` before `MultiLine` before
+/// `Folded` before `Streamed`, then by their lines. The default is an empty
+/// `OneLine`, the value of a field with nothing after the colon.
+///
+/// New variants may be added in minor releases: a `match` on an `Item` needs
+/// a wildcard arm, or can go through [`Item::lines`] instead.
+#[derive(Debug, PartialEq, Eq, Clone, Hash, PartialOrd, Ord)]
+#[non_exhaustive]
 pub enum Item {
     OneLine(String),
     MultiLine(Vec<String>),
+    /// A value on the field line followed by continuation lines,
+    /// e.g. `Description`
+    Folded(String, Vec<String>),
+    /// A value handed to a callback of
+    /// [`ParagraphReader::on_field`](crate::ParagraphReader::on_field)
+    /// instead of being kept. It has no lines and can't be written back.
+    Streamed,
+}
+
+impl Default for Item {
+    fn default() -> Self {
+        Item::OneLine(String::new())
+    }
+}
+
+impl Item {
+    /// Append a line to the value
+    ///
+    /// A [`Item::OneLine`] is promoted to a [`Item::MultiLine`] holding the
+    /// old value followed by `line`; an empty `OneLine` becomes a `MultiLine`
+    /// of just `line`. A [`Item::Folded`] gets `line` as a new continuation
+    /// line. A [`Item::Streamed`] has no lines to keep and becomes a
+    /// `MultiLine` of just `line`.
+    ///
+    /// ```rust
+    /// use eight_deep_parser::Item;
+    ///
+    /// let mut item = Item::OneLine("a".to_string());
+    /// item.push_line("b");
+    ///
+    /// assert_eq!(item, Item::MultiLine(vec!["a".to_string(), "b".to_string()]));
+    /// ```
+    pub fn push_line(&mut self, line: impl Into<String>) {
+        let line = line.into();
+
+        match self {
+            Item::OneLine(s) if s.is_empty() => *self = Item::MultiLine(vec![line]),
+            Item::OneLine(s) => *self = Item::MultiLine(vec![std::mem::take(s), line]),
+            Item::MultiLine(v) | Item::Folded(_, v) => v.push(line),
+            Item::Streamed => *self = Item::MultiLine(vec![line]),
+        }
+    }
+
+    /// The lines of the value, whatever the variant
+    ///
+    /// A [`Item::OneLine`] is one line, except when empty: like
+    /// [`Item::push_line`], an empty `OneLine` counts as no line at all, so
+    /// it gives `[]`. A [`Item::Folded`] gives its first line, then the
+    /// continuation lines.
+    ///
+    /// ```rust
+    /// use eight_deep_parser::Item;
+    ///
+    /// assert_eq!(Item::OneLine("a".to_string()).lines(), ["a"]);
+    /// assert!(Item::OneLine(String::new()).lines().is_empty());
+    /// assert_eq!(
+    ///     Item::MultiLine(vec!["a".to_string(), "b".to_string()]).lines(),
+    ///     ["a", "b"]
+    /// );
+    /// ```
+    pub fn lines(&self) -> Vec<&str> {
+        match self {
+            Item::OneLine(s) if s.is_empty() => vec![],
+            _ => self.value_lines().collect(),
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -29,220 +218,1243 @@ type NomParseItem<'a> = Vec<(&'a [u8], (&'a [u8], Vec<u8>))>;
 /// Parse a single package:
 ///
 /// ```rust
-/// use std::process::Command;
-/// use eight_deep_parser::{parse_multi, parse_one, Item};
-///
-/// let command = Command::new("dpkg")
-///     .arg("-s")
-///     .arg("plasma-workspace")
-///     .output()
-///     .unwrap();
-///
-/// let stdout = command.stdout;
+/// use eight_deep_parser::{parse_one, Item};
 ///
-/// let r = parse_one(std::str::from_utf8(&stdout).unwrap()).unwrap();
+/// let r = parse_one(
+///     "Package: bash
+/// Essential: yes
+/// Conffiles:
+///  /etc/bash.bashrc 89269e1298235f1b12b4c16e4065ad0d
+///  /etc/skel/.bashrc ee35a240758f374832e809ae0ea4883a
+/// Description: GNU Bourne Again SHell
+///  Bash is an sh-compatible command language interpreter.
+/// ",
+/// )
+/// .unwrap();
 ///
+/// assert_eq!(r.get("Package").unwrap(), &Item::OneLine("bash".to_string()));
 /// assert_eq!(
-///     r.get("Package").unwrap(),
-///     &Item::OneLine("plasma-workspace".to_string())
+///     r["Conffiles"],
+///     Item::MultiLine(vec![
+///         "/etc/bash.bashrc 89269e1298235f1b12b4c16e4065ad0d".to_string(),
+///         "/etc/skel/.bashrc ee35a240758f374832e809ae0ea4883a".to_string(),
+///     ])
 /// );
-///```
+/// ```
+///
+/// The input must hold exactly one paragraph: a line in it that is neither
+/// a field nor a continuation line is reported as [`ParseError::Malformed`],
+/// anything but whitespace after it as [`ParseError::TrailingData`], and
+/// input of nothing but whitespace and `#` comment lines as
+/// [`ParseError::Empty`].
 pub fn parse_one(s: &str) -> Result<IndexMap<String, Item>> {
-    let (_, parse_v) = parser::single_package(s.as_bytes())?;
+    parse_one_with(s, &ParseOptions::default())
+}
+
+/// Parse a single package with the given [`ParseOptions`]:
+///
+/// Like [`parse_one`], anything but whitespace after the paragraph is an
+/// [`ParseError::TrailingData`] error.
+///
+/// ```rust
+/// use eight_deep_parser::{parse_one_with, DuplicateKeys, ParseError, ParseOptions};
+///
+/// let opts = ParseOptions {
+///     duplicates: DuplicateKeys::Error,
+///     ..Default::default()
+/// };
+///
+/// let r = parse_one_with("Package: a\nPackage: b\n", &opts);
+///
+/// assert!(matches!(r, Err(ParseError::DuplicateKey { .. })));
+/// ```
+///
+/// With the `tracing` feature this runs in a `parse_one` span, which
+/// records what [`parse_multi_with`] does.
+pub fn parse_one_with(s: &str, opts: &ParseOptions) -> Result<IndexMap<String, Item>> {
+    #[cfg(feature = "tracing")]
+    let span = tracing::debug_span!(
+        "parse_one",
+        len = s.len(),
+        paragraphs = tracing::field::Empty
+    )
+    .entered();
 
-    let result = to_map(parse_v)?;
+    if is_blank(s) {
+        return Err(ParseError::Empty);
+    }
+
+    let prepared = opts.prepare(s)?;
+    let result = parse_one_prepared(&prepared.text, opts).map_err(|e| prepared.error(s, e))?;
+
+    #[cfg(feature = "tracing")]
+    span.record("paragraphs", 1);
 
     Ok(result)
 }
 
-/// Parse multi package:
-/// (e.g: /var/lib/dpkg/status)
+/// [`parse_one_with`] on `s` as rewritten by [`ParseOptions::prepare`], with
+/// positions in errors relative to it
+fn parse_one_prepared(s: &str, opts: &ParseOptions) -> Result<IndexMap<String, Item>> {
+    let (rest, parse_v) = parser::single_package(s.as_bytes()).map_err(|e| {
+        match s.lines().next().filter(|x| !x.trim().is_empty()) {
+            Some(_) => ParseError::Malformed { line: 1 },
+            None => e.into(),
+        }
+    })?;
+
+    check_field_names(s, &parse_v, opts)?;
+
+    if !rest.iter().all(|x| x.is_ascii_whitespace()) {
+        let offset = s.len() - rest.len();
+
+        return Err(match malformed_line(s, offset) {
+            Some(line) => ParseError::Malformed { line },
+            None => ParseError::TrailingData { offset },
+        });
+    }
+
+    let result = to_map(parse_v, opts)?;
+
+    Ok(result)
+}
+
+/// Parse the first package of `s` and return the input after it:
+///
+/// Blank lines before the paragraph and the one ending it are consumed, so
+/// calling this again on the rest parses the next paragraph.
+///
+/// ```rust
+/// use eight_deep_parser::{parse_one_rest, Item};
+///
+/// let (a, rest) = parse_one_rest("Package: a\n\nPackage: b\n").unwrap();
+///
+/// assert_eq!(a["Package"], Item::OneLine("a".to_string()));
+/// assert_eq!(rest, "Package: b\n");
+/// ```
+pub fn parse_one_rest(s: &str) -> Result<(IndexMap<String, Item>, &str)> {
+    if is_blank(s) {
+        return Err(ParseError::Empty);
+    }
+
+    let (rest, parse_v) = parser::next_package(s.as_bytes())?;
+
+    if let Some(line) = malformed_line(s, s.len() - rest.len()) {
+        return Err(ParseError::Malformed { line });
+    }
+
+    let result = to_map(parse_v, &ParseOptions::default())?;
+
+    Ok((result, &s[s.len() - rest.len()..]))
+}
+
+/// A piece of a field value, see [`parse_one_streaming`]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FieldChunk<'a> {
+    /// The text after the colon on the field line, empty for a field whose
+    /// value starts on the next line
+    Value(&'a str),
+    /// A continuation line without its leading space
+    Line(&'a str),
+}
+
+/// Parse a single package, handing every field to `on_field` piece by piece
+/// instead of building a map
+///
+/// Every field gives one [`FieldChunk::Value`] followed by a
+/// [`FieldChunk::Line`] per continuation line, so a huge value is never held
+/// in memory as a whole. Like [`parse_one`], anything but whitespace after
+/// the paragraph is an [`ParseError::TrailingData`] error; fields already
+/// delivered stay delivered.
 ///
 /// ```rust
-/// use std::{fs, io::Read, process::Command};
-/// use eight_deep_parser::{parse_multi, Item};
+/// use eight_deep_parser::{parse_one_streaming, FieldChunk};
 ///
-/// let dir = fs::read_dir("/var/lib/apt/lists").unwrap();
+/// let mut lines = 0;
 ///
-/// for i in dir.flatten() {
-///     if !i.file_name().to_str().unwrap().ends_with("_Packages") {
-///         continue;
+/// parse_one_streaming("Package: a\nChangelog:\n line 1\n line 2\n", |key, chunk| {
+///     if let FieldChunk::Line(_) = chunk {
+///         assert_eq!(key, "Changelog");
+///         lines += 1;
 ///     }
+/// })
+/// .unwrap();
+///
+/// assert_eq!(lines, 2);
+/// ```
+pub fn parse_one_streaming<F>(s: &str, mut on_field: F) -> Result<()>
+where
+    F: FnMut(&str, FieldChunk),
+{
+    if is_blank(s) {
+        return Err(ParseError::Empty);
+    }
+
+    let mut input = s.as_bytes();
+    let mut first = true;
+
+    loop {
+        let (rest, (key, value)) = match parser::field_line(input) {
+            Ok(r) => r,
+            Err(e) if first => return Err(e.into()),
+            Err(_) => break,
+        };
+
+        first = false;
+        input = rest;
+
+        let (key, _) = trim_key(std::str::from_utf8(key)?);
+        on_field(key, FieldChunk::Value(std::str::from_utf8(value)?));
+
+        while let Ok((rest, line)) = parser::multi_line_single(input) {
+            input = rest;
+
+            let line = std::str::from_utf8(line)?;
+            on_field(
+                key,
+                FieldChunk::Line(ContinuationWhitespace::StripOne.strip(line)),
+            );
+        }
+    }
+
+    if !input.iter().all(|x| x.is_ascii_whitespace()) {
+        return Err(ParseError::TrailingData {
+            offset: s.len() - input.len(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Parse multi package:
+/// (e.g: /var/lib/dpkg/status)
 ///
-///     let mut f = std::fs::File::open(i.path()).unwrap();
-///     let mut buf = Vec::new();
-///     f.read_to_end(&mut buf).unwrap();
+/// ```rust
+/// use eight_deep_parser::{parse_multi, ParagraphExt};
 ///
-///     let r = parse_multi(std::str::from_utf8(&buf).unwrap());
+/// # let packages = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/Packages"));
+/// // e.g. the contents of /var/lib/apt/lists/*_Packages
+/// let r = parse_multi(packages).unwrap();
 ///
-///     assert!(r.is_ok())
-/// }
+/// assert_eq!(r[0].get_one("Package"), Some("hello"));
+/// assert_eq!(r[1].get_one("Multi-Arch"), Some("same"));
 /// ```
+///
+/// Input of nothing but whitespace and `#` comment lines gives no
+/// paragraph.
 pub fn parse_multi(s: &str) -> Result<Vec<IndexMap<String, Item>>> {
-    if s.is_empty() {
+    parse_multi_with(s, &ParseOptions::default())
+}
+
+/// Parse multi package from bytes, like a file read with [`std::fs::read`]
+///
+/// Invalid UTF-8 fails with [`ParseError::InvalidUtf8`], telling where the
+/// bad byte is in `s`, in which paragraph and field, and the bytes around
+/// it.
+///
+/// ```rust
+/// use eight_deep_parser::{parse_multi_bytes, ParseError};
+///
+/// let e = parse_multi_bytes(b"Package: a\n\nPackage: b\nMaintainer: J\xe9r\xf4me\n").unwrap_err();
+///
+/// assert!(matches!(
+///     e,
+///     ParseError::InvalidUtf8 { offset: 36, paragraph: Some(1), key: Some(ref key), .. }
+///         if key == "Maintainer"
+/// ));
+/// ```
+pub fn parse_multi_bytes(s: &[u8]) -> Result<Vec<IndexMap<String, Item>>> {
+    match std::str::from_utf8(s) {
+        Ok(s) => parse_multi(s),
+        Err(e) => Err(ParseError::invalid_utf8(s, &e, 0, Some(0))),
+    }
+}
+
+/// Parse multi package with the given [`ParseOptions`]
+///
+/// With the `tracing` feature this runs in a `parse_multi` span at the
+/// debug level, recording `len`, the length of `s`, and on success
+/// `paragraphs`, how many were parsed. What the options let through gives
+/// debug events: skipped orphan lines and garbage, whitespace trimmed
+/// before a colon and duplicate fields resolved. Trailing data dropped under
+/// [`TrailingData::Skip`] gives a warn event.
+pub fn parse_multi_with(s: &str, opts: &ParseOptions) -> Result<Vec<IndexMap<String, Item>>> {
+    #[cfg(feature = "tracing")]
+    let span = tracing::debug_span!(
+        "parse_multi",
+        len = s.len(),
+        paragraphs = tracing::field::Empty
+    )
+    .entered();
+
+    let result = if opts.trailing_data == TrailingData::Resync {
+        parse_multi_recover(s.as_bytes(), opts).0
+    } else {
+        let prepared = opts.prepare(s)?;
+
+        parse_multi_prepared(&prepared, opts).map_err(|e| prepared.error(s, e))?
+    };
+
+    #[cfg(feature = "tracing")]
+    span.record("paragraphs", result.len());
+
+    Ok(result)
+}
+
+/// [`parse_multi_with`] on the input as rewritten by
+/// [`ParseOptions::prepare`], with positions in errors relative to the text
+fn parse_multi_prepared(
+    prepared: &Prepared,
+    opts: &ParseOptions,
+) -> Result<Vec<IndexMap<String, Item>>> {
+    let s = &*prepared.text;
+
+    if is_blank(s) {
         return Ok(Vec::new());
     }
 
-    let (_, parse_v) = parser::multi_package(s.as_bytes())?;
+    let first = s.len() - s.trim_start().len();
+    let (rest, mut parse_v) = parser::multi_package_keep_empty(s.as_bytes()).map_err(|e| {
+        match malformed_line(s, first) {
+            Some(line) => ParseError::Malformed { line },
+            None => e.into(),
+        }
+        .in_paragraph(0, first)
+    })?;
+    let offset = s.len() - rest.len();
+
+    while parse_v.last().map(|x| x.is_empty()).unwrap_or(false) {
+        parse_v.pop();
+    }
 
     let mut result = vec![];
+    let mut start = offset;
 
     for i in parse_v {
-        result.push(to_map(i)?);
+        if i.is_empty() && !opts.keep_empty {
+            continue;
+        }
+
+        // the offset of a paragraph is where its first key is in `s`
+        start = i
+            .first()
+            .map_or(0, |(k, _)| k.as_ptr() as usize - s.as_ptr() as usize);
+        let p = check_field_names(s, &i, opts)
+            .and_then(|_| to_map(i, opts))
+            .map_err(|e| e.in_paragraph(result.len(), start))?;
+
+        result.push(p);
+    }
+
+    if let Some(line) = malformed_line(s, offset) {
+        return Err(
+            ParseError::Malformed { line }.in_paragraph(result.len().saturating_sub(1), start)
+        );
+    }
+
+    if opts.trailing_data == TrailingData::Error && !is_blank(&s[offset..]) {
+        let start = offset + (s[offset..].len() - s[offset..].trim_start().len());
+
+        return Err(ParseError::TrailingData { offset }.in_paragraph(result.len(), start));
+    }
+
+    #[cfg(feature = "tracing")]
+    if !is_blank(&s[offset..]) {
+        tracing::warn!(offset = prepared.offset(offset), "discarded trailing data");
     }
 
     Ok(result)
 }
 
-fn to_map(parse_v: NomParseItem) -> Result<IndexMap<String, Item>> {
-    let mut result = IndexMap::new();
-    for (k, v) in parse_v {
-        let (one, multi) = v;
-        let k = std::str::from_utf8(k)?.to_string();
+/// Parse multi package from untrusted input, failing once it holds more than
+/// `max_paragraphs` paragraphs or a field value longer than
+/// `max_field_bytes`
+///
+/// A field value is counted with its continuation lines and the line breaks
+/// between them. The limits are checked while scanning, before a paragraph
+/// or value is built, so a huge download can't make the parser allocate
+/// without bound. Within the limits the result is the same as
+/// [`parse_multi`].
+///
+/// ```rust
+/// use eight_deep_parser::{parse_multi_limited, ParseError};
+///
+/// let s = "Package: a\n\nPackage: b\n";
+///
+/// assert_eq!(parse_multi_limited(s, 2, 16).unwrap().len(), 2);
+/// assert!(matches!(
+///     parse_multi_limited(s, 1, 16),
+///     Err(ParseError::TooManyParagraphs { limit: 1 })
+/// ));
+/// ```
+pub fn parse_multi_limited(
+    s: &str,
+    max_paragraphs: usize,
+    max_field_bytes: usize,
+) -> Result<Vec<IndexMap<String, Item>>> {
+    let prepared = ParseOptions::default().prepare(s)?;
+
+    parse_limited_prepared(&prepared.text, max_paragraphs, max_field_bytes)
+        .map_err(|e| prepared.error(s, e))
+}
 
-        if one.is_empty() {
-            let multi = std::str::from_utf8(&multi)?;
-            let multi = multi.split('\n').map(|x| x.to_string()).collect();
+/// [`parse_multi_limited`] on `s` as rewritten by [`ParseOptions::prepare`],
+/// with positions in errors relative to it
+fn parse_limited_prepared(
+    s: &str,
+    max_paragraphs: usize,
+    max_field_bytes: usize,
+) -> Result<Vec<IndexMap<String, Item>>> {
+    if is_blank(s) {
+        return Ok(Vec::new());
+    }
 
-            result.insert(k, Item::MultiLine(multi));
-            continue;
+    let mut input = s.as_bytes();
+    let mut result = vec![];
+
+    loop {
+        while let Ok((rest, _)) = parser::blank_line(input) {
+            input = rest;
+        }
+
+        let mut paragraph = vec![];
+
+        while let Ok((rest, (key, value))) = parser::field_line(input) {
+            input = rest;
+
+            if paragraph.is_empty() && result.len() == max_paragraphs {
+                return Err(ParseError::TooManyParagraphs {
+                    limit: max_paragraphs,
+                });
+            }
+
+            let mut len = value.len();
+            let mut lines = vec![];
+
+            while let Ok((rest, line)) = parser::multi_line_single(input) {
+                input = rest;
+                len += line.len() + 1;
+                lines.push(line);
+            }
+
+            if len > max_field_bytes {
+                return Err(ParseError::FieldTooLong {
+                    key: trim_key(&String::from_utf8_lossy(key)).0.to_string(),
+                    limit: max_field_bytes,
+                });
+            }
+
+            paragraph.push((key, (value, lines.join(&b'\n'))));
+        }
+
+        if paragraph.is_empty() {
+            break;
         }
 
-        result.insert(k, Item::OneLine(std::str::from_utf8(one)?.to_string()));
+        result.push(to_map(paragraph, &ParseOptions::default())?);
+    }
+
+    if let Some(line) = malformed_line(s, s.len() - input.len()) {
+        return Err(ParseError::Malformed { line });
     }
 
     Ok(result)
 }
 
-/// Parse back:
-/// 
+/// Parse multi package after an optional header line that isn't a field,
+/// like a format version marker
+///
+/// The first line is the header if it has no `:`, or starts with
+/// whitespace or `#`. It is returned without its line break.
+///
 /// ```rust
-/// use indexmap::IndexMap;
-/// use eight_deep_parser::{parse_back, Item};
-/// 
-/// fn test_parse_back() {
-///     let mut map = vec![];
+/// use eight_deep_parser::{parse_multi_with_header, ParagraphExt};
 ///
-///     let mut item1 = IndexMap::new();
-///     item1.insert("a".to_string(), Item::OneLine("b".to_string()));
-///     item1.insert(
-///         "c".to_string(),
-///         Item::MultiLine(vec!["a".to_string(), "b".to_string()]),
-///     );
-///     item1.insert("d".to_string(), Item::OneLine("e".to_string()));
-///     map.push(item1);
+/// let (header, r) = parse_multi_with_header("FORMAT 2\nPackage: a\n\nPackage: b\n").unwrap();
 ///
-///     let mut item2 = IndexMap::new();
-///     item2.insert("a".to_string(), Item::OneLine("b".to_string()));
-///     map.push(item2);
+/// assert_eq!(header.as_deref(), Some("FORMAT 2"));
+/// assert_eq!(r[1].get_one("Package"), Some("b"));
+/// ```
+pub fn parse_multi_with_header(s: &str) -> Result<(Option<String>, Vec<Paragraph>)> {
+    let (first, rest) = s.split_once('\n').unwrap_or((s, ""));
+
+    if first.is_empty() || cst::field_colon(first).is_some() {
+        return Ok((None, parse_multi(s)?));
+    }
+
+    Ok((Some(first.to_string()), parse_multi(rest)?))
+}
+
+/// Parse multi package, handing every field to `visitor` as it is collected:
 ///
-///     let s = parse_back(&map);
+/// The visitor may rewrite the value in place and decides through the
+/// returned [`FieldAction`] whether the field is kept, dropped or stored
+/// under another name.
 ///
-///     assert_eq!(
-///         s,
-///         r#"a: b
-/// c:
-///   a
-///   b
-/// d: e
+/// ```rust
+/// use eight_deep_parser::{parse_multi_visit, FieldAction, Item};
 ///
-/// a: b
+/// let s = "Package: a\nDescription: b\n\nPackage: c\n";
 ///
-/// "#
-///     )
-/// }
+/// let r = parse_multi_visit(s, |k, _| match k {
+///     "Description" => FieldAction::Drop,
+///     "Package" => FieldAction::Rename("Name".to_string()),
+///     _ => FieldAction::Keep,
+/// })
+/// .unwrap();
+///
+/// assert_eq!(r[0].get("Name").unwrap(), &Item::OneLine("a".to_string()));
+/// assert!(r[0].get("Description").is_none());
+/// ```
+pub fn parse_multi_visit<F>(s: &str, mut visitor: F) -> Result<Vec<IndexMap<String, Item>>>
+where
+    F: FnMut(&str, &mut Item) -> FieldAction,
+{
+    let s = &*OrphanLines::Skip.apply(s, &mut vec![])?;
+
+    if is_blank(s) {
+        return Ok(Vec::new());
+    }
+
+    let parse_v = multi_package(s)?;
+
+    let mut result = vec![];
+
+    for i in parse_v {
+        result.push(to_map_visit(i, &ParseOptions::default(), &mut visitor)?);
+    }
+
+    Ok(result)
+}
 
+/// Parse multi package, keeping only the fields named in `wanted`
+///
+/// Names are matched case-insensitively. Every line is still read, but the
+/// values of other fields are never turned into [`Item`]s, which is faster
+/// on a large index when only a few fields are needed.
+///
+/// ```rust
+/// use eight_deep_parser::{parse_multi_fields, ParagraphExt};
+///
+/// let s = "Package: a\nVersion: 1\nDescription: b\n c\n\nPackage: d\nVersion: 2\n";
+/// let r = parse_multi_fields(s, &["package", "Version"]).unwrap();
+///
+/// assert_eq!(r[0].keys().collect::<Vec<_>>(), ["Package", "Version"]);
+/// assert_eq!(r[1].get_one("Version"), Some("2"));
 /// ```
+pub fn parse_multi_fields(s: &str, wanted: &[&str]) -> Result<Vec<IndexMap<String, Item>>> {
+    let opts = ParseOptions::default();
+    let s = &*opts.orphan_lines.apply(s, &mut vec![])?;
 
-pub fn parse_back(map: &[IndexMap<String, Item>]) -> String {
-    let mut s = String::new();
-    for i in map {
-        for (k, v) in i {
-            s += &format!("{}:", k);
+    if is_blank(s) {
+        return Ok(Vec::new());
+    }
 
-            match v {
-                Item::OneLine(v) => s += &format!(" {}\n", v),
-                Item::MultiLine(v) => {
-                    s += "\n";
-                    for i in v {
-                        s += &format!("  {}\n", i);
-                    }
-                }
-            }
-        }
+    let parse_v = multi_package(s)?;
 
-        s += "\n";
+    let mut result = vec![];
+
+    for mut i in parse_v {
+        i.retain(|(k, _)| {
+            let k = k.trim_ascii_end();
+            wanted.iter().any(|x| k.eq_ignore_ascii_case(x.as_bytes()))
+        });
+        result.push(to_map(i, &opts)?);
     }
 
-    s
+    Ok(result)
 }
 
-#[cfg(test)]
-mod tests {
-    use std::{fs, io::Read, process::Command};
+/// A transform of a field value, see [`parse_with_decoders`]
+///
+/// The error is a message, reported as [`ParseError::Decode`].
+pub type Decoder<'a> = Box<dyn Fn(&[u8]) -> std::result::Result<String, String> + 'a>;
 
-    use indexmap::IndexMap;
+/// Parse multi package, running the values of some fields through a decoder:
+///
+/// `decoders` maps field names, matched case-insensitively, to the decoder
+/// of their values. A decoder gets the text on the field line and every
+/// continuation line separately; other fields are kept as they are.
+///
+/// ```rust
+/// use std::collections::HashMap;
+/// use eight_deep_parser::{parse_with_decoders, Decoder, Item};
+///
+/// let mut decoders: HashMap<&str, Decoder> = HashMap::new();
+/// decoders.insert("X-Upper", Box::new(|x| Ok(String::from_utf8_lossy(x).to_uppercase())));
+///
+/// let r = parse_with_decoders("Package: a\nX-Upper: b\n", &decoders).unwrap();
+///
+/// assert_eq!(r[0]["X-Upper"], Item::OneLine("B".to_string()));
+/// ```
+pub fn parse_with_decoders(
+    s: &str,
+    decoders: &HashMap<&str, Decoder>,
+) -> Result<Vec<IndexMap<String, Item>>> {
+    let decoders = decoders
+        .iter()
+        .map(|(k, v)| (k.to_ascii_lowercase(), v))
+        .collect::<HashMap<_, _>>();
 
-    use crate::{parse_back, parse_multi, parse_one, Item};
+    let mut error = None;
 
-    #[test]
-    fn parse_one_it_works() {
-        let command = Command::new("dpkg")
-            .arg("-s")
-            .arg("plasma-workspace")
-            .output()
-            .unwrap();
-        let stdout = command.stdout;
+    let result = parse_multi_visit(s, |k, item| {
+        if error.is_some() {
+            return FieldAction::Keep;
+        }
 
-        let r = parse_one(std::str::from_utf8(&stdout).unwrap()).unwrap();
+        if let Some(decoder) = decoders.get(&k.to_ascii_lowercase()) {
+            let decode = |x: &mut String| -> Result<()> {
+                *x = decoder(x.as_bytes()).map_err(|reason| ParseError::Decode {
+                    key: k.to_string(),
+                    reason,
+                })?;
 
-        assert_eq!(
-            r.get("Package").unwrap(),
-            &Item::OneLine("plasma-workspace".to_string())
-        );
+                Ok(())
+            };
 
-        let right = vec![
-            "/etc/pam.d/kde a33459447160292012baca99cb9820b3",
-            "/etc/xdg/autostart/gmenudbusmenuproxy.desktop 4bf33ab6a937c4991c0ec418bfff11a0",
-            "/etc/xdg/autostart/klipper.desktop cc58958cfa37d7f4001e24e3de34abbd",
-            "/etc/xdg/autostart/org.kde.plasmashell.desktop 9552c32cf4e0c3a56b2884f6b08d7c72",
-            "/etc/xdg/autostart/xembedsniproxy.desktop 76011e12682833a1b4b3a01c7faac001",
-            "/etc/xdg/plasmanotifyrc f9713a8fb2a4abb43e592f0c12f3fab5",
-            "/etc/xdg/taskmanagerrulesrc 9df6c5d4530892fac71c219f27892f5b",
-        ];
+            let r = match item {
+                Item::OneLine(x) => decode(x),
+                Item::MultiLine(v) => v.iter_mut().try_for_each(decode),
+                Item::Folded(x, v) => decode(x).and_then(|_| v.iter_mut().try_for_each(decode)),
+                Item::Streamed => Ok(()),
+            };
 
-        let right = right.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+            error = r.err();
+        }
 
-        assert_eq!(r.get("Conffiles").unwrap(), &Item::MultiLine(right));
+        FieldAction::Keep
+    })?;
 
-        assert_eq!(
-            r.get("Description").unwrap(),
-            &Item::OneLine("The KDE Plasma Workspace, API and runtime libraries".to_string())
-        );
+    match error {
+        Some(e) => Err(e),
+        None => Ok(result),
     }
+}
 
-    #[test]
-    fn parse_multi_it_works() {
-        let dir = fs::read_dir("/var/lib/apt/lists").unwrap();
+/// What a [`parse_multi_visit`] visitor wants done with a field
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum FieldAction {
+    /// Store the field under its own name
+    Keep,
+    /// Leave the field out of the paragraph
+    Drop,
+    /// Store the field under the given name
+    Rename(String),
+}
+
+fn to_map(parse_v: NomParseItem, opts: &ParseOptions) -> Result<IndexMap<String, Item>> {
+    to_map_visit(parse_v, opts, &mut |_, _| FieldAction::Keep)
+}
 
-        for i in dir.flatten() {
-            if !i.file_name().to_str().unwrap().ends_with("_Packages") {
-                continue;
+fn to_map_visit<F>(
+    parse_v: NomParseItem,
+    opts: &ParseOptions,
+    visitor: &mut F,
+) -> Result<IndexMap<String, Item>>
+where
+    F: FnMut(&str, &mut Item) -> FieldAction,
+{
+    let mut result: IndexMap<String, Item> = IndexMap::new();
+    for (k, v) in parse_v {
+        let k = match trim_key(std::str::from_utf8(k)?) {
+            (k, true) if opts.space_before_colon == SpaceBeforeColon::Error => {
+                return Err(ParseError::SpaceBeforeColon { key: k.to_string() })
+            }
+            #[cfg(feature = "tracing")]
+            (k, true) => {
+                tracing::debug!(key = %k, "trimmed the whitespace before the colon");
+                k
             }
+            (k, _) => k,
+        };
+        let mut item = to_item(v, opts.continuation)?;
 
-            let mut f = std::fs::File::open(i.path()).unwrap();
-            let mut buf = Vec::new();
-            f.read_to_end(&mut buf).unwrap();
+        let k = match visitor(k, &mut item) {
+            FieldAction::Keep => k.to_string(),
+            FieldAction::Drop => continue,
+            FieldAction::Rename(k) => k,
+        };
 
-            let r = parse_multi(std::str::from_utf8(&buf).unwrap());
+        let k = match opts.normalize_keys {
+            Some(style) => keys::normalize_key(&k, style),
+            None => k,
+        };
 
-            assert!(r.is_ok())
-        }
-    }
+        match result.get_mut(&k) {
+            Some(_) if opts.duplicates == DuplicateKeys::Error => {
+                return Err(ParseError::DuplicateKey { key: k })
+            }
+            Some(first) if opts.duplicates == DuplicateKeys::Concat => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(key = %k, "appended a duplicate field to the first one");
 
-    #[test]
-    fn test_parse_back() {
-        let mut map = vec![];
+                for line in item.lines() {
+                    first.push_line(line);
+                }
+            }
+            _ => {
+                // the last one wins
+                #[cfg(feature = "tracing")]
+                if result.contains_key(&k) {
+                    tracing::debug!(key = %k, "replaced a duplicate field");
+                }
+
+                result.insert(k, item);
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+/// The paragraphs of `s`, failing on a line that isn't part of one, see
+/// [`malformed_line`]
+fn multi_package(s: &str) -> Result<Vec<NomParseItem<'_>>> {
+    let (rest, parse_v) = parser::multi_package(s.as_bytes())?;
+
+    match malformed_line(s, s.len() - rest.len()) {
+        Some(line) => Err(ParseError::Malformed { line }),
+        None => Ok(parse_v),
+    }
+}
+
+/// The number of the line at `offset`, where parsing stopped, if it is
+/// malformed: a line that isn't a field, a continuation line nor a blank
+/// line, inside a paragraph or before the first one. After a blank line
+/// that follows a paragraph it is trailing data instead.
+fn malformed_line(s: &str, offset: usize) -> Option<usize> {
+    if s[offset..].trim().is_empty() {
+        return None;
+    }
+
+    let mut paragraph_start = false;
+
+    // a line of whitespace is a continuation line in a paragraph and a
+    // blank line before one, so look further back
+    for line in s[..offset].lines().rev() {
+        if line.is_empty() {
+            paragraph_start = true;
+        } else if !line.trim_matches([' ', '\t', '\r']).is_empty() {
+            if paragraph_start {
+                return None;
+            }
+            break;
+        }
+    }
+
+    Some(s[..offset].matches('\n').count() + 1)
+}
+
+/// Nothing but blank lines and `#` comment lines, which parse to no
+/// paragraph
+fn is_blank(s: &str) -> bool {
+    s.lines().all(|x| x.trim().is_empty() || x.starts_with('#'))
+}
+
+/// With [`ParseOptions::strict_field_names`], fail on the line of the
+/// first field of `parse_v` whose name holds whitespace or control
+/// characters, the keys of `parse_v` being slices of `s`
+fn check_field_names(s: &str, parse_v: &NomParseItem, opts: &ParseOptions) -> Result<()> {
+    if !opts.strict_field_names {
+        return Ok(());
+    }
+
+    for (k, _) in parse_v {
+        if invalid_field_name(&String::from_utf8_lossy(k)) {
+            let offset = k.as_ptr() as usize - s.as_ptr() as usize;
+
+            return Err(ParseError::Malformed {
+                line: s[..offset].matches('\n').count() + 1,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether the field name `k`, as written before the colon, holds
+/// whitespace or control characters, see
+/// [`ParseOptions::strict_field_names`]
+pub(crate) fn invalid_field_name(k: &str) -> bool {
+    trim_key(k)
+        .0
+        .chars()
+        .any(|x| x.is_whitespace() || x.is_control())
+}
+
+/// The field name without the whitespace before the colon, and whether
+/// there was any
+fn trim_key(k: &str) -> (&str, bool) {
+    let trimmed = k.trim_end_matches([' ', '\t']);
+
+    (trimmed, trimmed.len() != k.len())
+}
+
+fn to_item((one, multi): (&[u8], Vec<u8>), ws: ContinuationWhitespace) -> Result<Item> {
+    let item = if one.is_empty() {
+        let multi = std::str::from_utf8(&multi)?;
+        let multi = split_lines(multi, ws);
+
+        Item::MultiLine(multi)
+    } else if !multi.is_empty() {
+        let multi = std::str::from_utf8(&multi)?;
+        let multi = split_lines(multi, ws);
+
+        Item::Folded(std::str::from_utf8(one)?.to_string(), multi)
+    } else {
+        Item::OneLine(std::str::from_utf8(one)?.to_string())
+    };
+
+    Ok(item)
+}
+
+fn split_lines(multi: &str, ws: ContinuationWhitespace) -> Vec<String> {
+    if multi.is_empty() {
+        return vec![String::new()];
+    }
+
+    multi.split('\n').map(|x| ws.strip(x).to_string()).collect()
+}
+
+/// The `Description-md5` of a `Description` field value, as found in
+/// Packages files and used to look up translations
+///
+/// ```rust
+/// use eight_deep_parser::{description_md5, parse_one, ParagraphExt};
+///
+/// let r = parse_one("Package: dash\nDescription: POSIX-compliant shell\n").unwrap();
+///
+/// assert_eq!(description_md5(r.get_field("Description").unwrap()).len(), 32);
+/// ```
+#[cfg(feature = "md5")]
+pub fn description_md5(item: &Item) -> String {
+    let (first, lines) = match item {
+        Item::OneLine(s) => (s.as_str(), &[][..]),
+        Item::MultiLine(v) => ("", &v[..]),
+        Item::Folded(s, v) => (s.as_str(), &v[..]),
+        Item::Streamed => ("", &[][..]),
+    };
+
+    let mut s = format!("{}\n", first);
+
+    for line in lines {
+        s += " ";
+        s += line;
+        s += "\n";
+    }
+
+    md5::md5_hex(s.as_bytes())
+}
+
+/// Parse multi package, sharing one allocation between all equal field names:
+///
+/// Every paragraph of a Packages file repeats the same few dozen keys, so
+/// this keeps a single `Arc<str>` per distinct key instead of a `String`
+/// per field.
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use eight_deep_parser::parse_multi_interned;
+///
+/// let r = parse_multi_interned("Package: a\n\nPackage: b\n").unwrap();
+///
+/// let a = r[0].get_index(0).unwrap().0;
+/// let b = r[1].get_index(0).unwrap().0;
+///
+/// assert!(Arc::ptr_eq(a, b));
+/// ```
+pub fn parse_multi_interned(s: &str) -> Result<Vec<IndexMap<Arc<str>, Item>>> {
+    parse_interned(s, |_, x| x)
+}
+
+/// [`parse_multi_interned`], storing each value as `f` turns it, given the
+/// field name
+pub(crate) fn parse_interned<T>(
+    s: &str,
+    mut f: impl FnMut(&str, Item) -> T,
+) -> Result<Vec<IndexMap<Arc<str>, T>>> {
+    let s = &*OrphanLines::Skip.apply(s, &mut vec![])?;
+
+    if is_blank(s) {
+        return Ok(Vec::new());
+    }
+
+    let parse_v = multi_package(s)?;
+
+    let mut pool: HashMap<&[u8], Arc<str>> = HashMap::new();
+    let mut result = vec![];
+
+    for i in parse_v {
+        let mut map = IndexMap::with_capacity(i.len());
+        for (k, v) in i {
+            let k = match pool.get(k) {
+                Some(k) => k.clone(),
+                None => {
+                    let interned: Arc<str> = Arc::from(trim_key(std::str::from_utf8(k)?).0);
+                    pool.insert(k, interned.clone());
+                    interned
+                }
+            };
+
+            let v = f(&k, to_item(v, ContinuationWhitespace::default())?);
+            map.insert(k, v);
+        }
+
+        result.push(map);
+    }
+
+    Ok(result)
+}
+
+/// Parse back:
+///
+/// ```rust
+/// use indexmap::IndexMap;
+/// use eight_deep_parser::{parse_back, Item};
+///
+/// fn test_parse_back() {
+///     let mut map = vec![];
+///
+///     let mut item1 = IndexMap::new();
+///     item1.insert("a".to_string(), Item::OneLine("b".to_string()));
+///     item1.insert(
+///         "c".to_string(),
+///         Item::MultiLine(vec!["a".to_string(), "b".to_string()]),
+///     );
+///     item1.insert("d".to_string(), Item::OneLine("e".to_string()));
+///     map.push(item1);
+///
+///     let mut item2 = IndexMap::new();
+///     item2.insert("a".to_string(), Item::OneLine("b".to_string()));
+///     map.push(item2);
+///
+///     let s = parse_back(&map);
+///
+///     assert_eq!(
+///         s,
+///         r#"a: b
+/// c:
+///  a
+///  b
+/// d: e
+///
+/// a: b
+/// "#
+///     )
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if the paragraphs can't be written in a form that parses back to
+/// the same data, see [`try_parse_back`].
+pub fn parse_back(map: &[IndexMap<String, Item>]) -> String {
+    parse_back_with(map, &SerializeOptions::default())
+}
+
+/// Parse back, checking that the output will parse into the same data:
+///
+/// Field names must be non-empty, must not start with whitespace or `#`,
+/// must not end with whitespace and must not contain `:` or line breaks.
+/// Values must not contain line breaks, apart from the ones between the
+/// lines of a [`Item::MultiLine`] or [`Item::Folded`], and must be in a
+/// form the parser gives: a [`Item::OneLine`] or the first line of a
+/// [`Item::Folded`] is non-empty and doesn't start with whitespace, and a
+/// [`Item::MultiLine`] or [`Item::Folded`] has at least one continuation
+/// line. A `\r` is a line break too, so values of CRLF input
+/// parsed with [`LineEndings::Lf`], which keep the `\r`, can't be written.
+///
+/// Lines end with `\n` whatever the input they were parsed from used,
+/// unless [`SerializeOptions::crlf`] is set.
+///
+/// ```rust
+/// use eight_deep_parser::{try_parse_back, IndexMap, Item, SerializeError};
+///
+/// let mut item = IndexMap::new();
+/// item.insert("a".to_string(), Item::OneLine("b\nc: d".to_string()));
+///
+/// assert_eq!(
+///     try_parse_back(&[item]),
+///     Err(SerializeError::NewlineInValue {
+///         paragraph: 0,
+///         key: "a".to_string()
+///     })
+/// );
+/// ```
+pub fn try_parse_back(
+    map: &[IndexMap<String, Item>],
+) -> std::result::Result<String, SerializeError> {
+    try_parse_back_with(map, &SerializeOptions::default())
+}
+
+/// Parse back with the given [`SerializeOptions`]:
+///
+/// ```rust
+/// use eight_deep_parser::{
+///     parse_back_with, parse_multi_with, ContinuationWhitespace, ParseOptions, SerializeOptions,
+/// };
+///
+/// let s = "a:\n   indented\n\tb\n";
+///
+/// let map = parse_multi_with(
+///     s,
+///     &ParseOptions {
+///         continuation: ContinuationWhitespace::Preserve,
+///         ..Default::default()
+///     },
+/// )
+/// .unwrap();
+///
+/// let opts = SerializeOptions {
+///     continuation: ContinuationWhitespace::Preserve,
+///     ..Default::default()
+/// };
+///
+/// assert_eq!(parse_back_with(&map, &opts), s);
+/// ```
+///
+/// # Panics
+///
+/// Panics where [`try_parse_back_with`] would return an error.
+pub fn parse_back_with(map: &[IndexMap<String, Item>], opts: &SerializeOptions) -> String {
+    match try_parse_back_with(map, opts) {
+        Ok(s) => s,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+/// Parse back with the given [`SerializeOptions`], checking the input like
+/// [`try_parse_back`]
+pub fn try_parse_back_with(
+    map: &[IndexMap<String, Item>],
+    opts: &SerializeOptions,
+) -> std::result::Result<String, SerializeError> {
+    serialize(map, opts, &[])
+}
+
+/// Parse back, putting the comments back where
+/// [`Cst::paragraphs_with_comments`] found them
+///
+/// A comment between two paragraphs comes out between them, after the blank
+/// line. Blank lines are written as [`parse_back`] writes them.
+///
+/// ```rust
+/// use eight_deep_parser::{parse_back_with_comments, Cst, ParseOptions};
+///
+/// let s = "# header\nPackage: a\n# about b\nB: c\n\n# second\nPackage: d\n";
+/// let (r, comments) = Cst::parse(s).paragraphs_with_comments(&ParseOptions::default()).unwrap();
+///
+/// assert_eq!(r.len(), 2);
+/// assert_eq!(parse_back_with_comments(&r, &comments), s);
+/// ```
+///
+/// # Panics
+///
+/// Panics like [`parse_back`].
+pub fn parse_back_with_comments(map: &[IndexMap<String, Item>], comments: &[Comment]) -> String {
+    match serialize(map, &SerializeOptions::default(), comments) {
+        Ok(s) => s,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+fn serialize(
+    map: &[IndexMap<String, Item>],
+    opts: &SerializeOptions,
+    comments: &[Comment],
+) -> std::result::Result<String, SerializeError> {
+    let write_comments = |s: &mut String, paragraph: usize, fields: Range<usize>| {
+        for c in comments
+            .iter()
+            .filter(|x| x.paragraph == paragraph && fields.contains(&x.field))
+        {
+            *s += &c.text;
+            s.push('\n');
+        }
+    };
+
+    let mut s = String::new();
+    for (index, i) in map.iter().enumerate() {
+        if index != 0 {
+            s += "\n";
+        }
+
+        for (field, (k, v)) in i.iter().enumerate() {
+            check_field(index, k, v)?;
+
+            write_comments(&mut s, index, field..field + 1);
+
+            s += k;
+            s.push(':');
+
+            match v {
+                Item::OneLine(v) => first_line(&mut s, v),
+                Item::MultiLine(v) => {
+                    s += "\n";
+                    for i in v {
+                        continuation_line(&mut s, i, opts);
+                    }
+                }
+                Item::Folded(first, v) => {
+                    first_line(&mut s, first);
+                    for i in v {
+                        continuation_line(&mut s, i, opts);
+                    }
+                }
+                Item::Streamed => unreachable!("refused by check_field"),
+            }
+        }
+
+        write_comments(&mut s, index, i.len()..usize::MAX);
+    }
+
+    if opts.trailing_blank_line && !map.is_empty() {
+        s += "\n";
+    }
+
+    if comments.iter().any(|x| x.paragraph >= map.len()) {
+        if !opts.trailing_blank_line && !map.is_empty() {
+            s += "\n";
+        }
+
+        for paragraph in map.len()..=comments.iter().map(|x| x.paragraph).max().unwrap_or(0) {
+            write_comments(&mut s, paragraph, 0..usize::MAX);
+        }
+    }
+
+    if opts.crlf {
+        s = s.replace('\n', "\r\n");
+    }
+
+    Ok(s)
+}
+
+fn check_field(paragraph: usize, k: &str, v: &Item) -> std::result::Result<(), SerializeError> {
+    if k.is_empty()
+        || k.starts_with(char::is_whitespace)
+        || k.starts_with('#')
+        || k.ends_with([' ', '\t'])
+        || k.contains([':', '\n', '\r'])
+    {
+        return Err(SerializeError::InvalidKey {
+            paragraph,
+            key: k.to_string(),
+        });
+    }
+
+    let (first, rest) = match v {
+        Item::OneLine(s) => (Some(s), &[][..]),
+        Item::MultiLine(v) => (None, &v[..]),
+        Item::Folded(s, v) => (Some(s), &v[..]),
+        Item::Streamed => {
+            return Err(SerializeError::Streamed {
+                paragraph,
+                key: k.to_string(),
+            })
+        }
+    };
+
+    if first
+        .into_iter()
+        .chain(rest)
+        .any(|x| x.contains(['\n', '\r']))
+    {
+        return Err(SerializeError::NewlineInValue {
+            paragraph,
+            key: k.to_string(),
+        });
+    }
+
+    // the separator eats the whitespace after the colon, `Key:` alone is a
+    // `MultiLine` of one empty line and a field line alone is a `OneLine`
+    let lossy = !matches!(v, Item::OneLine(_)) && rest.is_empty()
+        || first.is_some_and(|x| x.is_empty() || x.starts_with([' ', '\t']));
+
+    if lossy {
+        return Err(SerializeError::InvalidValue {
+            paragraph,
+            key: k.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+fn first_line(s: &mut String, line: &str) {
+    s.push(' ');
+    *s += line;
+    s.push('\n');
+}
+
+fn continuation_line(s: &mut String, line: &str, opts: &SerializeOptions) {
+    if opts.continuation != ContinuationWhitespace::Preserve || !line.starts_with([' ', '\t']) {
+        s.push(' ');
+    }
+
+    *s += line;
+    s.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use indexmap::IndexMap;
+
+    use crate::{
+        parse_back, parse_back_with, parse_multi, parse_multi_interned, parse_multi_visit,
+        parse_multi_with, parse_one, parse_one_with, ContinuationWhitespace, DuplicateKeys,
+        FieldAction, FieldChunk, Item, KeyStyle, LineEndings, OrphanLines, ParagraphExt,
+        ParseError, ParseOptions, SerializeError, SerializeOptions, SpaceBeforeColon, TrailingData,
+    };
+    use crate::{
+        parse_back_with_comments, parse_multi_bytes, parse_multi_fields, parse_multi_limited,
+        parse_multi_with_header, parse_one_rest, parse_one_streaming, parse_with_decoders,
+        test_util::fixtures, try_parse_back, Cst, Decoder, ParagraphDecoder,
+    };
+
+    #[test]
+    fn parse_one_it_works() {
+        let (bash, _) = fixtures::STATUS.split_once("\n\n").unwrap();
+        let r = parse_one(&format!("{}\n", bash)).unwrap();
+
+        assert_eq!(
+            r.get("Package").unwrap(),
+            &Item::OneLine("bash".to_string())
+        );
+
+        let right = [
+            "/etc/bash.bashrc 89269e1298235f1b12b4c16e4065ad0d",
+            "/etc/skel/.bash_logout 22bfb8c1dd94b5f3813a2b25da67463f",
+            "/etc/skel/.bashrc ee35a240758f374832e809ae0ea4883a",
+            "/etc/skel/.profile f4e81ade7d6f9fb342541152d08e7a97",
+        ];
+
+        let right = right.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(r.get("Conffiles").unwrap(), &Item::MultiLine(right));
+
+        assert_eq!(r.description().unwrap().synopsis, "GNU Bourne Again SHell");
+    }
+
+    #[test]
+    fn parse_multi_it_works() {
+        for s in [
+            fixtures::STATUS,
+            fixtures::PACKAGES,
+            fixtures::SOURCES,
+            fixtures::RELEASE,
+        ] {
+            assert!(parse_multi(s).is_ok());
+        }
+
+        assert_eq!(parse_multi(fixtures::PACKAGES).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_parse_back() {
+        let mut map = vec![];
 
         let mut item1 = IndexMap::new();
         item1.insert("a".to_string(), Item::OneLine("b".to_string()));
@@ -263,13 +1475,1373 @@ mod tests {
             s,
             r#"a: b
 c:
-  a
-  b
+ a
+ b
 d: e
 
 a: b
-
 "#
         )
     }
+
+    #[test]
+    fn test_parse_multi_visit_drop() {
+        let s = "Package: a\nDescription: b\nVersion: 1\n\nPackage: c\nDescription: d\n";
+
+        let r = parse_multi_visit(s, |k, _| {
+            if k == "Description" {
+                FieldAction::Drop
+            } else {
+                FieldAction::Keep
+            }
+        })
+        .unwrap();
+
+        assert_eq!(r.len(), 2);
+        assert!(r.iter().all(|x| x.get("Description").is_none()));
+        assert_eq!(
+            r[0].get("Version").unwrap(),
+            &Item::OneLine("1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_multi_fields() {
+        let r =
+            parse_multi_fields(fixtures::PACKAGES, &["package", "VERSION", "Nonexistent"]).unwrap();
+
+        assert_eq!(r.len(), 4);
+        assert!(r
+            .iter()
+            .all(|x| x.keys().collect::<Vec<_>>() == ["Package", "Version"]));
+        assert_eq!(
+            r.iter().map(|x| x.get_one("Package")).collect::<Vec<_>>(),
+            fixtures::packages()
+                .iter()
+                .map(|x| x.get_one("Package"))
+                .collect::<Vec<_>>()
+        );
+
+        let r =
+            parse_multi_fields("Package : a\nDepends: b\n\nDepends: c\n", &["Package"]).unwrap();
+
+        assert_eq!(r.len(), 2);
+        assert_eq!(r[0].get_one("Package"), Some("a"));
+        assert!(r[1].is_empty());
+        assert!(parse_multi_fields(fixtures::PACKAGES, &[])
+            .unwrap()
+            .iter()
+            .all(|x| x.is_empty()));
+    }
+
+    #[test]
+    fn test_parse_multi_visit_rewrite() {
+        let s = "Package: a\nVersion: 1\n";
+
+        let r = parse_multi_visit(s, |k, v| {
+            if k == "Version" {
+                *v = Item::OneLine("2".to_string());
+                return FieldAction::Rename("New-Version".to_string());
+            }
+
+            FieldAction::Keep
+        })
+        .unwrap();
+
+        assert_eq!(r[0].get_index(1).unwrap().0, "New-Version");
+        assert_eq!(
+            r[0].get("New-Version").unwrap(),
+            &Item::OneLine("2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_duplicate_last_wins() {
+        let s = "Foo: bar\nBaz: qux\nFoo:\n baz\n";
+
+        let r = parse_multi(s).unwrap();
+
+        assert_eq!(r[0].len(), 2);
+        assert_eq!(
+            r[0].get_index(0).unwrap(),
+            (
+                &"Foo".to_string(),
+                &Item::MultiLine(vec!["baz".to_string()])
+            )
+        );
+
+        let s = "Foo:\n baz\nFoo: bar\n";
+
+        let r = parse_multi(s).unwrap();
+
+        assert_eq!(r[0].get("Foo").unwrap(), &Item::OneLine("bar".to_string()));
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let mut s = fixtures::STATUS.as_bytes().to_vec();
+        let paragraph = fixtures::STATUS
+            .match_indices("\nPackage: ")
+            .nth(1)
+            .unwrap()
+            .0;
+        let description = paragraph
+            + fixtures::STATUS[paragraph..]
+                .find("\nDescription: ")
+                .unwrap();
+        let bad = description + fixtures::STATUS[description + 1..].find("\n ").unwrap() + 10;
+        s[bad] = 0xff;
+
+        let e = parse_multi_bytes(&s).unwrap_err();
+        let context = s[bad - 8..bad + 8]
+            .iter()
+            .map(|x| format!("{:02x}", x))
+            .collect::<Vec<_>>()
+            .join(" ")
+            .replacen("ff", "[ff]", 1);
+
+        match &e {
+            ParseError::InvalidUtf8 {
+                offset,
+                paragraph,
+                key,
+                context: c,
+            } => {
+                assert_eq!(*offset, bad);
+                assert_eq!(*paragraph, Some(2));
+                assert_eq!(key.as_deref(), Some("Description"));
+                assert_eq!(*c, context);
+            }
+            e => panic!("{:?}", e),
+        }
+
+        assert_eq!(
+            e.to_string(),
+            format!(
+                "Invalid UTF-8 at byte {} in paragraph 2, field `Description`: {}",
+                bad, context
+            )
+        );
+
+        for chunk in [1, 100, s.len()] {
+            let mut decoder = ParagraphDecoder::new();
+            let mut errors = vec![];
+
+            for x in s.chunks(chunk) {
+                decoder.push(x);
+
+                while let Some(p) = decoder.next_paragraph() {
+                    errors.extend(p.err());
+                }
+            }
+
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].to_string(), e.to_string());
+        }
+
+        // in a field name, at the start of the input, and between paragraphs
+        let e = parse_multi_bytes(b"\xffPackage: a\n").unwrap_err();
+        assert!(matches!(
+            e,
+            ParseError::InvalidUtf8 { offset: 0, paragraph: Some(0), key: Some(ref k), ref context }
+                if k == "\u{fffd}Package" && context == "[ff] 50 61 63 6b 61 67 65 3a 20 61 0a"
+        ));
+        let e = parse_multi_bytes(b"Package: a\n\n\xff\n").unwrap_err();
+        assert!(matches!(
+            e,
+            ParseError::InvalidUtf8 {
+                offset: 12,
+                paragraph: Some(1),
+                key: None,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_long_line() {
+        let value = format!("{}libfoo", "libfoo (>= 1.0), ".repeat(10 << 20 >> 4));
+        let s = format!("Package: a\nDepends: {}\nVersion: 1\n", value);
+        let r = parse_multi(&s).unwrap();
+
+        // a failing assert_eq! would print megabytes
+        assert!(r[0].get_one("Depends") == Some(value.as_str()));
+        assert!(parse_back(&r) == s);
+        assert!(matches!(
+            parse_multi_limited(&s, 1, 1 << 20),
+            Err(ParseError::FieldTooLong { key, .. }) if key == "Depends"
+        ));
+        assert!(parse_multi_limited(&s, 1, s.len()).unwrap() == r);
+    }
+
+    #[test]
+    fn test_trailing_data_multi() {
+        let strict = ParseOptions {
+            trailing_data: TrailingData::Error,
+            ..Default::default()
+        };
+        let s = "Package: a\n\nPackage: b\n\nVersion=1\n";
+
+        assert_eq!(parse_multi(s).unwrap().len(), 2);
+        assert!(matches!(
+            parse_multi_with(s, &strict).unwrap_err().root(),
+            ParseError::TrailingData { offset: 24 }
+        ));
+        assert!(matches!(
+            parse_multi_with(s, &ParseOptions::strict_rfc())
+                .unwrap_err()
+                .root(),
+            ParseError::TrailingData { .. }
+        ));
+
+        for s in [fixtures::PACKAGES, "Package: a\n\n \n# end\n\n", ""] {
+            assert_eq!(
+                parse_multi_with(s, &strict).unwrap(),
+                parse_multi(s).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_malformed_line() {
+        // never taken into the name of the next field
+        for (s, line) in [
+            ("Package: a\n\nPackage: b\nVersion=1\nArch: c\n", 4),
+            ("Package: a\n\nPackage: b\n \t\nVersion=1\n", 5),
+            ("Package: a\n\nPackage: b\n# c\n", 4),
+        ] {
+            for opts in [ParseOptions::default(), ParseOptions::strict_rfc()] {
+                let e = parse_multi_with(s, &opts).unwrap_err();
+
+                assert!(
+                    matches!(
+                        e,
+                        ParseError::Paragraph {
+                            index: 1,
+                            offset: 12,
+                            ..
+                        }
+                    ),
+                    "{:?}",
+                    e
+                );
+                assert!(matches!(e.root(), ParseError::Malformed { line: l } if *l == line));
+            }
+
+            assert!(matches!(
+                parse_one(&s[12..]),
+                Err(ParseError::Malformed { line: l }) if l == line - 2
+            ));
+            assert!(matches!(
+                parse_multi_limited(s, 8, 64),
+                Err(ParseError::Malformed { line: l }) if l == line
+            ));
+            assert!(matches!(
+                parse_multi_visit(s, |_, _| FieldAction::Keep),
+                Err(ParseError::Malformed { line: l }) if l == line
+            ));
+        }
+
+        assert!(matches!(
+            parse_one("broken\nPackage: a\n"),
+            Err(ParseError::Malformed { line: 1 })
+        ));
+        assert!(matches!(
+            parse_one_rest("Package: a\nbroken\n"),
+            Err(ParseError::Malformed { line: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_duplicate_concat() {
+        let s = "Package: a\nDepends: b,\nVersion: 1\ndepends: c\nDEPENDS:\n d,\n e\n";
+        let opts = ParseOptions {
+            duplicates: DuplicateKeys::Concat,
+            normalize_keys: Some(KeyStyle::TrainCase),
+            ..Default::default()
+        };
+        let r = parse_one_with(s, &opts).unwrap();
+
+        assert_eq!(
+            r.keys().collect::<Vec<_>>(),
+            ["Package", "Depends", "Version"]
+        );
+        assert_eq!(r["Depends"].lines(), ["b,", "c", "d,", "e"]);
+
+        let last_wins = ParseOptions {
+            normalize_keys: Some(KeyStyle::TrainCase),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            parse_one_with(s, &last_wins).unwrap()["Depends"].lines(),
+            ["d,", "e"]
+        );
+        assert_eq!(parse_one(s).unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_paragraph_error() {
+        let strict = ParseOptions::strict_rfc();
+        let s = "Package: a\n\nPackage: b\n\n\n  \n: c\n\nPackage: d\n";
+
+        match parse_multi_with(s, &strict).unwrap_err() {
+            ParseError::Paragraph {
+                index: 2,
+                offset: 28,
+                source,
+            } => assert!(
+                matches!(*source, ParseError::TrailingData { .. }),
+                "{:?}",
+                source
+            ),
+            e => panic!("{:?}", e),
+        }
+
+        // a field twice in the third paragraph of a real index
+        let third = fixtures::PACKAGES
+            .match_indices("\nPackage: ")
+            .nth(1)
+            .unwrap()
+            .0
+            + 1;
+        let mut s = fixtures::PACKAGES.to_string();
+        s.insert_str(third + "Package: bash\n".len(), "Section: x\n");
+        let e = parse_multi_with(&s, &strict).unwrap_err();
+
+        assert!(matches!(e, ParseError::Paragraph { index: 2, offset, .. } if offset == third));
+        assert!(matches!(e.root(), ParseError::DuplicateKey { key } if key == "Section"));
+        assert_eq!(e.kind(), "duplicate_key");
+        assert!(e.to_string().starts_with("Paragraph 2 at byte "), "{}", e);
+
+        let e = parse_multi(": a\n").unwrap_err();
+
+        assert!(matches!(
+            e,
+            ParseError::Paragraph {
+                index: 0,
+                offset: 0,
+                ..
+            }
+        ));
+        assert!(matches!(e.root(), ParseError::Malformed { line: 1 }));
+    }
+
+    #[test]
+    fn test_paragraph_error_rewritten_input() {
+        // comments are skipped and `\r\n` becomes `\n` before parsing, but
+        // positions are in the input
+        let opts = ParseOptions {
+            skip_comments: true,
+            ..ParseOptions::dpkg_status()
+        };
+        let s = "# c\r\nPackage: a\r\n\r\n# d\r\nPackage: b\r\n\r\nPackage: c\r\nVersion=1\r\n";
+        let e = parse_multi_with(s, &opts).unwrap_err();
+
+        assert!(
+            matches!(e, ParseError::Paragraph { index: 2, offset, .. } if offset == s.find("Package: c").unwrap()),
+            "{:?}",
+            e
+        );
+        assert!(matches!(e.root(), ParseError::Malformed { line: 8 }));
+        assert_eq!(e.hint(s).as_deref(), Some("did you mean `Version: 1`?"));
+
+        let strict = ParseOptions {
+            skip_comments: true,
+            line_endings: LineEndings::CrLf,
+            ..ParseOptions::strict_rfc()
+        };
+        let s = "# c\r\nPackage: a\r\n\r\n# d\r\nVersion=1\r\n";
+        let e = parse_multi_with(s, &strict).unwrap_err();
+
+        assert!(
+            matches!(e.root(), ParseError::TrailingData { offset } if *offset == s.find("Version").unwrap()),
+            "{:?}",
+            e
+        );
+
+        let e = parse_multi_with("# c\r\n\r\n x\r\n", &strict).unwrap_err();
+
+        assert!(
+            matches!(e, ParseError::OrphanContinuation { line: 3 }),
+            "{:?}",
+            e
+        );
+
+        let e = parse_one_with("# c\r\nPackage: a\r\nVersion=1\r\n", &strict).unwrap_err();
+
+        assert!(matches!(e, ParseError::Malformed { line: 3 }), "{:?}", e);
+    }
+
+    #[test]
+    fn test_duplicate_error() {
+        let opts = ParseOptions {
+            duplicates: DuplicateKeys::Error,
+            ..Default::default()
+        };
+
+        let r = parse_multi_with("Package: a\n\nFoo: bar\nFoo:\n baz\n", &opts);
+
+        assert!(matches!(
+            r.unwrap_err().root(),
+            ParseError::DuplicateKey { key } if key == "Foo"
+        ));
+
+        let r = parse_multi_with("Package: a\n\nPackage: b\n", &opts);
+
+        assert_eq!(r.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_space_before_colon() {
+        let s = "Package : x\nVersion\t:\t1\nDepends :\n a\n";
+
+        let r = parse_one(s).unwrap();
+
+        assert_eq!(
+            r.keys().collect::<Vec<_>>(),
+            ["Package", "Version", "Depends"]
+        );
+        assert_eq!(r["Package"], Item::OneLine("x".to_string()));
+        assert_eq!(r["Depends"], Item::MultiLine(vec!["a".to_string()]));
+
+        let opts = ParseOptions {
+            space_before_colon: SpaceBeforeColon::Error,
+            ..Default::default()
+        };
+
+        let r = parse_one_with(s, &opts);
+
+        assert!(matches!(r, Err(ParseError::SpaceBeforeColon { key }) if key == "Package"));
+        assert!(parse_one_with("Package: x\n", &opts).is_ok());
+
+        let r = parse_multi_interned(s).unwrap();
+
+        assert_eq!(&**r[0].get_index(0).unwrap().0, "Package");
+    }
+
+    #[test]
+    fn test_skip_comments() {
+        let opts = ParseOptions {
+            skip_comments: true,
+            ..Default::default()
+        };
+        let s =
+            "# header\nPackage: a\n# note\nDepends:\n b\n# inside\n c\n\n# only\n\nPackage: d\n";
+        let r = parse_multi_with(s, &opts).unwrap();
+
+        assert_eq!(r.len(), 2);
+        assert_eq!(
+            r[0]["Depends"],
+            Item::MultiLine(vec!["b".to_string(), "c".to_string()])
+        );
+        assert_eq!(r[1].get_one("Package"), Some("d"));
+        assert!(matches!(
+            parse_multi(s).unwrap_err().root(),
+            ParseError::Malformed { line: 1 }
+        ));
+    }
+
+    #[test]
+    fn test_strict_field_names() {
+        let strict = ParseOptions::strict_rfc();
+
+        for (s, line) in [
+            ("A: 1\nno colon line\nC: 2\n", 2),
+            ("A: 1\nTwo Words: 2\n", 2),
+            ("A: 1\n\nB: 2\nC\u{7}: 3\n", 4),
+        ] {
+            assert!(
+                matches!(
+                    parse_multi_with(s, &strict).unwrap_err().root(),
+                    ParseError::Malformed { line: l } if *l == line
+                ),
+                "{:?}",
+                s
+            );
+        }
+
+        assert!(matches!(
+            parse_one_with("Package: a\nTwo\tWords: b\n", &strict),
+            Err(ParseError::Malformed { line: 2 })
+        ));
+        assert!(parse_one("Package: a\nTwo Words: b\n").is_ok());
+
+        // left to the space before colon policy
+        assert!(matches!(
+            parse_one_with("Package : a\n", &strict),
+            Err(ParseError::SpaceBeforeColon { .. })
+        ));
+    }
+
+    #[test]
+    fn test_presets() {
+        let status = ParseOptions::dpkg_status();
+        let apt = ParseOptions::apt_packages();
+        let sources = ParseOptions::deb822_sources();
+        let strict = ParseOptions::strict_rfc();
+
+        assert_eq!(
+            parse_multi_with(fixtures::STATUS_STATES, &status).unwrap(),
+            parse_multi(fixtures::STATUS_STATES).unwrap()
+        );
+
+        // dpkg reads field names case-insensitively
+        let s = "Package: a\nVersion: 1\npackage: b\n";
+
+        assert!(matches!(
+            parse_one_with(s, &status),
+            Err(ParseError::DuplicateKey { key }) if key == "Package"
+        ));
+        assert!(parse_one_with(s, &strict).is_ok());
+
+        let crlf = fixtures::PACKAGES.replace('\n', "\r\n");
+
+        assert_eq!(
+            parse_multi_with(&crlf, &apt).unwrap(),
+            parse_multi(fixtures::PACKAGES).unwrap()
+        );
+        assert_eq!(
+            parse_multi_with(&crlf, &strict).unwrap()[0].get_one("Package"),
+            Some("hello\r")
+        );
+
+        let r = parse_multi_with(fixtures::DEB822_SOURCES, &sources).unwrap();
+
+        assert_eq!(r.len(), 2);
+        assert_eq!(r[1].get_one("Enabled"), Some("no"));
+        // comments are only skipped in deb822 sources
+        assert!(matches!(
+            parse_multi_with(fixtures::DEB822_SOURCES, &status)
+                .unwrap_err()
+                .root(),
+            ParseError::Malformed { line: 1 }
+        ));
+    }
+
+    #[test]
+    fn test_orphan_continuation() {
+        let strict = ParseOptions {
+            orphan_lines: OrphanLines::Error,
+            ..Default::default()
+        };
+
+        let s = " lost\nPackage: x\n";
+
+        assert_eq!(
+            parse_one(s).unwrap().keys().collect::<Vec<_>>(),
+            ["Package"]
+        );
+        assert!(matches!(
+            parse_one_with(s, &strict),
+            Err(ParseError::OrphanContinuation { line: 1 })
+        ));
+
+        // a paragraph of nothing else used to end the parse there
+        let s = "Package: a\nDepends:\n b\n\n c\n\td\n\nPackage: e\n";
+        let r = parse_multi(s).unwrap();
+
+        assert_eq!(r.len(), 2);
+        assert_eq!(r[0]["Depends"], Item::MultiLine(vec!["b".to_string()]));
+        assert_eq!(r[1]["Package"], Item::OneLine("e".to_string()));
+        assert_eq!(parse_multi_interned(s).unwrap().len(), 2);
+        assert!(matches!(
+            parse_multi_with(s, &strict),
+            Err(ParseError::OrphanContinuation { line: 5 })
+        ));
+
+        let r = parse_multi_with("Package: a\n \n\n \t\nPackage: b\n", &strict).unwrap();
+
+        assert_eq!(
+            r[0]["Package"],
+            Item::Folded("a".to_string(), vec!["".to_string()])
+        );
+        assert_eq!(r.len(), 2);
+    }
+
+    #[test]
+    fn test_empty_input() {
+        let keep_empty = ParseOptions {
+            keep_empty: true,
+            ..Default::default()
+        };
+
+        for s in [
+            "",
+            "\n",
+            " \n\t\n",
+            "# just a comment\n",
+            "\n# a\n  \n#b",
+            "\r\n",
+        ] {
+            assert!(matches!(parse_one(s), Err(ParseError::Empty)), "{:?}", s);
+            assert!(
+                matches!(parse_one_rest(s), Err(ParseError::Empty)),
+                "{:?}",
+                s
+            );
+            assert!(matches!(
+                parse_one_streaming(s, |_, _| ()),
+                Err(ParseError::Empty)
+            ));
+
+            assert!(parse_multi(s).unwrap().is_empty(), "{:?}", s);
+            assert!(parse_multi_with(s, &keep_empty).unwrap().is_empty());
+            assert!(parse_multi_visit(s, |_, _| FieldAction::Keep)
+                .unwrap()
+                .is_empty());
+            assert!(parse_multi_interned(s).unwrap().is_empty());
+            assert!(parse_multi_limited(s, 0, 0).unwrap().is_empty());
+            assert!(parse_with_decoders(s, &HashMap::new()).unwrap().is_empty());
+            assert!(crate::find_package_sorted(std::io::Cursor::new(s), "a")
+                .unwrap()
+                .is_none());
+        }
+    }
+
+    #[test]
+    fn test_no_final_newline() {
+        let s = "Package: a\nVersion: 1\n\nPackage: b\nDepends:\n c,\n d";
+        let r = parse_multi(s).unwrap();
+
+        assert_eq!(r.len(), 2);
+        assert_eq!(r, parse_multi(&format!("{}\n", s)).unwrap());
+        assert_eq!(r, parse_multi_limited(s, 2, 100).unwrap());
+        assert_eq!(parse_multi_interned(s).unwrap().len(), 2);
+        assert_eq!(
+            r[1]["Depends"],
+            Item::MultiLine(vec!["c,".to_string(), "d".to_string()])
+        );
+        assert_eq!(
+            parse_one("Package: a").unwrap()["Package"],
+            Item::OneLine("a".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_multi_limited() {
+        for s in [
+            fixtures::STATUS,
+            fixtures::PACKAGES,
+            "\n\nPackage: a\n\n\n",
+            "\n \n",
+            "",
+        ] {
+            assert_eq!(
+                parse_multi_limited(s, usize::MAX, usize::MAX).unwrap(),
+                parse_multi(s).unwrap()
+            );
+        }
+        assert!(parse_multi_limited("garbage\n", 1, 1).is_err());
+
+        let s = "Package: a\nDepends:\n b,\n c\n\nPackage: d\n\nPackage: e\n";
+
+        assert_eq!(parse_multi_limited(s, 3, 7).unwrap().len(), 3);
+        assert!(matches!(
+            parse_multi_limited(s, 2, 7),
+            Err(ParseError::TooManyParagraphs { limit: 2 })
+        ));
+        assert!(matches!(
+            parse_multi_limited(s, 3, 6),
+            Err(ParseError::FieldTooLong { key, limit: 6 }) if key == "Depends"
+        ));
+        assert!(matches!(
+            parse_multi_limited("Package  : abc\n", 1, 2),
+            Err(ParseError::FieldTooLong { key, .. }) if key == "Package"
+        ));
+    }
+
+    #[test]
+    fn test_parse_multi_interned() {
+        let s = "Package: a\nDepends:\n b\nVersion: 1\n\nPackage: c\nVersion: 2\n";
+
+        let r = parse_multi_interned(s).unwrap();
+        let plain = parse_multi(s).unwrap();
+
+        assert_eq!(r.len(), 2);
+
+        let (a, b) = (&r[0], &r[1]);
+        assert!(std::sync::Arc::ptr_eq(
+            a.get_index(0).unwrap().0,
+            b.get_index(0).unwrap().0
+        ));
+        assert!(std::sync::Arc::ptr_eq(
+            a.get_index(2).unwrap().0,
+            b.get_index(1).unwrap().0
+        ));
+
+        for (interned, plain) in r.iter().zip(plain.iter()) {
+            assert!(interned
+                .iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .eq(plain.iter().map(|(k, v)| (k.clone(), v))));
+        }
+    }
+
+    #[test]
+    fn test_continuation_whitespace() {
+        let s = "Package: a\nMulti:\n one\n   three\n\t tab\nFolded: x\n   y\n";
+
+        let parse = |continuation| {
+            parse_multi_with(
+                s,
+                &ParseOptions {
+                    continuation,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+
+        let lines = |v: &[&str]| v.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+
+        let r = parse(ContinuationWhitespace::StripOne);
+        assert_eq!(
+            r[0].get("Multi").unwrap(),
+            &Item::MultiLine(lines(&["one", "  three", " tab"]))
+        );
+        assert_eq!(
+            r[0].get("Folded").unwrap(),
+            &Item::Folded("x".to_string(), lines(&["  y"]))
+        );
+        assert_eq!(parse_multi(&parse_back(&r)).unwrap(), r);
+
+        let r = parse(ContinuationWhitespace::StripAll);
+        assert_eq!(
+            r[0].get("Multi").unwrap(),
+            &Item::MultiLine(lines(&["one", "three", "tab"]))
+        );
+        assert_eq!(parse_multi(&parse_back(&r)).unwrap(), r);
+
+        let r = parse(ContinuationWhitespace::Preserve);
+        assert_eq!(
+            r[0].get("Multi").unwrap(),
+            &Item::MultiLine(lines(&[" one", "   three", "\t tab"]))
+        );
+
+        let opts = SerializeOptions {
+            continuation: ContinuationWhitespace::Preserve,
+            ..Default::default()
+        };
+        assert_eq!(parse_back_with(&r, &opts), s);
+    }
+
+    #[test]
+    fn test_parse_one_trailing_data() {
+        let r = parse_one("Package: x\n\nleftover");
+
+        assert!(matches!(r, Err(ParseError::TrailingData { offset: 12 })));
+
+        let r = parse_one("Package: x\n\n \n");
+
+        assert!(r.is_ok());
+    }
+
+    #[test]
+    fn test_try_parse_back_invalid() {
+        let check = |k: &str, v: Item| {
+            let mut p = IndexMap::new();
+            p.insert("Package".to_string(), Item::OneLine("a".to_string()));
+            p.insert(k.to_string(), v);
+
+            try_parse_back(&[IndexMap::new(), p])
+        };
+
+        let one = |s: &str| Item::OneLine(s.to_string());
+
+        for k in ["", "a: b", "a\nb", " a", "a ", "a\t", "#a"] {
+            assert_eq!(
+                check(k, one("b")),
+                Err(SerializeError::InvalidKey {
+                    paragraph: 1,
+                    key: k.to_string()
+                })
+            );
+        }
+
+        for v in [
+            one("a\nb"),
+            one("a\r"),
+            Item::MultiLine(vec!["a".to_string(), "b\nc: d".to_string()]),
+            Item::Folded("a\n".to_string(), vec!["b".to_string()]),
+        ] {
+            assert_eq!(
+                check("Foo", v),
+                Err(SerializeError::NewlineInValue {
+                    paragraph: 1,
+                    key: "Foo".to_string()
+                })
+            );
+        }
+
+        for v in [
+            one(""),
+            one(" a"),
+            one("\ta"),
+            Item::MultiLine(vec![]),
+            Item::Folded("".to_string(), vec!["b".to_string()]),
+            Item::Folded(" a".to_string(), vec!["b".to_string()]),
+            Item::Folded("a".to_string(), vec![]),
+        ] {
+            assert_eq!(
+                check("Foo", v),
+                Err(SerializeError::InvalidValue {
+                    paragraph: 1,
+                    key: "Foo".to_string()
+                })
+            );
+        }
+
+        assert!(check("Foo", one("a: b")).is_ok());
+        assert!(check("a#b c", one("a ")).is_ok());
+        assert!(check("Foo", Item::MultiLine(vec!["".to_string()])).is_ok());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_parse_back_panics() {
+        let mut p = IndexMap::new();
+        p.insert("a".to_string(), Item::OneLine("a\nb: c".to_string()));
+
+        parse_back(&[p]);
+    }
+
+    #[test]
+    fn test_trailing_separator() {
+        let apt = "Package: a\nVersion: 1\n\nPackage: b\nVersion: 2\n";
+        let blank = format!("{}\n", apt);
+
+        let r = parse_multi(apt).unwrap();
+
+        assert_eq!(r.len(), 2);
+        assert_eq!(parse_multi(&blank).unwrap(), r);
+        assert_eq!(parse_multi(&format!("{}\n\n", blank)).unwrap(), r);
+
+        assert_eq!(parse_back(&r), apt);
+
+        let opts = SerializeOptions {
+            trailing_blank_line: true,
+            ..Default::default()
+        };
+
+        assert_eq!(parse_back_with(&r, &opts), blank);
+        assert_eq!(parse_back(&parse_multi(&blank).unwrap()), apt);
+        assert_eq!(parse_back(&[]), "");
+    }
+
+    #[test]
+    fn test_continuation_marker_is_one_space() {
+        let one = "Package: a\nDescription: x\n body\n  verbatim\n";
+        let two = "Package: a\nDescription: x\n  body\n   verbatim\n";
+
+        let lines = |v: &[&str]| v.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+
+        let r = parse_one(one).unwrap();
+        assert_eq!(
+            r.get("Description").unwrap(),
+            &Item::Folded("x".to_string(), lines(&["body", " verbatim"]))
+        );
+        assert_eq!(parse_back(&[r]), one);
+
+        let r = parse_one(two).unwrap();
+        assert_eq!(
+            r.get("Description").unwrap(),
+            &Item::Folded("x".to_string(), lines(&[" body", "  verbatim"]))
+        );
+        assert_eq!(parse_back(&[r]), two);
+    }
+
+    #[test]
+    fn test_push_line() {
+        let lines = |v: &[&str]| v.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+
+        let mut item = Item::OneLine("a".to_string());
+        item.push_line("b");
+        item.push_line(String::from("c"));
+        assert_eq!(item, Item::MultiLine(lines(&["a", "b", "c"])));
+
+        let mut item = Item::OneLine(String::new());
+        item.push_line("a");
+        assert_eq!(item, Item::MultiLine(lines(&["a"])));
+
+        let mut item = Item::Folded("a".to_string(), lines(&["b"]));
+        item.push_line("c");
+        assert_eq!(item, Item::Folded("a".to_string(), lines(&["b", "c"])));
+    }
+
+    #[test]
+    fn test_item_ord_hash() {
+        let one = |x: &str| Item::OneLine(x.to_string());
+        let multi = |x: &[&str]| Item::MultiLine(x.iter().map(|x| x.to_string()).collect());
+
+        let mut items = vec![
+            Item::Folded("a".to_string(), vec![]),
+            multi(&["b"]),
+            one("b"),
+            multi(&["a", "z"]),
+            one("a"),
+            Item::default(),
+        ];
+        items.sort();
+
+        assert_eq!(
+            items,
+            [
+                one(""),
+                one("a"),
+                one("b"),
+                multi(&["a", "z"]),
+                multi(&["b"]),
+                Item::Folded("a".to_string(), vec![]),
+            ]
+        );
+
+        let set = items
+            .iter()
+            .chain(&items)
+            .collect::<std::collections::HashSet<_>>();
+
+        assert_eq!(set.len(), items.len());
+    }
+
+    #[test]
+    fn test_lines() {
+        let mut item = Item::OneLine(String::new());
+
+        for (line, expected) in [("a", &["a"][..]), ("b", &["a", "b"])] {
+            item.push_line(line);
+            assert_eq!(item.lines(), expected);
+        }
+
+        let item = Item::Folded("a".to_string(), vec!["b".to_string(), "".to_string()]);
+
+        assert_eq!(item.lines(), ["a", "b", ""]);
+        assert!(Item::MultiLine(vec![]).lines().is_empty());
+    }
+
+    #[test]
+    fn test_parse_one_streaming() {
+        let s = "Package: bash\nDescription: GNU Bourne Again SHell\n Bash is an sh-compatible command language interpreter.\n .\n Bash is ultimately intended\nDepends:\n libc6,\n\tlibtinfo6\n";
+
+        let mut r: IndexMap<String, Item> = IndexMap::new();
+
+        parse_one_streaming(s, |key, chunk| match chunk {
+            FieldChunk::Value(v) => {
+                r.insert(key.to_string(), Item::OneLine(v.to_string()));
+            }
+            FieldChunk::Line(line) => match r.get_mut(key).unwrap() {
+                Item::OneLine(v) if !v.is_empty() => {
+                    let v = std::mem::take(v);
+                    r.insert(key.to_string(), Item::Folded(v, vec![line.to_string()]));
+                }
+                item => item.push_line(line),
+            },
+        })
+        .unwrap();
+
+        assert_eq!(r, parse_one(s).unwrap());
+    }
+
+    #[test]
+    fn test_parse_one_streaming_huge_field() {
+        let mut s = "Package: a\nChangelog:\n".to_string();
+        for i in 0..100_000 {
+            s += &format!(" line {}\n", i);
+        }
+        s += "Version: 1\n\nPackage: b\n";
+
+        let mut lines = 0;
+        let mut keys = vec![];
+
+        let r = parse_one_streaming(&s, |key, chunk| match chunk {
+            FieldChunk::Value(_) => keys.push(key.to_string()),
+            FieldChunk::Line(line) => {
+                assert_eq!(line, format!("line {}", lines));
+                lines += 1;
+            }
+        });
+
+        assert_eq!(lines, 100_000);
+        assert_eq!(keys, vec!["Package", "Changelog", "Version"]);
+        assert!(matches!(r, Err(ParseError::TrailingData { .. })));
+        assert!(parse_one_streaming("", |_, _| {}).is_err());
+    }
+
+    fn hex_decoder() -> Decoder<'static> {
+        Box::new(|x: &[u8]| {
+            if !x.len().is_multiple_of(2) {
+                return Err("odd length".to_string());
+            }
+
+            let bytes = x
+                .chunks(2)
+                .map(|c| {
+                    std::str::from_utf8(c)
+                        .ok()
+                        .and_then(|c| u8::from_str_radix(c, 16).ok())
+                        .ok_or_else(|| format!("invalid hex `{}`", String::from_utf8_lossy(c)))
+                })
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+
+            String::from_utf8(bytes).map_err(|e| e.to_string())
+        })
+    }
+
+    #[test]
+    fn test_parse_with_decoders() {
+        let mut decoders: HashMap<&str, Decoder> = HashMap::new();
+        decoders.insert("X-Hex-Note", hex_decoder());
+
+        let s = "Package: a\nX-Hex-Note: 68656c6c6f\n 776f726c64\nVersion: 1\n\nPackage: b\nx-hex-note: 6869\n";
+        let r = parse_with_decoders(s, &decoders).unwrap();
+
+        assert_eq!(
+            r[0]["X-Hex-Note"],
+            Item::Folded("hello".to_string(), vec!["world".to_string()])
+        );
+        assert_eq!(r[0]["Version"], Item::OneLine("1".to_string()));
+        assert_eq!(r[1]["x-hex-note"], Item::OneLine("hi".to_string()));
+
+        let r = parse_with_decoders("Package: a\nX-Hex-Note: 6g\n", &decoders);
+
+        assert!(matches!(
+            r,
+            Err(ParseError::Decode { key, reason }) if key == "X-Hex-Note" && reason.contains("6g")
+        ));
+    }
+
+    #[test]
+    fn test_parse_one_rest() {
+        let s = "Package: a\nDepends:\n b,\n c\n\n\n \nPackage: b\nVersion: 1\n\n";
+
+        let (a, rest) = parse_one_rest(s).unwrap();
+
+        assert_eq!(a.len(), 2);
+        assert_eq!(
+            a["Depends"],
+            Item::MultiLine(vec!["b,".to_string(), "c".to_string()])
+        );
+        assert_eq!(rest, "\n \nPackage: b\nVersion: 1\n\n");
+
+        let (b, rest) = parse_one_rest(rest).unwrap();
+
+        assert_eq!(b["Version"], Item::OneLine("1".to_string()));
+        assert_eq!(rest, "");
+        assert!(parse_one_rest(rest).is_err());
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let lf = "Package: a\nDescription: b\n c\n .\n d\n\nPackage: e\n";
+        let expected = parse_multi(lf).unwrap();
+
+        let opts = |line_endings| ParseOptions {
+            line_endings,
+            ..Default::default()
+        };
+
+        for eol in ["\n", "\r\n", "\r"] {
+            let s = lf.replace('\n', eol);
+            let first = s.split(&eol.repeat(2)).next().unwrap().to_string() + eol;
+
+            assert_eq!(
+                parse_multi_with(&s, &opts(LineEndings::Auto)).unwrap(),
+                expected,
+                "{:?}",
+                eol
+            );
+            assert_eq!(
+                parse_one_with(&first, &opts(LineEndings::Auto)).unwrap(),
+                expected[0],
+                "{:?}",
+                eol
+            );
+        }
+
+        let crlf = lf.replace('\n', "\r\n");
+
+        assert_eq!(
+            parse_multi_with(&crlf, &opts(LineEndings::CrLf)).unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_multi_with(&crlf, &opts(LineEndings::Lf)).unwrap()[0]["Package"],
+            Item::OneLine("a\r".to_string())
+        );
+        // without a line break the whole text is the value of the first field
+        assert_eq!(
+            parse_multi_with(&lf.replace('\n', "\r"), &opts(LineEndings::CrLf)).unwrap()[0].len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_serialize_line_endings() {
+        let lf = "Package: a\nDescription: b\n c\n .\n d\nConffiles:\n /etc/a 1\n\nPackage: e\n";
+        let crlf = lf.replace('\n', "\r\n");
+        let r = parse_multi_with(
+            &crlf,
+            &ParseOptions {
+                line_endings: LineEndings::CrLf,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(parse_back(&r), lf);
+
+        let opts = SerializeOptions {
+            crlf: true,
+            trailing_blank_line: true,
+            ..Default::default()
+        };
+
+        assert_eq!(parse_back_with(&r, &opts), crlf.clone() + "\r\n");
+        assert_eq!(
+            parse_back_with(
+                &r,
+                &SerializeOptions {
+                    crlf: true,
+                    ..Default::default()
+                }
+            ),
+            crlf
+        );
+
+        // the `\r` kept by `LineEndings::Lf` is never written
+        assert!(try_parse_back(&parse_multi(&crlf).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_keep_empty() {
+        let s = "Package: a\n\n\nPackage: b\n\n";
+
+        let r = parse_multi(s).unwrap();
+
+        assert_eq!(r.len(), 2);
+
+        let opts = ParseOptions {
+            keep_empty: true,
+            ..Default::default()
+        };
+
+        let r = parse_multi_with(s, &opts).unwrap();
+
+        assert_eq!(r.len(), 3);
+        assert!(r[1].is_empty());
+        assert_eq!(
+            r[2].get("Package").unwrap(),
+            &Item::OneLine("b".to_string())
+        );
+
+        assert_eq!(parse_multi_with(&parse_back(&r), &opts).unwrap(), r);
+    }
+
+    #[test]
+    fn test_parse_back_with_comments() {
+        let s = "# leading
+# comments
+Package: a
+# inside
+Version: 1
+# after the fields
+
+# between
+Package: b
+Version: 2
+
+# trailing
+";
+        let (r, comments) = Cst::parse(s)
+            .paragraphs_with_comments(&ParseOptions::default())
+            .unwrap();
+
+        assert_eq!(
+            r,
+            parse_multi("Package: a\nVersion: 1\n\nPackage: b\nVersion: 2\n").unwrap()
+        );
+        assert_eq!(comments.len(), 6);
+        assert_eq!(parse_back_with_comments(&r, &comments), s);
+
+        let no_trailing = s.trim_end_matches("\n# trailing\n");
+
+        let (r, comments) = Cst::parse(no_trailing)
+            .paragraphs_with_comments(&ParseOptions::default())
+            .unwrap();
+
+        assert_eq!(parse_back_with_comments(&r, &comments), no_trailing);
+        assert_eq!(parse_back_with_comments(&r, &[]), parse_back(&r));
+    }
+
+    #[test]
+    fn test_parse_multi_with_header() {
+        let (header, r) = parse_multi_with_header(
+            "8dparser index v1\nPackage: a\nVersion: 1\n\nPackage: b\nVersion: 2\n",
+        )
+        .unwrap();
+
+        assert_eq!(header.as_deref(), Some("8dparser index v1"));
+        assert_eq!(
+            r,
+            parse_multi("Package: a\nVersion: 1\n\nPackage: b\nVersion: 2\n").unwrap()
+        );
+
+        for s in ["Package: a\n", "\nPackage: a\n", ""] {
+            let (header, r) = parse_multi_with_header(s).unwrap();
+
+            assert_eq!(header, None);
+            assert_eq!(r, parse_multi(s).unwrap());
+        }
+
+        let (header, r) = parse_multi_with_header("# generated\n\nPackage: a\n").unwrap();
+
+        assert_eq!(header.as_deref(), Some("# generated"));
+        assert_eq!(r.len(), 1);
+    }
+
+    #[cfg(feature = "tracing")]
+    type Log<T> = std::sync::Arc<std::sync::Mutex<Vec<T>>>;
+
+    /// A `tracing` subscriber keeping the fields of every span and event as
+    /// `name=value`
+    #[cfg(feature = "tracing")]
+    #[derive(Clone, Default)]
+    struct Capture {
+        spans: Log<(&'static str, Vec<String>)>,
+        events: Log<(tracing::Level, Vec<String>)>,
+    }
+
+    #[cfg(feature = "tracing")]
+    struct Fields<'a>(&'a mut Vec<String>);
+
+    #[cfg(feature = "tracing")]
+    impl tracing::field::Visit for Fields<'_> {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0.push(format!("{}={:?}", field.name(), value));
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    impl tracing::Subscriber for Capture {
+        fn enabled(&self, _: &tracing::Metadata) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &tracing::span::Attributes) -> tracing::span::Id {
+            let mut fields = vec![];
+            span.record(&mut Fields(&mut fields));
+
+            let mut spans = self.spans.lock().unwrap();
+            spans.push((span.metadata().name(), fields));
+
+            tracing::span::Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, span: &tracing::span::Id, values: &tracing::span::Record) {
+            let mut spans = self.spans.lock().unwrap();
+            values.record(&mut Fields(&mut spans[span.into_u64() as usize - 1].1));
+        }
+
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+        fn event(&self, event: &tracing::Event) {
+            let mut fields = vec![];
+            event.record(&mut Fields(&mut fields));
+
+            self.events
+                .lock()
+                .unwrap()
+                .push((*event.metadata().level(), fields));
+        }
+
+        fn enter(&self, _: &tracing::span::Id) {}
+
+        fn exit(&self, _: &tracing::span::Id) {}
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_tracing() {
+        use tracing::Level;
+
+        let fields = |v: &[&str]| v.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+
+        // an orphan line, a space before a colon, a duplicate and trailing data
+        let capture = Capture::default();
+        let s = "Package: a\nVersion: 1\nVersion: 2\n\n x\nPackage : b\n\nno colon\n";
+        let r = tracing::subscriber::with_default(capture.clone(), || parse_multi(s).unwrap());
+
+        assert_eq!(r.len(), 2);
+        assert_eq!(
+            *capture.spans.lock().unwrap(),
+            [("parse_multi", fields(&["len=59", "paragraphs=2"]))]
+        );
+        assert_eq!(
+            *capture.events.lock().unwrap(),
+            [
+                (
+                    Level::DEBUG,
+                    fields(&[
+                        "message=skipped an orphan continuation line",
+                        "offset=34",
+                        "len=3"
+                    ])
+                ),
+                (
+                    Level::DEBUG,
+                    fields(&["message=replaced a duplicate field", "key=Version"])
+                ),
+                (
+                    Level::DEBUG,
+                    fields(&[
+                        "message=trimmed the whitespace before the colon",
+                        "key=Package"
+                    ])
+                ),
+                (
+                    Level::WARN,
+                    fields(&["message=discarded trailing data", "offset=50"])
+                ),
+            ]
+        );
+
+        // every paragraph tried by `TrailingData::Resync` is a `parse_one`
+        let capture = Capture::default();
+        let opts = ParseOptions {
+            duplicates: DuplicateKeys::Concat,
+            trailing_data: TrailingData::Resync,
+            ..Default::default()
+        };
+        let s = "Package: a\nA: 1\nA: 2\n\n\u{1}garbage\n\nPackage: b\n";
+        let r = tracing::subscriber::with_default(capture.clone(), || {
+            parse_multi_with(s, &opts).unwrap()
+        });
+
+        assert_eq!(r.len(), 2);
+        assert_eq!(
+            *capture.spans.lock().unwrap(),
+            [
+                ("parse_multi", fields(&["len=43", "paragraphs=2"])),
+                ("parse_one", fields(&["len=21", "paragraphs=1"])),
+                ("parse_one", fields(&["len=9"])),
+                ("parse_one", fields(&["len=11", "paragraphs=1"])),
+            ]
+        );
+        assert_eq!(
+            *capture.events.lock().unwrap(),
+            [
+                (
+                    Level::DEBUG,
+                    fields(&[
+                        "message=appended a duplicate field to the first one",
+                        "key=A"
+                    ])
+                ),
+                (
+                    Level::DEBUG,
+                    fields(&["message=skipped garbage", "offset=22", "len=9"])
+                ),
+            ]
+        );
+    }
 }
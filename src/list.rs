@@ -0,0 +1,190 @@
+use crate::{Item, Paragraph, ParagraphExt};
+
+/// Separator of a list field, see [`ParagraphExt::get_list`](crate::ParagraphExt::get_list)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListSep {
+    /// Entries separated by spaces, tabs or line breaks, e.g. `Architectures`
+    Whitespace,
+    /// Entries separated by commas, e.g. `Tag` or `Depends`
+    Comma,
+}
+
+impl Item {
+    /// Split a whitespace-separated list field, e.g. `Components` in a
+    /// Release file. Continuation lines are treated as part of the same list.
+    ///
+    /// ```rust
+    /// use eight_deep_parser::{parse_one, ParagraphExt};
+    ///
+    /// let r = parse_one("Origin: Debian\nComponents: main contrib non-free-firmware\n").unwrap();
+    ///
+    /// assert_eq!(
+    ///     r.get_field("Components").unwrap().split_whitespace_list(),
+    ///     vec!["main", "contrib", "non-free-firmware"]
+    /// );
+    /// ```
+    pub fn split_whitespace_list(&self) -> Vec<&str> {
+        self.value_lines()
+            .flat_map(|x| x.split_whitespace())
+            .collect()
+    }
+
+    /// Split a comma-separated list field and trim every entry
+    ///
+    /// Commas inside parentheses don't split, so this also gives a rough
+    /// split of relation fields like `Depends`. The lines of the value are
+    /// joined by a space first, so an entry may continue on the next line.
+    /// Empty entries are dropped.
+    ///
+    /// ```rust
+    /// use eight_deep_parser::{parse_one, ParagraphExt};
+    ///
+    /// let r = parse_one("Package: bash\nTag: implemented-in::c, interface::shell,\n role::program\n").unwrap();
+    ///
+    /// assert_eq!(
+    ///     r.get_field("Tag").unwrap().split_comma_list(),
+    ///     vec!["implemented-in::c", "interface::shell", "role::program"]
+    /// );
+    /// ```
+    pub fn split_comma_list(&self) -> Vec<String> {
+        let line = self.value_lines().collect::<Vec<_>>().join(" ");
+
+        split_commas(&line).map(|x| x.to_string()).collect()
+    }
+
+    pub(crate) fn value_lines(&self) -> impl Iterator<Item = &str> {
+        let (first, rest) = match self {
+            Item::OneLine(s) => (Some(s), &[][..]),
+            Item::MultiLine(v) => (None, &v[..]),
+            Item::Folded(s, v) => (Some(s), &v[..]),
+            Item::Streamed => (None, &[][..]),
+        };
+
+        first.into_iter().chain(rest).map(|x| x.as_str())
+    }
+}
+
+/// The comma-separated list in field `key` of `p`, the same as
+/// [`ParagraphExt::get_list`] with [`ListSep::Comma`]
+///
+/// ```rust
+/// use eight_deep_parser::{get_list, parse_one};
+///
+/// let r = parse_one("Package: a\nDepends: libc6\n (>= 2.34), zlib1g,\n dpkg\n").unwrap();
+///
+/// assert_eq!(
+///     get_list(&r, "depends").unwrap(),
+///     vec!["libc6 (>= 2.34)", "zlib1g", "dpkg"]
+/// );
+/// ```
+pub fn get_list(p: &Paragraph, key: &str) -> Option<Vec<String>> {
+    p.get_list(key, ListSep::Comma)
+}
+
+/// The trimmed non-empty entries of `line`, split on commas outside
+/// parentheses
+pub(crate) fn split_commas(line: &str) -> impl Iterator<Item = &str> {
+    let mut result = vec![];
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for (i, c) in line.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                result.push(line[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    result.push(line[start..].trim());
+    result.into_iter().filter(|x| !x.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{get_list, parse_one, Item, ListSep, ParagraphExt};
+
+    #[test]
+    fn test_split_whitespace_list() {
+        let item = Item::Folded(
+            "amd64 arm64".to_string(),
+            vec!["  loongarch64\triscv64".to_string()],
+        );
+
+        assert_eq!(
+            item.split_whitespace_list(),
+            vec!["amd64", "arm64", "loongarch64", "riscv64"]
+        );
+        assert!(Item::OneLine("".to_string())
+            .split_whitespace_list()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_split_comma_list() {
+        let item = Item::MultiLine(vec![
+            "libc6 (>= 2.34), libfoo (<< 1,2) | libbar,".to_string(),
+            "zlib1g".to_string(),
+        ]);
+
+        assert_eq!(
+            item.split_comma_list(),
+            vec!["libc6 (>= 2.34)", "libfoo (<< 1,2) | libbar", "zlib1g"]
+        );
+
+        let r = parse_one(
+            "Package: a\nDepends: libc6 (>= 2.34), libfoo\n (<< 1,2) | libbar (>=\n 3), zlib1g\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            r.get_field("Depends").unwrap().split_comma_list(),
+            vec![
+                "libc6 (>= 2.34)",
+                "libfoo (<< 1,2) | libbar (>= 3)",
+                "zlib1g"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_list() {
+        let r = parse_one("Package: a\nArchitectures: amd64 i386\nDepends: b, c\n").unwrap();
+
+        assert_eq!(
+            r.get_list("architectures", ListSep::Whitespace).unwrap(),
+            vec!["amd64", "i386"]
+        );
+        assert_eq!(
+            r.get_list("Depends", ListSep::Comma).unwrap(),
+            vec!["b", "c"]
+        );
+        assert_eq!(r.get_list("Tag", ListSep::Comma), None);
+    }
+
+    #[test]
+    fn test_get_list_owned() {
+        let r = parse_one(
+            "Package: a\nTag: role::program, , use::editing\nProvides:\n editor,\n  vi (= 1),\n x-editor\n",
+        )
+        .unwrap();
+
+        let owned = |v: &[&str]| v.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            get_list(&r, "Tag"),
+            Some(owned(&["role::program", "use::editing"]))
+        );
+        assert_eq!(
+            get_list(&r, "provides"),
+            Some(owned(&["editor", "vi (= 1)", "x-editor"]))
+        );
+        assert_eq!(get_list(&r, "Package"), Some(owned(&["a"])));
+        assert_eq!(get_list(&r, "Depends"), None);
+        assert_eq!(get_list(&r, "tag"), r.get_list("Tag", ListSep::Comma));
+    }
+}
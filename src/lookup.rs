@@ -0,0 +1,757 @@
+//! Finding packages in a `Packages` file without parsing all of it
+
+use std::{
+    cmp::Ordering,
+    fs::File,
+    io::{BufRead, BufReader, Read, Seek, SeekFrom},
+    ops::Range,
+    path::{Path, PathBuf},
+    time::{Duration, UNIX_EPOCH},
+};
+
+use crate::{error::Result, parse_one, IndexError, IndexMap, Paragraph, ParagraphExt, ParseError};
+
+/// The paragraph of package `name` in a `Packages` file sorted by the
+/// `Package` field, as the archive writes it
+///
+/// Instead of parsing the whole file this binary-searches it: it seeks to
+/// the middle of the range left, skips to the next paragraph, reads its
+/// `Package` field and halves the range, so only a few paragraphs are read.
+/// Names are compared byte by byte. If several paragraphs have the name,
+/// e.g. different versions, the first one is returned.
+///
+/// When a paragraph turns up out of order the file isn't sorted, and it is
+/// scanned from the start instead. That is only noticed on the paragraphs
+/// the search reads, so an unsorted file may still give `None` for a
+/// package it has.
+///
+/// ```rust
+/// use eight_deep_parser::{find_package_sorted, ParagraphExt};
+/// use std::io::Cursor;
+///
+/// let s = "Package: a\nVersion: 1\n\nPackage: b\nVersion: 2\n\nPackage: c\nVersion: 3\n";
+///
+/// let r = find_package_sorted(Cursor::new(s), "b").unwrap().unwrap();
+///
+/// assert_eq!(r.get_one("Version"), Some("2"));
+/// assert!(find_package_sorted(Cursor::new(s), "d").unwrap().is_none());
+/// ```
+pub fn find_package_sorted<R: Read + Seek>(r: R, name: &str) -> Result<Option<Paragraph>> {
+    Ok(find_packages_sorted(r, &[name])?.pop().flatten())
+}
+
+/// Like [`find_package_sorted`] for each of `names`, in the same order
+///
+/// With `names` sorted too, every search starts where the one before it
+/// ended, so the seeks near the start of the file are done once. Names out
+/// of order still work, the search for them starts over.
+///
+/// ```rust
+/// use eight_deep_parser::{find_packages_sorted, ParagraphExt};
+/// use std::io::Cursor;
+///
+/// let s = "Package: a\nVersion: 1\n\nPackage: b\nVersion: 2\n\nPackage: c\nVersion: 3\n";
+///
+/// let r = find_packages_sorted(Cursor::new(s), &["a", "bb", "c"]).unwrap();
+///
+/// assert_eq!(r[0].as_ref().unwrap().get_one("Version"), Some("1"));
+/// assert!(r[1].is_none());
+/// assert_eq!(r[2].as_ref().unwrap().get_one("Version"), Some("3"));
+/// ```
+pub fn find_packages_sorted<R: Read + Seek>(
+    r: R,
+    names: &[&str],
+) -> Result<Vec<Option<Paragraph>>> {
+    let mut reader = Reader::new(r)?;
+    let mut result = Vec::with_capacity(names.len());
+    let mut lo = 0;
+
+    for (i, name) in names.iter().enumerate() {
+        if i > 0 && names[i - 1] > *name {
+            lo = 0;
+        }
+
+        match reader.search(name, lo)? {
+            Search::Found(entry) => {
+                lo = entry.start;
+                result.push(Some(entry.paragraph));
+            }
+            Search::Missing { lo: next } => {
+                lo = next;
+                result.push(None);
+            }
+            Search::Unsorted => return reader.scan_all(names),
+        }
+    }
+
+    Ok(result)
+}
+
+/// Where each package of a `Packages` file is, for reading single
+/// paragraphs of a large file again and again
+///
+/// Building it parses the file once. It can be saved next to the file and
+/// loaded on the next run, which fails with [`IndexError::Stale`] if the
+/// file's size or modification time changed since, or with
+/// [`IndexError::Corrupt`] if the index file isn't one written by
+/// [`PackageIndex::save`]. Either way the caller builds it again.
+///
+/// ```rust
+/// use eight_deep_parser::{PackageIndex, ParagraphExt};
+///
+/// # let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/Packages-sorted");
+/// let index = PackageIndex::build(path).unwrap();
+/// let p = index.get("hello").unwrap().unwrap();
+///
+/// assert_eq!(p.get_one("Section"), Some("devel"));
+/// assert!(index.get("nano").unwrap().is_none());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageIndex {
+    source: PathBuf,
+    size: u64,
+    mtime: Duration,
+    entries: IndexMap<String, Vec<Range<u64>>>,
+}
+
+impl PackageIndex {
+    const MAGIC: &'static [u8; 8] = b"8dpindex";
+    const VERSION: u32 = 1;
+
+    /// Index every paragraph of the file at `path` by its `Package` field
+    ///
+    /// Paragraphs without one are left out.
+    pub fn build(path: impl AsRef<Path>) -> Result<Self> {
+        let source = path.as_ref().to_path_buf();
+        let (size, mtime) = stat(&source)?;
+        let mut reader = Reader::new(File::open(&source)?)?;
+        let mut entries: IndexMap<_, Vec<_>> = IndexMap::new();
+
+        reader.seek(0)?;
+
+        while let Some(entry) = reader.next_paragraph()? {
+            if let Some(name) = entry.paragraph.get_one("Package") {
+                entries
+                    .entry(name.to_string())
+                    .or_default()
+                    .push(entry.start..entry.end);
+            }
+        }
+
+        Ok(Self {
+            source,
+            size,
+            mtime,
+            entries,
+        })
+    }
+
+    /// The indexed file
+    pub fn source(&self) -> &Path {
+        &self.source
+    }
+
+    /// The package names, in the order first seen in the file
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(|x| x.as_str())
+    }
+
+    /// Byte ranges of the paragraphs of `name` in the file, in file order
+    pub fn ranges(&self, name: &str) -> &[Range<u64>] {
+        self.entries.get(name).map_or(&[], |x| x.as_slice())
+    }
+
+    /// The first paragraph of package `name`, read from the file
+    pub fn get(&self, name: &str) -> Result<Option<Paragraph>> {
+        Ok(self.get_all_n(name, 1)?.pop())
+    }
+
+    /// Every paragraph of package `name`, e.g. one per version
+    pub fn get_all(&self, name: &str) -> Result<Vec<Paragraph>> {
+        self.get_all_n(name, usize::MAX)
+    }
+
+    fn get_all_n(&self, name: &str, n: usize) -> Result<Vec<Paragraph>> {
+        let ranges = self.ranges(name);
+
+        if ranges.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut reader = Reader::new(File::open(&self.source)?)?;
+        let mut result = vec![];
+
+        for range in ranges.iter().take(n) {
+            reader.seek(range.start)?;
+
+            if let Some(entry) = reader.next_paragraph()? {
+                result.push(entry.paragraph);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Write the index to `path`
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let source = self.source.to_str().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "indexed path isn't valid UTF-8",
+            )
+        })?;
+
+        let mut out = Vec::new();
+        out.extend_from_slice(Self::MAGIC);
+        out.extend_from_slice(&Self::VERSION.to_le_bytes());
+        out.extend_from_slice(&self.size.to_le_bytes());
+        out.extend_from_slice(&self.mtime.as_secs().to_le_bytes());
+        out.extend_from_slice(&self.mtime.subsec_nanos().to_le_bytes());
+        write_str(&mut out, source);
+        out.extend_from_slice(&(self.entries.len() as u64).to_le_bytes());
+
+        for (name, ranges) in &self.entries {
+            write_str(&mut out, name);
+            out.extend_from_slice(&(ranges.len() as u64).to_le_bytes());
+
+            for range in ranges {
+                out.extend_from_slice(&range.start.to_le_bytes());
+                out.extend_from_slice(&range.end.to_le_bytes());
+            }
+        }
+
+        std::fs::write(path, out)
+    }
+
+    /// Read an index written by [`PackageIndex::save`] and check that the
+    /// indexed file didn't change since
+    pub fn load(path: impl AsRef<Path>) -> std::result::Result<Self, IndexError> {
+        let data = std::fs::read(path)?;
+        let mut r = Bytes(&data);
+
+        if r.take(8)? != Self::MAGIC {
+            return Err(corrupt("not an index file"));
+        }
+
+        let version = r.u32()?;
+        if version != Self::VERSION {
+            return Err(corrupt(format!("unsupported version {}", version)));
+        }
+
+        let size = r.u64()?;
+        let mtime = Duration::new(r.u64()?, r.u32()?);
+        let source = PathBuf::from(r.str()?);
+        let mut entries = IndexMap::new();
+
+        for _ in 0..r.u64()? {
+            let name = r.str()?.to_string();
+            let mut ranges = vec![];
+
+            for _ in 0..r.u64()? {
+                let (start, end) = (r.u64()?, r.u64()?);
+                if start >= end || end > size {
+                    return Err(corrupt(format!("bad range of `{}`", name)));
+                }
+                ranges.push(start..end);
+            }
+
+            entries.insert(name, ranges);
+        }
+
+        if !r.0.is_empty() {
+            return Err(corrupt("trailing data"));
+        }
+
+        if stat(&source)? != (size, mtime) {
+            return Err(IndexError::Stale {
+                path: source.display().to_string(),
+            });
+        }
+
+        Ok(Self {
+            source,
+            size,
+            mtime,
+            entries,
+        })
+    }
+}
+
+/// Size and modification time of the file at `path`
+fn stat(path: &Path) -> std::io::Result<(u64, Duration)> {
+    let meta = std::fs::metadata(path)?;
+    let mtime = meta
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    Ok((meta.len(), mtime))
+}
+
+fn write_str(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(&(s.len() as u32).to_le_bytes());
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn corrupt(reason: impl Into<String>) -> IndexError {
+    IndexError::Corrupt {
+        reason: reason.into(),
+    }
+}
+
+/// The rest of an index file
+struct Bytes<'a>(&'a [u8]);
+
+impl<'a> Bytes<'a> {
+    fn take(&mut self, n: usize) -> std::result::Result<&'a [u8], IndexError> {
+        if self.0.len() < n {
+            return Err(corrupt("truncated"));
+        }
+
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> std::result::Result<u32, IndexError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> std::result::Result<u64, IndexError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn str(&mut self) -> std::result::Result<&'a str, IndexError> {
+        let len = self.u32()? as usize;
+
+        std::str::from_utf8(self.take(len)?).map_err(|_| corrupt("invalid UTF-8"))
+    }
+}
+
+/// A paragraph read from the file and where it is
+struct Entry {
+    /// Offset of the first line
+    start: u64,
+    /// Offset right after the last line
+    end: u64,
+    paragraph: Paragraph,
+}
+
+impl Entry {
+    fn name(&self) -> &str {
+        self.paragraph.get_one("Package").unwrap_or_default()
+    }
+}
+
+/// The first paragraph after the line holding a given offset
+struct Probe {
+    /// Offset of the line after the one holding the given offset
+    line: u64,
+    /// The blank line ending the paragraph the search landed in, and the
+    /// paragraph after it
+    next: Option<(u64, Entry)>,
+}
+
+enum Search {
+    Found(Entry),
+    /// Every paragraph before `lo` sorts before the name
+    Missing {
+        lo: u64,
+    },
+    Unsorted,
+}
+
+struct Reader<R> {
+    inner: BufReader<R>,
+    pos: u64,
+    len: u64,
+    line: Vec<u8>,
+}
+
+impl<R: Read + Seek> Reader<R> {
+    fn new(r: R) -> Result<Self> {
+        let mut inner = BufReader::new(r);
+        let len = inner.seek(SeekFrom::End(0))?;
+
+        Ok(Self {
+            inner,
+            pos: len,
+            len,
+            line: vec![],
+        })
+    }
+
+    fn seek(&mut self, pos: u64) -> Result<()> {
+        self.inner.seek(SeekFrom::Start(pos))?;
+        self.pos = pos;
+
+        Ok(())
+    }
+
+    /// Read the next line into `self.line`, `false` at the end
+    fn next_line(&mut self) -> Result<bool> {
+        self.line.clear();
+        let n = self.inner.read_until(b'\n', &mut self.line)?;
+        self.pos += n as u64;
+
+        Ok(n != 0)
+    }
+
+    fn is_blank(&self) -> bool {
+        self.line
+            .iter()
+            .all(|x| matches!(x, b' ' | b'\t' | b'\r' | b'\n'))
+    }
+
+    /// The paragraph after the blank lines at the current offset
+    fn next_paragraph(&mut self) -> Result<Option<Entry>> {
+        let mut start = self.pos;
+
+        loop {
+            if !self.next_line()? {
+                return Ok(None);
+            }
+            if !self.is_blank() && !self.line.starts_with(b"#") {
+                break;
+            }
+            start = self.pos;
+        }
+
+        let mut text = std::mem::take(&mut self.line);
+        let mut end = self.pos;
+
+        while self.next_line()? && !self.is_blank() {
+            text.extend_from_slice(&self.line);
+            end = self.pos;
+        }
+
+        if !text.ends_with(b"\n") {
+            text.push(b'\n');
+        }
+
+        let text = std::str::from_utf8(&text)
+            .map_err(|e| ParseError::invalid_utf8(&text, &e, start as usize, None))?;
+
+        Ok(Some(Entry {
+            start,
+            end,
+            paragraph: parse_one(text)?,
+        }))
+    }
+
+    fn probe(&mut self, mid: u64) -> Result<Probe> {
+        self.seek(mid.saturating_sub(1))?;
+
+        if mid > 0 {
+            self.next_line()?;
+        }
+
+        let line = self.pos;
+
+        if mid == 0 {
+            let next = self.next_paragraph()?.map(|x| (0, x));
+
+            return Ok(Probe { line, next });
+        }
+
+        loop {
+            let at = self.pos;
+
+            if !self.next_line()? {
+                return Ok(Probe { line, next: None });
+            }
+            if self.is_blank() {
+                let next = self.next_paragraph()?.map(|x| (at, x));
+
+                return Ok(Probe { line, next });
+            }
+        }
+    }
+
+    /// The first paragraph named `name` after `lo`, which is the start of a
+    /// paragraph or of the blank lines before it
+    fn search(&mut self, name: &str, mut lo: u64) -> Result<Search> {
+        // the first paragraph named `name` starts at `hi` or before
+        let mut hi = self.len;
+        // bounds on the names between `lo` and `hi` in a sorted file
+        let mut low: Option<String> = None;
+        let mut high: Option<String> = None;
+        let mut found = None;
+
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let probe = self.probe(mid)?;
+
+            let (boundary, entry) = match probe.next {
+                Some((boundary, entry)) if entry.start <= hi => (boundary, entry),
+                // no paragraph starts after the line holding `mid`, but one
+                // may start right after it
+                _ if probe.line < hi => {
+                    hi = probe.line;
+                    continue;
+                }
+                _ => break,
+            };
+            let got = entry.name().to_owned();
+
+            if low.as_deref().is_some_and(|x| got.as_str() < x)
+                || high.as_deref().is_some_and(|x| got.as_str() > x)
+            {
+                return Ok(Search::Unsorted);
+            }
+
+            match got.as_str().cmp(name) {
+                Ordering::Less => {
+                    lo = entry.end;
+                    low = Some(got);
+                }
+                Ordering::Equal => {
+                    hi = boundary;
+                    high = Some(got);
+                    found = Some(entry);
+                }
+                Ordering::Greater => {
+                    hi = boundary;
+                    high = Some(got);
+                }
+            }
+        }
+
+        // what's left is a paragraph or two, read them in order
+        self.seek(lo)?;
+
+        while let Some(entry) = self.next_paragraph()? {
+            if entry.start > hi {
+                break;
+            }
+
+            let got = entry.name().to_owned();
+
+            if low.as_deref().is_some_and(|x| got.as_str() < x) {
+                return Ok(Search::Unsorted);
+            }
+
+            match got.as_str().cmp(name) {
+                Ordering::Less => low = Some(got),
+                Ordering::Equal => return Ok(Search::Found(entry)),
+                Ordering::Greater if found.is_some() => return Ok(Search::Unsorted),
+                Ordering::Greater => break,
+            }
+        }
+
+        Ok(match found {
+            Some(entry) => Search::Found(entry),
+            None => Search::Missing { lo },
+        })
+    }
+
+    /// The first paragraph of each of `names`, reading the whole file
+    fn scan_all(&mut self, names: &[&str]) -> Result<Vec<Option<Paragraph>>> {
+        let mut result = vec![None; names.len()];
+        let mut left = names.len();
+
+        self.seek(0)?;
+
+        while left > 0 {
+            let entry = match self.next_paragraph()? {
+                Some(entry) => entry,
+                None => break,
+            };
+
+            for (i, name) in names.iter().enumerate() {
+                if result[i].is_none() && entry.name() == *name {
+                    result[i] = Some(entry.paragraph.clone());
+                    left -= 1;
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, io::Cursor};
+
+    use super::{find_package_sorted, find_packages_sorted, PackageIndex};
+    use crate::{parse_back, parse_multi, test_util::fixtures, IndexError, ParagraphExt};
+
+    /// A path of its own for each test in the temporary directory
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("8dparser-{}-{}", std::process::id(), name))
+    }
+
+    fn sorted() -> File {
+        File::open(fixtures::PACKAGES_SORTED_PATH).unwrap()
+    }
+
+    #[test]
+    fn test_find_package_sorted() {
+        let all =
+            parse_multi(&std::fs::read_to_string(fixtures::PACKAGES_SORTED_PATH).unwrap()).unwrap();
+
+        assert_eq!(all.len(), 24);
+
+        for p in &all {
+            let name = p.get_one("Package").unwrap();
+            let r = find_package_sorted(sorted(), name).unwrap();
+
+            assert_eq!(r.as_ref(), Some(p), "{}", name);
+        }
+
+        // before the first, after the last, and in between
+        for name in ["aaa", "adduse", "zzz", "zlib1g-dev", "e2fsprogs", ""] {
+            assert!(find_package_sorted(sorted(), name).unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn test_find_packages_sorted() {
+        let names = [
+            "aaa", "apt", "bash", "dash", "gzip", "hello", "nano", "zlib1g",
+        ];
+        let r = find_packages_sorted(sorted(), &names).unwrap();
+
+        for (name, p) in names.iter().zip(&r) {
+            assert_eq!(p, &find_package_sorted(sorted(), name).unwrap());
+        }
+
+        assert_eq!(r.iter().filter(|x| x.is_some()).count(), 6);
+
+        // out of order
+        let r = find_packages_sorted(sorted(), &["tar", "apt", "tar"]).unwrap();
+
+        assert!(r.iter().all(|x| x.is_some()));
+        assert_eq!(r[1].as_ref().unwrap().get_one("Package"), Some("apt"));
+    }
+
+    #[test]
+    fn test_find_package_sorted_duplicates() {
+        let s = "Package: a\nVersion: 1\n\nPackage: b\nVersion: 1\n\nPackage: b\nVersion: 2\n\n\
+                 Package: b\nVersion: 3\n\nPackage: c\nVersion: 1\n";
+        let r = find_package_sorted(Cursor::new(s), "b").unwrap().unwrap();
+
+        assert_eq!(r.get_one("Version"), Some("1"));
+    }
+
+    #[test]
+    fn test_find_package_unsorted() {
+        let mut all = fixtures::packages();
+        all.extend(
+            parse_multi(&std::fs::read_to_string(fixtures::PACKAGES_SORTED_PATH).unwrap())
+                .unwrap()
+                .into_iter()
+                .rev(),
+        );
+        let s = parse_back(&all);
+
+        for name in ["adduser", "hello", "login", "zlib1g"] {
+            let r = find_package_sorted(Cursor::new(&s), name).unwrap().unwrap();
+
+            assert_eq!(r.get_one("Package"), Some(name));
+        }
+    }
+
+    #[test]
+    fn test_find_package_sorted_edges() {
+        assert!(find_package_sorted(Cursor::new(""), "a").unwrap().is_none());
+        assert!(find_package_sorted(Cursor::new("\n\n"), "a")
+            .unwrap()
+            .is_none());
+
+        let r = find_package_sorted(Cursor::new("\n\nPackage: a\nX:\n b"), "a").unwrap();
+
+        assert_eq!(r.unwrap().get_one("Package"), Some("a"));
+    }
+
+    #[test]
+    fn test_package_index() {
+        let index = PackageIndex::build(fixtures::PACKAGES_SORTED_PATH).unwrap();
+        let all =
+            parse_multi(&std::fs::read_to_string(fixtures::PACKAGES_SORTED_PATH).unwrap()).unwrap();
+
+        assert_eq!(index.names().count(), 24);
+
+        for p in &all {
+            let name = p.get_one("Package").unwrap();
+
+            assert_eq!(index.get(name).unwrap().as_ref(), Some(p));
+            assert_eq!(index.get_all(name).unwrap().len(), 1);
+        }
+
+        assert!(index.get("nano").unwrap().is_none());
+        assert!(index.get_all("nano").unwrap().is_empty());
+
+        let path = temp_path("index");
+        index.save(&path).unwrap();
+
+        assert_eq!(PackageIndex::load(&path).unwrap(), index);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_package_index_duplicates() {
+        let source = temp_path("dup-Packages");
+        std::fs::write(
+            &source,
+            "Package: a\nVersion: 1\n\nPackage: b\nVersion: 1\n\n\nPackage: a\nVersion: 2\n",
+        )
+        .unwrap();
+
+        let index = PackageIndex::build(&source).unwrap();
+        let r = index.get_all("a").unwrap();
+
+        assert_eq!(index.names().collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(index.ranges("a").len(), 2);
+        assert_eq!(r[1].get_one("Version"), Some("2"));
+        assert_eq!(index.get("a").unwrap().as_ref(), Some(&r[0]));
+
+        std::fs::remove_file(&source).unwrap();
+    }
+
+    #[test]
+    fn test_package_index_stale_corrupt() {
+        let source = temp_path("stale-Packages");
+        let path = temp_path("stale-index");
+        std::fs::copy(fixtures::PACKAGES_SORTED_PATH, &source).unwrap();
+
+        let index = PackageIndex::build(&source).unwrap();
+        index.save(&path).unwrap();
+
+        let data = std::fs::read(&path).unwrap();
+
+        for bad in [
+            &data[..data.len() - 1],
+            &data[1..],
+            &[data.clone(), vec![0]].concat(),
+        ] {
+            std::fs::write(&path, bad).unwrap();
+
+            assert!(matches!(
+                PackageIndex::load(&path),
+                Err(IndexError::Corrupt { .. })
+            ));
+        }
+
+        std::fs::write(&path, &data).unwrap();
+        std::fs::write(
+            &source,
+            std::fs::read_to_string(&source).unwrap() + "Package: zzz\n",
+        )
+        .unwrap();
+
+        assert!(matches!(
+            PackageIndex::load(&path),
+            Err(IndexError::Stale { .. })
+        ));
+
+        std::fs::remove_file(&source).unwrap();
+
+        assert!(matches!(PackageIndex::load(&path), Err(IndexError::Io(_))));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,637 @@
+use std::borrow::Cow;
+
+use crate::{cst, error::Result, IndexMap, ParseError};
+
+/// Options for [`parse_one_with`](crate::parse_one_with) and
+/// [`parse_multi_with`](crate::parse_multi_with)
+///
+/// `ParseOptions::default()` gives the behavior of [`parse_one`](crate::parse_one)
+/// and [`parse_multi`](crate::parse_multi).
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// What to do when a paragraph contains the same field more than once
+    pub duplicates: DuplicateKeys,
+    /// How much leading whitespace to strip from continuation lines
+    pub continuation: ContinuationWhitespace,
+    /// Keep empty paragraphs in [`parse_multi_with`](crate::parse_multi_with)
+    ///
+    /// Paragraphs are separated by one blank line; every further blank line
+    /// is an empty paragraph, except at the end of the input. Off by
+    /// default, dropping them.
+    pub keep_empty: bool,
+    /// Which line terminators to accept, converted to `\n` before parsing
+    ///
+    /// Byte offsets in errors refer to the converted input.
+    pub line_endings: LineEndings,
+    /// Respell field names while parsing, see
+    /// [`normalize_keys`](crate::normalize_keys)
+    ///
+    /// Fields that end up with the same name are duplicates under
+    /// [`ParseOptions::duplicates`]. Off by default, keeping the names as
+    /// written.
+    pub normalize_keys: Option<KeyStyle>,
+    /// What to do with whitespace between a field name and its colon
+    pub space_before_colon: SpaceBeforeColon,
+    /// Fail with [`ParseError::Malformed`] on a field name holding
+    /// whitespace or control characters, which Debian policy doesn't allow
+    ///
+    /// Whitespace before the colon is left to
+    /// [`ParseOptions::space_before_colon`]. Off by default, accepting names
+    /// like `Two Words`.
+    pub strict_field_names: bool,
+    /// What to do with continuation lines before the first field of a
+    /// paragraph
+    pub orphan_lines: OrphanLines,
+    /// Drop lines starting with `#`, as allowed in `debian/control` and
+    /// deb822 `.sources` files
+    ///
+    /// A comment line neither ends a paragraph nor continues a field; the
+    /// field after it can still be continued. Off by default, when a comment
+    /// line is [`ParseError::Malformed`], or trailing data after a blank
+    /// line.
+    pub skip_comments: bool,
+    /// What to do with input after the last paragraph of
+    /// [`parse_multi_with`](crate::parse_multi_with) that doesn't parse as
+    /// another one
+    pub trailing_data: TrailingData,
+}
+
+impl ParseOptions {
+    /// How dpkg reads `/var/lib/dpkg/status` and `.deb` control files
+    ///
+    /// Sets [`DuplicateKeys::Error`], [`SpaceBeforeColon::Error`],
+    /// [`OrphanLines::Error`], [`TrailingData::Error`] and
+    /// [`KeyStyle::KnownOnly`], so that
+    /// `Package` and `package` are a duplicate like they are for dpkg. Lines
+    /// end with `\n` and continuation lines keep the `.` of empty lines, as
+    /// by default.
+    pub fn dpkg_status() -> Self {
+        Self {
+            duplicates: DuplicateKeys::Error,
+            normalize_keys: Some(KeyStyle::KnownOnly),
+            space_before_colon: SpaceBeforeColon::Error,
+            orphan_lines: OrphanLines::Error,
+            trailing_data: TrailingData::Error,
+            ..Self::default()
+        }
+    }
+
+    /// How apt reads `Packages` and `Sources` indices
+    ///
+    /// Sets [`LineEndings::CrLf`]; the rest is the lenient default: the
+    /// last of duplicate fields wins, whitespace before a colon is trimmed
+    /// and orphan continuation lines are dropped. Invalid UTF-8 is still an
+    /// error, and so is a broken paragraph: a
+    /// [`ParagraphDecoder`](crate::ParagraphDecoder) goes on after it.
+    pub fn apt_packages() -> Self {
+        Self {
+            line_endings: LineEndings::CrLf,
+            ..Self::default()
+        }
+    }
+
+    /// How apt reads deb822 `.sources` files in `sources.list.d`
+    ///
+    /// Sets [`ParseOptions::skip_comments`] and [`LineEndings::CrLf`], and
+    /// is lenient otherwise like [`ParseOptions::apt_packages`]. Fields like
+    /// `Suites` hold several values, which
+    /// [`SourcesEntry`](crate::SourcesEntry) splits.
+    pub fn deb822_sources() -> Self {
+        Self {
+            skip_comments: true,
+            ..Self::apt_packages()
+        }
+    }
+
+    /// Accept only what Debian policy §5.1 allows
+    ///
+    /// Sets [`DuplicateKeys::Error`], [`SpaceBeforeColon::Error`],
+    /// [`ParseOptions::strict_field_names`], [`OrphanLines::Error`] and
+    /// [`TrailingData::Error`]. Field names are kept as written, lines end
+    /// with `\n` only and comments aren't skipped, as by default.
+    pub fn strict_rfc() -> Self {
+        Self {
+            duplicates: DuplicateKeys::Error,
+            space_before_colon: SpaceBeforeColon::Error,
+            strict_field_names: true,
+            orphan_lines: OrphanLines::Error,
+            trailing_data: TrailingData::Error,
+            ..Self::default()
+        }
+    }
+
+    /// `s` without its comment lines if [`ParseOptions::skip_comments`] is
+    /// set
+    pub(crate) fn strip_comments<'a>(&self, s: &'a str, removed: &mut Removed) -> Cow<'a, str> {
+        if !self.skip_comments || !s.lines().any(|x| x.starts_with('#')) {
+            return Cow::Borrowed(s);
+        }
+
+        let mut result = String::with_capacity(s.len());
+
+        for line in s.split_inclusive('\n') {
+            if line.starts_with('#') {
+                removed.push((result.len(), line.len()));
+            } else {
+                result += line;
+            }
+        }
+
+        Cow::Owned(result)
+    }
+
+    /// `s` rewritten for the parser: line endings normalized, then comment
+    /// and orphan lines removed as the options ask
+    pub(crate) fn prepare<'a>(&self, s: &'a str) -> Result<Prepared<'a>> {
+        let mut steps = vec![vec![], vec![]];
+
+        let text = self.line_endings.normalize(s, &mut steps[0]);
+        let stripped = match self.strip_comments(&text, &mut steps[1]) {
+            Cow::Owned(x) => Some(x),
+            Cow::Borrowed(_) => None,
+        };
+
+        let mut prepared = Prepared {
+            text: stripped.map_or(text, Cow::Owned),
+            line_endings: self.line_endings,
+            steps,
+        };
+
+        let mut removed = vec![];
+        let kept = match self.orphan_lines.apply(&prepared.text, &mut removed) {
+            Ok(Cow::Owned(x)) => Some(x),
+            Ok(Cow::Borrowed(_)) => None,
+            Err(e) => return Err(prepared.error(s, e)),
+        };
+
+        if let Some(kept) = kept {
+            // `removed` is in the text without orphan lines, the earlier
+            // steps map from the text with them
+            #[cfg(feature = "tracing")]
+            removed.iter().fold(0, |before, (at, len)| {
+                tracing::debug!(
+                    offset = prepared.offset(at + before),
+                    len,
+                    "skipped an orphan continuation line"
+                );
+                before + len
+            });
+
+            prepared.text = Cow::Owned(kept);
+            prepared.steps.push(removed);
+        }
+
+        Ok(prepared)
+    }
+}
+
+/// The bytes a rewriting step removed, as the offset in its output where
+/// they were and how many
+pub(crate) type Removed = Vec<(usize, usize)>;
+
+/// The input as rewritten by [`ParseOptions::prepare`], remembering what was
+/// removed to tell where positions in the text are in the input
+pub(crate) struct Prepared<'a> {
+    pub(crate) text: Cow<'a, str>,
+    line_endings: LineEndings,
+    steps: Vec<Removed>,
+}
+
+impl Prepared<'_> {
+    /// The offset in the input of byte `offset` of the text
+    pub(crate) fn offset(&self, offset: usize) -> usize {
+        self.steps.iter().rev().fold(offset, |offset, step| {
+            offset
+                + step
+                    .iter()
+                    .take_while(|(at, _)| *at <= offset)
+                    .map(|(_, len)| len)
+                    .sum::<usize>()
+        })
+    }
+
+    /// The line of `input` that line `line` of the text, starting from 1,
+    /// comes from
+    pub(crate) fn line(&self, input: &str, line: usize) -> usize {
+        let start = self
+            .text
+            .split_inclusive('\n')
+            .take(line.saturating_sub(1))
+            .map(str::len)
+            .sum();
+        let before = &input[..self.offset(start)];
+
+        self.line_endings
+            .normalize(before, &mut vec![])
+            .matches('\n')
+            .count()
+            + 1
+    }
+
+    /// `e` with its positions in the text moved to `input`
+    pub(crate) fn error(&self, input: &str, e: ParseError) -> ParseError {
+        match e {
+            ParseError::Paragraph {
+                index,
+                offset,
+                source,
+            } => ParseError::Paragraph {
+                index,
+                offset: self.offset(offset),
+                source: Box::new(self.error(input, *source)),
+            },
+            ParseError::TrailingData { offset } => ParseError::TrailingData {
+                offset: self.offset(offset),
+            },
+            ParseError::Malformed { line } => ParseError::Malformed {
+                line: self.line(input, line),
+            },
+            ParseError::OrphanContinuation { line } => ParseError::OrphanContinuation {
+                line: self.line(input, line),
+            },
+            e => e,
+        }
+    }
+}
+
+/// Policy for continuation lines that start a paragraph, so there is no
+/// field they can belong to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OrphanLines {
+    /// Drop them
+    #[default]
+    Skip,
+    /// Fail with
+    /// [`ParseError::OrphanContinuation`](crate::ParseError::OrphanContinuation)
+    Error,
+}
+
+impl OrphanLines {
+    /// `s` without its orphan lines
+    pub(crate) fn apply<'a>(self, s: &'a str, removed: &mut Removed) -> Result<Cow<'a, str>> {
+        let mut kept: Option<String> = None;
+        let mut paragraph_start = true;
+        let mut offset = 0;
+
+        for (i, line) in s.split_inclusive('\n').enumerate() {
+            let start = offset;
+            offset += line.len();
+
+            // in a paragraph a line of spaces is an empty continuation line,
+            // only an empty line ends it
+            let blank = cst::is_blank_line(line.as_bytes());
+            if cst::ends_paragraph(line.as_bytes()) || paragraph_start && blank {
+                paragraph_start = true;
+            } else if paragraph_start && line.starts_with([' ', '\t']) {
+                if self == OrphanLines::Error {
+                    return Err(ParseError::OrphanContinuation { line: i + 1 });
+                }
+
+                let kept = kept.get_or_insert_with(|| s[..start].to_string());
+                removed.push((kept.len(), line.len()));
+                continue;
+            } else {
+                paragraph_start = false;
+            }
+
+            if let Some(kept) = &mut kept {
+                kept.push_str(line);
+            }
+        }
+
+        Ok(match kept {
+            Some(kept) => Cow::Owned(kept),
+            None => Cow::Borrowed(s),
+        })
+    }
+}
+
+/// Policy for input left after the last paragraph, like a line without a
+/// colon after a blank line, which often means the file is truncated or
+/// corrupt
+///
+/// Whitespace and `#` comment lines are never trailing data. A line without
+/// a colon inside a paragraph fails with
+/// [`ParseError::Malformed`](crate::ParseError::Malformed) whatever the
+/// policy, except [`TrailingData::Resync`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrailingData {
+    /// Drop it
+    #[default]
+    Skip,
+    /// Fail with
+    /// [`ParseError::TrailingData`](crate::ParseError::TrailingData)
+    Error,
+    /// Skip it up to the next paragraph that parses and go on, like
+    /// [`parse_multi_recover`](crate::parse_multi_recover), which also
+    /// tells what was skipped
+    Resync,
+}
+
+/// Policy for a field written as `Package : a`
+///
+/// Debian policy doesn't allow whitespace before the colon, but some
+/// handwritten files have it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpaceBeforeColon {
+    /// Strip it from the field name, giving `Package`
+    #[default]
+    Trim,
+    /// Fail with
+    /// [`ParseError::SpaceBeforeColon`](crate::ParseError::SpaceBeforeColon)
+    Error,
+}
+
+/// How [`normalize_keys`](crate::normalize_keys) spells field names
+///
+/// Known Debian fields, see [`known_fields`](crate::known_fields), are
+/// always spelled canonically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyStyle {
+    /// Capitalize the first letter of every `-`-separated part of other
+    /// names, and lowercase the rest
+    #[default]
+    TrainCase,
+    /// Leave other names as they are
+    KnownOnly,
+}
+
+/// Line terminators accepted by [`ParseOptions::line_endings`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEndings {
+    /// `\n`, `\r\n` and a lone `\r` (classic Mac OS) all end a line
+    Auto,
+    /// Only `\n` ends a line, a `\r` is part of the value
+    #[default]
+    Lf,
+    /// `\r\n` and `\n` end a line, a lone `\r` is part of the value
+    CrLf,
+}
+
+impl LineEndings {
+    pub(crate) fn normalize<'a>(self, s: &'a str, removed: &mut Removed) -> Cow<'a, str> {
+        if self == LineEndings::Lf || !s.contains('\r') {
+            return Cow::Borrowed(s);
+        }
+
+        let mut result = String::with_capacity(s.len());
+
+        for (i, line) in s.split("\r\n").enumerate() {
+            if i != 0 {
+                removed.push((result.len(), 1));
+                result.push('\n');
+            }
+
+            result += line;
+        }
+
+        Cow::Owned(match self {
+            LineEndings::Auto => result.replace('\r', "\n"),
+            _ => result,
+        })
+    }
+}
+
+/// Policy for a field appearing more than once in one paragraph
+///
+/// Keys are compared exactly, so `Depends` and `depends` are different fields,
+/// unless [`ParseOptions::normalize_keys`] spells them the same. With
+/// [`KeyStyle::TrainCase`] names differing only by case are the same field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateKeys {
+    /// The last occurrence wins: its value replaces the earlier one, whatever
+    /// kind of [`Item`](crate::Item) either of them is. The field keeps the
+    /// position of its first occurrence.
+    #[default]
+    LastWins,
+    /// Fail with [`ParseError::DuplicateKey`](crate::ParseError::DuplicateKey)
+    Error,
+    /// Append the lines of the later value to the earlier one, as by
+    /// [`Item::push_line`](crate::Item::push_line). The field keeps the
+    /// position of its first occurrence.
+    ///
+    /// No separator is added, so relation fields need a trailing comma on
+    /// the earlier value to stay valid.
+    Concat,
+}
+
+/// Leading whitespace handling of continuation lines
+///
+/// A continuation line starts with a space or a tab. Whether any further
+/// whitespace is part of the value depends on the field, e.g. the extended
+/// `Description` uses it for verbatim lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ContinuationWhitespace {
+    /// Strip the first space or tab and keep the rest as part of the value
+    #[default]
+    StripOne,
+    /// Strip all leading whitespace
+    StripAll,
+    /// Keep the line as it was written, including the first space or tab.
+    /// Values parsed this way are written back as-is by
+    /// [`parse_back_with`](crate::parse_back_with).
+    Preserve,
+}
+
+impl ContinuationWhitespace {
+    pub(crate) fn strip(self, line: &str) -> &str {
+        match self {
+            ContinuationWhitespace::StripOne => &line[1..],
+            ContinuationWhitespace::StripAll => line.trim_start_matches([' ', '\t']),
+            ContinuationWhitespace::Preserve => line,
+        }
+    }
+}
+
+/// Options for [`parse_back_with`](crate::parse_back_with)
+///
+/// `SerializeOptions::default()` gives the output of
+/// [`parse_back`](crate::parse_back).
+#[derive(Debug, Clone, Default)]
+pub struct SerializeOptions {
+    /// The policy the values were parsed with.
+    ///
+    /// With [`ContinuationWhitespace::Preserve`] lines already starting
+    /// with a space or tab are written unchanged, otherwise every
+    /// continuation line is indented by a single space.
+    pub continuation: ContinuationWhitespace,
+    /// End the output with a blank line after the last paragraph.
+    ///
+    /// Off by default: like the files written by apt and dpkg, the output
+    /// ends with the newline of the last field. Parsing accepts any number
+    /// of trailing blank lines either way.
+    pub trailing_blank_line: bool,
+    /// End lines with `\r\n` instead of `\n`, for consumers that want
+    /// Windows-style output
+    pub crlf: bool,
+}
+
+/// Options for [`substitute`](crate::substitute)
+#[derive(Debug, Clone)]
+pub struct SubstOptions {
+    /// What to do with a `${name}` that isn't in the variables
+    pub unknown: UnknownVar,
+    /// How deep variables may refer to other variables. With the default of
+    /// 8, the value of a variable can use variables nested 8 levels deep.
+    pub max_depth: usize,
+}
+
+impl Default for SubstOptions {
+    fn default() -> Self {
+        Self {
+            unknown: UnknownVar::default(),
+            max_depth: 8,
+        }
+    }
+}
+
+/// Handling of unknown variables, see [`SubstOptions::unknown`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownVar {
+    /// Fail with [`SubstError::Unknown`](crate::SubstError::Unknown)
+    #[default]
+    Error,
+    /// Leave `${name}` in the value
+    Keep,
+    /// Replace it with nothing, like dpkg-gencontrol
+    Remove,
+}
+
+/// Options for [`generate_packages_index`](crate::generate_packages_index)
+#[cfg(feature = "deb")]
+#[derive(Debug, Clone)]
+pub struct PackagesIndexOptions {
+    /// Prepended to the path of each `.deb` below the root to give its
+    /// `Filename`, e.g. `pool/` when the root is the `pool` directory of the
+    /// repository. Empty by default.
+    pub prefix: String,
+    /// The digest fields to add, in this order. `MD5sum` and `SHA256` by
+    /// default.
+    pub digests: Vec<crate::Algorithm>,
+}
+
+#[cfg(feature = "deb")]
+impl Default for PackagesIndexOptions {
+    fn default() -> Self {
+        Self {
+            prefix: String::new(),
+            digests: vec![crate::Algorithm::Md5, crate::Algorithm::Sha256],
+        }
+    }
+}
+
+/// Limits of a [`ParagraphReader`](crate::ParagraphReader), for input that
+/// can't be trusted, e.g. an index downloaded from a third-party mirror
+///
+/// Going over one fails with
+/// [`ParseError::LimitExceeded`](crate::ParseError::LimitExceeded) before
+/// more than one byte over it is read. There are no limits by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadLimits {
+    /// Bytes of one paragraph, with its line breaks
+    pub max_paragraph_bytes: usize,
+    /// Bytes of the whole input
+    pub max_total_bytes: usize,
+}
+
+impl Default for ReadLimits {
+    fn default() -> Self {
+        Self {
+            max_paragraph_bytes: usize::MAX,
+            max_total_bytes: usize::MAX,
+        }
+    }
+}
+
+/// What [`ParagraphReader::on_field`](crate::ParagraphReader::on_field)
+/// leaves in the paragraph of a field handed to its callback
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StreamedFields {
+    /// The field, in its place, with [`Item::Streamed`](crate::Item::Streamed)
+    /// as its value
+    #[default]
+    Mark,
+    /// Nothing, as if the field wasn't there
+    Omit,
+}
+
+/// How a field is compared by [`semantic_diff_by`](crate::semantic_diff_by),
+/// see [`DiffOptions`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FieldCompare {
+    /// Line by line, like [`semantic_diff`](crate::semantic_diff)
+    #[default]
+    OrderedExact,
+    /// As a set of lines without whitespace around them, e.g. `Conffiles`
+    UnorderedTrimmed,
+    /// Not at all, e.g. `Description-md5`, which changes with the
+    /// description
+    Ignore,
+}
+
+/// Options for [`semantic_diff_by`](crate::semantic_diff_by) and
+/// [`document_diff_by`](crate::document_diff_by): how each field is compared
+///
+/// `DiffOptions::default()` compares the file and checksum lists of dpkg
+/// and source packages (`Conffiles`, `Files`, `Checksums-*` and
+/// `Package-List`) as sets, and ignores `Description-md5`. Other fields are
+/// compared with [`FieldCompare::OrderedExact`].
+///
+/// ```rust
+/// use eight_deep_parser::{DiffOptions, FieldCompare};
+///
+/// let mut opts = DiffOptions::default();
+/// opts.set("Description-md5", FieldCompare::OrderedExact);
+///
+/// assert_eq!(opts.get("conffiles"), FieldCompare::UnorderedTrimmed);
+/// assert_eq!(opts.get("Description-md5"), FieldCompare::OrderedExact);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffOptions {
+    /// By lowercase field name
+    fields: IndexMap<String, FieldCompare>,
+}
+
+impl DiffOptions {
+    /// Every field compared with [`FieldCompare::OrderedExact`], like
+    /// [`semantic_diff`](crate::semantic_diff)
+    pub fn exact() -> Self {
+        Self {
+            fields: IndexMap::new(),
+        }
+    }
+
+    /// How the field `key` is compared, ignoring case
+    pub fn get(&self, key: &str) -> FieldCompare {
+        self.fields
+            .get(&key.to_ascii_lowercase())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Compare the field `key` with `compare`, ignoring case
+    pub fn set(&mut self, key: &str, compare: FieldCompare) -> &mut Self {
+        self.fields.insert(key.to_ascii_lowercase(), compare);
+        self
+    }
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        let mut opts = Self::exact();
+
+        for key in [
+            "Conffiles",
+            "Files",
+            "Checksums-Sha1",
+            "Checksums-Sha256",
+            "Checksums-Sha512",
+            "Package-List",
+        ] {
+            opts.set(key, FieldCompare::UnorderedTrimmed);
+        }
+        opts.set("Description-md5", FieldCompare::Ignore);
+
+        opts
+    }
+}
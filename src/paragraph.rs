@@ -0,0 +1,769 @@
+use std::collections::HashSet;
+
+use crate::{
+    control::{parse_yes_no, split_section},
+    identity, parse_relations, relation, Description, IndexMap, IndexSet, Item, ListSep, MultiArch,
+    PackageId, PackageStatus, Priority, Provide, Relation, RelationError, Version,
+};
+
+/// One parsed paragraph (stanza), as returned by [`parse_one`](crate::parse_one)
+///
+/// # Field order
+///
+/// The order of the fields is kept from parsing to writing back, which is
+/// what the `IndexMap` is for:
+///
+/// - parsing gives the fields in the order of the input; a repeated field
+///   merged by [`DuplicateKeys`](crate::DuplicateKeys) stays where it was
+///   first seen
+/// - inserting a new field appends it, inserting or
+///   [setting](ParagraphExt::set_field) an existing one keeps its position
+/// - [`ParagraphExt::remove_field`] and `shift_remove` keep the order of the
+///   other fields. `IndexMap::remove` doesn't: it moves the last field into
+///   the gap.
+/// - [`rename_field`], [`normalize_keys`](crate::normalize_keys) and
+///   [`strip_field`] leave the other fields where they are
+/// - [`parse_back`](crate::parse_back) and the other writers write the
+///   fields in the order of the map
+/// - iterating, by reference or by value, gives the fields in order
+///
+/// The tests of the `order_tests` module hold every operation to this.
+///
+/// # Owned iteration
+///
+/// A paragraph consumed with `into_iter`, or
+/// [`ParagraphExt::into_fields`], gives its names and values as owned
+/// `(String, Item)` pairs, moved out of the map without being cloned, for
+/// turning a paragraph into another structure.
+pub type Paragraph = IndexMap<String, Item>;
+
+/// Convenience accessors and setters on a parsed [`Paragraph`]
+///
+/// Field names are matched case-insensitively, as deb822 requires.
+pub trait ParagraphExt {
+    /// Get a field, ignoring the case of `key`
+    fn get_field(&self, key: &str) -> Option<&Item>;
+
+    /// Get a one line field, ignoring the case of `key`
+    fn get_one(&self, key: &str) -> Option<&str>;
+
+    /// Get a one line field like [`ParagraphExt::get_one`], or `default`
+    ///
+    /// Only [`Item::OneLine`] values are returned: a [`Item::MultiLine`] or
+    /// [`Item::Folded`] field gives `default` too, rather than its lines
+    /// joined, and so does a field written `Key:` with nothing after it,
+    /// which parses as a `MultiLine`. Read those with
+    /// [`ParagraphExt::get_field`].
+    ///
+    /// ```rust
+    /// use eight_deep_parser::{parse_one, ParagraphExt};
+    ///
+    /// let r = parse_one("Package: a\nSection: misc\n").unwrap();
+    ///
+    /// assert_eq!(r.get_one_or("section", "unknown"), "misc");
+    /// assert_eq!(r.get_one_or("Priority", "optional"), "optional");
+    /// ```
+    fn get_one_or<'a>(&'a self, key: &str, default: &'a str) -> &'a str {
+        self.get_one(key).unwrap_or(default)
+    }
+
+    /// Get the `Description` field split into synopsis and extended text
+    fn description(&self) -> Option<Description>;
+
+    /// Get the `Description-<lang>` field of a `Translation-*` file, e.g.
+    /// `Description-fr` for `lang` `fr`, split like [`ParagraphExt::description`]
+    fn translated_description(&self, lang: &str) -> Option<Description> {
+        self.get_field(&format!("Description-{}", lang))
+            .map(Description::from_item)
+    }
+
+    /// Get a list field split by `sep`, matching `key` case-insensitively
+    ///
+    /// See [`Item::split_whitespace_list`] and [`Item::split_comma_list`]:
+    /// entries are trimmed, empty ones are dropped, and with
+    /// [`ListSep::Comma`] an entry may continue on the next line.
+    fn get_list(&self, key: &str, sep: ListSep) -> Option<Vec<String>>;
+
+    /// Get the `Package` and `Architecture` of a binary package
+    ///
+    /// `Architecture: all` packages are installed once for all
+    /// architectures, so by convention their identity has no architecture.
+    fn identity(&self) -> Option<PackageId> {
+        self.identity_with(false)
+    }
+
+    /// Like [`ParagraphExt::identity`], keeping `all` as the architecture if
+    /// `keep_arch_all` is set
+    fn identity_with(&self, keep_arch_all: bool) -> Option<PackageId>;
+
+    /// Parse a relation field like `Depends`, see [`parse_relations`]
+    ///
+    /// Line breaks count as whitespace, so relations may span lines.
+    fn relations(&self, key: &str) -> Option<Result<Vec<Vec<Relation>>, RelationError>> {
+        let item = self.get_field(key)?;
+
+        Some(parse_relations(
+            &item.value_lines().collect::<Vec<_>>().join(" "),
+        ))
+    }
+
+    /// Parse the `Provides` field, empty if there is none
+    ///
+    /// A versioned provide must use `=`, and alternatives aren't allowed.
+    fn provides(&self) -> Result<Vec<Provide>, RelationError>;
+
+    /// Get the `Multi-Arch` field
+    fn multi_arch(&self) -> Option<MultiArch> {
+        self.get_one("Multi-Arch").map(MultiArch::from)
+    }
+
+    /// Get the `Essential` field, `None` if absent or neither `yes` nor `no`
+    fn essential(&self) -> Option<bool> {
+        self.get_one("Essential").and_then(parse_yes_no)
+    }
+
+    /// Get the `Protected` field, `None` if absent or neither `yes` nor `no`
+    fn protected(&self) -> Option<bool> {
+        self.get_one("Protected").and_then(parse_yes_no)
+    }
+
+    /// Get the `Status` field of the dpkg database, `None` if absent or not
+    /// three words
+    fn status(&self) -> Option<PackageStatus> {
+        self.get_one("Status").and_then(PackageStatus::parse)
+    }
+
+    /// Get the `Priority` field
+    fn priority(&self) -> Option<Priority> {
+        self.get_one("Priority").map(Priority::from)
+    }
+
+    /// Get the `Section` field as archive area, if any, and section name:
+    /// `universe/net` gives `(Some("universe"), "net")`
+    fn section(&self) -> Option<(Option<&str>, &str)> {
+        self.get_one("Section").map(split_section)
+    }
+
+    /// Set a field, ignoring the case of `key`, giving the old value
+    ///
+    /// An existing field keeps its position and its name as written, a new
+    /// one is appended.
+    ///
+    /// ```rust
+    /// use eight_deep_parser::{parse_one, Item, ParagraphExt};
+    ///
+    /// let mut r = parse_one("Package: a\nversion: 1\nSection: misc\n").unwrap();
+    /// r.set_field("Version", Item::OneLine("2".to_string()));
+    ///
+    /// assert_eq!(r.get_index_of("version"), Some(1));
+    /// assert_eq!(r.get_one("Version"), Some("2"));
+    /// ```
+    fn set_field(&mut self, key: &str, value: Item) -> Option<Item>;
+
+    /// Remove a field, ignoring the case of `key`, keeping the order of the
+    /// others
+    fn remove_field(&mut self, key: &str) -> Option<Item>;
+
+    /// Consume the paragraph, giving its fields in order, without cloning
+    /// them
+    ///
+    /// ```rust
+    /// use eight_deep_parser::{parse_one, Item, ParagraphExt};
+    ///
+    /// let r = parse_one("Package: a\nDepends: b,\n c\n").unwrap();
+    /// let (keys, values): (Vec<String>, Vec<Item>) = r.into_fields().unzip();
+    ///
+    /// assert_eq!(keys, ["Package", "Depends"]);
+    /// assert_eq!(values[1].lines(), ["b,", "c"]);
+    /// ```
+    fn into_fields(self) -> indexmap::map::IntoIter<String, Item>
+    where
+        Self: Sized;
+}
+
+impl ParagraphExt for Paragraph {
+    fn get_field(&self, key: &str) -> Option<&Item> {
+        if let Some(v) = self.get(key) {
+            return Some(v);
+        }
+
+        self.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v)
+    }
+
+    fn get_one(&self, key: &str) -> Option<&str> {
+        match self.get_field(key)? {
+            Item::OneLine(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn description(&self) -> Option<Description> {
+        self.get_field("Description").map(Description::from_item)
+    }
+
+    fn get_list(&self, key: &str, sep: ListSep) -> Option<Vec<String>> {
+        let item = self.get_field(key)?;
+
+        Some(match sep {
+            ListSep::Whitespace => item
+                .split_whitespace_list()
+                .into_iter()
+                .map(|x| x.to_string())
+                .collect(),
+            ListSep::Comma => item.split_comma_list(),
+        })
+    }
+
+    fn identity_with(&self, keep_arch_all: bool) -> Option<PackageId> {
+        identity::identity(self, keep_arch_all)
+    }
+
+    fn provides(&self) -> Result<Vec<Provide>, RelationError> {
+        relation::provides(self)
+    }
+
+    fn set_field(&mut self, key: &str, value: Item) -> Option<Item> {
+        match field_index(self, key) {
+            Some(i) => Some(std::mem::replace(&mut self[i], value)),
+            None => {
+                self.insert(key.to_string(), value);
+                None
+            }
+        }
+    }
+
+    fn remove_field(&mut self, key: &str) -> Option<Item> {
+        let i = field_index(self, key)?;
+
+        self.shift_remove_index(i).map(|(_, v)| v)
+    }
+
+    fn into_fields(self) -> indexmap::map::IntoIter<String, Item> {
+        self.into_iter()
+    }
+}
+
+/// Whether two paragraphs have the same fields in the same order
+///
+/// `==` on a [`Paragraph`] ignores the order of the fields, like deb822
+/// does.
+///
+/// ```rust
+/// use eight_deep_parser::{eq_in_order, parse_one};
+///
+/// let a = parse_one("Package: a\nVersion: 1\n").unwrap();
+/// let b = parse_one("Version: 1\nPackage: a\n").unwrap();
+///
+/// assert_eq!(a, b);
+/// assert!(!eq_in_order(&a, &b));
+/// ```
+pub fn eq_in_order(a: &Paragraph, b: &Paragraph) -> bool {
+    a.iter().eq(b.iter())
+}
+
+fn field_index(p: &Paragraph, key: &str) -> Option<usize> {
+    p.get_index_of(key)
+        .or_else(|| p.keys().position(|k| k.eq_ignore_ascii_case(key)))
+}
+
+/// Rename the field `from` to `to` without moving it
+///
+/// Returns `false` if there is no field `from`. An existing field `to` is
+/// replaced.
+///
+/// ```rust
+/// use eight_deep_parser::{parse_one, rename_field};
+///
+/// let mut r = parse_one("Package: a\nSize: 1\nVersion: 2\n").unwrap();
+///
+/// assert!(rename_field(&mut r, "Size", "Installed-Size"));
+/// assert_eq!(r.get_index_of("Installed-Size"), Some(1));
+/// ```
+pub fn rename_field(p: &mut Paragraph, from: &str, to: &str) -> bool {
+    let mut index = match p.get_index_of(from) {
+        Some(index) => index,
+        None => return false,
+    };
+
+    if from == to {
+        return true;
+    }
+
+    if let Some((i, _, _)) = p.shift_remove_full(to) {
+        if i < index {
+            index -= 1;
+        }
+    }
+
+    let (_, v) = p.shift_remove_index(index).unwrap();
+    p.insert(to.to_string(), v);
+    p.move_index(p.len() - 1, index);
+
+    true
+}
+
+/// Remove the field `key` from every paragraph of `ps`, ignoring case,
+/// keeping the order of the other fields
+///
+/// Returns the number of fields removed; a paragraph with both `Maintainer`
+/// and `maintainer` loses both.
+///
+/// ```rust
+/// use eight_deep_parser::{parse_multi, strip_field};
+///
+/// let mut r = parse_multi("Package: a\nMaintainer: x\n\nPackage: b\nmaintainer: y\n").unwrap();
+///
+/// assert_eq!(strip_field(&mut r, "Maintainer"), 2);
+/// assert!(r.iter().all(|x| x.len() == 1));
+/// ```
+pub fn strip_field(ps: &mut [IndexMap<String, Item>], key: &str) -> usize {
+    let mut removed = 0;
+
+    for p in ps {
+        let len = p.len();
+        p.retain(|k, _| !k.eq_ignore_ascii_case(key));
+        removed += len - p.len();
+    }
+
+    removed
+}
+
+/// Every field name used in `ps`, in the order first seen
+///
+/// Names are compared exactly, so `Depends` and `depends` are both listed.
+///
+/// ```rust
+/// use eight_deep_parser::{field_names, parse_multi};
+///
+/// let r = parse_multi("Package: a\nVersion: 1\n\nPackage: b\nDepends: a\n").unwrap();
+///
+/// assert_eq!(
+///     field_names(&r).into_iter().collect::<Vec<_>>(),
+///     vec!["Package", "Version", "Depends"]
+/// );
+/// ```
+pub fn field_names(ps: &[IndexMap<String, Item>]) -> IndexSet<String> {
+    let mut result = IndexSet::new();
+
+    for p in ps {
+        for k in p.keys() {
+            if !result.contains(k) {
+                result.insert(k.clone());
+            }
+        }
+    }
+
+    result
+}
+
+/// Index of the first paragraph in `ps` whose field `key` has the one-line
+/// value `value`
+///
+/// The name is matched ignoring case like [`ParagraphExt::get_field`], the
+/// value exactly.
+///
+/// ```rust
+/// use eight_deep_parser::{parse_multi, position_by_field};
+///
+/// let r = parse_multi("Package: a\n\nPackage: b\n\nPackage: b\n").unwrap();
+///
+/// assert_eq!(position_by_field(&r, "package", "b"), Some(1));
+/// assert_eq!(position_by_field(&r, "Package", "B"), None);
+/// ```
+pub fn position_by_field(ps: &[Paragraph], key: &str, value: &str) -> Option<usize> {
+    ps.iter().position(|x| x.get_one(key) == Some(value))
+}
+
+/// Sort `ps` by the one-line value of the field `key`, compared as text
+///
+/// The name is matched ignoring case like [`ParagraphExt::get_field`].
+/// Paragraphs without the field come last. The sort is stable.
+///
+/// ```rust
+/// use eight_deep_parser::{parse_multi, sort_by_field, ParagraphExt};
+///
+/// let mut r = parse_multi("Package: b\n\nSource: c\n\nPackage: a\n").unwrap();
+/// sort_by_field(&mut r, "package");
+///
+/// assert_eq!(r[0].get_one("Package"), Some("a"));
+/// assert_eq!(r[2].get_one("Source"), Some("c"));
+/// ```
+pub fn sort_by_field(ps: &mut [Paragraph], key: &str) {
+    sort_by_field_with(ps, key, false)
+}
+
+/// Like [`sort_by_field`], comparing values as Debian versions if
+/// `versions` is set, so that `1.0~rc1` comes before `1.0`
+///
+/// Values that aren't valid versions come last, like missing ones.
+pub fn sort_by_field_with(ps: &mut [Paragraph], key: &str, versions: bool) {
+    if versions {
+        ps.sort_by_cached_key(|p| {
+            let v = p.get_one(key).and_then(|x| x.parse::<Version>().ok());
+            (v.is_none(), v)
+        });
+    } else {
+        ps.sort_by_cached_key(|p| {
+            let v = p.get_one(key).map(|x| x.to_string());
+            (v.is_none(), v)
+        });
+    }
+}
+
+/// The field names of a paragraph, for checking many names at once
+///
+/// Matching is case-insensitive like [`ParagraphExt::get_field`], but
+/// without scanning the paragraph for every name.
+///
+/// ```rust
+/// use eight_deep_parser::{parse_one, FieldSet};
+///
+/// let r = parse_one("Package: a\nVersion: 1\n").unwrap();
+/// let fields = FieldSet::new(&r);
+///
+/// assert!(fields.contains("version"));
+/// assert!(!fields.contains("Architecture"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FieldSet {
+    keys: HashSet<String>,
+}
+
+impl FieldSet {
+    pub fn new(p: &Paragraph) -> Self {
+        Self {
+            keys: p.keys().map(|x| x.to_ascii_lowercase()).collect(),
+        }
+    }
+
+    /// Whether the paragraph has the field `key`, ignoring case
+    pub fn contains(&self, key: &str) -> bool {
+        if key.bytes().any(|x| x.is_ascii_uppercase()) {
+            self.keys.contains(&key.to_ascii_lowercase())
+        } else {
+            self.keys.contains(key)
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        field_names, parse_multi, parse_one, position_by_field, rename_field, sort_by_field,
+        sort_by_field_with, strip_field, test_util::fixtures, FieldSet, Item, ParagraphExt,
+    };
+
+    #[test]
+    fn test_get_one_or() {
+        let r =
+            parse_one("Package: a\nEmpty:\nDescription: b\n c\nConffiles:\n /etc/a 1\n").unwrap();
+
+        assert_eq!(r.get_one_or("Package", "x"), "a");
+        assert_eq!(r.get_one_or("PACKAGE", "x"), "a");
+        assert_eq!(r.get_one_or("Version", "x"), "x");
+        assert_eq!(r.get_one_or("Description", "x"), "x");
+        assert_eq!(r.get_one_or("Conffiles", "x"), "x");
+        assert_eq!(r.get_one_or("Empty", "x"), "x");
+    }
+
+    #[test]
+    fn test_rename_field() {
+        let mut r = parse_one("A: 1\nB: 2\nC: 3\n").unwrap();
+
+        assert!(rename_field(&mut r, "B", "D"));
+        assert_eq!(r.keys().collect::<Vec<_>>(), vec!["A", "D", "C"]);
+        assert_eq!(r.get("D").unwrap(), &Item::OneLine("2".to_string()));
+
+        assert!(!rename_field(&mut r, "B", "E"));
+        assert_eq!(r.keys().collect::<Vec<_>>(), vec!["A", "D", "C"]);
+    }
+
+    #[test]
+    fn test_rename_field_replaces_existing() {
+        let mut r = parse_one("A: 1\nB: 2\nC: 3\n").unwrap();
+
+        assert!(rename_field(&mut r, "C", "A"));
+        assert_eq!(r.keys().collect::<Vec<_>>(), vec!["B", "A"]);
+        assert_eq!(r.get("A").unwrap(), &Item::OneLine("3".to_string()));
+    }
+
+    #[test]
+    fn test_strip_field() {
+        let mut r = parse_multi(
+            "Package: a\nMaintainer: x <x@example.org>\nVersion: 1\n\nPackage: b\n\nMAINTAINER: y\nmaintainer: z\nPackage: c\n",
+        )
+        .unwrap();
+
+        assert_eq!(strip_field(&mut r, "maintainer"), 3);
+        assert!(r
+            .iter()
+            .all(|p| p.keys().all(|k| !k.eq_ignore_ascii_case("Maintainer"))));
+        assert_eq!(r[0].keys().collect::<Vec<_>>(), ["Package", "Version"]);
+        assert_eq!(r[2].keys().collect::<Vec<_>>(), ["Package"]);
+        assert_eq!(strip_field(&mut r, "Maintainer"), 0);
+    }
+
+    #[test]
+    fn test_into_fields() {
+        let r = parse_multi(fixtures::PACKAGES).unwrap().remove(0);
+        // where the names and the first line of the values are on the heap
+        let before = r
+            .iter()
+            .map(|(k, v)| (k.clone(), k.as_ptr(), v.lines()[0].as_ptr()))
+            .collect::<Vec<_>>();
+
+        let moved = r.into_fields().collect::<Vec<_>>();
+
+        assert_eq!(moved.len(), before.len());
+        for ((k, v), (key, key_ptr, value_ptr)) in moved.iter().zip(&before) {
+            assert_eq!(k, key);
+            assert_eq!(k.as_ptr(), *key_ptr);
+            assert_eq!(v.lines()[0].as_ptr(), *value_ptr);
+        }
+    }
+
+    #[test]
+    fn test_field_set() {
+        let r = parse_one("Package: bash\nPRE-DEPENDS: libc6\nmulti-arch: foreign\n").unwrap();
+        let fields = FieldSet::new(&r);
+
+        assert_eq!(fields.len(), 3);
+
+        for key in [
+            "package",
+            "Package",
+            "Pre-Depends",
+            "pre-depends",
+            "Multi-Arch",
+        ] {
+            assert!(fields.contains(key), "{}", key);
+        }
+
+        for key in ["Depends", "Package ", ""] {
+            assert!(!fields.contains(key), "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_field_names() {
+        let r = parse_multi(
+            "Package: a\nVersion: 1\nDepends: b\n\nPackage: b\nVersion: 2\nProvides: c\n\nSource: d\nPackage: d\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            field_names(&r).into_iter().collect::<Vec<_>>(),
+            vec!["Package", "Version", "Depends", "Provides", "Source"]
+        );
+        assert!(field_names(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_position_by_field() {
+        let ps = parse_multi(fixtures::STATUS_STATES).unwrap();
+        let first = ps
+            .iter()
+            .position(|x| x.get_one("Package") == Some("libc6"))
+            .unwrap();
+
+        assert!(ps[first + 1..]
+            .iter()
+            .any(|x| x.get_one("Package") == Some("libc6")));
+        assert_eq!(position_by_field(&ps, "PACKAGE", "libc6"), Some(first));
+        assert_eq!(position_by_field(&ps, "Package", "LIBC6"), None);
+        assert_eq!(position_by_field(&ps, "Nonexistent", "libc6"), None);
+        assert_eq!(position_by_field(&[], "Package", "libc6"), None);
+    }
+
+    #[test]
+    fn test_sort_by_field() {
+        let mut ps = fixtures::packages();
+        ps.extend(parse_multi("Source: x\n\nPackage: aaa\nVersion: 1\n").unwrap());
+        sort_by_field(&mut ps, "PACKAGE");
+
+        assert_eq!(
+            ps.iter().map(|x| x.get_one("Package")).collect::<Vec<_>>(),
+            [
+                Some("aaa"),
+                Some("base-files"),
+                Some("bash"),
+                Some("hello"),
+                Some("zlib1g"),
+                None
+            ]
+        );
+
+        let mut ps = parse_multi(
+            "Version: 1.0\n\nVersion: 1:0.1\n\nVersion: 1.0~rc1\n\nVersion: 1.0-0\n\nVersion: x:1\n\nPackage: a\n\nVersion: 0.9\n",
+        )
+        .unwrap();
+        let versions = |ps: &[crate::Paragraph]| {
+            ps.iter()
+                .map(|x| x.get_one("Version").unwrap_or("-").to_string())
+                .collect::<Vec<_>>()
+        };
+
+        sort_by_field_with(&mut ps, "Version", true);
+
+        assert_eq!(
+            versions(&ps),
+            ["0.9", "1.0~rc1", "1.0", "1.0-0", "1:0.1", "x:1", "-"]
+        );
+
+        sort_by_field(&mut ps, "Version");
+
+        assert_eq!(
+            versions(&ps),
+            ["0.9", "1.0", "1.0-0", "1.0~rc1", "1:0.1", "x:1", "-"]
+        );
+    }
+}
+
+/// The field order contract of [`Paragraph`]: every operation that touches
+/// a paragraph is checked to keep the order it promises
+#[cfg(test)]
+mod order_tests {
+    use crate::{
+        normalize_keys, parse_back, parse_multi, parse_multi_with, parse_one, rename_field,
+        strip_field, test_util::fixtures, transform, Cst, DuplicateKeys, Item, KeyStyle, Paragraph,
+        ParagraphExt, ParseOptions,
+    };
+
+    fn keys(p: &Paragraph) -> Vec<&str> {
+        p.keys().map(|x| x.as_str()).collect()
+    }
+
+    fn one(s: &str) -> Item {
+        Item::OneLine(s.to_string())
+    }
+
+    /// Field names in the order of the input, paragraph by paragraph
+    fn source_keys(s: &str) -> Vec<Vec<&str>> {
+        s.split("\n\n")
+            .filter(|x| !x.trim().is_empty())
+            .map(|x| {
+                x.lines()
+                    .filter(|x| !x.starts_with([' ', '\t', '#']))
+                    .filter_map(|x| Some(x.split_once(':')?.0))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_keeps_source_order() {
+        for s in [
+            fixtures::STATUS,
+            fixtures::PACKAGES,
+            fixtures::SOURCES,
+            fixtures::RELEASE,
+        ] {
+            let expected = source_keys(s);
+
+            for opts in [ParseOptions::default(), ParseOptions::dpkg_status()] {
+                let r = parse_multi_with(s, &opts).unwrap();
+                assert_eq!(r.iter().map(keys).collect::<Vec<_>>(), expected);
+            }
+
+            let cst = Cst::parse(s).paragraphs(&ParseOptions::default()).unwrap();
+            assert_eq!(cst.iter().map(keys).collect::<Vec<_>>(), expected);
+        }
+
+        for duplicates in [DuplicateKeys::LastWins, DuplicateKeys::Concat] {
+            let opts = ParseOptions {
+                duplicates,
+                ..Default::default()
+            };
+            let r = parse_multi_with("A: 1\nB: 2\nA: 3\nC: 4\n", &opts).unwrap();
+
+            assert_eq!(keys(&r[0]), ["A", "B", "C"], "{:?}", duplicates);
+        }
+    }
+
+    #[test]
+    fn test_insert_and_set() {
+        let mut p = parse_one("Package: a\nversion: 1\nSection: misc\n").unwrap();
+
+        p.insert("Priority".to_string(), one("optional"));
+        assert_eq!(keys(&p), ["Package", "version", "Section", "Priority"]);
+
+        p.insert("Section".to_string(), one("devel"));
+        assert_eq!(keys(&p), ["Package", "version", "Section", "Priority"]);
+
+        assert_eq!(p.set_field("VERSION", one("2")), Some(one("1")));
+        assert_eq!(keys(&p), ["Package", "version", "Section", "Priority"]);
+        assert_eq!(p["version"], one("2"));
+
+        assert_eq!(p.set_field("Depends", one("b")), None);
+        assert_eq!(
+            keys(&p),
+            ["Package", "version", "Section", "Priority", "Depends"]
+        );
+    }
+
+    #[test]
+    fn test_remove_and_reinsert() {
+        let mut p = parse_one("A: 1\nB: 2\nC: 3\nD: 4\n").unwrap();
+
+        assert_eq!(p.remove_field("b"), Some(one("2")));
+        assert_eq!(keys(&p), ["A", "C", "D"]);
+        assert_eq!(p.remove_field("b"), None);
+
+        p.set_field("B", one("2"));
+        assert_eq!(keys(&p), ["A", "C", "D", "B"]);
+
+        p.shift_remove("A");
+        p.insert("A".to_string(), one("1"));
+        assert_eq!(keys(&p), ["C", "D", "B", "A"]);
+    }
+
+    #[test]
+    fn test_rename_normalize_strip() {
+        let mut ps = parse_multi("package: a\ndepends: b\nx-custom: c\nmaintainer: d\n").unwrap();
+
+        assert!(rename_field(&mut ps[0], "depends", "Pre-Depends"));
+        assert_eq!(
+            keys(&ps[0]),
+            ["package", "Pre-Depends", "x-custom", "maintainer"]
+        );
+
+        normalize_keys(&mut ps[0], KeyStyle::TrainCase);
+        assert_eq!(
+            keys(&ps[0]),
+            ["Package", "Pre-Depends", "X-Custom", "Maintainer"]
+        );
+
+        strip_field(&mut ps, "pre-depends");
+        assert_eq!(keys(&ps[0]), ["Package", "X-Custom", "Maintainer"]);
+    }
+
+    #[test]
+    fn test_serialize_in_map_order() {
+        let mut p = parse_one("Package: a\nVersion: 1\nDepends: b,\n c\n").unwrap();
+        p.set_field("version", one("2"));
+        p.remove_field("Package");
+        p.insert("Package".to_string(), one("a"));
+        p.move_index(1, 0);
+
+        let s = parse_back(std::slice::from_ref(&p));
+        assert_eq!(s, "Depends: b,\n c\nVersion: 2\nPackage: a\n");
+        assert_eq!(source_keys(&s), [keys(&p)]);
+
+        let back = parse_one(&s).unwrap();
+        assert_eq!(keys(&back), keys(&p));
+
+        let mut out = vec![];
+        transform(s.as_bytes(), &mut out, Some).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), s);
+    }
+}
@@ -1,37 +1,32 @@
 use nom::{
-    bytes::complete::{tag, take_until},
-    character::complete::{char, multispace0, space0},
-    combinator::{map, verify},
+    branch::alt,
+    bytes::complete::{tag, take_till},
+    character::complete::{char, one_of, space0},
+    combinator::{eof, map, opt, peek, recognize, verify},
     multi::{many0, many1},
-    sequence::{delimited, preceded, separated_pair, terminated, tuple},
+    sequence::{pair, preceded, separated_pair, terminated, tuple},
     IResult,
 };
 
-type KeyValueResult<'a> = IResult<&'a [u8], (&'a [u8], (&'a [u8], Vec<u8>))>;
-type SinglePackageResult<'a> = IResult<&'a [u8], Vec<(&'a [u8], (&'a [u8], Vec<u8>))>>;
-type MultiPackageResult<'a> = IResult<&'a [u8], Vec<Vec<(&'a [u8], (&'a [u8], Vec<u8>))>>>;
-
-#[inline]
-fn key_name(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    verify(handle_key, |input: &[u8]| {
-        if !input.is_empty() {
-            input[0] != b'\n'
-        } else {
-            false
-        }
-    })(input)
-}
-
+/// A field value: the text after the colon on the field line, and the
+/// continuation lines joined by `\n`, each with its leading whitespace
+pub type ValueFieldResult<'a> = IResult<&'a [u8], (&'a [u8], Vec<u8>)>;
+/// A field name and its value, see [`ValueFieldResult`]
+pub type KeyValueResult<'a> = IResult<&'a [u8], (&'a [u8], (&'a [u8], Vec<u8>))>;
+/// The fields of one paragraph, in order
+pub type SinglePackageResult<'a> = IResult<&'a [u8], Vec<(&'a [u8], (&'a [u8], Vec<u8>))>>;
+/// The fields of every paragraph, in order
+pub type MultiPackageResult<'a> = IResult<&'a [u8], Vec<Vec<(&'a [u8], (&'a [u8], Vec<u8>))>>>;
+
+/// A field name, up to but not including the colon. Fails on a line that
+/// starts with whitespace or has no colon.
 #[inline]
-fn handle_key(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    preceded(handle_key_name, take_until(":"))(input)
-}
-
-#[inline]
-fn handle_key_name(input: &[u8]) -> IResult<&[u8], ()> {
-    map(
-        many0(delimited(tag(" "), take_until("\n"), tag("\n"))),
-        |_| (),
+pub fn key_name(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    terminated(
+        verify(take_till(|c| c == b':' || c == b'\n'), |k: &[u8]| {
+            !k.is_empty() && !matches!(k[0], b' ' | b'\t')
+        }),
+        peek(char(':')),
     )(input)
 }
 
@@ -40,24 +35,46 @@ fn separator(input: &[u8]) -> IResult<&[u8], ()> {
     map(tuple((char(':'), space0)), |_| ())(input)
 }
 
+/// A whole field: name, colon and value including continuation lines
 #[inline]
-fn key_value(input: &[u8]) -> KeyValueResult {
+pub fn key_value(input: &[u8]) -> KeyValueResult<'_> {
     separated_pair(key_name, separator, value_field)(input)
 }
 
+/// A field name and the text after the colon on the field line, leaving the
+/// continuation lines in the input
+#[inline]
+pub(crate) fn field_line(input: &[u8]) -> IResult<&[u8], (&[u8], &[u8])> {
+    separated_pair(key_name, separator, single_line)(input)
+}
+
+/// A field value after the colon and its following spaces, see
+/// [`ValueFieldResult`]
 #[inline]
-fn value_field(input: &[u8]) -> IResult<&[u8], (&[u8], Vec<u8>)> {
+pub fn value_field(input: &[u8]) -> ValueFieldResult<'_> {
     tuple((single_line, multi_to_one))(input)
 }
 
+/// The rest of a line, ended by a line break or by the end of the input
 #[inline]
 fn single_line(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    terminated(take_until("\n"), tag("\n"))(input)
+    terminated(take_till(|c| c == b'\n'), line_end)(input)
 }
 
+/// A line break, or the end of the input for a last line without one
 #[inline]
-fn multi_line_single(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    delimited(tag(" "), take_until("\n"), tag("\n"))(input)
+fn line_end(input: &[u8]) -> IResult<&[u8], ()> {
+    alt((map(tag("\n"), |_| ()), map(eof, |_| ())))(input)
+}
+
+/// A continuation line, returned with its leading whitespace: stripping it
+/// is up to [`ContinuationWhitespace`](crate::ContinuationWhitespace)
+#[inline]
+pub(crate) fn multi_line_single(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    terminated(
+        recognize(pair(one_of(" \t"), take_till(|c| c == b'\n'))),
+        line_end,
+    )(input)
 }
 
 #[inline]
@@ -65,30 +82,61 @@ fn multi_line(input: &[u8]) -> IResult<&[u8], Vec<&[u8]>> {
     many0(multi_line_single)(input)
 }
 
+/// The continuation lines joined by `\n`, checked for UTF-8 later with the
+/// rest of the value
 fn multi_to_one(input: &[u8]) -> IResult<&[u8], Vec<u8>> {
-    let ctx = multi_line(input)?;
-
-    let mut s = String::new();
-    for (i, c) in ctx.1.iter().enumerate() {
-        s += std::str::from_utf8(c).unwrap();
-        if i != ctx.1.len() - 1 {
-            s += "\n";
+    let (input, ctx) = multi_line(input)?;
+
+    let len = ctx
+        .iter()
+        .map(|x| x.len() + 1)
+        .sum::<usize>()
+        .saturating_sub(1);
+    let mut v = Vec::with_capacity(len);
+
+    for (i, c) in ctx.iter().enumerate() {
+        if i != 0 {
+            v.push(b'\n');
         }
+        v.extend_from_slice(c);
     }
 
-    let s = s.as_bytes().to_vec();
+    Ok((input, v))
+}
 
-    Ok((input, s))
+/// A line holding nothing but spaces and tabs, and a carriage return
+/// before its line break
+#[inline]
+pub(crate) fn blank_line(input: &[u8]) -> IResult<&[u8], ()> {
+    map(tuple((space0, opt(char('\r')), char('\n'))), |_| ())(input)
 }
 
+/// One paragraph and the blank line terminating it, if any
 #[inline]
-pub fn single_package(input: &[u8]) -> SinglePackageResult {
-    terminated(many1(key_value), multispace0)(input)
+pub fn single_package(input: &[u8]) -> SinglePackageResult<'_> {
+    terminated(many1(key_value), opt(blank_line))(input)
 }
 
+/// Paragraphs separated by one or more blank lines
 #[inline]
-pub fn multi_package(input: &[u8]) -> MultiPackageResult {
-    many1(single_package)(input)
+pub fn multi_package(input: &[u8]) -> MultiPackageResult<'_> {
+    terminated(
+        many1(preceded(many0(blank_line), single_package)),
+        many0(blank_line),
+    )(input)
+}
+
+/// One paragraph after any number of blank lines
+#[inline]
+pub(crate) fn next_package(input: &[u8]) -> SinglePackageResult<'_> {
+    preceded(many0(blank_line), single_package)(input)
+}
+
+/// Like [`multi_package`], but every blank line that doesn't end a paragraph
+/// is an empty paragraph
+#[inline]
+pub fn multi_package_keep_empty(input: &[u8]) -> MultiPackageResult<'_> {
+    many1(alt((single_package, map(blank_line, |_| Vec::new()))))(input)
 }
 
 #[test]
@@ -110,10 +158,7 @@ fn test_value_field() {
     let test = b"\n a\n b\n c\n";
     let r = value_field(test);
 
-    assert_eq!(
-        r,
-        Ok((&b" a\n b\n c\n"[..], (&b""[..], b"a\nb\nc".to_vec())))
-    );
+    assert_eq!(r, Ok((&b""[..], (&b""[..], b" a\n b\n c".to_vec()))));
 }
 
 #[test]
@@ -121,7 +166,15 @@ fn test_multi_line() {
     let test = b" a\n b\n c\nD: E";
     let r = multi_line(test);
 
-    assert_eq!(r, Ok((&b"D: E"[..], vec![&b"a"[..], &b"b"[..], &b"c"[..]])))
+    assert_eq!(
+        r,
+        Ok((&b"D: E"[..], vec![&b" a"[..], &b" b"[..], &b" c"[..]]))
+    );
+
+    let test = b"   a\n\t b\n\nD: E";
+    let r = multi_line(test);
+
+    assert_eq!(r, Ok((&b"\nD: E"[..], vec![&b"   a"[..], &b"\t b"[..]])))
 }
 
 #[test]
@@ -130,16 +183,30 @@ fn test_multi_line_to_one() {
 
     let r = multi_to_one(test);
 
-    assert_eq!(r, Ok((&b" c\n d\n e\n"[..], b"c\nd\ne".to_vec())))
+    assert_eq!(r, Ok((&b""[..], b" c\n d\n e".to_vec())))
 }
 
 #[test]
-fn test_handle_key() {
-    let test = b" b\n c\nD: E";
+fn test_multi_line_to_one_invalid_utf8() {
+    let test = b" \xff\xfe\n b\n";
+
+    let r = multi_to_one(test);
 
-    let r = handle_key(test);
+    assert_eq!(r, Ok((&b""[..], b" \xff\xfe\n b".to_vec())))
+}
 
-    assert_eq!(r, Ok((&b": E"[..], &b"D"[..])))
+#[test]
+fn test_key_name_line_start() {
+    // a key never takes in the lines before it
+    for test in [
+        &b" b\n c\nD: E"[..],
+        b"broken\nD: E",
+        b"\nD: E",
+        b": E",
+        b"broken",
+    ] {
+        assert!(key_name(test).is_err(), "{:?}", test);
+    }
 }
 
 #[test]
@@ -167,7 +234,7 @@ fn test_key_value() {
 
     assert_eq!(
         r,
-        Ok((&b" d\n e\n"[..], (&b"c"[..], (&b""[..], b"d\ne".to_vec()))))
+        Ok((&b""[..], (&b"c"[..], (&b""[..], b" d\n e".to_vec()))))
     );
 }
 
@@ -183,9 +250,101 @@ fn test_single_package() {
             &b""[..],
             vec![
                 (&b"Package"[..], (&b"a"[..], b"".to_vec())),
-                (&b"Multi"[..], (&b""[..], b"a\nb\nc".to_vec())),
+                (&b"Multi"[..], (&b""[..], b" a\n b\n c".to_vec())),
                 (&b"D"[..], (&b"E"[..], b"".to_vec())),
             ]
         ))
     )
 }
+
+#[test]
+fn test_multi_package_trailing_multi_line() {
+    let test = b"Package: a\nMulti:\n a\n b\n\nPackage: b\n";
+
+    let r = multi_package(test);
+
+    assert_eq!(
+        r,
+        Ok((
+            &b""[..],
+            vec![
+                vec![
+                    (&b"Package"[..], (&b"a"[..], b"".to_vec())),
+                    (&b"Multi"[..], (&b""[..], b" a\n b".to_vec())),
+                ],
+                vec![(&b"Package"[..], (&b"b"[..], b"".to_vec()))],
+            ]
+        ))
+    )
+}
+
+#[test]
+fn test_single_package_terminator() {
+    let test = b"Package: a\n\n\nPackage: b\n";
+
+    let r = single_package(test);
+
+    assert_eq!(
+        r,
+        Ok((
+            &b"\nPackage: b\n"[..],
+            vec![(&b"Package"[..], (&b"a"[..], b"".to_vec()))]
+        ))
+    )
+}
+
+#[test]
+fn test_multi_package_blank_lines() {
+    for sep in ["\n", "\n\n", "\n\n\n\n", "\n\t\n\n", "\r\n"] {
+        let test = format!(
+            "\nPackage: a\nA: 1\n{}Package: b\n{}Package: c\n\n",
+            sep, sep
+        );
+
+        let (rest, r) = multi_package(test.as_bytes()).unwrap();
+
+        assert_eq!(rest, b"");
+        assert_eq!(
+            r.iter().map(|x| x.len()).collect::<Vec<_>>(),
+            vec![2, 1, 1],
+            "{:?}",
+            sep
+        );
+        assert_eq!(r[2][0], (&b"Package"[..], (&b"c"[..], b"".to_vec())));
+    }
+}
+
+#[test]
+fn test_multi_package_keep_empty() {
+    let test = b"\nPackage: a\n\n\nPackage: b\n\n";
+
+    let (rest, r) = multi_package_keep_empty(test).unwrap();
+
+    assert_eq!(rest, b"");
+    assert_eq!(
+        r.iter().map(|x| x.len()).collect::<Vec<_>>(),
+        vec![0, 1, 0, 1]
+    );
+}
+
+#[test]
+fn test_multi_package_no_final_newline() {
+    for test in [
+        &b"Package: a\n\nPackage: b"[..],
+        b"Package: a\n\nPackage: b\n",
+    ] {
+        let (rest, r) = multi_package(test).unwrap();
+
+        assert_eq!(rest, b"");
+        assert_eq!(r.len(), 2);
+        assert_eq!(r[1], vec![(&b"Package"[..], (&b"b"[..], b"".to_vec()))]);
+    }
+
+    let (rest, r) = multi_package(b"Package: a\nDepends:\n x\n\nDepends:\n y,\n z").unwrap();
+
+    assert_eq!(rest, b"");
+    assert_eq!(
+        r[1],
+        vec![(&b"Depends"[..], (&b""[..], b" y,\n z".to_vec()))]
+    );
+}
@@ -0,0 +1,150 @@
+use std::{
+    borrow::Borrow,
+    fmt::Display,
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
+
+use crate::{error::Result, parse_multi, Paragraph};
+
+/// Where a paragraph was parsed from, e.g. the path of a list file
+///
+/// Cloning is cheap, so every paragraph of a source can carry one.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Origin(Arc<str>);
+
+impl Origin {
+    pub fn new(name: impl Into<Arc<str>>) -> Self {
+        Self(name.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for Origin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A paragraph with the [`Origin`] it was parsed from and its position
+/// there
+///
+/// It derefs to the [`Paragraph`], and functions taking paragraphs as
+/// `Borrow<Paragraph>`, like [`index_by`](crate::index_by) and
+/// [`merge_by`](crate::merge_by), take it as well and keep the tags. To
+/// write it back, take the `paragraph`: tags aren't serialized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaggedParagraph {
+    pub origin: Origin,
+    pub paragraph: Paragraph,
+    /// Position of the paragraph in its source
+    pub index_in_source: usize,
+}
+
+impl Deref for TaggedParagraph {
+    type Target = Paragraph;
+
+    fn deref(&self) -> &Self::Target {
+        &self.paragraph
+    }
+}
+
+impl DerefMut for TaggedParagraph {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.paragraph
+    }
+}
+
+impl Borrow<Paragraph> for TaggedParagraph {
+    fn borrow(&self) -> &Paragraph {
+        &self.paragraph
+    }
+}
+
+/// Parse multi package like [`parse_multi`], tagging every paragraph with
+/// `origin`
+///
+/// ```rust
+/// use eight_deep_parser::{parse_multi_tagged, Origin, ParagraphExt};
+///
+/// let r = parse_multi_tagged("Package: a\n\nPackage: b\n", Origin::new("main_Packages")).unwrap();
+///
+/// assert_eq!(r[1].get_one("Package"), Some("b"));
+/// assert_eq!(r[1].origin.as_str(), "main_Packages");
+/// assert_eq!(r[1].index_in_source, 1);
+/// ```
+pub fn parse_multi_tagged(s: &str, origin: Origin) -> Result<Vec<TaggedParagraph>> {
+    Ok(parse_multi(s)?
+        .into_iter()
+        .enumerate()
+        .map(|(index_in_source, paragraph)| TaggedParagraph {
+            origin: origin.clone(),
+            paragraph,
+            index_in_source,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        index_by, merge_by, parse_back, parse_multi_tagged, KeyBy, Origin, PackageId, ParagraphExt,
+    };
+
+    const MAIN: &str = "Package: openssl
+Version: 3.0.15-1~deb12u1
+
+Package: bash
+Version: 5.2.15-2+b7
+
+Package: curl
+Version: 7.88.1-10+deb12u8
+";
+
+    const SECURITY: &str = "Package: curl
+Version: 7.88.1-10+deb12u12
+
+Package: openssl
+Version: 3.0.16-1~deb12u1
+";
+
+    #[test]
+    fn test_merge_tagged() {
+        let mut all = parse_multi_tagged(MAIN, Origin::new("bookworm/main")).unwrap();
+        all.extend(parse_multi_tagged(SECURITY, Origin::new("bookworm-security/main")).unwrap());
+
+        let merged = merge_by(all, KeyBy::Package);
+
+        assert_eq!(
+            merged
+                .iter()
+                .map(|x| (
+                    x.get_one("Package").unwrap(),
+                    x.origin.as_str(),
+                    x.index_in_source
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("openssl", "bookworm-security/main", 1),
+                ("bash", "bookworm/main", 1),
+                ("curl", "bookworm-security/main", 0),
+            ]
+        );
+        assert_eq!(merged[0].get_one("Version"), Some("3.0.16-1~deb12u1"));
+
+        let index = index_by(&merged, KeyBy::Package);
+
+        assert_eq!(
+            merged[index[&PackageId::new("curl", None)][0]].origin,
+            Origin::new("bookworm-security/main")
+        );
+
+        let s = parse_back(&merged.into_iter().map(|x| x.paragraph).collect::<Vec<_>>());
+
+        assert!(s.starts_with("Package: openssl\nVersion: 3.0.16-1~deb12u1\n\n"));
+        assert!(!s.contains("bookworm"));
+    }
+}
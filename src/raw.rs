@@ -0,0 +1,74 @@
+//! The nom combinators the parser is built from
+//!
+//! Use these to embed deb822 paragraphs in a larger format by composing them
+//! with your own nom parsers. They work on bytes and return raw slices: no
+//! UTF-8 checking, and continuation lines keep their leading whitespace.
+//!
+//! This module follows the grammar as it evolves and isn't covered by the
+//! semver guarantees of the rest of the crate.
+
+pub use crate::parser::{
+    key_name, key_value, multi_package, single_package, value_field, KeyValueResult,
+    MultiPackageResult, SinglePackageResult, ValueFieldResult,
+};
+
+#[cfg(test)]
+mod tests {
+    use nom::{
+        branch::alt,
+        bytes::complete::{tag, take_until},
+        combinator::map,
+        multi::many1,
+        sequence::delimited,
+        IResult,
+    };
+
+    use super::{key_value, single_package};
+
+    #[derive(Debug, PartialEq, Eq)]
+    enum Block<'a> {
+        Marker(&'a [u8]),
+        Stanza(Vec<&'a [u8]>),
+    }
+
+    /// deb822 with `%% name` marker lines between the stanzas
+    fn extended(input: &[u8]) -> IResult<&[u8], Vec<Block<'_>>> {
+        many1(alt((
+            map(
+                delimited(tag("%% "), take_until("\n"), tag("\n")),
+                Block::Marker,
+            ),
+            map(single_package, |x| {
+                Block::Stanza(x.into_iter().map(|(k, _)| k).collect())
+            }),
+        )))(input)
+    }
+
+    #[test]
+    fn test_extension_parser() {
+        let test = b"%% first\nPackage: a\nDepends:\n b\n\n%% second\nPackage: c\nVersion: 1\n";
+
+        let (rest, r) = extended(test).unwrap();
+
+        assert_eq!(rest, b"");
+        assert_eq!(
+            r,
+            vec![
+                Block::Marker(b"first"),
+                Block::Stanza(vec![b"Package", b"Depends"]),
+                Block::Marker(b"second"),
+                Block::Stanza(vec![b"Package", b"Version"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_key_value() {
+        let (rest, (k, (one, multi))) = key_value(b"Multi: a\n b\nNext: c\n").unwrap();
+
+        assert_eq!(rest, b"Next: c\n");
+        assert_eq!(k, b"Multi");
+        assert_eq!(one, b"a");
+        assert_eq!(multi, b" b");
+    }
+}
@@ -0,0 +1,245 @@
+use std::ops::Range;
+
+use crate::{incremental::paragraph_spans, parse_one_with, Paragraph, ParseOptions};
+
+/// Something [`parse_multi_recover`] skipped to go on parsing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Diagnostic {
+    /// Bytes that don't parse as paragraphs, from the first line of the
+    /// paragraph they are in to the end of their last line
+    GarbageSkipped { offset: usize, len: usize },
+}
+
+/// Parse multi package, skipping input that doesn't parse, like binary
+/// garbage spliced into a corrupted download
+///
+/// Paragraphs are split like [`ParagraphDecoder`](crate::ParagraphDecoder)
+/// does and parsed one by one with [`parse_one_with`] and `opts`. On a
+/// paragraph that isn't UTF-8 or doesn't parse, the parser resynchronizes:
+/// it skips to the next paragraph that starts, after a blank line, with a
+/// plausible field line, `^[A-Za-z0-9][A-Za-z0-9-]*:` followed by
+/// whitespace, and that parses. What was skipped is recorded as a
+/// [`Diagnostic::GarbageSkipped`].
+///
+/// A line that is neither a field, a continuation line nor blank, like one
+/// without a colon, is garbage too. A paragraph the garbage is in is skipped with it. Within the
+/// paragraphs that parse, the result is that of
+/// [`parse_multi_with`](crate::parse_multi_with), without the empty
+/// paragraphs of [`ParseOptions::keep_empty`].
+///
+/// ```rust
+/// use eight_deep_parser::{parse_multi_recover, Diagnostic, ParagraphExt, ParseOptions};
+///
+/// let s = b"Package: a\n\n\x1f\x8b\x08\x00\xde\xad\n\nPackage: b\n";
+/// let (r, diagnostics) = parse_multi_recover(s, &ParseOptions::default());
+///
+/// assert_eq!(r[1].get_one("Package"), Some("b"));
+/// assert_eq!(diagnostics, [Diagnostic::GarbageSkipped { offset: 12, len: 7 }]);
+/// ```
+pub fn parse_multi_recover(s: &[u8], opts: &ParseOptions) -> (Vec<Paragraph>, Vec<Diagnostic>) {
+    let mut paragraphs = vec![];
+    let mut diagnostics = vec![];
+    let mut garbage: Option<Range<usize>> = None;
+
+    for span in paragraph_spans(s) {
+        let text = &s[span.clone()];
+        let p = if garbage.is_some() && !plausible_start(text) {
+            None
+        } else {
+            std::str::from_utf8(text)
+                .ok()
+                .and_then(|x| parse_one_with(x, opts).ok())
+        };
+
+        match p {
+            Some(p) => {
+                diagnostics.extend(garbage.take().map(skipped));
+                paragraphs.push(p);
+            }
+            None => garbage.get_or_insert(span.clone()).end = span.end,
+        }
+    }
+
+    diagnostics.extend(garbage.map(skipped));
+
+    (paragraphs, diagnostics)
+}
+
+fn skipped(span: Range<usize>) -> Diagnostic {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(offset = span.start, len = span.len(), "skipped garbage");
+
+    Diagnostic::GarbageSkipped {
+        offset: span.start,
+        len: span.len(),
+    }
+}
+
+/// Whether the first line of `text` looks like the first field of a
+/// paragraph
+pub(crate) fn plausible_start(text: &[u8]) -> bool {
+    let name = text
+        .iter()
+        .take_while(|x| x.is_ascii_alphanumeric() || **x == b'-')
+        .count();
+
+    name > 0
+        && text[0] != b'-'
+        && matches!(
+            &text[name..],
+            [b':'] | [b':', b' ' | b'\t' | b'\r' | b'\n', ..]
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        arbitrary::Gen, parse_multi, parse_multi_recover, parse_multi_with, test_util::fixtures,
+        Diagnostic, ParagraphExt, ParseOptions, TrailingData,
+    };
+
+    /// A few KB of something like gzip data: its magic, then random bytes
+    fn garbage(g: &mut Gen) -> Vec<u8> {
+        let mut v = b"\x1f\x8b\x08\x00".to_vec();
+        v.extend((0..4096).map(|_| g.below(256) as u8));
+        v.push(0xff);
+
+        v
+    }
+
+    fn packages(r: &[crate::Paragraph]) -> Vec<&str> {
+        r.iter().map(|x| x.get_one("Package").unwrap()).collect()
+    }
+
+    #[test]
+    fn test_parse_multi_recover() {
+        let mut g = Gen::new(0x8d_9a7b);
+        let clean = fixtures::PACKAGES.as_bytes();
+        let full = parse_multi(fixtures::PACKAGES).unwrap();
+        let opts = ParseOptions::default();
+
+        assert_eq!(parse_multi_recover(clean, &opts), (full.clone(), vec![]));
+
+        // at the start, followed by a blank line
+        let junk = garbage(&mut g);
+        let s = [&junk[..], b"\n\n", clean].concat();
+        let (r, diagnostics) = parse_multi_recover(&s, &opts);
+
+        assert_eq!(r, full);
+        assert_eq!(
+            diagnostics,
+            [Diagnostic::GarbageSkipped {
+                offset: 0,
+                len: junk.len() + 1
+            }]
+        );
+
+        // in the middle of the second paragraph, which is lost with it
+        let second = fixtures::PACKAGES.find("\n\n").unwrap() + 2;
+        let at = second + fixtures::PACKAGES[second..].find('\n').unwrap() + 1;
+        let end = at + fixtures::PACKAGES[at..].find("\n\n").unwrap() + 1;
+        let junk = garbage(&mut g);
+        let s = [&clean[..at], &junk, b"\n", &clean[at..]].concat();
+        let (r, diagnostics) = parse_multi_recover(&s, &opts);
+
+        assert_eq!(r.len(), full.len() - 1);
+        assert_eq!(packages(&r[1..]), packages(&full[2..]));
+        assert_eq!(
+            diagnostics,
+            [Diagnostic::GarbageSkipped {
+                offset: second,
+                len: end - second + junk.len() + 1
+            }]
+        );
+
+        // at the end, after a blank line
+        let junk = garbage(&mut g);
+        let s = [clean, b"\n", &junk].concat();
+        let (r, diagnostics) = parse_multi_recover(&s, &opts);
+
+        assert_eq!(r, full);
+        assert_eq!(
+            diagnostics,
+            [Diagnostic::GarbageSkipped {
+                offset: clean.len() + 1,
+                len: junk.len()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_resync() {
+        let s = "Package: a\n\nPackage: b\nVersion=1\n\n-x: y\n\nbroken\n\n  \nPackage: c\n";
+        let resync = ParseOptions {
+            trailing_data: TrailingData::Resync,
+            ..Default::default()
+        };
+
+        // a paragraph only starts at a plausible field line
+        let (r, diagnostics) = parse_multi_recover(s.as_bytes(), &resync);
+        assert_eq!(packages(&r), ["a", "c"]);
+        assert_eq!(
+            diagnostics,
+            [Diagnostic::GarbageSkipped {
+                offset: 12,
+                len: 36
+            }]
+        );
+
+        assert_eq!(packages(&parse_multi_with(s, &resync).unwrap()), ["a", "c"]);
+    }
+
+    #[test]
+    fn test_resync_clean_input() {
+        let resync = ParseOptions {
+            trailing_data: TrailingData::Resync,
+            ..Default::default()
+        };
+
+        for s in [
+            fixtures::STATUS,
+            fixtures::SOURCES,
+            fixtures::SOURCES_TEXLIVE,
+            // lines of whitespace continue a field
+            "A: a\n b\n \n c\nB: x\n",
+            "\n \t\nPackage: a\n\t\nVersion: 1\n\n  \n\nPackage: b\n \n",
+            "Package: a\r\nVersion: 1\r\n\r\nPackage: b\r\n",
+        ] {
+            let expected = parse_multi(s).unwrap();
+
+            assert_eq!(
+                parse_multi_recover(s.as_bytes(), &resync),
+                (expected.clone(), vec![]),
+                "{:?}",
+                s
+            );
+            assert_eq!(parse_multi_with(s, &resync).unwrap(), expected, "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn test_resync_colon_less_lines() {
+        let opts = ParseOptions::default();
+
+        for (s, garbage) in [
+            ("garbage\n\nPackage: x\n", 0..8),
+            ("Package: a\nno colon\n\nPackage: x\n", 0..20),
+            ("Package: a\n\nbroken\n more\n\nPackage: x\n", 12..25),
+            ("Package: a\n\n# c\nbroken\n\nPackage: x\n", 16..23),
+        ] {
+            let (r, diagnostics) = parse_multi_recover(s.as_bytes(), &opts);
+
+            assert_eq!(packages(&r).last(), Some(&"x"), "{:?}", s);
+            assert_eq!(
+                diagnostics,
+                [Diagnostic::GarbageSkipped {
+                    offset: garbage.start,
+                    len: garbage.len()
+                }],
+                "{:?}",
+                s
+            );
+        }
+    }
+}
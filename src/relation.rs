@@ -0,0 +1,642 @@
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
+
+use crate::{IndexMap, IndexSet, Item, Paragraph, ParagraphExt, RelationError, Version};
+
+/// The operator of a version constraint in a relation field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VersionOp {
+    /// `<<`
+    Lt,
+    /// `<=`, or the obsolete `<`
+    Le,
+    /// `=`
+    Eq,
+    /// `>=`, or the obsolete `>`
+    Ge,
+    /// `>>`
+    Gt,
+}
+
+impl VersionOp {
+    /// Whether a version comparing `ord` to the required one satisfies this
+    pub fn matches(self, ord: Ordering) -> bool {
+        match self {
+            VersionOp::Lt => ord == Ordering::Less,
+            VersionOp::Le => ord != Ordering::Greater,
+            VersionOp::Eq => ord == Ordering::Equal,
+            VersionOp::Ge => ord != Ordering::Less,
+            VersionOp::Gt => ord == Ordering::Greater,
+        }
+    }
+}
+
+impl FromStr for VersionOp {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "<<" => VersionOp::Lt,
+            "<=" | "<" => VersionOp::Le,
+            "=" => VersionOp::Eq,
+            ">=" | ">" => VersionOp::Ge,
+            ">>" => VersionOp::Gt,
+            _ => return Err(()),
+        })
+    }
+}
+
+impl Display for VersionOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            VersionOp::Lt => "<<",
+            VersionOp::Le => "<=",
+            VersionOp::Eq => "=",
+            VersionOp::Ge => ">=",
+            VersionOp::Gt => ">>",
+        })
+    }
+}
+
+/// One package in a relation field like `Depends`:
+/// `name[:arch] [(op version)] [[arch ...]] [<profile ...>]...`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relation {
+    pub name: String,
+    /// Architecture qualifier after the colon, e.g. `any` in `python3:any`
+    pub arch: Option<String>,
+    pub version: Option<(VersionOp, Version)>,
+    /// Architecture restrictions, e.g. `["amd64", "!i386"]`
+    pub arches: Vec<String>,
+    /// Build profile restrictions, one list per `<...>` group
+    pub profiles: Vec<Vec<String>>,
+}
+
+impl Relation {
+    /// Whether a package of `version` satisfies the version constraint
+    ///
+    /// A package without a version only satisfies a relation without one.
+    pub fn satisfied_by(&self, version: Option<&Version>) -> bool {
+        match (&self.version, version) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some((op, required)), Some(v)) => op.matches(v.cmp(required)),
+        }
+    }
+}
+
+impl FromStr for Relation {
+    type Err = RelationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || RelationError::Syntax {
+            relation: s.trim().to_string(),
+        };
+
+        let s = s.trim();
+        let end = s
+            .find(|c: char| c.is_whitespace() || "([<".contains(c))
+            .unwrap_or(s.len());
+        let (name, mut rest) = s.split_at(end);
+
+        let (name, arch) = match name.split_once(':') {
+            Some((name, arch)) => (name, Some(arch)),
+            None => (name, None),
+        };
+
+        if name.is_empty() || arch.map(|x| x.is_empty()).unwrap_or(false) {
+            return Err(invalid());
+        }
+
+        let mut result = Relation {
+            name: name.to_string(),
+            arch: arch.map(|x| x.to_string()),
+            version: None,
+            arches: vec![],
+            profiles: vec![],
+        };
+
+        loop {
+            rest = rest.trim_start();
+
+            let close = match rest.chars().next() {
+                None => break,
+                Some('(') => ')',
+                Some('[') => ']',
+                Some('<') => '>',
+                Some(_) => return Err(invalid()),
+            };
+
+            let end = rest.find(close).ok_or_else(invalid)?;
+            let inner = rest[1..end].trim();
+
+            match close {
+                ')' if result.version.is_none() => {
+                    let split = inner
+                        .find(|c: char| !"<>=".contains(c))
+                        .unwrap_or(inner.len());
+                    let op = inner[..split].parse().map_err(|_| invalid())?;
+                    let version = inner[split..].parse()?;
+
+                    result.version = Some((op, version));
+                }
+                ']' if result.arches.is_empty() => {
+                    result.arches = inner.split_whitespace().map(|x| x.to_string()).collect();
+                }
+                '>' => result
+                    .profiles
+                    .push(inner.split_whitespace().map(|x| x.to_string()).collect()),
+                _ => return Err(invalid()),
+            }
+
+            rest = &rest[end + 1..];
+        }
+
+        Ok(result)
+    }
+}
+
+impl Display for Relation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name)?;
+
+        if let Some(arch) = &self.arch {
+            write!(f, ":{}", arch)?;
+        }
+        if let Some((op, version)) = &self.version {
+            write!(f, " ({} {})", op, version)?;
+        }
+        if !self.arches.is_empty() {
+            write!(f, " [{}]", self.arches.join(" "))?;
+        }
+        for profile in &self.profiles {
+            write!(f, " <{}>", profile.join(" "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse a relation field like `Depends` into its comma-separated clauses,
+/// each a list of `|`-separated alternatives
+///
+/// ```rust
+/// use eight_deep_parser::{parse_relations, VersionOp};
+///
+/// let r = parse_relations("libc6 (>= 2.34), default-mta | mail-transport-agent").unwrap();
+///
+/// assert_eq!(r.len(), 2);
+/// assert_eq!(r[0][0].version.as_ref().unwrap().0, VersionOp::Ge);
+/// assert_eq!(r[1][1].name, "mail-transport-agent");
+/// ```
+pub fn parse_relations(s: &str) -> Result<Vec<Vec<Relation>>, RelationError> {
+    s.split(',')
+        .filter(|x| !x.trim().is_empty())
+        .map(|clause| clause.split('|').map(|x| x.parse()).collect())
+        .collect()
+}
+
+/// The package names in a relation field like `Provides` or `Replaces`,
+/// alternatives included, each once in the order first seen
+///
+/// ```rust
+/// use eight_deep_parser::{parse_one, relation_names_set};
+///
+/// let r = parse_one("Provides: mail-transport-agent, default-mta (= 1), mail-transport-agent\n").unwrap();
+///
+/// assert_eq!(
+///     relation_names_set(&r["Provides"]).unwrap().into_iter().collect::<Vec<_>>(),
+///     vec!["mail-transport-agent", "default-mta"]
+/// );
+/// ```
+pub fn relation_names_set(item: &Item) -> Result<IndexSet<String>, RelationError> {
+    let clauses = parse_relations(&item.value_lines().collect::<Vec<_>>().join(" "))?;
+
+    Ok(clauses.into_iter().flatten().map(|x| x.name).collect())
+}
+
+/// Rewrite relation fields like `Depends` in canonical form, see
+/// [`canonicalize_relations_with`]
+pub fn canonicalize_relations(p: &mut Paragraph, fields: &[&str]) -> Vec<(String, RelationError)> {
+    canonicalize_relations_with(p, fields, false)
+}
+
+/// Rewrite relation fields in canonical form: one line, `, ` between
+/// clauses, ` | ` between alternatives and the spacing of
+/// [`Relation`]'s `Display`
+///
+/// Repeated clauses are dropped, and with `sort` the clauses are sorted
+/// alphabetically. Alternatives keep their order, as it is the order of
+/// preference. Fields are matched ignoring case. A field that doesn't parse
+/// is left as it is and returned with its error.
+///
+/// ```rust
+/// use eight_deep_parser::{canonicalize_relations_with, parse_one, ParagraphExt};
+///
+/// let mut p = parse_one("Package: a\nDepends: foo(>=1.2) ,bar|baz [amd64],\n foo (>= 1.2)\n").unwrap();
+/// let invalid = canonicalize_relations_with(&mut p, &["Depends"], true);
+///
+/// assert!(invalid.is_empty());
+/// assert_eq!(p.get_one("Depends"), Some("bar | baz [amd64], foo (>= 1.2)"));
+/// ```
+pub fn canonicalize_relations_with(
+    p: &mut Paragraph,
+    fields: &[&str],
+    sort: bool,
+) -> Vec<(String, RelationError)> {
+    let mut invalid = vec![];
+
+    for (key, item) in p.iter_mut() {
+        if !fields.iter().any(|x| x.eq_ignore_ascii_case(key)) {
+            continue;
+        }
+
+        match canonical(item, sort, false) {
+            Ok(canonical) => *item = canonical,
+            Err(e) => invalid.push((key.clone(), e)),
+        }
+    }
+
+    invalid
+}
+
+/// A relation field with its clauses and the alternatives of each clause
+/// sorted alphabetically, in the canonical form of
+/// [`canonicalize_relations_with`]
+///
+/// Unlike [`canonicalize_relations_with`], this throws away the order of
+/// preference of the alternatives, for output that stays the same whatever
+/// order the relations were written in. Versions, architecture and profile
+/// restrictions are kept.
+///
+/// ```rust
+/// use eight_deep_parser::{sort_relations, Item};
+///
+/// let item = Item::OneLine("c (>= 1) [amd64], b | a:any".to_string());
+///
+/// assert_eq!(
+///     sort_relations(&item).unwrap(),
+///     Item::OneLine("a:any | b, c (>= 1) [amd64]".to_string())
+/// );
+/// ```
+pub fn sort_relations(item: &Item) -> Result<Item, RelationError> {
+    canonical(item, true, true)
+}
+
+fn canonical(item: &Item, sort: bool, sort_alternatives: bool) -> Result<Item, RelationError> {
+    let clauses = parse_relations(&item.value_lines().collect::<Vec<_>>().join(" "))?;
+
+    let mut result: Vec<String> = vec![];
+    for clause in clauses {
+        let mut alternatives = clause.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+
+        if sort_alternatives {
+            alternatives.sort();
+            alternatives.dedup();
+        }
+
+        let clause = alternatives.join(" | ");
+
+        if !result.contains(&clause) {
+            result.push(clause);
+        }
+    }
+
+    if sort {
+        result.sort();
+    }
+
+    Ok(Item::OneLine(result.join(", ")))
+}
+
+/// A virtual package named in a `Provides` field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provide {
+    pub name: String,
+    /// The version of `name (= version)`
+    pub version: Option<Version>,
+}
+
+pub(crate) fn provides(p: &Paragraph) -> Result<Vec<Provide>, RelationError> {
+    let clauses = match p.relations("Provides") {
+        Some(clauses) => clauses?,
+        None => return Ok(vec![]),
+    };
+
+    let mut result = vec![];
+
+    for mut clause in clauses {
+        let relation = clause.pop().unwrap();
+
+        // Only `=` makes sense in a provide, and there are no alternatives
+        let version = match relation.version {
+            Some((VersionOp::Eq, version)) if clause.is_empty() => Some(version),
+            None if clause.is_empty() => None,
+            _ => {
+                clause.push(relation);
+
+                return Err(RelationError::Syntax {
+                    relation: clause
+                        .iter()
+                        .map(|x| x.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" | "),
+                });
+            }
+        };
+
+        result.push(Provide {
+            name: relation.name,
+            version,
+        });
+    }
+
+    Ok(result)
+}
+
+/// Map every name a package can be depended on by to the packages answering
+/// to it: its own `Package` name and every name in its `Provides`
+///
+/// A package is listed once per name, even if it provides its own name.
+/// Paragraphs without a `Package` field are left out, and so are `Provides`
+/// fields that don't parse.
+///
+/// ```rust
+/// use eight_deep_parser::{parse_multi, resolve_provides};
+///
+/// let r = parse_multi(
+///     "Package: postfix\nProvides: mail-transport-agent\n\nPackage: exim4-daemon-light\nProvides: mail-transport-agent\n",
+/// )
+/// .unwrap();
+/// let providers = resolve_provides(&r);
+///
+/// assert_eq!(providers["mail-transport-agent"].len(), 2);
+/// assert_eq!(providers["postfix"].len(), 1);
+/// ```
+pub fn resolve_provides(paragraphs: &[Paragraph]) -> IndexMap<String, Vec<&Paragraph>> {
+    let mut result: IndexMap<String, Vec<&Paragraph>> = IndexMap::new();
+
+    for p in paragraphs {
+        let name = match p.get_one("Package") {
+            Some(name) => name,
+            None => continue,
+        };
+
+        let provides = p.provides().unwrap_or_default();
+
+        for name in std::iter::once(name).chain(provides.iter().map(|x| x.name.as_str())) {
+            let entry = result.entry(name.to_string()).or_default();
+
+            if !entry.iter().any(|x| std::ptr::eq(*x, p)) {
+                entry.push(p);
+            }
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        canonicalize_relations, canonicalize_relations_with, parse_multi, parse_one,
+        parse_relations, relation_names_set, resolve_provides, sort_relations, Item, ParagraphExt,
+        Provide, Relation, RelationError, VersionOp,
+    };
+
+    #[test]
+    fn test_relation() {
+        let r: Relation = "python3:any (>= 3.11~) [amd64 !i386] <!nocheck> <cross>"
+            .parse()
+            .unwrap();
+
+        assert_eq!(r.name, "python3");
+        assert_eq!(r.arch.as_deref(), Some("any"));
+        assert_eq!(r.version.as_ref().unwrap().0, VersionOp::Ge);
+        assert_eq!(r.version.as_ref().unwrap().1.to_string(), "3.11~");
+        assert_eq!(r.arches, vec!["amd64", "!i386"]);
+        assert_eq!(r.profiles, vec![vec!["!nocheck"], vec!["cross"]]);
+        assert_eq!(
+            r.to_string(),
+            "python3:any (>= 3.11~) [amd64 !i386] <!nocheck> <cross>"
+        );
+
+        let r: Relation = "libgcc-s1(>>1:3.0)".parse().unwrap();
+
+        assert_eq!(r.to_string(), "libgcc-s1 (>> 1:3.0)");
+        assert!(r.satisfied_by(Some(&"1:12.2.0-14".parse().unwrap())));
+        assert!(!r.satisfied_by(Some(&"12.2.0-14".parse().unwrap())));
+        assert!(!r.satisfied_by(None));
+    }
+
+    #[test]
+    fn test_relation_invalid() {
+        for s in [
+            "",
+            "(>= 1.0)",
+            "a (1.0)",
+            "a (>= 1.0",
+            "a b",
+            "a (~ 1.0)",
+            "a:",
+        ] {
+            assert_eq!(
+                s.parse::<Relation>(),
+                Err(RelationError::Syntax {
+                    relation: s.to_string()
+                }),
+                "{}",
+                s
+            );
+        }
+
+        assert!(parse_relations("a, b ||").is_err());
+    }
+
+    #[test]
+    fn test_relations_field() {
+        let r = parse_one(
+            "Package: libc6
+Depends: libgcc-s1
+Breaks: aide (<< 0.17.3-4+b3), busybox (<< 1.30.1-6),
+ hurd (<< 1:0.9.git20220301-2),
+",
+        )
+        .unwrap();
+
+        let breaks = r.relations("Breaks").unwrap().unwrap();
+
+        assert_eq!(
+            breaks.iter().map(|x| x[0].to_string()).collect::<Vec<_>>(),
+            vec![
+                "aide (<< 0.17.3-4+b3)",
+                "busybox (<< 1.30.1-6)",
+                "hurd (<< 1:0.9.git20220301-2)"
+            ]
+        );
+        assert_eq!(r.relations("Depends").unwrap().unwrap().len(), 1);
+        assert!(r.relations("Pre-Depends").is_none());
+    }
+
+    #[test]
+    fn test_provides() {
+        let r = parse_one(
+            "Package: g++
+Provides: c++-compiler, g++-x86-64-linux-gnu (= 4:12.2.0-3)
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            r.provides().unwrap(),
+            vec![
+                Provide {
+                    name: "c++-compiler".to_string(),
+                    version: None
+                },
+                Provide {
+                    name: "g++-x86-64-linux-gnu".to_string(),
+                    version: Some("4:12.2.0-3".parse().unwrap())
+                }
+            ]
+        );
+        assert_eq!(parse_one("Package: a\n").unwrap().provides(), Ok(vec![]));
+
+        for s in ["a (>= 1.0)", "a | b"] {
+            let r = parse_one(&format!("Package: x\nProvides: {}\n", s)).unwrap();
+
+            assert_eq!(
+                r.provides(),
+                Err(RelationError::Syntax {
+                    relation: s.to_string()
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolve_provides() {
+        let r = parse_multi(
+            "Package: gcc
+Provides: c-compiler, gcc-x86-64-linux-gnu (= 4:12.2.0-3)
+
+Package: clang
+Provides: c-compiler, c++-compiler
+
+Package: dbus-system-bus-common
+Provides: dbus-system-bus-common, dbus-system-bus (= 1.14.10-1~deb12u1)
+
+Package: bash
+",
+        )
+        .unwrap();
+
+        let providers = resolve_provides(&r);
+        let names = |name: &str| {
+            providers[name]
+                .iter()
+                .map(|x| x.get_one("Package").unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names("c-compiler"), vec!["gcc", "clang"]);
+        assert_eq!(names("gcc-x86-64-linux-gnu"), vec!["gcc"]);
+        assert_eq!(names("dbus-system-bus"), vec!["dbus-system-bus-common"]);
+        assert_eq!(
+            names("dbus-system-bus-common"),
+            vec!["dbus-system-bus-common"]
+        );
+        assert_eq!(names("bash"), vec!["bash"]);
+        assert!(!providers.contains_key("c++"));
+    }
+
+    #[test]
+    fn test_canonicalize_relations() {
+        let mut p = parse_one(
+            "Package: a
+Depends: libc6(>=2.34),libssl3 (>= 3.0.0) ,
+ default-mta|mail-transport-agent,libc6 (>= 2.34)
+Recommends: zz, aa [ amd64  i386 ]
+Breaks: broken (>= 1
+Conflicts: b
+",
+        )
+        .unwrap();
+
+        let invalid = canonicalize_relations(&mut p, &["depends", "Recommends", "Breaks"]);
+
+        assert_eq!(invalid.len(), 1);
+        assert_eq!(invalid[0].0, "Breaks");
+        assert_eq!(
+            p.get_one("Depends"),
+            Some("libc6 (>= 2.34), libssl3 (>= 3.0.0), default-mta | mail-transport-agent")
+        );
+        assert_eq!(p.get_one("Recommends"), Some("zz, aa [amd64 i386]"));
+        assert_eq!(p.get_one("Breaks"), Some("broken (>= 1"));
+
+        let once = p.clone();
+        canonicalize_relations(&mut p, &["Depends", "Recommends"]);
+
+        assert_eq!(p, once);
+
+        canonicalize_relations_with(&mut p, &["Depends", "Recommends"], true);
+        let sorted = p.clone();
+
+        assert_eq!(
+            p.get_one("Depends"),
+            Some("default-mta | mail-transport-agent, libc6 (>= 2.34), libssl3 (>= 3.0.0)")
+        );
+        assert_eq!(p.get_one("Recommends"), Some("aa [amd64 i386], zz"));
+
+        canonicalize_relations_with(&mut p, &["Depends", "Recommends"], true);
+
+        assert_eq!(p, sorted);
+    }
+
+    #[test]
+    fn test_sort_relations() {
+        let item = Item::MultiLine(vec![
+            "b | a, c,".to_string(),
+            "a|b, d:any (<< 2~) [!i386] <!nocheck> | a".to_string(),
+        ]);
+        let sorted = sort_relations(&item).unwrap();
+
+        assert_eq!(
+            sorted,
+            Item::OneLine("a | b, a | d:any (<< 2~) [!i386] <!nocheck>, c".to_string())
+        );
+        assert_eq!(sort_relations(&sorted).unwrap(), sorted);
+        assert_eq!(
+            sort_relations(&Item::OneLine("b | a, c".to_string())).unwrap(),
+            Item::OneLine("a | b, c".to_string())
+        );
+        assert!(sort_relations(&Item::OneLine("a (>> ".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_relation_names_set() {
+        let r = parse_one(
+            "Package: exim4-daemon-light
+Provides: mail-transport-agent, exim4-localscanapi-6.0,
+ mail-transport-agent (= 4.96), exim4-localscanapi-6.0
+Replaces: exim4-base (<< 4.96) | exim4-config, exim4-base
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            relation_names_set(&r["Provides"])
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["mail-transport-agent", "exim4-localscanapi-6.0"]
+        );
+        assert_eq!(
+            relation_names_set(&r["Replaces"])
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["exim4-base", "exim4-config"]
+        );
+        assert!(relation_names_set(&Item::OneLine("a (>= ".to_string())).is_err());
+    }
+}
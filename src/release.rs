@@ -0,0 +1,496 @@
+use std::fmt::Display;
+
+#[cfg(feature = "digest")]
+use crate::VerifyError;
+use crate::{
+    control::parse_yes_no, ChecksumEntry, Paragraph, ParagraphExt, ReleaseError, RowError,
+};
+
+/// A digest algorithm of a Release file, ordered from weakest to strongest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Algorithm {
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+impl Algorithm {
+    /// All algorithms, strongest first
+    pub const ALL: [Algorithm; 3] = [Algorithm::Sha256, Algorithm::Sha1, Algorithm::Md5];
+
+    /// The Release field listing digests of this algorithm
+    pub fn field(self) -> &'static str {
+        match self {
+            Algorithm::Md5 => "MD5Sum",
+            Algorithm::Sha1 => "SHA1",
+            Algorithm::Sha256 => "SHA256",
+        }
+    }
+
+    /// Lowercase hex digest of `data`
+    #[cfg(feature = "digest")]
+    pub fn digest(self, data: &[u8]) -> String {
+        match self {
+            Algorithm::Md5 => crate::md5::md5_hex(data),
+            Algorithm::Sha1 => crate::digest::sha1_hex(data),
+            Algorithm::Sha256 => crate::digest::sha256_hex(data),
+        }
+    }
+}
+
+impl Display for Algorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.field())
+    }
+}
+
+/// The result of checking one file in [`ReleaseFile::verify_dir`]
+#[cfg(feature = "digest")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyStatus {
+    Ok,
+    /// The file doesn't exist
+    Missing,
+    /// The file exists but can't be read, with the reason
+    Unreadable(String),
+    /// The size or digest doesn't match
+    Mismatch(VerifyError),
+}
+
+/// A `Release` file of an apt repository, or the content of an
+/// `InRelease` file once the signature is stripped
+///
+/// This is a view over the parsed paragraph, like
+/// [`SourcesEntry`](crate::SourcesEntry).
+///
+/// ```rust
+/// use eight_deep_parser::{parse_one, Algorithm, ReleaseFile};
+///
+/// let r = parse_one("Suite: stable\nMD5Sum:\n d41d8cd98f00b204e9800998ecf8427e 0 main/binary-amd64/Packages\nSHA256:\n e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855 0 main/binary-amd64/Packages\n").unwrap();
+/// let release = ReleaseFile::new(r);
+///
+/// let (algorithm, entry) = release.find("main/binary-amd64/Packages").unwrap().unwrap();
+///
+/// assert_eq!(algorithm, Algorithm::Sha256);
+/// assert_eq!(entry.size, 0);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReleaseFile {
+    paragraph: Paragraph,
+}
+
+impl ReleaseFile {
+    pub fn new(paragraph: Paragraph) -> Self {
+        Self { paragraph }
+    }
+
+    pub fn paragraph(&self) -> &Paragraph {
+        &self.paragraph
+    }
+
+    pub fn into_paragraph(self) -> Paragraph {
+        self.paragraph
+    }
+
+    /// The files listed for `algorithm`, empty if there is no such field
+    pub fn entries(&self, algorithm: Algorithm) -> Result<Vec<ChecksumEntry>, RowError> {
+        match self.paragraph.get_field(algorithm.field()) {
+            Some(item) => ChecksumEntry::parse_field(algorithm.field(), item),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Every listed path, once, in the order first seen, strongest
+    /// algorithm first
+    pub fn paths(&self) -> Result<Vec<String>, RowError> {
+        let mut result: Vec<String> = vec![];
+
+        for algorithm in Algorithm::ALL {
+            for entry in self.entries(algorithm)? {
+                if !result.contains(&entry.path) {
+                    result.push(entry.path);
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// The entry of `path` under the strongest algorithm listing it
+    pub fn find(&self, path: &str) -> Result<Option<(Algorithm, ChecksumEntry)>, RowError> {
+        for algorithm in Algorithm::ALL {
+            if let Some(entry) = self
+                .entries(algorithm)?
+                .into_iter()
+                .find(|x| x.path == path)
+            {
+                return Ok(Some((algorithm, entry)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Whether the repository serves index files under `by-hash`, from the
+    /// `Acquire-By-Hash` field
+    pub fn supports_by_hash(&self) -> bool {
+        self.paragraph
+            .get_one("Acquire-By-Hash")
+            .and_then(parse_yes_no)
+            .unwrap_or(false)
+    }
+
+    /// The path of `relative_path` in the `by-hash` directory next to it,
+    /// e.g. `main/binary-amd64/by-hash/SHA256/<digest>` for
+    /// `main/binary-amd64/Packages.xz`, or `None` if it isn't listed for
+    /// `algorithm`
+    ///
+    /// Entries of the path that disagree, listing it twice with different
+    /// digests or with different sizes for different algorithms, are a
+    /// [`ReleaseError::Conflict`].
+    pub fn by_hash_path(
+        &self,
+        relative_path: &str,
+        algorithm: Algorithm,
+    ) -> Result<Option<String>, ReleaseError> {
+        let mut found: Option<ChecksumEntry> = None;
+        let mut size = None;
+
+        for a in Algorithm::ALL {
+            for entry in self
+                .entries(a)?
+                .into_iter()
+                .filter(|x| x.path == relative_path)
+            {
+                let conflict = size.map(|x| x != entry.size).unwrap_or(false)
+                    || (a == algorithm
+                        && found
+                            .as_ref()
+                            .map(|x| !x.digest.eq_ignore_ascii_case(&entry.digest))
+                            .unwrap_or(false));
+
+                if conflict {
+                    return Err(ReleaseError::Conflict {
+                        path: relative_path.to_string(),
+                    });
+                }
+
+                size = Some(entry.size);
+
+                if a == algorithm {
+                    found = Some(entry);
+                }
+            }
+        }
+
+        Ok(found.map(|entry| {
+            let dir = match relative_path.rsplit_once('/') {
+                Some((dir, _)) => format!("{}/", dir),
+                None => String::new(),
+            };
+
+            format!("{}by-hash/{}/{}", dir, algorithm.field(), entry.digest)
+        }))
+    }
+
+    /// Check `data` against the size and the strongest digest listed for
+    /// `relative_path`
+    #[cfg(feature = "digest")]
+    pub fn verify_file(&self, relative_path: &str, data: &[u8]) -> Result<(), VerifyError> {
+        let (algorithm, entry) =
+            self.find(relative_path)?
+                .ok_or_else(|| VerifyError::NotListed {
+                    path: relative_path.to_string(),
+                })?;
+
+        if entry.size != data.len() as u64 {
+            return Err(VerifyError::Size {
+                path: entry.path,
+                expected: entry.size,
+                actual: data.len() as u64,
+            });
+        }
+
+        let actual = algorithm.digest(data);
+
+        if !actual.eq_ignore_ascii_case(&entry.digest) {
+            return Err(VerifyError::Digest {
+                path: entry.path,
+                algorithm,
+                expected: entry.digest,
+                actual,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Check every listed file under `root`, the directory holding the
+    /// Release file
+    #[cfg(feature = "digest")]
+    pub fn verify_dir(
+        &self,
+        root: &std::path::Path,
+    ) -> Result<Vec<(String, VerifyStatus)>, RowError> {
+        let mut result = vec![];
+
+        for path in self.paths()? {
+            let status = match std::fs::read(root.join(&path)) {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => VerifyStatus::Missing,
+                Err(e) => VerifyStatus::Unreadable(e.to_string()),
+                Ok(data) => match self.verify_file(&path, &data) {
+                    Ok(()) => VerifyStatus::Ok,
+                    Err(e) => VerifyStatus::Mismatch(e),
+                },
+            };
+
+            result.push((path, status));
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_one, Algorithm, ReleaseError, ReleaseFile, RowError};
+
+    #[test]
+    fn test_release_entries() {
+        let r = parse_one(
+            "Origin: Debian
+Suite: oldstable-security
+Acquire-By-Hash: yes
+MD5Sum:
+ d41d8cd98f00b204e9800998ecf8427e        0 contrib/Contents-amd64
+ d61f27bd17de546264aa58f40f3aafaa       20 contrib/Contents-amd64.gz
+SHA256:
+ e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855        0 contrib/Contents-amd64
+",
+        )
+        .unwrap();
+        let release = ReleaseFile::new(r);
+
+        assert_eq!(release.entries(Algorithm::Md5).unwrap().len(), 2);
+        assert!(release.entries(Algorithm::Sha1).unwrap().is_empty());
+        assert_eq!(
+            release.paths().unwrap(),
+            vec!["contrib/Contents-amd64", "contrib/Contents-amd64.gz"]
+        );
+        assert_eq!(
+            release
+                .find("contrib/Contents-amd64.gz")
+                .unwrap()
+                .unwrap()
+                .0,
+            Algorithm::Md5
+        );
+        assert_eq!(release.find("main/Contents-amd64").unwrap(), None);
+
+        let release = ReleaseFile::new(parse_one("SHA1:\n abc 12\n").unwrap());
+
+        assert_eq!(
+            release.paths(),
+            Err(RowError {
+                field: "SHA1".to_string(),
+                row: "abc 12".to_string()
+            })
+        );
+    }
+
+    const BOOKWORM: &str = "Origin: Debian
+Label: Debian
+Suite: oldstable
+Version: 12.14
+Codename: bookworm
+Date: Sat, 16 May 2026 10:54:28 UTC
+Acquire-By-Hash: yes
+No-Support-for-Architecture-all: Packages
+Architectures: all amd64 arm64 armel armhf i386 mips64el mipsel ppc64el s390x
+Components: main contrib non-free-firmware non-free
+Description: Debian 12.14 Released 16 May 2026
+MD5Sum:
+ 4f1a309608a67f3e268a987340227a9f 50057461 main/binary-amd64/Packages
+ cbefe42a2181f4441f94ec9beb3f0274 12084574 main/binary-amd64/Packages.gz
+ 58b60a74b605bab462f1a2774577452e  8790116 main/binary-amd64/Packages.xz
+ 63381cece0061ed5e4cadc8a5cf875bf      120 main/binary-amd64/Release
+SHA256:
+ 81097cabc561cf9c51576cd57738e84d8d636762a69691e7adf0645882f402c9 50057461 main/binary-amd64/Packages
+ 4c17b207e53ea8eb6a24d09f7c5de130bfe69868d613fd97f3d8bb9bad2be6fc 12084574 main/binary-amd64/Packages.gz
+ 62d555a3a8be1d6de520fcd497ae85f2317c6dbb1249976e07720043e77b85d8  8790116 main/binary-amd64/Packages.xz
+ 15d07d46db8e6130f4338974a2b0db3ad7b17922e8bf945ae45dc2b7a28b6ef2      120 main/binary-amd64/Release
+";
+
+    #[test]
+    fn test_by_hash_path() {
+        let release = ReleaseFile::new(parse_one(BOOKWORM).unwrap());
+
+        assert!(release.supports_by_hash());
+        assert_eq!(
+            release
+                .by_hash_path("main/binary-amd64/Packages.xz", Algorithm::Sha256)
+                .unwrap()
+                .as_deref(),
+            Some("main/binary-amd64/by-hash/SHA256/62d555a3a8be1d6de520fcd497ae85f2317c6dbb1249976e07720043e77b85d8")
+        );
+        assert_eq!(
+            release
+                .by_hash_path("main/binary-amd64/Packages", Algorithm::Md5)
+                .unwrap()
+                .as_deref(),
+            Some("main/binary-amd64/by-hash/MD5Sum/4f1a309608a67f3e268a987340227a9f")
+        );
+        assert_eq!(
+            release
+                .by_hash_path("main/binary-amd64/Packages", Algorithm::Sha1)
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            release
+                .by_hash_path("main/binary-i386/Packages", Algorithm::Sha256)
+                .unwrap(),
+            None
+        );
+
+        let release =
+            ReleaseFile::new(parse_one(&BOOKWORM.replace("Acquire-By-Hash: yes\n", "")).unwrap());
+
+        assert!(!release.supports_by_hash());
+    }
+
+    #[test]
+    fn test_by_hash_path_conflict() {
+        let conflict = Err(ReleaseError::Conflict {
+            path: "main/binary-amd64/Packages.gz".to_string(),
+        });
+
+        let s = BOOKWORM.replace(
+            " 4c17b207e53ea8eb6a24d09f7c5de130bfe69868d613fd97f3d8bb9bad2be6fc 12084574 main/binary-amd64/Packages.gz\n",
+            " 4c17b207e53ea8eb6a24d09f7c5de130bfe69868d613fd97f3d8bb9bad2be6fc 12084574 main/binary-amd64/Packages.gz\n 0c17b207e53ea8eb6a24d09f7c5de130bfe69868d613fd97f3d8bb9bad2be6fc 12084574 main/binary-amd64/Packages.gz\n",
+        );
+        let release = ReleaseFile::new(parse_one(&s).unwrap());
+
+        assert_eq!(
+            release.by_hash_path("main/binary-amd64/Packages.gz", Algorithm::Sha256),
+            conflict
+        );
+
+        let s = BOOKWORM.replace(
+            "cbefe42a2181f4441f94ec9beb3f0274 12084574",
+            "cbefe42a2181f4441f94ec9beb3f0274 12084575",
+        );
+        let release = ReleaseFile::new(parse_one(&s).unwrap());
+
+        assert_eq!(
+            release.by_hash_path("main/binary-amd64/Packages.gz", Algorithm::Sha256),
+            conflict
+        );
+        assert!(release
+            .by_hash_path("main/binary-amd64/Packages.xz", Algorithm::Sha256)
+            .is_ok());
+    }
+
+    #[cfg(feature = "digest")]
+    mod verify {
+        use crate::{parse_one, Algorithm, ReleaseFile, VerifyError, VerifyStatus};
+
+        const PACKAGES: &[u8] = b"Package: hello\nVersion: 2.10-3\n";
+        const SOURCES: &[u8] = b"Package: hello\nBinary: hello\n";
+
+        fn repo() -> ReleaseFile {
+            let row = |algorithm: Algorithm, path: &str, data: &[u8]| {
+                format!(" {} {} {}\n", algorithm.digest(data), data.len(), path)
+            };
+
+            let mut s = "Suite: stable\nMD5Sum:\n".to_string();
+            s += &row(Algorithm::Md5, "main/binary-amd64/Packages", PACKAGES);
+            s += &row(Algorithm::Md5, "main/source/Sources", SOURCES);
+            s += &row(Algorithm::Md5, "main/i18n/Translation-en", b"");
+            s += "SHA256:\n";
+            s += &row(Algorithm::Sha256, "main/binary-amd64/Packages", PACKAGES);
+            s += &row(Algorithm::Sha256, "main/source/Sources", SOURCES);
+
+            ReleaseFile::new(parse_one(&s).unwrap())
+        }
+
+        #[test]
+        fn test_verify_file() {
+            let release = repo();
+
+            assert_eq!(
+                release.verify_file("main/binary-amd64/Packages", PACKAGES),
+                Ok(())
+            );
+            assert_eq!(release.verify_file("main/i18n/Translation-en", b""), Ok(()));
+
+            let corrupted = b"Package: hellO\nVersion: 2.10-3\n";
+
+            assert_eq!(
+                release.verify_file("main/binary-amd64/Packages", corrupted),
+                Err(VerifyError::Digest {
+                    path: "main/binary-amd64/Packages".to_string(),
+                    algorithm: Algorithm::Sha256,
+                    expected: Algorithm::Sha256.digest(PACKAGES),
+                    actual: Algorithm::Sha256.digest(corrupted),
+                })
+            );
+            assert_eq!(
+                release.verify_file("main/source/Sources", b""),
+                Err(VerifyError::Size {
+                    path: "main/source/Sources".to_string(),
+                    expected: SOURCES.len() as u64,
+                    actual: 0,
+                })
+            );
+            assert_eq!(
+                release.verify_file("main/Contents-amd64", b""),
+                Err(VerifyError::NotListed {
+                    path: "main/Contents-amd64".to_string()
+                })
+            );
+
+            let e = release
+                .verify_file("main/binary-amd64/Packages", corrupted)
+                .unwrap_err()
+                .to_string();
+
+            assert!(e.contains("SHA256"));
+            assert!(e.contains(&Algorithm::Sha256.digest(PACKAGES)));
+        }
+
+        #[test]
+        fn test_verify_dir() {
+            let root = std::env::temp_dir().join(format!("8dparser-verify-{}", std::process::id()));
+            std::fs::create_dir_all(root.join("main/binary-amd64")).unwrap();
+            std::fs::create_dir_all(root.join("main/source")).unwrap();
+            std::fs::write(root.join("main/binary-amd64/Packages"), PACKAGES).unwrap();
+            std::fs::write(
+                root.join("main/source/Sources"),
+                b"Package: hello\nBinary: hellO\n",
+            )
+            .unwrap();
+
+            let report = repo().verify_dir(&root).unwrap();
+            std::fs::remove_dir_all(&root).unwrap();
+
+            assert_eq!(report.len(), 3);
+            assert_eq!(
+                report[0],
+                ("main/binary-amd64/Packages".to_string(), VerifyStatus::Ok)
+            );
+            assert!(matches!(
+                &report[1],
+                (path, VerifyStatus::Mismatch(VerifyError::Digest { algorithm: Algorithm::Sha256, .. }))
+                    if path == "main/source/Sources"
+            ));
+            assert_eq!(
+                report[2],
+                (
+                    "main/i18n/Translation-en".to_string(),
+                    VerifyStatus::Missing
+                )
+            );
+        }
+    }
+}
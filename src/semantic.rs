@@ -0,0 +1,489 @@
+use crate::{DiffOptions, FieldCompare, IndexMap, Item, Paragraph, ParagraphExt};
+
+/// A difference found by [`semantic_diff`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldDelta {
+    OnlyInA {
+        key: String,
+        value: Item,
+    },
+    OnlyInB {
+        key: String,
+        value: Item,
+    },
+    /// The field is in both, under the name used in `a`, with different
+    /// values
+    Changed {
+        key: String,
+        a: Item,
+        b: Item,
+    },
+    /// The values differ, but not for [`FieldCompare::UnorderedTrimmed`],
+    /// the way the field is compared by [`semantic_diff_by`]: only the order
+    /// of the lines or the whitespace around them changed
+    Equivalent {
+        key: String,
+        a: Item,
+        b: Item,
+    },
+}
+
+/// Whether two paragraphs hold the same data, see [`semantic_diff`]
+///
+/// ```rust
+/// use eight_deep_parser::{parse_one, semantic_eq};
+///
+/// let a = parse_one("Package: a\nDepends: b \nDescription: c\n d\n").unwrap();
+/// let b = parse_one("description:\n c\n d\npackage: a\nDepends: b\n").unwrap();
+///
+/// assert!(semantic_eq(&a, &b));
+/// ```
+pub fn semantic_eq(a: &IndexMap<String, Item>, b: &IndexMap<String, Item>) -> bool {
+    semantic_diff(a, b).is_empty()
+}
+
+/// The fields that differ between two paragraphs, ignoring formatting
+///
+/// Field names are matched ignoring case, and field order doesn't matter.
+/// Values are compared line by line without trailing whitespace, or
+/// leading whitespace on the field line. An empty field line is skipped, so
+/// it doesn't matter whether a value is an [`Item::OneLine`], an
+/// [`Item::Folded`] or an [`Item::MultiLine`].
+///
+/// Fields only in `a` and changed fields come in the order of `a`, followed
+/// by the fields only in `b`.
+pub fn semantic_diff(a: &IndexMap<String, Item>, b: &IndexMap<String, Item>) -> Vec<FieldDelta> {
+    semantic_diff_with(a, b, &[])
+}
+
+/// Like [`semantic_diff`], ignoring the order of the lines of the fields in
+/// `unordered`, e.g. `Conffiles`
+///
+/// ```rust
+/// use eight_deep_parser::{parse_one, semantic_diff_with};
+///
+/// let a = parse_one("Conffiles:\n /etc/a 01\n /etc/b 02\n").unwrap();
+/// let b = parse_one("Conffiles:\n /etc/b 02\n /etc/a 01\n").unwrap();
+///
+/// assert!(semantic_diff_with(&a, &b, &["Conffiles"]).is_empty());
+/// ```
+pub fn semantic_diff_with(
+    a: &IndexMap<String, Item>,
+    b: &IndexMap<String, Item>,
+    unordered: &[&str],
+) -> Vec<FieldDelta> {
+    diff(a, b, |k| {
+        Some(match unordered.iter().any(|x| x.eq_ignore_ascii_case(k)) {
+            true => Lines::Sorted,
+            false => Lines::Ordered,
+        })
+    })
+}
+
+/// Like [`semantic_diff`], comparing each field the way `opts` says
+///
+/// Fields compared with [`FieldCompare::Ignore`] are left out even when
+/// only one paragraph has them. Fields compared with
+/// [`FieldCompare::UnorderedTrimmed`] whose values only match that way are
+/// [`FieldDelta::Equivalent`], telling what [`semantic_diff`] would have
+/// found.
+///
+/// ```rust
+/// use eight_deep_parser::{parse_one, semantic_diff_by, DiffOptions, FieldDelta};
+///
+/// let a = parse_one("Conffiles:\n /etc/a 01\n /etc/b 02\nDescription-md5: 01\n").unwrap();
+/// let b = parse_one("Conffiles:\n /etc/b 02 \n /etc/a 01\n").unwrap();
+///
+/// let delta = semantic_diff_by(&a, &b, &DiffOptions::default());
+///
+/// assert!(matches!(&delta[..], [FieldDelta::Equivalent { key, .. }] if key == "Conffiles"));
+/// ```
+pub fn semantic_diff_by(
+    a: &IndexMap<String, Item>,
+    b: &IndexMap<String, Item>,
+    opts: &DiffOptions,
+) -> Vec<FieldDelta> {
+    diff(a, b, |k| match opts.get(k) {
+        FieldCompare::OrderedExact => Some(Lines::Ordered),
+        FieldCompare::UnorderedTrimmed => Some(Lines::Set),
+        FieldCompare::Ignore => None,
+    })
+}
+
+/// Whether two paragraphs hold the same data, see [`semantic_diff_by`]
+///
+/// [`FieldDelta::Equivalent`] fields count as the same.
+pub fn semantic_eq_by(
+    a: &IndexMap<String, Item>,
+    b: &IndexMap<String, Item>,
+    opts: &DiffOptions,
+) -> bool {
+    semantic_diff_by(a, b, opts)
+        .iter()
+        .all(|x| matches!(x, FieldDelta::Equivalent { .. }))
+}
+
+/// How the lines of a value are compared
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Lines {
+    Ordered,
+    /// Sorted, for [`semantic_diff_with`]
+    Sorted,
+    /// Trimmed, sorted and without duplicates or empty lines
+    Set,
+}
+
+/// The differences between `a` and `b`, comparing the fields `lines` gives
+/// a way for
+fn diff(
+    a: &IndexMap<String, Item>,
+    b: &IndexMap<String, Item>,
+    lines: impl Fn(&str) -> Option<Lines>,
+) -> Vec<FieldDelta> {
+    let find = |p: &IndexMap<String, Item>, key: &str| {
+        p.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.clone())
+    };
+
+    let mut result = vec![];
+
+    for (k, v) in a {
+        let lines = match lines(k) {
+            Some(lines) => lines,
+            None => continue,
+        };
+
+        match find(b, k) {
+            None => result.push(FieldDelta::OnlyInA {
+                key: k.clone(),
+                value: v.clone(),
+            }),
+            Some(other) => {
+                if normalize(v, lines) != normalize(&other, lines) {
+                    result.push(FieldDelta::Changed {
+                        key: k.clone(),
+                        a: v.clone(),
+                        b: other,
+                    });
+                } else if lines == Lines::Set
+                    && normalize(v, Lines::Ordered) != normalize(&other, Lines::Ordered)
+                {
+                    result.push(FieldDelta::Equivalent {
+                        key: k.clone(),
+                        a: v.clone(),
+                        b: other,
+                    });
+                }
+            }
+        }
+    }
+
+    for (k, v) in b {
+        if lines(k).is_some() && find(a, k).is_none() {
+            result.push(FieldDelta::OnlyInB {
+                key: k.clone(),
+                value: v.clone(),
+            });
+        }
+    }
+
+    result
+}
+
+/// The differences between two documents, see [`document_diff`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DocumentDiff<'a> {
+    /// Paragraphs of `new` whose key isn't in `old`
+    pub added: Vec<&'a Paragraph>,
+    /// Paragraphs of `old` whose key isn't in `new`
+    pub removed: Vec<&'a Paragraph>,
+    /// The key as written in `new`, and the differences from `old`
+    pub changed: Vec<(String, Vec<FieldDelta>)>,
+}
+
+impl DocumentDiff<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Match the paragraphs of two documents, e.g. two versions of a `Packages`
+/// index, by the value of `key_field` and compare them with [`semantic_diff`]
+///
+/// Key values are matched ignoring case. Paragraphs without `key_field` are
+/// skipped, and if several have the same key, the last one counts.
+/// `added` and `changed` come in the order of `new`, `removed` in the order
+/// of `old`.
+///
+/// ```rust
+/// use eight_deep_parser::{document_diff, parse_multi};
+///
+/// let old = parse_multi("Package: a\nVersion: 1\n\nPackage: b\n").unwrap();
+/// let new = parse_multi("Package: a\nVersion: 2\n\nPackage: c\n").unwrap();
+///
+/// let diff = document_diff(&old, &new, "Package");
+///
+/// assert_eq!(diff.added, vec![&new[1]]);
+/// assert_eq!(diff.removed, vec![&old[1]]);
+/// assert_eq!(diff.changed[0].0, "a");
+/// ```
+pub fn document_diff<'a>(
+    old: &'a [Paragraph],
+    new: &'a [Paragraph],
+    key_field: &str,
+) -> DocumentDiff<'a> {
+    document_diff_by(old, new, key_field, &DiffOptions::exact())
+}
+
+/// Like [`document_diff`], comparing paragraphs with [`semantic_diff_by`]
+///
+/// Paragraphs whose only differences are [`FieldDelta::Equivalent`] are in
+/// `changed` too.
+pub fn document_diff_by<'a>(
+    old: &'a [Paragraph],
+    new: &'a [Paragraph],
+    key_field: &str,
+    opts: &DiffOptions,
+) -> DocumentDiff<'a> {
+    let index = |ps: &'a [Paragraph]| {
+        let mut result = IndexMap::new();
+
+        for p in ps {
+            if let Some(key) = p.get_field(key_field) {
+                let key = key.value_lines().collect::<Vec<_>>().join(" ");
+                result.insert(key.to_lowercase(), (key, p));
+            }
+        }
+
+        result
+    };
+
+    let old_index = index(old);
+    let new_index = index(new);
+    let mut result = DocumentDiff::default();
+
+    for (k, (key, p)) in &new_index {
+        match old_index.get(k) {
+            None => result.added.push(*p),
+            Some((_, old)) => {
+                let delta = semantic_diff_by(old, p, opts);
+
+                if !delta.is_empty() {
+                    result.changed.push((key.clone(), delta));
+                }
+            }
+        }
+    }
+
+    for (k, (_, p)) in &old_index {
+        if !new_index.contains_key(k) {
+            result.removed.push(*p);
+        }
+    }
+
+    result
+}
+
+fn normalize(item: &Item, how: Lines) -> Vec<&str> {
+    let (first, rest) = match item {
+        Item::OneLine(s) => (Some(s.trim()), &[][..]),
+        Item::MultiLine(v) => (None, &v[..]),
+        Item::Folded(s, v) => (Some(s.trim()), &v[..]),
+        Item::Streamed => (None, &[][..]),
+    };
+
+    let mut lines = first
+        .filter(|x| !x.is_empty())
+        .into_iter()
+        .chain(rest.iter().map(|x| x.trim_end()))
+        .collect::<Vec<_>>();
+
+    // `Key:` and `Key:` followed by an empty line are both no value
+    if lines == [""] {
+        lines.clear();
+    }
+
+    match how {
+        Lines::Ordered => {}
+        Lines::Sorted => lines.sort_unstable(),
+        Lines::Set => {
+            lines = lines
+                .into_iter()
+                .map(str::trim)
+                .filter(|x| !x.is_empty())
+                .collect();
+            lines.sort_unstable();
+            lines.dedup();
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        arbitrary::Gen, document_diff, document_diff_by, parse_back, parse_multi, parse_one,
+        semantic_diff, semantic_diff_by, semantic_diff_with, semantic_eq, semantic_eq_by,
+        try_parse_back, DiffOptions, FieldCompare, FieldDelta, IndexMap, Item, SerializeError,
+    };
+
+    #[test]
+    fn test_semantic_diff() {
+        let a = parse_one(
+            "Package: bash\nVersion: 5.2.15-2+b7\nConffiles:\n /etc/bash.bashrc 89269e1298235f1b12b4c16e4065ad0d\n /etc/skel/.bashrc ee35a240758f374832e809ae0ea4883a\nEssential: yes\n",
+        )
+        .unwrap();
+        let b = parse_one(
+            "package: bash\nVersion: 5.2.15-2+b8\nconffiles:\n /etc/skel/.bashrc ee35a240758f374832e809ae0ea4883a\n /etc/bash.bashrc 89269e1298235f1b12b4c16e4065ad0d\nMulti-Arch: foreign\n",
+        )
+        .unwrap();
+
+        assert!(!semantic_eq(&a, &b));
+        assert_eq!(
+            semantic_diff_with(&a, &b, &["Conffiles"]),
+            vec![
+                FieldDelta::Changed {
+                    key: "Version".to_string(),
+                    a: Item::OneLine("5.2.15-2+b7".to_string()),
+                    b: Item::OneLine("5.2.15-2+b8".to_string()),
+                },
+                FieldDelta::OnlyInA {
+                    key: "Essential".to_string(),
+                    value: Item::OneLine("yes".to_string()),
+                },
+                FieldDelta::OnlyInB {
+                    key: "Multi-Arch".to_string(),
+                    value: Item::OneLine("foreign".to_string()),
+                },
+            ]
+        );
+        assert_eq!(semantic_diff(&a, &b).len(), 4);
+    }
+
+    #[test]
+    fn test_semantic_diff_by() {
+        let a = parse_one(
+            "Package: bash\nConffiles:\n /etc/bash.bashrc 89269e1298235f1b12b4c16e4065ad0d\n /etc/skel/.bashrc ee35a240758f374832e809ae0ea4883a\nDescription-md5: 4b1d7cc6f0c3e4dc1f3b8ef5ab5a8ef3\n",
+        )
+        .unwrap();
+        let b = parse_one(
+            "Package: bash\nConffiles:\n /etc/skel/.bashrc ee35a240758f374832e809ae0ea4883a \n  /etc/bash.bashrc 89269e1298235f1b12b4c16e4065ad0d\n\t/etc/bash.bashrc 89269e1298235f1b12b4c16e4065ad0d\n",
+        )
+        .unwrap();
+        let mut opts = DiffOptions::default();
+
+        assert_eq!(
+            semantic_diff_by(&a, &b, &opts),
+            vec![FieldDelta::Equivalent {
+                key: "Conffiles".to_string(),
+                a: a["Conffiles"].clone(),
+                b: b["Conffiles"].clone(),
+            }]
+        );
+        assert!(semantic_eq_by(&a, &b, &opts));
+        assert_eq!(semantic_diff(&a, &b).len(), 2);
+        assert_eq!(
+            semantic_diff_by(&a, &b, &DiffOptions::exact()),
+            semantic_diff(&a, &b)
+        );
+
+        let c = parse_one(&parse_back(std::slice::from_ref(&b)).replace("ee35", "ee36")).unwrap();
+        assert!(matches!(
+            &semantic_diff_by(&a, &c, &opts)[..],
+            [FieldDelta::Changed { key, .. }] if key == "Conffiles"
+        ));
+
+        opts.set("conffiles", FieldCompare::Ignore)
+            .set("DESCRIPTION-MD5", FieldCompare::OrderedExact);
+        assert!(matches!(
+            &semantic_diff_by(&a, &c, &opts)[..],
+            [FieldDelta::OnlyInA { key, .. }] if key == "Description-md5"
+        ));
+        assert!(!semantic_eq_by(&a, &b, &opts));
+    }
+
+    #[test]
+    fn test_document_diff() {
+        let old = parse_multi(
+            "Package: hello\nVersion: 2.10-2\n\nPackage: zlib1g\nVersion: 1:1.2.13.dfsg-1\n\nPackage: Bash\nVersion: 5.2.15-2+b7\n",
+        )
+        .unwrap();
+        let new = parse_multi(
+            "package: bash\nVersion: 5.2.15-2+b7\n\nPackage: hello\nVersion: 2.10-3\n\nPackage: zsync\nVersion: 0.6.2-4\n\nSource: orphan\n",
+        )
+        .unwrap();
+
+        let diff = document_diff(&old, &new, "Package");
+
+        assert_eq!(diff.added, vec![&new[2]]);
+        assert_eq!(diff.removed, vec![&old[1]]);
+        // `Bash` and `bash` are the same package, but the value did change
+        assert_eq!(
+            diff.changed,
+            vec![
+                (
+                    "bash".to_string(),
+                    vec![FieldDelta::Changed {
+                        key: "Package".to_string(),
+                        a: Item::OneLine("Bash".to_string()),
+                        b: Item::OneLine("bash".to_string()),
+                    }]
+                ),
+                (
+                    "hello".to_string(),
+                    vec![FieldDelta::Changed {
+                        key: "Version".to_string(),
+                        a: Item::OneLine("2.10-2".to_string()),
+                        b: Item::OneLine("2.10-3".to_string()),
+                    }]
+                )
+            ]
+        );
+        assert!(document_diff(&new, &new, "package").is_empty());
+
+        let reordered = parse_multi("Source: a\nFiles:\n 01 1 a.dsc\n 02 2 a.tar.xz\n").unwrap();
+        let back = parse_multi("Source: a\nFiles:\n 02 2 a.tar.xz\n 01 1 a.dsc\n").unwrap();
+        let diff = document_diff_by(&reordered, &back, "Source", &DiffOptions::default());
+
+        assert!(matches!(
+            &diff.changed[..],
+            [(key, delta)] if key == "a" && matches!(&delta[..], [FieldDelta::Equivalent { .. }])
+        ));
+    }
+
+    #[test]
+    fn test_semantic_eq_round_trip() {
+        let mut g = Gen::new(0x8d8d_8d8d);
+        // also values that can't be written
+        let lines = |g: &mut Gen| (0..g.below(4)).map(|_| g.text(8)).collect();
+
+        for _ in 0..500 {
+            let mut p = IndexMap::new();
+
+            for i in 0..g.below(6) + 1 {
+                let key = format!("{}-{}", g.choose(&["Package", "x-Field", "DESC"]), i);
+                let item = match g.below(3) {
+                    0 => Item::OneLine(g.text(8)),
+                    1 => Item::MultiLine(lines(&mut g)),
+                    _ => Item::Folded(g.text(8), lines(&mut g)),
+                };
+
+                p.insert(key, item);
+            }
+
+            // the serializer refuses the values that have no exact form
+            let s = match try_parse_back(&[p.clone()]) {
+                Ok(s) => s,
+                Err(e) => {
+                    assert!(matches!(e, SerializeError::InvalidValue { .. }), "{}", e);
+                    continue;
+                }
+            };
+            let back = parse_one(&s).unwrap();
+
+            assert!(semantic_eq(&p, &back), "{:?}\n{}", p, s);
+        }
+    }
+}
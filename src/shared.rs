@@ -0,0 +1,175 @@
+use std::sync::Arc;
+
+use crate::{index_by, IndexMap, KeyBy, PackageId, Paragraph};
+
+/// Paragraphs indexed by package, shared between threads
+///
+/// Cloning is cheap, it only counts references. A clone changed with
+/// [`SharedParagraphs::make_mut`] copies the paragraph it changes and keeps
+/// sharing the others; other clones don't see the change.
+///
+/// ```rust
+/// use eight_deep_parser::{parse_multi, Item, ParagraphExt, SharedParagraphs};
+///
+/// let shared = SharedParagraphs::new(parse_multi("Package: a\nVersion: 1\n").unwrap());
+/// let mut local = shared.clone();
+///
+/// local
+///     .make_mut("a")
+///     .unwrap()
+///     .insert("Version".to_string(), Item::OneLine("2".to_string()));
+///
+/// assert_eq!(shared.get("a").unwrap().get_one("Version"), Some("1"));
+/// assert_eq!(local.get("a").unwrap().get_one("Version"), Some("2"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SharedParagraphs {
+    paragraphs: Arc<Vec<Arc<Paragraph>>>,
+    index: Arc<IndexMap<PackageId, Vec<usize>>>,
+}
+
+impl SharedParagraphs {
+    /// Index `paragraphs` by [`KeyBy::Package`]
+    pub fn new(paragraphs: Vec<Paragraph>) -> Self {
+        Self::with_key(paragraphs, KeyBy::Package)
+    }
+
+    /// Index `paragraphs` by `key`, like [`index_by`]
+    ///
+    /// With [`KeyBy::PackageId`] paragraphs are looked up as `name:arch`, or
+    /// `name` for those without an architecture or of `Architecture: all`.
+    pub fn with_key(paragraphs: Vec<Paragraph>, key: KeyBy) -> Self {
+        let index = index_by(&paragraphs, key);
+
+        Self {
+            paragraphs: Arc::new(paragraphs.into_iter().map(Arc::new).collect()),
+            index: Arc::new(index),
+        }
+    }
+
+    /// The first paragraph with the key `id`, `name` or `name:arch`
+    pub fn get(&self, id: &str) -> Option<&Paragraph> {
+        let i = self.position(id)?;
+
+        Some(&self.paragraphs[i])
+    }
+
+    /// The first paragraph with the key `id` for changing, copied first if
+    /// another clone shares it
+    ///
+    /// The index isn't updated, so the paragraph keeps its key even if its
+    /// `Package` field is changed.
+    pub fn make_mut(&mut self, id: &str) -> Option<&mut Paragraph> {
+        let i = self.position(id)?;
+
+        Some(Arc::make_mut(&mut Arc::make_mut(&mut self.paragraphs)[i]))
+    }
+
+    /// Every paragraph, in the order given
+    pub fn iter(&self) -> impl Iterator<Item = &Paragraph> {
+        self.paragraphs.iter().map(|x| &**x)
+    }
+
+    pub fn len(&self) -> usize {
+        self.paragraphs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paragraphs.is_empty()
+    }
+
+    fn position(&self, id: &str) -> Option<usize> {
+        self.index
+            .get(&id.parse::<PackageId>().ok()?)?
+            .first()
+            .copied()
+    }
+}
+
+impl From<Vec<Paragraph>> for SharedParagraphs {
+    fn from(paragraphs: Vec<Paragraph>) -> Self {
+        Self::new(paragraphs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::{parse_multi, test_util::fixtures, Item, KeyBy, ParagraphExt, SharedParagraphs};
+
+    #[test]
+    fn test_shared_paragraphs_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<SharedParagraphs>();
+    }
+
+    #[test]
+    fn test_shared_paragraphs_cow() {
+        let shared = SharedParagraphs::from(fixtures::status());
+        let mut local = shared.clone();
+
+        assert_eq!(local.len(), 4);
+        assert!(std::ptr::eq(
+            shared.get("bash").unwrap(),
+            local.get("bash").unwrap()
+        ));
+
+        local
+            .make_mut("bash")
+            .unwrap()
+            .insert("Version".to_string(), Item::OneLine("9".to_string()));
+
+        assert_eq!(local.get("bash").unwrap().get_one("Version"), Some("9"));
+        assert_eq!(shared.get("bash").unwrap(), &fixtures::installed("bash"));
+        assert!(!std::ptr::eq(
+            shared.get("bash").unwrap(),
+            local.get("bash").unwrap()
+        ));
+        assert!(std::ptr::eq(
+            shared.get("dpkg").unwrap(),
+            local.get("dpkg").unwrap()
+        ));
+        assert!(local.make_mut("missing").is_none());
+
+        let workers = (0..4)
+            .map(|i| {
+                let mut local = shared.clone();
+
+                thread::spawn(move || {
+                    local
+                        .make_mut("dpkg")
+                        .unwrap()
+                        .insert("Worker".to_string(), Item::OneLine(i.to_string()));
+                    local
+                        .get("dpkg")
+                        .unwrap()
+                        .get_one("Worker")
+                        .map(|x| x.to_string())
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for (i, worker) in workers.into_iter().enumerate() {
+            assert_eq!(worker.join().unwrap(), Some(i.to_string()));
+        }
+
+        assert_eq!(shared.get("dpkg").unwrap().get_one("Worker"), None);
+    }
+
+    #[test]
+    fn test_shared_paragraphs_with_key() {
+        let ps = parse_multi(fixtures::STATUS_STATES).unwrap();
+        let shared = SharedParagraphs::with_key(ps.clone(), KeyBy::PackageId);
+
+        assert_eq!(
+            shared.get("libc6:i386").unwrap().get_one("Architecture"),
+            Some("i386")
+        );
+        assert!(shared.get("libc6").is_none());
+        assert!(shared.get("not a name").is_none());
+        assert_eq!(shared.iter().cloned().collect::<Vec<_>>(), ps);
+        assert!(SharedParagraphs::default().is_empty());
+    }
+}
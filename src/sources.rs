@@ -0,0 +1,334 @@
+use std::fmt::Display;
+
+use crate::{control::parse_yes_no, Item, Paragraph, ParagraphExt};
+
+/// An entry of the `Types` field of a `.sources` file
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SourceType {
+    /// `deb`, binary packages
+    Deb,
+    /// `deb-src`, source packages
+    DebSrc,
+    Other(String),
+}
+
+impl From<&str> for SourceType {
+    fn from(s: &str) -> Self {
+        match s {
+            "deb" => SourceType::Deb,
+            "deb-src" => SourceType::DebSrc,
+            _ => SourceType::Other(s.to_string()),
+        }
+    }
+}
+
+impl Display for SourceType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SourceType::Deb => "deb",
+            SourceType::DebSrc => "deb-src",
+            SourceType::Other(s) => s,
+        })
+    }
+}
+
+/// The `Signed-By` field of a `.sources` file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignedBy {
+    /// Keyring paths or key fingerprints, as written
+    Path(String),
+    /// An ASCII-armored key block, one line per line of the block
+    InlineKey(Vec<String>),
+}
+
+const KEY_BLOCK_START: &str = "-----BEGIN PGP PUBLIC KEY BLOCK-----";
+
+impl SignedBy {
+    /// Read the field value
+    ///
+    /// A value holding a `-----BEGIN PGP PUBLIC KEY BLOCK-----` line is an
+    /// inline key; its blank lines are written as ` .` in the file and show
+    /// up here as empty strings.
+    pub fn from_item(item: &Item) -> Self {
+        let lines = item.value_lines().collect::<Vec<_>>();
+
+        if lines.iter().any(|x| x.trim() == KEY_BLOCK_START) {
+            SignedBy::InlineKey(
+                lines
+                    .into_iter()
+                    .skip_while(|x| x.is_empty())
+                    .map(|x| {
+                        if x == "." {
+                            String::new()
+                        } else {
+                            x.to_string()
+                        }
+                    })
+                    .collect(),
+            )
+        } else {
+            SignedBy::Path(lines.join(" ").trim().to_string())
+        }
+    }
+
+    /// Encode into a field value, writing blank lines of a key as `.`
+    pub fn to_item(&self) -> Item {
+        match self {
+            SignedBy::Path(s) => Item::OneLine(s.clone()),
+            SignedBy::InlineKey(lines) => Item::MultiLine(
+                lines
+                    .iter()
+                    .map(|x| {
+                        if x.is_empty() {
+                            ".".to_string()
+                        } else {
+                            x.clone()
+                        }
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// A paragraph of a deb822 apt sources file
+/// (`/etc/apt/sources.list.d/*.sources`)
+///
+/// This is a view over the paragraph: fields without an accessor are kept
+/// and [`SourcesEntry::into_paragraph`] gives it back for
+/// [`parse_back`](crate::parse_back).
+///
+/// ```rust
+/// use eight_deep_parser::{parse_one, SourceType, SourcesEntry};
+///
+/// let r = parse_one("Types: deb deb-src\nURIs: http://deb.debian.org/debian\nSuites: bookworm bookworm-updates\nComponents: main\n").unwrap();
+/// let entry = SourcesEntry::new(r);
+///
+/// assert_eq!(entry.types(), vec![SourceType::Deb, SourceType::DebSrc]);
+/// assert_eq!(entry.suites(), vec!["bookworm", "bookworm-updates"]);
+/// assert!(entry.is_valid());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourcesEntry {
+    paragraph: Paragraph,
+}
+
+impl SourcesEntry {
+    pub fn new(paragraph: Paragraph) -> Self {
+        Self { paragraph }
+    }
+
+    pub fn paragraph(&self) -> &Paragraph {
+        &self.paragraph
+    }
+
+    pub fn into_paragraph(self) -> Paragraph {
+        self.paragraph
+    }
+
+    fn list(&self, key: &str) -> Vec<&str> {
+        self.paragraph
+            .get_field(key)
+            .map(Item::split_whitespace_list)
+            .unwrap_or_default()
+    }
+
+    pub fn types(&self) -> Vec<SourceType> {
+        self.list("Types")
+            .into_iter()
+            .map(SourceType::from)
+            .collect()
+    }
+
+    pub fn uris(&self) -> Vec<&str> {
+        self.list("URIs")
+    }
+
+    pub fn suites(&self) -> Vec<&str> {
+        self.list("Suites")
+    }
+
+    pub fn components(&self) -> Vec<&str> {
+        self.list("Components")
+    }
+
+    /// Whether apt uses this entry: `true` unless `Enabled` is `no`
+    pub fn enabled(&self) -> bool {
+        self.paragraph
+            .get_one("Enabled")
+            .and_then(parse_yes_no)
+            .unwrap_or(true)
+    }
+
+    pub fn signed_by(&self) -> Option<SignedBy> {
+        self.paragraph
+            .get_field("Signed-By")
+            .map(SignedBy::from_item)
+    }
+
+    /// Set `Signed-By`, keeping its position if it's already there
+    pub fn set_signed_by(&mut self, signed_by: &SignedBy) {
+        let item = signed_by.to_item();
+
+        match self
+            .paragraph
+            .iter_mut()
+            .find(|(k, _)| k.eq_ignore_ascii_case("Signed-By"))
+        {
+            Some((_, v)) => *v = item,
+            None => {
+                self.paragraph.insert("Signed-By".to_string(), item);
+            }
+        }
+    }
+
+    /// Mandatory fields that are missing or empty
+    ///
+    /// `Types`, `URIs` and `Suites` are always mandatory; `Components` is
+    /// too unless every suite is an exact path, ending in `/`.
+    pub fn missing_fields(&self) -> Vec<&'static str> {
+        let mut result = vec![];
+
+        for key in ["Types", "URIs", "Suites"] {
+            if self.list(key).is_empty() {
+                result.push(key);
+            }
+        }
+
+        let suites = self.suites();
+        let exact_path = !suites.is_empty() && suites.iter().all(|x| x.ends_with('/'));
+
+        if self.components().is_empty() && !exact_path {
+            result.push("Components");
+        }
+
+        result
+    }
+
+    /// Whether no mandatory field is missing, see
+    /// [`SourcesEntry::missing_fields`]
+    pub fn is_valid(&self) -> bool {
+        self.missing_fields().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_back, parse_multi, SignedBy, SourceType, SourcesEntry};
+
+    const SOURCES: &str = "Types: deb
+URIs: http://deb.debian.org/debian
+Suites: bookworm bookworm-updates
+Components: main contrib
+ non-free-firmware
+Signed-By: /usr/share/keyrings/debian-archive-keyring.gpg
+
+Types: deb deb-src
+URIs: https://repo.example.org/apt
+Suites: stable
+Components: main
+Enabled: no
+Signed-By:
+ -----BEGIN PGP PUBLIC KEY BLOCK-----
+ .
+ mDMEZbv1JRYJKwYBBAHaRw8BAQdAm8cdtBa3xQKoLfbPfmxVz1V2/FtKcHMXrYbp
+ Y2tDpnO0G0V4YW1wbGUgUmVwbyA8YXB0QGV4YW1wbGUub3JnPoiTBBMWCgA7FiEE
+ =d7Xk
+ -----END PGP PUBLIC KEY BLOCK-----
+
+Types: deb
+URIs: file:/srv/local-repo
+Suites: ./
+Architectures: amd64
+";
+
+    #[test]
+    fn test_sources_entry() {
+        let r = parse_multi(SOURCES).unwrap();
+        let entries = r.into_iter().map(SourcesEntry::new).collect::<Vec<_>>();
+
+        assert_eq!(entries[0].types(), vec![SourceType::Deb]);
+        assert_eq!(entries[0].uris(), vec!["http://deb.debian.org/debian"]);
+        assert_eq!(
+            entries[0].components(),
+            vec!["main", "contrib", "non-free-firmware"]
+        );
+        assert!(entries[0].enabled());
+        assert!(!entries[1].enabled());
+        assert_eq!(
+            entries[1].types(),
+            vec![SourceType::Deb, SourceType::DebSrc]
+        );
+        assert_eq!(
+            entries[0].signed_by(),
+            Some(SignedBy::Path(
+                "/usr/share/keyrings/debian-archive-keyring.gpg".to_string()
+            ))
+        );
+        assert_eq!(entries[2].signed_by(), None);
+    }
+
+    #[test]
+    fn test_sources_valid() {
+        let r = parse_multi(SOURCES).unwrap();
+
+        for p in r {
+            assert!(SourcesEntry::new(p).is_valid());
+        }
+
+        let r = parse_multi("Types: deb\nSuites: stable\n\nURIs: file:/a\nSuites: ./\n").unwrap();
+
+        assert_eq!(
+            SourcesEntry::new(r[0].clone()).missing_fields(),
+            vec!["URIs", "Components"]
+        );
+        assert_eq!(
+            SourcesEntry::new(r[1].clone()).missing_fields(),
+            vec!["Types"]
+        );
+    }
+
+    #[test]
+    fn test_signed_by_inline_key() {
+        let r = parse_multi(SOURCES).unwrap();
+        let mut entry = SourcesEntry::new(r[1].clone());
+
+        let key = match entry.signed_by().unwrap() {
+            SignedBy::InlineKey(key) => key,
+            x => panic!("{:?}", x),
+        };
+
+        assert_eq!(key.len(), 6);
+        assert_eq!(key[0], "-----BEGIN PGP PUBLIC KEY BLOCK-----");
+        assert_eq!(key[1], "");
+        assert_eq!(key[5], "-----END PGP PUBLIC KEY BLOCK-----");
+
+        entry.set_signed_by(&SignedBy::InlineKey(key));
+
+        assert_eq!(&entry.paragraph, &r[1]);
+
+        let s = parse_back(&[entry.into_paragraph()]);
+
+        assert_eq!(parse_multi(&s).unwrap()[0], r[1]);
+        assert!(s.contains("Signed-By:\n -----BEGIN PGP PUBLIC KEY BLOCK-----\n .\n mDMEZbv1JRYJ"));
+    }
+
+    #[test]
+    fn test_set_signed_by() {
+        let r = parse_multi(SOURCES).unwrap();
+        let mut entry = SourcesEntry::new(r[0].clone());
+
+        entry.set_signed_by(&SignedBy::Path("/etc/apt/keyrings/a.gpg".to_string()));
+
+        assert_eq!(entry.paragraph().get_index_of("Signed-By"), Some(4));
+        assert_eq!(
+            entry.signed_by(),
+            Some(SignedBy::Path("/etc/apt/keyrings/a.gpg".to_string()))
+        );
+
+        let mut entry = SourcesEntry::new(r[2].clone());
+        entry.set_signed_by(&SignedBy::Path("/etc/apt/keyrings/a.gpg".to_string()));
+
+        assert_eq!(entry.paragraph().get_index_of("Signed-By"), Some(4));
+    }
+}
@@ -0,0 +1,115 @@
+use crate::{ParseError, Result};
+
+/// Counts of [`quick_stats`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct QuickStats {
+    pub paragraphs: usize,
+    /// Fields in all paragraphs
+    pub fields: usize,
+    /// Length of the input
+    pub bytes: usize,
+    /// Fields in the paragraph with the most of them
+    pub max_paragraph_fields: usize,
+}
+
+/// Count the paragraphs and fields of `s` without parsing the values
+///
+/// Only the first byte of each line and whether it has a `:` are looked
+/// at, nothing is allocated. The counts are those of
+/// [`parse_multi`](crate::parse_multi), except that `#` comment lines are
+/// skipped and a field repeated in a paragraph counts every time. A line
+/// that is neither a field, a continuation line, a comment nor blank fails
+/// with [`ParseError::Malformed`].
+///
+/// ```rust
+/// use eight_deep_parser::quick_stats;
+///
+/// let stats = quick_stats(b"Package: a\nDepends: b,\n c\n\nPackage: d\n").unwrap();
+///
+/// assert_eq!((stats.paragraphs, stats.fields, stats.max_paragraph_fields), (2, 3, 2));
+/// ```
+pub fn quick_stats(s: &[u8]) -> Result<QuickStats> {
+    let mut stats = QuickStats {
+        bytes: s.len(),
+        ..Default::default()
+    };
+    let mut fields = 0;
+
+    for (i, line) in s.split(|&x| x == b'\n').enumerate() {
+        match line.first() {
+            None => {
+                stats.end_paragraph(fields);
+                fields = 0;
+            }
+            Some(b' ' | b'\t' | b'#') => {}
+            Some(_) if line.contains(&b':') => fields += 1,
+            Some(_) => return Err(ParseError::Malformed { line: i + 1 }),
+        }
+    }
+
+    stats.end_paragraph(fields);
+
+    Ok(stats)
+}
+
+impl QuickStats {
+    fn end_paragraph(&mut self, fields: usize) {
+        if fields != 0 {
+            self.paragraphs += 1;
+            self.fields += fields;
+            self.max_paragraph_fields = self.max_paragraph_fields.max(fields);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_multi, quick_stats, test_util::fixtures, ParseError, QuickStats};
+
+    #[test]
+    fn test_quick_stats_fixtures() {
+        let sorted = std::fs::read_to_string(fixtures::PACKAGES_SORTED_PATH).unwrap();
+
+        for s in [
+            fixtures::STATUS,
+            fixtures::STATUS_STATES,
+            fixtures::PACKAGES,
+            &sorted,
+            fixtures::SOURCES,
+            fixtures::RELEASE,
+        ] {
+            let r = parse_multi(s).unwrap();
+
+            assert_eq!(
+                quick_stats(s.as_bytes()).unwrap(),
+                QuickStats {
+                    paragraphs: r.len(),
+                    fields: r.iter().map(|x| x.len()).sum(),
+                    bytes: s.len(),
+                    max_paragraph_fields: r.iter().map(|x| x.len()).max().unwrap(),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_quick_stats() {
+        let s = b"\n \n# header\n orphan\nPackage: a\n \n .\n\n\n# between\nPackage: b\nVersion: 1";
+        let stats = quick_stats(s).unwrap();
+
+        assert_eq!(
+            stats,
+            QuickStats {
+                paragraphs: 2,
+                fields: 3,
+                bytes: s.len(),
+                max_paragraph_fields: 2,
+            }
+        );
+        assert_eq!(quick_stats(b"").unwrap(), QuickStats::default());
+        assert!(matches!(
+            quick_stats(b"Package: a\nbroken\n"),
+            Err(ParseError::Malformed { line: 2 })
+        ));
+    }
+}
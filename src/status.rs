@@ -0,0 +1,290 @@
+use std::path::Path;
+
+use crate::{parse_back, parse_multi, IndexMap, PackageState, Paragraph, ParagraphExt, Result};
+
+/// The fields dpkg knows, in the order it writes them. Other fields follow
+/// in their original order.
+const DPKG_FIELD_ORDER: &[&str] = &[
+    "Package",
+    "Essential",
+    "Protected",
+    "Status",
+    "Priority",
+    "Section",
+    "Installed-Size",
+    "Origin",
+    "Maintainer",
+    "Bugs",
+    "Architecture",
+    "Multi-Arch",
+    "Source",
+    "Version",
+    "Revision",
+    "Config-Version",
+    "Replaces",
+    "Provides",
+    "Depends",
+    "Pre-Depends",
+    "Recommends",
+    "Suggests",
+    "Breaks",
+    "Conflicts",
+    "Enhances",
+    "Conffiles",
+    "Filename",
+    "Size",
+    "MD5sum",
+    "MSDOS-Filename",
+    "Description",
+    "Triggers-Pending",
+    "Triggers-Awaited",
+];
+
+/// Write a paragraph the way `dpkg -s` and `/var/lib/dpkg/status` do
+///
+/// Fields dpkg knows are written in dpkg's order, matched
+/// case-insensitively, and the others after them in their original order.
+/// Values are formatted as by [`parse_back`], which is what dpkg does too:
+/// one space after the colon, continuation lines like `Conffiles` and the
+/// extended `Description` indented by one space.
+///
+/// ```rust
+/// use eight_deep_parser::{parse_back_dpkg_status, parse_one};
+///
+/// let r = parse_one("Version: 1.0\nHomepage: https://example.org\nPackage: a\n").unwrap();
+///
+/// assert_eq!(
+///     parse_back_dpkg_status(&r),
+///     "Package: a\nVersion: 1.0\nHomepage: https://example.org\n"
+/// );
+/// ```
+///
+/// # Panics
+///
+/// Panics like [`parse_back`] if the paragraph can't be written.
+pub fn parse_back_dpkg_status(p: &Paragraph) -> String {
+    let mut ordered = Paragraph::with_capacity(p.len());
+
+    for field in DPKG_FIELD_ORDER {
+        if let Some((k, v)) = p.iter().find(|(k, _)| k.eq_ignore_ascii_case(field)) {
+            ordered.insert(k.clone(), v.clone());
+        }
+    }
+
+    for (k, v) in p {
+        if !ordered.contains_key(k) {
+            ordered.insert(k.clone(), v.clone());
+        }
+    }
+
+    parse_back(&[ordered])
+}
+
+/// The dpkg database, `/var/lib/dpkg/status`, with its paragraphs indexed
+/// by package name
+///
+/// ```rust
+/// use eight_deep_parser::{ParagraphExt, StatusFile};
+///
+/// let status = StatusFile::parse(
+///     "Package: a\nStatus: install ok installed\n\n\
+///      Package: b\nStatus: deinstall ok config-files\n",
+/// )
+/// .unwrap();
+///
+/// assert_eq!(status.installed().count(), 1);
+/// assert_eq!(status.get("b", None).unwrap().get_one("Status"), Some("deinstall ok config-files"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusFile {
+    paragraphs: Vec<Paragraph>,
+    by_name: IndexMap<String, Vec<usize>>,
+}
+
+impl StatusFile {
+    pub fn parse(s: &str) -> Result<Self> {
+        Ok(Self::new(parse_multi(s)?))
+    }
+
+    /// Read and parse a status file, usually `/var/lib/dpkg/status`
+    pub fn read(path: impl AsRef<Path>) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn new(paragraphs: Vec<Paragraph>) -> Self {
+        let mut by_name: IndexMap<String, Vec<usize>> = IndexMap::new();
+
+        for (i, p) in paragraphs.iter().enumerate() {
+            if let Some(name) = p.get_one("Package") {
+                by_name.entry(name.to_string()).or_default().push(i);
+            }
+        }
+
+        Self {
+            paragraphs,
+            by_name,
+        }
+    }
+
+    pub fn paragraphs(&self) -> &[Paragraph] {
+        &self.paragraphs
+    }
+
+    /// The installed packages: state `installed` with the flag `ok`, wanted
+    /// for install or held, like `dpkg-query` lists them
+    pub fn installed(&self) -> impl Iterator<Item = &Paragraph> {
+        self.paragraphs.iter().filter(|p| {
+            p.status().is_some_and(|x| {
+                x.state == PackageState::Installed
+                    && x.flag == "ok"
+                    && (x.want == "install" || x.want == "hold")
+            })
+        })
+    }
+
+    /// The removed packages whose configuration files are left, which
+    /// `dpkg --purge` would clean up
+    pub fn config_files_only(&self) -> impl Iterator<Item = &Paragraph> {
+        self.in_state(PackageState::ConfigFiles)
+    }
+
+    /// The packages whose configuration failed or was interrupted
+    pub fn half_configured(&self) -> impl Iterator<Item = &Paragraph> {
+        self.in_state(PackageState::HalfConfigured)
+    }
+
+    /// The installed packages marked `Essential: yes`, see
+    /// [`StatusFile::installed`]
+    pub fn essential_packages(&self) -> impl Iterator<Item = &Paragraph> {
+        self.installed().filter(|p| p.essential() == Some(true))
+    }
+
+    /// The paragraph of `name`, of architecture `arch` if given
+    ///
+    /// `Architecture: all` packages match any `arch`. Without `arch` the
+    /// package must be known for one architecture only: a `Multi-Arch: same`
+    /// library installed for several gives `None`, as `dpkg -s` refuses
+    /// such an ambiguous name.
+    pub fn get(&self, name: &str, arch: Option<&str>) -> Option<&Paragraph> {
+        let mut found = self.by_name.get(name)?.iter().map(|&i| &self.paragraphs[i]);
+
+        match arch {
+            Some(arch) => found.find(|p| {
+                p.identity()
+                    .is_some_and(|x| x.arch.is_none() || x.arch.as_deref() == Some(arch))
+            }),
+            None => {
+                let first = found.next()?;
+
+                found.next().is_none().then_some(first)
+            }
+        }
+    }
+
+    fn in_state(&self, state: PackageState) -> impl Iterator<Item = &Paragraph> {
+        self.paragraphs
+            .iter()
+            .filter(move |p| p.status().is_some_and(|x| x.state == state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse_back_dpkg_status, parse_one, test_util::fixtures, Paragraph, ParagraphExt, StatusFile,
+    };
+
+    // `dpkg -s bash` on Debian 12
+    const BASH: &str = r#"Package: bash
+Essential: yes
+Status: install ok installed
+Priority: required
+Section: shells
+Installed-Size: 7164
+Maintainer: Matthias Klose <doko@debian.org>
+Architecture: amd64
+Multi-Arch: foreign
+Source: bash (5.2.15-2)
+Version: 5.2.15-2+b8
+Replaces: bash-completion (<< 20060301-0), bash-doc (<= 2.05-1)
+Depends: base-files (>= 2.1.12), debianutils (>= 5.6-0.1)
+Pre-Depends: libc6 (>= 2.36), libtinfo6 (>= 6)
+Recommends: bash-completion (>= 20060301-0)
+Suggests: bash-doc
+Conflicts: bash-completion (<< 20060301-0)
+Conffiles:
+ /etc/bash.bashrc 89269e1298235f1b12b4c16e4065ad0d
+ /etc/skel/.bash_logout 22bfb8c1dd94b5f3813a2b25da67463f
+ /etc/skel/.bashrc ee35a240758f374832e809ae0ea4883a
+ /etc/skel/.profile f4e81ade7d6f9fb342541152d08e7a97
+Description: GNU Bourne Again SHell
+ Bash is an sh-compatible command language interpreter that executes
+ commands read from the standard input or from a file.  Bash also
+ incorporates useful features from the Korn and C shells (ksh and csh).
+ .
+ Bash is ultimately intended to be a conformant implementation of the
+ IEEE POSIX Shell and Tools specification (IEEE Working Group 1003.2).
+ .
+ The Programmable Completion Code, by Ian Macdonald, is now found in
+ the bash-completion package.
+Homepage: http://tiswww.case.edu/php/chet/bash/bashtop.html
+"#;
+
+    #[test]
+    fn test_parse_back_dpkg_status() {
+        let r = parse_one(BASH).unwrap();
+
+        assert_eq!(parse_back_dpkg_status(&r), BASH);
+
+        let shuffled = r
+            .iter()
+            .rev()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect::<Paragraph>();
+
+        assert_eq!(shuffled.get_index(0).unwrap().0, "Homepage");
+        assert_eq!(parse_back_dpkg_status(&shuffled), BASH);
+    }
+
+    fn names<'a>(ps: impl Iterator<Item = &'a Paragraph>) -> Vec<String> {
+        ps.map(|p| p.identity().unwrap().to_string()).collect()
+    }
+
+    #[test]
+    fn test_status_file() {
+        let status = StatusFile::parse(fixtures::STATUS_STATES).unwrap();
+
+        assert_eq!(
+            names(status.installed()),
+            [
+                "bash:amd64",
+                "libc6:amd64",
+                "libc6:i386",
+                "base-files:amd64",
+                "tzdata"
+            ]
+        );
+        assert_eq!(names(status.config_files_only()), ["apache2:amd64"]);
+        assert_eq!(names(status.half_configured()), ["postfix:amd64"]);
+        assert_eq!(
+            names(status.essential_packages()),
+            ["bash:amd64", "base-files:amd64"]
+        );
+
+        let get = |name, arch| status.get(name, arch).and_then(|x| x.identity());
+
+        assert_eq!(
+            get("libc6", Some("i386")).unwrap().to_string(),
+            "libc6:i386"
+        );
+        assert_eq!(get("libc6", None), None);
+        assert_eq!(get("libc6", Some("arm64")), None);
+        assert_eq!(get("tzdata", Some("arm64")).unwrap().to_string(), "tzdata");
+        assert_eq!(get("bash", None).unwrap().to_string(), "bash:amd64");
+        assert_eq!(get("zsh", None), None);
+
+        let legacy = StatusFile::parse(fixtures::STATUS).unwrap();
+
+        assert_eq!(legacy.installed().count(), legacy.paragraphs().len());
+    }
+}
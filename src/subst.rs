@@ -0,0 +1,253 @@
+use crate::{
+    error::SubstError,
+    fields::{FieldInfo, FieldKind},
+    IndexMap, IndexSet, Item, Paragraph, SubstOptions, UnknownVar,
+};
+
+/// Expand `${name}` substitution variables in every value, like
+/// `${misc:Depends}` in a `debian/control` template
+///
+/// The value of a variable may use other variables, up to
+/// [`SubstOptions::max_depth`]. An unterminated `${` is left as it is.
+///
+/// In relation fields like `Depends`, where a variable was expanded, empty
+/// clauses and alternatives are dropped with their separators as
+/// dpkg-gencontrol does, and the field is written on one line.
+///
+/// Returns the names of the variables used, in the order first used. On
+/// error the paragraph is left unchanged.
+///
+/// ```rust
+/// use eight_deep_parser::{parse_one, substitute, IndexMap, ParagraphExt, SubstOptions};
+///
+/// let mut p = parse_one("Package: a\nDepends: libc6, ${misc:Depends}\n").unwrap();
+/// let mut vars = IndexMap::new();
+/// vars.insert("misc:Depends".to_string(), String::new());
+///
+/// let used = substitute(&mut p, &vars, SubstOptions::default()).unwrap();
+///
+/// assert_eq!(p.get_one("Depends"), Some("libc6"));
+/// assert!(used.contains("misc:Depends"));
+/// ```
+pub fn substitute(
+    paragraph: &mut Paragraph,
+    vars: &IndexMap<String, String>,
+    opts: SubstOptions,
+) -> Result<IndexSet<String>, SubstError> {
+    let mut used = IndexSet::new();
+    let mut changed = vec![];
+
+    for (i, (k, v)) in paragraph.iter().enumerate() {
+        if !v.value_lines().any(|x| x.contains("${")) {
+            continue;
+        }
+
+        let mut expand = |s: &str| expand(s, vars, &opts, 0, &mut used);
+
+        let item = match v {
+            Item::OneLine(s) => Item::OneLine(expand(s)?),
+            Item::MultiLine(v) => {
+                Item::MultiLine(v.iter().map(|x| expand(x)).collect::<Result<_, _>>()?)
+            }
+            Item::Folded(s, v) => Item::Folded(
+                expand(s)?,
+                v.iter().map(|x| expand(x)).collect::<Result<_, _>>()?,
+            ),
+            Item::Streamed => Item::Streamed,
+        };
+
+        let item = if FieldInfo::lookup(k).is_some_and(|x| x.kind == FieldKind::Relations) {
+            Item::OneLine(clean_relations(
+                &item.value_lines().collect::<Vec<_>>().join(" "),
+            ))
+        } else {
+            item
+        };
+
+        changed.push((i, item));
+    }
+
+    for (i, item) in changed {
+        paragraph[i] = item;
+    }
+
+    Ok(used)
+}
+
+fn expand(
+    s: &str,
+    vars: &IndexMap<String, String>,
+    opts: &SubstOptions,
+    depth: usize,
+    used: &mut IndexSet<String>,
+) -> Result<String, SubstError> {
+    let mut result = String::new();
+    let mut rest = s;
+
+    while let Some(start) = rest.find("${") {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+
+        result += &rest[..start];
+        let name = &rest[start + 2..end];
+
+        match vars.get(name) {
+            Some(value) => {
+                used.insert(name.to_string());
+
+                if value.contains("${") && depth == opts.max_depth {
+                    return Err(SubstError::TooDeep {
+                        name: name.to_string(),
+                    });
+                }
+
+                result += &expand(value, vars, opts, depth + 1, used)?;
+            }
+            None => match opts.unknown {
+                UnknownVar::Error => {
+                    return Err(SubstError::Unknown {
+                        name: name.to_string(),
+                    })
+                }
+                UnknownVar::Keep => result += &rest[start..=end],
+                UnknownVar::Remove => (),
+            },
+        }
+
+        rest = &rest[end + 1..];
+    }
+
+    result += rest;
+
+    Ok(result)
+}
+
+/// Drop empty clauses and alternatives of a relation field
+fn clean_relations(s: &str) -> String {
+    s.split(',')
+        .map(|clause| {
+            clause
+                .split('|')
+                .map(|x| x.split_whitespace().collect::<Vec<_>>().join(" "))
+                .filter(|x| !x.is_empty())
+                .collect::<Vec<_>>()
+                .join(" | ")
+        })
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse_one, substitute, IndexMap, Item, ParagraphExt, SubstError, SubstOptions, UnknownVar,
+    };
+
+    fn vars(v: &[(&str, &str)]) -> IndexMap<String, String> {
+        v.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_substitute_relations() {
+        let mut p = parse_one(
+            "Package: a
+Depends: foo, ${misc:Depends}
+Recommends: ${misc:Recommends} | bar,
+ ${shlibs:Depends}
+Description: a ${misc:Depends}
+ uses ${source:Version}
+",
+        )
+        .unwrap();
+
+        let used = substitute(
+            &mut p,
+            &vars(&[
+                ("misc:Depends", ""),
+                ("shlibs:Depends", "libc6 (>= ${libc:Version})"),
+                ("libc:Version", "2.36"),
+                ("source:Version", "1.0"),
+            ]),
+            SubstOptions {
+                unknown: UnknownVar::Remove,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(p.get_one("Depends"), Some("foo"));
+        assert_eq!(p.get_one("Recommends"), Some("bar, libc6 (>= 2.36)"));
+        assert_eq!(
+            p["Description"],
+            Item::Folded("a ".to_string(), vec!["uses 1.0".to_string()])
+        );
+        assert_eq!(
+            used.into_iter().collect::<Vec<_>>(),
+            vec![
+                "misc:Depends",
+                "shlibs:Depends",
+                "libc:Version",
+                "source:Version"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_substitute_unknown() {
+        let mut p = parse_one("Package: a\nDepends: ${a}, ${b}\nHomepage: ${c\n").unwrap();
+        let before = p.clone();
+        let vars = vars(&[("a", "x")]);
+
+        assert_eq!(
+            substitute(&mut p, &vars, SubstOptions::default()),
+            Err(SubstError::Unknown {
+                name: "b".to_string()
+            })
+        );
+        assert_eq!(p, before);
+
+        substitute(
+            &mut p,
+            &vars,
+            SubstOptions {
+                unknown: UnknownVar::Keep,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(p.get_one("Depends"), Some("x, ${b}"));
+        assert_eq!(p.get_one("Homepage"), Some("${c"));
+    }
+
+    #[test]
+    fn test_substitute_too_deep() {
+        let mut p = parse_one("Package: ${a}\n").unwrap();
+
+        assert_eq!(
+            substitute(&mut p, &vars(&[("a", "${a}")]), SubstOptions::default()),
+            Err(SubstError::TooDeep {
+                name: "a".to_string()
+            })
+        );
+
+        let opts = SubstOptions {
+            max_depth: 1,
+            ..Default::default()
+        };
+
+        assert!(substitute(
+            &mut p,
+            &vars(&[("a", "${b}"), ("b", "${c}"), ("c", "d")]),
+            opts.clone()
+        )
+        .is_err());
+        assert!(substitute(&mut p, &vars(&[("a", "${b}"), ("b", "c")]), opts).is_ok());
+        assert_eq!(p.get_one("Package"), Some("c"));
+    }
+}
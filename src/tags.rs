@@ -0,0 +1,100 @@
+use crate::Item;
+
+/// Read a debtags `Tag` field into the full list of `facet::tag` entries
+///
+/// Brace groups are expanded, so `implemented-in::{c,c++}` gives
+/// `implemented-in::c` and `implemented-in::c++`.
+///
+/// ```rust
+/// use eight_deep_parser::{parse_tags, Item};
+///
+/// let item = Item::OneLine("role::program, implemented-in::{c,c++}".to_string());
+///
+/// assert_eq!(
+///     parse_tags(&item),
+///     vec!["role::program", "implemented-in::c", "implemented-in::c++"]
+/// );
+/// ```
+pub fn parse_tags(item: &Item) -> Vec<String> {
+    let value = item.value_lines().collect::<Vec<_>>().join(" ");
+
+    let mut result = vec![];
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for (i, c) in value.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                expand(value[start..i].trim(), &mut result);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    expand(value[start..].trim(), &mut result);
+
+    result
+}
+
+fn expand(tag: &str, result: &mut Vec<String>) {
+    if tag.is_empty() {
+        return;
+    }
+
+    let (open, close) = match (tag.find('{'), tag.find('}')) {
+        (Some(open), Some(close)) if open < close => (open, close),
+        _ => {
+            result.push(tag.to_string());
+            return;
+        }
+    };
+
+    let (prefix, suffix) = (&tag[..open], &tag[close + 1..]);
+
+    for i in tag[open + 1..close].split(',') {
+        expand(&format!("{}{}{}", prefix, i.trim(), suffix), result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_one, parse_tags, Item, ParagraphExt};
+
+    #[test]
+    fn test_parse_tags() {
+        let r = parse_one(
+            "Package: bash\nTag: implemented-in::{c,c++}, interface::shell,\n role::program, uitoolkit::{gtk, qt}, works-with::{file,text}\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            parse_tags(r.get_field("Tag").unwrap()),
+            vec![
+                "implemented-in::c",
+                "implemented-in::c++",
+                "interface::shell",
+                "role::program",
+                "uitoolkit::gtk",
+                "uitoolkit::qt",
+                "works-with::file",
+                "works-with::text",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_tags_plain() {
+        assert_eq!(
+            parse_tags(&Item::OneLine("role::program".to_string())),
+            vec!["role::program"]
+        );
+        assert!(parse_tags(&Item::OneLine("".to_string())).is_empty());
+        assert_eq!(
+            parse_tags(&Item::OneLine("x{a,b}::{c,d}".to_string())),
+            vec!["xa::c", "xa::d", "xb::c", "xb::d"]
+        );
+    }
+}
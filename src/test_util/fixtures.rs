@@ -0,0 +1,120 @@
+//! The corpus in `tests/fixtures`: real files of Debian archives, trimmed
+//! to a few entries
+//!
+//! The raw text is in the constants, the functions parse it.
+
+use crate::{parse_multi, parse_one, Paragraph, ParagraphExt, ReleaseFile};
+
+/// Excerpt of `/var/lib/dpkg/status`: `bash`, `zlib1g`, `base-files` and
+/// `dpkg`
+pub const STATUS: &str = include_str!("../../tests/fixtures/status");
+/// A made-up `/var/lib/dpkg/status` with a package in every state, and
+/// `libc6` installed for `amd64` and `i386`
+pub const STATUS_STATES: &str = include_str!("../../tests/fixtures/status-states");
+/// Excerpt of a binary `Packages` index: `hello`, `zlib1g`, `bash` and
+/// `base-files`
+pub const PACKAGES: &str = include_str!("../../tests/fixtures/Packages");
+/// Path of a binary `Packages` index of 24 packages, sorted by name, for
+/// reading from disk
+pub const PACKAGES_SORTED_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/Packages-sorted"
+);
+/// Excerpt of a `Sources` index: `hello` and `zlib`
+pub const SOURCES: &str = include_str!("../../tests/fixtures/Sources");
+/// A `Sources` stanza of `texlive-base`, whose checksum fields the
+/// streaming tests make long
+pub const SOURCES_TEXLIVE: &str = include_str!("../../tests/fixtures/Sources-texlive");
+/// A deb822 `debian.sources` with `#` comments, parsed with
+/// [`ParseOptions::deb822_sources`](crate::ParseOptions::deb822_sources)
+pub const DEB822_SOURCES: &str = include_str!("../../tests/fixtures/debian.sources");
+/// A `Release` file trimmed to a few entries
+pub const RELEASE: &str = include_str!("../../tests/fixtures/Release");
+/// A clearsigned `InRelease` file
+pub const IN_RELEASE: &str = include_str!("../../tests/fixtures/InRelease");
+
+/// [`STATUS`], parsed
+pub fn status() -> Vec<Paragraph> {
+    parse_multi(STATUS).expect("status fixture parses")
+}
+
+/// [`PACKAGES`], parsed
+pub fn packages() -> Vec<Paragraph> {
+    parse_multi(PACKAGES).expect("Packages fixture parses")
+}
+
+/// [`SOURCES`], parsed
+pub fn sources() -> Vec<Paragraph> {
+    parse_multi(SOURCES).expect("Sources fixture parses")
+}
+
+/// The paragraph of `package` in [`STATUS`]
+///
+/// ```rust
+/// use eight_deep_parser::{test_util::fixtures, ParagraphExt};
+///
+/// assert_eq!(fixtures::installed("bash").get_one("Essential"), Some("yes"));
+/// ```
+pub fn installed(package: &str) -> Paragraph {
+    status()
+        .into_iter()
+        .find(|x| x.get_one("Package") == Some(package))
+        .unwrap_or_else(|| panic!("`{}` isn't in the status fixture", package))
+}
+
+/// [`RELEASE`], parsed
+pub fn release() -> ReleaseFile {
+    ReleaseFile::new(parse_one(RELEASE).expect("Release fixture parses"))
+}
+
+/// The signed text of [`IN_RELEASE`], without the armor and the signature
+pub fn in_release_message() -> String {
+    let (_, rest) = IN_RELEASE
+        .split_once("\n\n")
+        .expect("InRelease fixture has an armor header");
+    let (message, _) = rest
+        .split_once("-----BEGIN PGP SIGNATURE-----")
+        .expect("InRelease fixture has a signature");
+
+    message
+        .lines()
+        .map(|x| x.strip_prefix("- ").unwrap_or(x))
+        .map(|x| format!("{}\n", x))
+        .collect()
+}
+
+/// The signed text of [`IN_RELEASE`], parsed
+pub fn in_release() -> ReleaseFile {
+    ReleaseFile::new(parse_one(&in_release_message()).expect("InRelease fixture parses"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{in_release, packages, release, sources, status};
+    use crate::{test_util::assert_round_trip, Algorithm, ParagraphExt};
+
+    #[test]
+    fn test_fixtures() {
+        for s in [
+            super::STATUS,
+            super::PACKAGES,
+            super::SOURCES,
+            super::RELEASE,
+        ] {
+            assert_round_trip(s);
+        }
+
+        assert_eq!(status().len(), 4);
+        assert_eq!(packages()[0].get_one("Package"), Some("hello"));
+        assert_eq!(sources()[1].get_one("Version"), Some("1:1.2.13.dfsg-1"));
+        assert_eq!(release().entries(Algorithm::Sha256).unwrap().len(), 6);
+
+        let signed = in_release();
+
+        assert_eq!(
+            signed.paragraph().get_one("Codename"),
+            Some("cloud-sdk-bookworm")
+        );
+        assert_eq!(signed.entries(Algorithm::Md5).unwrap().len(), 5);
+    }
+}
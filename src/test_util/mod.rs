@@ -0,0 +1,54 @@
+//! Helpers for testing code built on this crate
+//!
+//! Only available with the `test-util` feature, meant for
+//! `[dev-dependencies]`.
+
+pub mod fixtures;
+pub mod vectors;
+
+use crate::{parse_back, parse_multi};
+
+/// Assert that `input` survives a parse → [`parse_back`] → parse round trip
+///
+/// Panics if `input` doesn't parse, or if the serialized form parses into
+/// different data or serializes differently the second time.
+///
+/// ```rust
+/// use eight_deep_parser::test_util::assert_round_trip;
+///
+/// assert_round_trip("Package: a\nDescription: b\n c\n .\n d\n");
+/// ```
+#[track_caller]
+pub fn assert_round_trip(input: &str) {
+    let parsed = match parse_multi(input) {
+        Ok(parsed) => parsed,
+        Err(e) => panic!("input doesn't parse: {}", e),
+    };
+
+    let back = parse_back(&parsed);
+
+    let reparsed = match parse_multi(&back) {
+        Ok(reparsed) => reparsed,
+        Err(e) => panic!("serialized output doesn't parse: {}\n{}", e, back),
+    };
+
+    assert_eq!(parsed, reparsed, "round trip changed the data:\n{}", back);
+    assert_eq!(back, parse_back(&reparsed), "serializing is not stable");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::assert_round_trip;
+
+    #[test]
+    fn test_assert_round_trip() {
+        assert_round_trip("Package: a\nConffiles:\n /etc/a 123\n\nPackage: b\n\n");
+        assert_round_trip("");
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't parse")]
+    fn test_assert_round_trip_invalid() {
+        assert_round_trip("no field here\n");
+    }
+}
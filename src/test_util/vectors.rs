@@ -0,0 +1,219 @@
+//! The reference vectors in `tests/vectors`: what the parser makes of every
+//! syntax rule and edge case it supports, pinned so that a change in
+//! behavior shows up as a failing vector
+//!
+//! A vector is `<name>.in`, the input, with either `<name>.json`, the
+//! expected [`to_json`] of the paragraphs, or `<name>.err`, the expected
+//! error as `kind` or `kind line N` (see [`ParseError::kind`] and
+//! [`ParseError::line`]). An optional `<name>.options` sets
+//! [`ParseOptions`] fields as `field = value` lines, with values spelled in
+//! snake case, e.g. `duplicates = last_wins`; `preset = dpkg_status` starts
+//! from a preset, and `max_paragraphs` and `max_field_bytes` parse with
+//! [`parse_multi_limited`] instead.
+
+use std::{fs, path::Path};
+
+use crate::{
+    parse_multi_limited, parse_multi_with, to_json, ContinuationWhitespace, DuplicateKeys,
+    KeyStyle, LineEndings, OrphanLines, Paragraph, ParseError, ParseOptions, Result,
+    SpaceBeforeColon, TrailingData,
+};
+
+/// Path of the vector directory of this crate
+pub const VECTORS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/vectors");
+
+/// The expected `.err` of an error
+pub fn describe_error(e: &ParseError) -> String {
+    match e.line() {
+        Some(line) => format!("{} line {}", e.kind(), line),
+        None => e.kind().to_string(),
+    }
+}
+
+/// Run every vector of `dir`, panicking with the list of those that failed
+///
+/// With the `EIGHT_DEEP_BLESS` environment variable set, the expected
+/// `.json` or `.err` of failing vectors is written instead, to be reviewed
+/// before committing.
+#[track_caller]
+pub fn run_vectors(dir: impl AsRef<Path>) {
+    let dir = dir.as_ref();
+    let bless = std::env::var_os("EIGHT_DEEP_BLESS").is_some();
+    let mut names = fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("can't read {}: {}", dir.display(), e))
+        .filter_map(|x| {
+            let name = x.ok()?.file_name().into_string().ok()?;
+            Some(name.strip_suffix(".in")?.to_string())
+        })
+        .collect::<Vec<_>>();
+    names.sort();
+
+    assert!(!names.is_empty(), "no vectors in {}", dir.display());
+
+    let mut failed = vec![];
+
+    for name in &names {
+        let path = |ext: &str| dir.join(format!("{}.{}", name, ext));
+        let input = fs::read(path("in")).unwrap();
+        let options = fs::read_to_string(path("options")).unwrap_or_default();
+        let expected_json = fs::read_to_string(path("json")).ok();
+        let expected_err = fs::read_to_string(path("err")).ok();
+
+        let (ext, actual) = match parse_vector(&input, &options) {
+            Ok(ps) => ("json", to_json(&ps)),
+            Err(e) => ("err", describe_error(&e) + "\n"),
+        };
+
+        let expected = match ext {
+            "json" => &expected_json,
+            _ => &expected_err,
+        };
+
+        if expected.as_deref() == Some(&actual)
+            && (expected_json.is_some() != expected_err.is_some())
+        {
+            continue;
+        }
+
+        if bless {
+            let _ = fs::remove_file(path("json"));
+            let _ = fs::remove_file(path("err"));
+            fs::write(path(ext), &actual).unwrap();
+        }
+
+        failed.push(format!(
+            "{}: expected {}, got {}:\n{}",
+            name,
+            match (&expected_json, &expected_err) {
+                (Some(x), None) | (None, Some(x)) => x.as_str(),
+                (None, None) => "no .json or .err",
+                (Some(_), Some(_)) => "both a .json and a .err",
+            },
+            ext,
+            actual
+        ));
+    }
+
+    assert!(
+        failed.is_empty(),
+        "{} of {} vectors failed:\n\n{}",
+        failed.len(),
+        names.len(),
+        failed.join("\n")
+    );
+}
+
+fn parse_vector(input: &[u8], options: &str) -> Result<Vec<Paragraph>> {
+    let s = std::str::from_utf8(input)?;
+    let mut opts = ParseOptions::default();
+    let mut limits = None;
+    let mut other = false;
+
+    for line in options.lines().filter(|x| !x.trim().is_empty()) {
+        let (k, v) = line
+            .split_once('=')
+            .unwrap_or_else(|| panic!("not `field = value`: {}", line));
+        let (k, v) = (k.trim(), v.trim());
+        let unknown = || -> ! { panic!("unknown option: {}", line) };
+        other |= !k.starts_with("max_");
+
+        match k {
+            "preset" => {
+                opts = match v {
+                    "dpkg_status" => ParseOptions::dpkg_status(),
+                    "apt_packages" => ParseOptions::apt_packages(),
+                    "deb822_sources" => ParseOptions::deb822_sources(),
+                    "strict_rfc" => ParseOptions::strict_rfc(),
+                    _ => unknown(),
+                }
+            }
+            "duplicates" => {
+                opts.duplicates = match v {
+                    "last_wins" => DuplicateKeys::LastWins,
+                    "error" => DuplicateKeys::Error,
+                    "concat" => DuplicateKeys::Concat,
+                    _ => unknown(),
+                }
+            }
+            "continuation" => {
+                opts.continuation = match v {
+                    "strip_one" => ContinuationWhitespace::StripOne,
+                    "strip_all" => ContinuationWhitespace::StripAll,
+                    "preserve" => ContinuationWhitespace::Preserve,
+                    _ => unknown(),
+                }
+            }
+            "keep_empty" => opts.keep_empty = v.parse().unwrap_or_else(|_| unknown()),
+            "skip_comments" => opts.skip_comments = v.parse().unwrap_or_else(|_| unknown()),
+            "strict_field_names" => {
+                opts.strict_field_names = v.parse().unwrap_or_else(|_| unknown())
+            }
+            "line_endings" => {
+                opts.line_endings = match v {
+                    "auto" => LineEndings::Auto,
+                    "lf" => LineEndings::Lf,
+                    "crlf" => LineEndings::CrLf,
+                    _ => unknown(),
+                }
+            }
+            "normalize_keys" => {
+                opts.normalize_keys = match v {
+                    "none" => None,
+                    "train_case" => Some(KeyStyle::TrainCase),
+                    "known_only" => Some(KeyStyle::KnownOnly),
+                    _ => unknown(),
+                }
+            }
+            "space_before_colon" => {
+                opts.space_before_colon = match v {
+                    "trim" => SpaceBeforeColon::Trim,
+                    "error" => SpaceBeforeColon::Error,
+                    _ => unknown(),
+                }
+            }
+            "orphan_lines" => {
+                opts.orphan_lines = match v {
+                    "skip" => OrphanLines::Skip,
+                    "error" => OrphanLines::Error,
+                    _ => unknown(),
+                }
+            }
+            "trailing_data" => {
+                opts.trailing_data = match v {
+                    "skip" => TrailingData::Skip,
+                    "error" => TrailingData::Error,
+                    "resync" => TrailingData::Resync,
+                    _ => unknown(),
+                }
+            }
+            "max_paragraphs" | "max_field_bytes" => {
+                let (paragraphs, field_bytes) = limits.get_or_insert((usize::MAX, usize::MAX));
+                let limit = if k == "max_paragraphs" {
+                    paragraphs
+                } else {
+                    field_bytes
+                };
+                *limit = v.parse().unwrap_or_else(|_| unknown());
+            }
+            _ => unknown(),
+        }
+    }
+
+    match limits {
+        Some((paragraphs, field_bytes)) => {
+            assert!(!other, "limits can't be combined with other options");
+            parse_multi_limited(s, paragraphs, field_bytes)
+        }
+        None => parse_multi_with(s, &opts),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run_vectors, VECTORS_PATH};
+
+    #[test]
+    fn test_vectors() {
+        run_vectors(VECTORS_PATH);
+    }
+}
@@ -0,0 +1,218 @@
+use std::io::{BufRead, Write};
+
+use crate::{try_parse_back_with, Paragraph, ParagraphDecoder, Result, SerializeOptions};
+
+/// Counts of what [`transform`] did
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TransformStats {
+    /// Paragraphs parsed from the input
+    pub read: usize,
+    /// Paragraphs written to the output
+    pub written: usize,
+    /// Paragraphs the closure returned `None` for
+    pub dropped: usize,
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+}
+
+/// Pass every paragraph of `r` through `f` and write what it returns to `w`
+///
+/// Only one paragraph is held in memory at a time, so an index much larger
+/// than the memory can be filtered. Returning `None` drops the paragraph.
+/// The output is written like [`parse_back`](crate::parse_back); comment
+/// lines between paragraphs are dropped.
+///
+/// ```rust
+/// use eight_deep_parser::{transform, ParagraphExt};
+///
+/// let s = "Package: a\nArchitecture: amd64\nTag: x\n\nPackage: b\nArchitecture: i386\n";
+/// let mut out = vec![];
+///
+/// let stats = transform(s.as_bytes(), &mut out, |mut p| {
+///     p.shift_remove("Tag");
+///     (p.get_one("Architecture") == Some("amd64")).then_some(p)
+/// })
+/// .unwrap();
+///
+/// assert_eq!(out, b"Package: a\nArchitecture: amd64\n");
+/// assert_eq!((stats.read, stats.written, stats.dropped), (2, 1, 1));
+/// ```
+pub fn transform<R, W, F>(r: R, w: W, f: F) -> Result<TransformStats>
+where
+    R: BufRead,
+    W: Write,
+    F: FnMut(Paragraph) -> Option<Paragraph>,
+{
+    transform_with(r, w, &SerializeOptions::default(), f)
+}
+
+/// Like [`transform`], writing with the given [`SerializeOptions`]
+///
+/// Fails with [`ParseError::Serialize`](crate::ParseError::Serialize) if
+/// `f` returns a paragraph that can't be written; what came before it is
+/// already written.
+pub fn transform_with<R, W, F>(
+    mut r: R,
+    mut w: W,
+    opts: &SerializeOptions,
+    mut f: F,
+) -> Result<TransformStats>
+where
+    R: BufRead,
+    W: Write,
+    F: FnMut(Paragraph) -> Option<Paragraph>,
+{
+    let mut decoder = ParagraphDecoder::new();
+    let mut stats = TransformStats::default();
+    let eol = if opts.crlf { "\r\n" } else { "\n" };
+    let mut write = |p: Paragraph, stats: &mut TransformStats| -> Result<()> {
+        stats.read += 1;
+
+        let p = match f(p) {
+            Some(p) => p,
+            None => {
+                stats.dropped += 1;
+                return Ok(());
+            }
+        };
+
+        let mut s = try_parse_back_with(
+            &[p],
+            &SerializeOptions {
+                trailing_blank_line: false,
+                ..opts.clone()
+            },
+        )?;
+
+        if stats.written != 0 {
+            s.insert_str(0, eol);
+        }
+
+        w.write_all(s.as_bytes())?;
+        stats.written += 1;
+        stats.bytes_written += s.len() as u64;
+
+        Ok(())
+    };
+
+    loop {
+        let chunk = r.fill_buf()?;
+
+        if chunk.is_empty() {
+            break;
+        }
+
+        let len = chunk.len();
+        decoder.push(chunk);
+        r.consume(len);
+        stats.bytes_read += len as u64;
+
+        while let Some(p) = decoder.next_paragraph() {
+            write(p?, &mut stats)?;
+        }
+    }
+
+    if let Some(p) = decoder.finish()? {
+        write(p, &mut stats)?;
+    }
+
+    if opts.trailing_blank_line && stats.written != 0 {
+        w.write_all(eol.as_bytes())?;
+        stats.bytes_written += eol.len() as u64;
+    }
+
+    w.flush()?;
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use crate::{
+        parse_back, parse_back_with, parse_multi, test_util::fixtures, transform, transform_with,
+        Item, ParseError, SerializeOptions, TransformStats,
+    };
+
+    #[test]
+    fn test_transform_identity() {
+        let s = format!("{}\n\n{}", fixtures::STATUS, fixtures::PACKAGES);
+        let expected = parse_back(&parse_multi(&s).unwrap());
+
+        for capacity in [1, 7, 8192] {
+            let mut out = vec![];
+            let r = BufReader::with_capacity(capacity, s.as_bytes());
+            let stats = transform(r, &mut out, Some).unwrap();
+
+            assert_eq!(String::from_utf8(out).unwrap(), expected);
+            assert_eq!(
+                stats,
+                TransformStats {
+                    read: 8,
+                    written: 8,
+                    dropped: 0,
+                    bytes_read: s.len() as u64,
+                    bytes_written: expected.len() as u64,
+                }
+            );
+        }
+
+        for crlf in [false, true] {
+            let opts = SerializeOptions {
+                trailing_blank_line: true,
+                crlf,
+                ..Default::default()
+            };
+            let mut out = vec![];
+
+            let stats = transform_with(s.as_bytes(), &mut out, &opts, Some).unwrap();
+
+            assert_eq!(stats.bytes_written, out.len() as u64);
+            assert_eq!(
+                String::from_utf8(out).unwrap(),
+                parse_back_with(&parse_multi(&s).unwrap(), &opts)
+            );
+        }
+    }
+
+    #[test]
+    fn test_transform_drop() {
+        let mut i = 0;
+        let mut out = vec![];
+        let stats = transform(fixtures::PACKAGES.as_bytes(), &mut out, |p| {
+            i += 1;
+            (i % 2 == 1).then_some(p)
+        })
+        .unwrap();
+
+        let kept = parse_multi(fixtures::PACKAGES)
+            .unwrap()
+            .into_iter()
+            .step_by(2)
+            .collect::<Vec<_>>();
+
+        assert_eq!(String::from_utf8(out).unwrap(), parse_back(&kept));
+        assert_eq!((stats.read, stats.written, stats.dropped), (4, 2, 2));
+
+        let mut out = vec![];
+        let stats = transform(fixtures::PACKAGES.as_bytes(), &mut out, |_| None).unwrap();
+
+        assert!(out.is_empty());
+        assert_eq!(stats.bytes_written, 0);
+    }
+
+    #[test]
+    fn test_transform_errors() {
+        let r = transform("Package: a\n\nbroken\n".as_bytes(), vec![], Some);
+
+        assert!(r.is_err());
+
+        let r = transform("Package: a\n".as_bytes(), vec![], |mut p| {
+            p.insert("Bad:".to_string(), Item::default());
+            Some(p)
+        });
+
+        assert!(matches!(r, Err(ParseError::Serialize(_))));
+    }
+}
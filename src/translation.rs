@@ -0,0 +1,176 @@
+use crate::{error::Result, parse_multi, Description, IndexMap, Paragraph, ParagraphExt};
+
+/// Read the `Description-<lang>` of every paragraph of an apt
+/// `Translation-<lang>` file, keyed by `Description-md5`
+///
+/// Paragraphs lacking either field are left out.
+///
+/// ```rust
+/// use eight_deep_parser::parse_translations;
+///
+/// let r = parse_translations(
+///     "Package: bash\nDescription-md5: 3522aa7b4374048d6450e348a5bb45d9\nDescription-fr: Interpréteur GNU Bourne-Again SHell\n",
+///     "fr",
+/// )
+/// .unwrap();
+///
+/// assert_eq!(
+///     r["3522aa7b4374048d6450e348a5bb45d9"].synopsis,
+///     "Interpréteur GNU Bourne-Again SHell"
+/// );
+/// ```
+pub fn parse_translations(s: &str, lang: &str) -> Result<IndexMap<String, Description>> {
+    let mut result = IndexMap::new();
+
+    for p in parse_multi(s)? {
+        let md5 = match p.get_one("Description-md5") {
+            Some(md5) => md5,
+            None => continue,
+        };
+
+        if let Some(d) = p.translated_description(lang) {
+            result.insert(md5.to_string(), d);
+        }
+    }
+
+    Ok(result)
+}
+
+/// The `Description-md5` of a package: the one in the paragraph, or with the
+/// `md5` feature, the digest of the `Description` field
+fn package_md5(p: &Paragraph) -> Option<String> {
+    if let Some(md5) = p.get_one("Description-md5") {
+        return Some(md5.to_string());
+    }
+
+    #[cfg(feature = "md5")]
+    if let Some(item) = p.get_field("Description") {
+        return Some(crate::description_md5(item));
+    }
+
+    None
+}
+
+/// Replace the `Description` of every package in `packages` with its
+/// translation, matched by `Description-md5`
+///
+/// Without a `Description-md5` field the md5 is computed from the
+/// `Description` if the `md5` feature is enabled, otherwise the package is
+/// left alone. Returns the md5 of every package without a translation.
+pub fn apply_translations(
+    packages: &mut [Paragraph],
+    translations: &IndexMap<String, Description>,
+) -> Vec<String> {
+    let mut unmatched = vec![];
+
+    for p in packages {
+        let md5 = match package_md5(p) {
+            Some(md5) => md5,
+            None => continue,
+        };
+
+        match translations.get(&md5) {
+            Some(d) => {
+                let item = d.to_item();
+
+                match p.get_index_of("Description") {
+                    Some(i) => *p.get_index_mut(i).unwrap().1 = item,
+                    None => {
+                        p.insert("Description".to_string(), item);
+                    }
+                }
+            }
+            None => unmatched.push(md5),
+        }
+    }
+
+    unmatched
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{apply_translations, parse_multi, parse_translations, ParagraphExt};
+
+    const PACKAGES: &str = "Package: bash
+Version: 5.2.15-2+b7
+Description: GNU Bourne Again SHell
+ Bash is an sh-compatible command language interpreter that executes
+ commands read from the standard input or from a file.  Bash also
+ incorporates useful features from the Korn and C shells (ksh and csh).
+ .
+ Bash is ultimately intended to be a conformant implementation of the
+ IEEE POSIX Shell and Tools specification (IEEE Working Group 1003.2).
+ .
+ The Programmable Completion Code, by Ian Macdonald, is now found in
+ the bash-completion package.
+Description-md5: 3522aa7b4374048d6450e348a5bb45d9
+
+Package: dash
+Version: 0.5.12-2
+Description: POSIX-compliant shell
+Description-md5: f75f69d0ad5e5f2e1b3eaf1ee4da1bd3
+";
+
+    const TRANSLATION: &str = "Package: bash
+Description-md5: 3522aa7b4374048d6450e348a5bb45d9
+Description-fr: Interpréteur de commandes GNU Bourne-Again SHell
+ Bash est un interpréteur de commandes compatible avec sh qui exécute les
+ commandes lues depuis l'entrée standard ou depuis un fichier.
+ .
+ Bash peut être utilisé de façon interactive.
+
+Package: zsh
+Description-md5: 0a1ff1e2ad3d6d94d8c8e1b6a2c5b3f4
+Description-fr: Interpréteur de commandes adapté à une utilisation interactive
+
+Package: dash
+Description-md5: f75f69d0ad5e5f2e1b3eaf1ee4da1bd3
+Description-de: POSIX-konforme Shell
+";
+
+    #[test]
+    fn test_parse_translations() {
+        let r = parse_translations(TRANSLATION, "fr").unwrap();
+
+        assert_eq!(r.len(), 2);
+        assert_eq!(r[0].extended.len(), 4);
+        assert!(r.contains_key("0a1ff1e2ad3d6d94d8c8e1b6a2c5b3f4"));
+        assert_eq!(parse_translations(TRANSLATION, "de").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_apply_translations() {
+        let mut packages = parse_multi(PACKAGES).unwrap();
+        let translations = parse_translations(TRANSLATION, "fr").unwrap();
+
+        let unmatched = apply_translations(&mut packages, &translations);
+
+        assert_eq!(unmatched, vec!["f75f69d0ad5e5f2e1b3eaf1ee4da1bd3"]);
+        assert_eq!(
+            packages[0].description().unwrap().synopsis,
+            "Interpréteur de commandes GNU Bourne-Again SHell"
+        );
+        assert_eq!(packages[0].get_index_of("Description"), Some(2));
+        assert_eq!(
+            packages[1].description().unwrap().synopsis,
+            "POSIX-compliant shell"
+        );
+    }
+
+    #[cfg(feature = "md5")]
+    #[test]
+    fn test_apply_translations_computed_md5() {
+        let mut packages = parse_multi(PACKAGES).unwrap();
+        packages[0].shift_remove("Description-md5");
+
+        assert_eq!(
+            crate::description_md5(packages[0].get_field("Description").unwrap()),
+            "3522aa7b4374048d6450e348a5bb45d9"
+        );
+
+        let translations = parse_translations(TRANSLATION, "fr").unwrap();
+        apply_translations(&mut packages, &translations);
+
+        assert_eq!(packages[0].description().unwrap().extended.len(), 4);
+    }
+}
@@ -0,0 +1,341 @@
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
+
+use crate::{RelationError, VersionError};
+
+/// A Debian package version, `[epoch:]upstream[-revision]`
+///
+/// Versions are ordered the way dpkg orders them, so `1.0~rc1 < 1.0` and
+/// `1.0 == 1.0-0 == 0:1.0`.
+///
+/// Parsing only checks that the parts are there; check the characters
+/// with [`validate_version`].
+///
+/// ```rust
+/// use eight_deep_parser::Version;
+///
+/// let a: Version = "1:2.36-9+deb12u4".parse().unwrap();
+/// let b: Version = "2.37".parse().unwrap();
+///
+/// assert!(a > b);
+/// assert_eq!(a.epoch, 1);
+/// assert_eq!(a.revision.as_deref(), Some("9+deb12u4"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Version {
+    pub epoch: u32,
+    pub upstream: String,
+    pub revision: Option<String>,
+}
+
+impl FromStr for Version {
+    type Err = RelationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || RelationError::Version {
+            version: s.to_string(),
+        };
+
+        let v = s.trim();
+
+        let (epoch, rest) = match v.split_once(':') {
+            Some((epoch, rest)) => (epoch.parse::<u32>().map_err(|_| invalid())?, rest),
+            None => (0, v),
+        };
+
+        let (upstream, revision) = match rest.rsplit_once('-') {
+            Some((upstream, revision)) => (upstream, Some(revision)),
+            None => (rest, None),
+        };
+
+        if upstream.is_empty()
+            || revision.map(|x| x.is_empty()).unwrap_or(false)
+            || v.contains(char::is_whitespace)
+        {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            epoch,
+            upstream: upstream.to_string(),
+            revision: revision.map(|x| x.to_string()),
+        })
+    }
+}
+
+/// Check that `s` is a well-formed Debian version,
+/// `[epoch:]upstream[-revision]`
+///
+/// The epoch is a number, the upstream version starts with a digit and
+/// holds letters, digits and `.+~-`, and the revision holds letters,
+/// digits and `.+~`. A version is split like [`Version`] does: at the first
+/// `:` and the last `-`. Whitespace isn't allowed anywhere.
+///
+/// ```rust
+/// use eight_deep_parser::{validate_version, VersionError};
+///
+/// assert!(validate_version("1:2.36-9+deb12u4").is_ok());
+/// assert_eq!(
+///     validate_version("2.36_1"),
+///     Err(VersionError::InvalidChar { ch: '_', offset: 4 })
+/// );
+/// ```
+pub fn validate_version(s: &str) -> Result<(), VersionError> {
+    if s.is_empty() {
+        return Err(VersionError::Empty);
+    }
+
+    let (epoch, rest) = match s.split_once(':') {
+        Some((epoch, rest)) => (Some(epoch), rest),
+        None => (None, s),
+    };
+
+    if let Some(epoch) = epoch {
+        if epoch.is_empty() || !epoch.bytes().all(|x| x.is_ascii_digit()) {
+            return Err(VersionError::Epoch {
+                epoch: epoch.to_string(),
+            });
+        }
+    }
+
+    let (upstream, revision) = match rest.rsplit_once('-') {
+        Some((upstream, revision)) => (upstream, Some(revision)),
+        None => (rest, None),
+    };
+
+    if !upstream.starts_with(|x: char| x.is_ascii_digit()) {
+        return Err(VersionError::Upstream {
+            upstream: upstream.to_string(),
+        });
+    }
+
+    if revision == Some("") {
+        return Err(VersionError::EmptyRevision);
+    }
+
+    let check = |part: &str, start: usize, allowed: &str| match part
+        .char_indices()
+        .find(|(_, x)| !x.is_ascii_alphanumeric() && !allowed.contains(*x))
+    {
+        Some((i, ch)) => Err(VersionError::InvalidChar {
+            ch,
+            offset: start + i,
+        }),
+        None => Ok(()),
+    };
+
+    let start = s.len() - rest.len();
+    check(upstream, start, ".+~-")?;
+
+    if let Some(revision) = revision {
+        check(revision, start + upstream.len() + 1, ".+~")?;
+    }
+
+    Ok(())
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.epoch != 0 {
+            write!(f, "{}:", self.epoch)?;
+        }
+
+        f.write_str(&self.upstream)?;
+
+        if let Some(revision) = &self.revision {
+            write!(f, "-{}", revision)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.epoch
+            .cmp(&other.epoch)
+            .then_with(|| verrevcmp(&self.upstream, &other.upstream))
+            .then_with(|| {
+                verrevcmp(
+                    self.revision.as_deref().unwrap_or(""),
+                    other.revision.as_deref().unwrap_or(""),
+                )
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+/// Weight of a non-digit character: `~` sorts before everything, even the
+/// end of the string, and letters sort before other characters
+fn order(c: Option<u8>) -> i32 {
+    match c {
+        None => 0,
+        Some(c) if c.is_ascii_digit() => 0,
+        Some(c) if c.is_ascii_alphabetic() => c as i32,
+        Some(b'~') => -1,
+        Some(c) => c as i32 + 256,
+    }
+}
+
+/// dpkg's comparison of an upstream version or revision
+fn verrevcmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a.as_bytes(), b.as_bytes());
+
+    let is_digit = |x: &[u8]| x.first().map(|c| c.is_ascii_digit()).unwrap_or(false);
+
+    while !a.is_empty() || !b.is_empty() {
+        while (!a.is_empty() && !is_digit(a)) || (!b.is_empty() && !is_digit(b)) {
+            let (ac, bc) = (order(a.first().copied()), order(b.first().copied()));
+
+            if ac != bc {
+                return ac.cmp(&bc);
+            }
+
+            a = a.get(1..).unwrap_or_default();
+            b = b.get(1..).unwrap_or_default();
+        }
+
+        while a.first() == Some(&b'0') {
+            a = &a[1..];
+        }
+        while b.first() == Some(&b'0') {
+            b = &b[1..];
+        }
+
+        let mut first_diff = Ordering::Equal;
+
+        while is_digit(a) && is_digit(b) {
+            if first_diff == Ordering::Equal {
+                first_diff = a[0].cmp(&b[0]);
+            }
+
+            a = &a[1..];
+            b = &b[1..];
+        }
+
+        if is_digit(a) {
+            return Ordering::Greater;
+        }
+        if is_digit(b) {
+            return Ordering::Less;
+        }
+        if first_diff != Ordering::Equal {
+            return first_diff;
+        }
+    }
+
+    Ordering::Equal
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use crate::{validate_version, RelationError, Version, VersionError};
+
+    fn v(s: &str) -> Version {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_version_cmp() {
+        for (a, b, o) in [
+            ("1.0", "1.0", Ordering::Equal),
+            ("1.0", "1.0-0", Ordering::Equal),
+            ("0:1.0", "1.0", Ordering::Equal),
+            ("1.01", "1.1", Ordering::Equal),
+            ("1.0~rc1", "1.0", Ordering::Less),
+            ("1.0~~", "1.0~", Ordering::Less),
+            ("1.0", "1.0+b1", Ordering::Less),
+            ("1.0a", "1.0+", Ordering::Less),
+            ("1.9", "1.10", Ordering::Less),
+            ("1:0.1", "2.0", Ordering::Greater),
+            ("2.36-9+deb12u4", "2.36-9+deb12u10", Ordering::Less),
+            ("5.2.15-2+b8", "5.2.15-2+b7", Ordering::Greater),
+            ("1.2.3-1", "1.2.3-1ubuntu1", Ordering::Less),
+        ] {
+            assert_eq!(v(a).cmp(&v(b)), o, "{} {}", a, b);
+            assert_eq!(v(b).cmp(&v(a)), o.reverse(), "{} {}", b, a);
+        }
+    }
+
+    #[test]
+    fn test_version_str() {
+        let r = v("1:4.4.33-2");
+
+        assert_eq!(r.epoch, 1);
+        assert_eq!(r.upstream, "4.4.33");
+        assert_eq!(r.revision.as_deref(), Some("2"));
+
+        for s in ["1:4.4.33-2", "1.0", "2:1.0-rc-1"] {
+            assert_eq!(v(s).to_string(), s);
+        }
+
+        for s in ["", "a:1.0", "1:", "1.0-", "1 0"] {
+            assert_eq!(
+                s.parse::<Version>().unwrap_err(),
+                RelationError::Version {
+                    version: s.to_string()
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_version() {
+        for s in [
+            "0",
+            "1.0",
+            "1:2.36-9+deb12u4",
+            "2:1.0-rc-1",
+            "1.0~rc1+dfsg-0ubuntu1~22.04",
+            "20230101",
+            "9.2p1-2",
+            "0:1.0-1.1",
+        ] {
+            assert_eq!(validate_version(s), Ok(()), "{}", s);
+            assert!(s.parse::<Version>().is_ok(), "{}", s);
+        }
+
+        let epoch = |x: &str| VersionError::Epoch {
+            epoch: x.to_string(),
+        };
+        let upstream = |x: &str| VersionError::Upstream {
+            upstream: x.to_string(),
+        };
+        let invalid = |ch, offset| VersionError::InvalidChar { ch, offset };
+
+        for (s, e) in [
+            ("", VersionError::Empty),
+            ("a:1.0", epoch("a")),
+            (":1.0", epoch("")),
+            ("-1:1.0", epoch("-1")),
+            ("1:", upstream("")),
+            ("1.0:2", epoch("1.0")),
+            ("v1.0", upstream("v1.0")),
+            ("-1", upstream("")),
+            (" 1.0", upstream(" 1.0")),
+            ("1.0-", VersionError::EmptyRevision),
+            ("1.0_1", invalid('_', 3)),
+            ("1:1 0", invalid(' ', 3)),
+            ("1:1.0:2-1", invalid(':', 5)),
+            ("1.0-2:3", epoch("1.0-2")),
+            ("1.0-a_b", invalid('_', 5)),
+            ("1.0é", invalid('é', 3)),
+        ] {
+            assert_eq!(validate_version(s), Err(e), "{}", s);
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use std::ops::Range;
+
+use crate::incremental::paragraph_spans;
+
+/// A paragraph read in place from its text, without parsing it into a map
+///
+/// Fields are found by walking the lines of the text each time, and values
+/// are borrowed from it: a field of tens of thousands of lines, like the
+/// `Checksums-Sha256` of a large source package, is never copied. Lines are
+/// those [`Item::lines`](crate::Item::lines) gives for the parsed field,
+/// the value on the field line without the whitespace before it, then the
+/// continuation lines without their first space or tab. A field with
+/// nothing on its line and no continuation lines has no lines.
+///
+/// The text isn't checked: a line that is neither a field nor a
+/// continuation line is skipped.
+///
+/// ```rust
+/// use eight_deep_parser::ParagraphView;
+///
+/// let view = ParagraphView::new("Package: a\nFiles:\n 1 2 a.dsc\n 3 4 a.tar.xz\n");
+///
+/// assert_eq!(view.keys().collect::<Vec<_>>(), ["Package", "Files"]);
+/// assert_eq!(
+///     view.field_lines("files").collect::<Vec<_>>(),
+///     ["1 2 a.dsc", "3 4 a.tar.xz"]
+/// );
+/// assert_eq!(view.field_lines("Version").count(), 0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParagraphView<'a> {
+    text: &'a str,
+}
+
+/// The lines of a field value, read lazily from the text of its paragraph,
+/// see [`ParagraphView::field_lines`]
+#[derive(Debug, Clone, Default)]
+pub struct LineStream<'a> {
+    first: Option<&'a str>,
+    /// The continuation lines not read yet
+    rest: &'a str,
+}
+
+impl<'a> ParagraphView<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Self { text }
+    }
+
+    pub fn as_str(&self) -> &'a str {
+        self.text
+    }
+
+    /// The field names, as written and in order
+    pub fn keys(&self) -> impl Iterator<Item = &'a str> {
+        fields(self.text).map(|(k, _)| k)
+    }
+
+    /// Whether there is a field named `key`, ignoring case
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.keys().any(|x| x.eq_ignore_ascii_case(key))
+    }
+
+    /// The lines of the first field named `key`, ignoring case, none when
+    /// there is no such field
+    pub fn field_lines(&self, key: &str) -> LineStream<'a> {
+        fields(self.text)
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map_or(LineStream::default(), |(_, span)| {
+                LineStream::new(&self.text[span])
+            })
+    }
+}
+
+impl<'a> LineStream<'a> {
+    /// The lines of `field`, the text of a field from its name to the line
+    /// break of its last continuation line
+    pub(crate) fn new(field: &'a str) -> Self {
+        let (line, rest) = field.split_once('\n').unwrap_or((field, ""));
+        let first = line
+            .split_once(':')
+            .map(|(_, v)| v.trim_start_matches([' ', '\t']))
+            .filter(|x| !x.is_empty());
+
+        Self { first, rest }
+    }
+}
+
+impl<'a> Iterator for LineStream<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        if let Some(first) = self.first.take() {
+            return Some(first);
+        }
+
+        if !self.rest.starts_with([' ', '\t']) {
+            return None;
+        }
+
+        let (line, rest) = self.rest.split_once('\n').unwrap_or((self.rest, ""));
+        self.rest = rest;
+
+        Some(&line[1..])
+    }
+}
+
+/// The paragraphs of `s` as [`ParagraphView`]s, split like
+/// [`ParagraphDecoder`](crate::ParagraphDecoder) does: at empty lines,
+/// skipping lines of whitespace and comment lines between paragraphs
+///
+/// ```rust
+/// use eight_deep_parser::paragraph_views;
+///
+/// let s = "Package: a\n\n# b\nPackage: c\n";
+/// let packages = paragraph_views(s)
+///     .flat_map(|x| x.field_lines("Package"))
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(packages, ["a", "c"]);
+/// ```
+pub fn paragraph_views(s: &str) -> impl Iterator<Item = ParagraphView<'_>> {
+    paragraph_spans(s.as_bytes()).map(move |span| ParagraphView::new(&s[span]))
+}
+
+/// The fields of the text of a paragraph: their names and spans, from the
+/// start of the field line to the end of the last continuation line, with
+/// its line break
+pub(crate) fn fields(text: &str) -> impl Iterator<Item = (&str, Range<usize>)> {
+    let mut pos = 0;
+
+    std::iter::from_fn(move || {
+        while pos < text.len() {
+            let start = pos;
+            let mut end = text[pos..].find('\n').map_or(text.len(), |x| pos + x + 1);
+            let line = &text[start..end];
+
+            while text[end..].starts_with([' ', '\t']) {
+                end = text[end..].find('\n').map_or(text.len(), |x| end + x + 1);
+            }
+            pos = end;
+
+            if line.starts_with([' ', '\t']) {
+                continue;
+            }
+
+            if let Some((k, _)) = line.split_once(':') {
+                return Some((k.trim_end_matches([' ', '\t']), start..end));
+            }
+        }
+
+        None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{paragraph_views, parse_multi, test_util::fixtures, ParagraphView};
+
+    #[test]
+    fn test_field_lines() {
+        let s = format!("{}\n{}", fixtures::STATUS, fixtures::SOURCES_TEXLIVE);
+        let ps = parse_multi(&s).unwrap();
+        let views = paragraph_views(&s).collect::<Vec<_>>();
+
+        assert_eq!(views.len(), ps.len());
+
+        for (p, view) in ps.iter().zip(&views) {
+            assert!(view.keys().eq(p.keys().map(|x| x.as_str())));
+
+            for (k, v) in p {
+                assert_eq!(view.field_lines(k).collect::<Vec<_>>(), v.lines(), "{}", k);
+            }
+        }
+
+        let texlive = views.last().unwrap();
+        assert!(texlive.contains_key("checksums-sha256"));
+        assert_eq!(texlive.field_lines("Package-List").count(), 16);
+    }
+
+    #[test]
+    fn test_field_lines_edge_cases() {
+        let view = ParagraphView::new("A:\nB : x  \n\tc\n orphan:\nbroken\nC:\n .\n");
+
+        assert_eq!(view.keys().collect::<Vec<_>>(), ["A", "B", "C"]);
+        assert_eq!(view.field_lines("A").count(), 0);
+        assert_eq!(
+            view.field_lines("b").collect::<Vec<_>>(),
+            ["x  ", "c", "orphan:"]
+        );
+        assert_eq!(view.field_lines("C").collect::<Vec<_>>(), ["."]);
+        assert!(!view.contains_key("broken"));
+    }
+}